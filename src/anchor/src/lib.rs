@@ -0,0 +1,210 @@
+//! Anchor CPI wrappers around the QF program, so Anchor programs can call it with a
+//! `CpiContext` instead of building the account metas by hand
+//!
+//! The account structs list the accounts in the order the instruction expects them,
+//! `remaining_accounts` are appended after them. Like the `cpi` module of the program,
+//! `from_auth` and the other signers may be program derived addresses of the caller, in
+//! which case their seeds go in the context's signer seeds.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{entrypoint::ProgramResult, instruction::Instruction, program::invoke_signed},
+};
+use qf::instruction::QFInstruction;
+
+/// Accounts for a `Donate` CPI, a multisig `from_auth` takes its signers as remaining
+/// accounts
+#[derive(Accounts)]
+pub struct Donate<'info> {
+    #[account(mut)]
+    pub round: AccountInfo<'info>,
+    #[account(mut)]
+    pub from: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    #[account(mut)]
+    pub vault: AccountInfo<'info>,
+    #[account(signer)]
+    pub from_auth: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    /// `["sponsor", round, from]`
+    #[account(mut)]
+    pub sponsor: AccountInfo<'info>,
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+}
+
+/// Accounts for a `RegisterProject` CPI
+#[derive(Accounts)]
+pub struct RegisterProject<'info> {
+    /// `[round, owner, index]`
+    #[account(mut)]
+    pub project: AccountInfo<'info>,
+    #[account(mut)]
+    pub round: AccountInfo<'info>,
+    #[account(mut, signer)]
+    pub owner: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+    pub payout_wallet: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// Accounts for an `InitVoter` CPI
+#[derive(Accounts)]
+pub struct InitVoter<'info> {
+    /// `[project, wallet]`
+    #[account(mut)]
+    pub voter: AccountInfo<'info>,
+    pub wallet: AccountInfo<'info>,
+    pub project: AccountInfo<'info>,
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+    pub round: AccountInfo<'info>,
+    /// `["registry", round, wallet]`
+    #[account(mut)]
+    pub registry: AccountInfo<'info>,
+}
+
+/// Accounts for a `Vote` CPI
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    #[account(mut)]
+    pub round: AccountInfo<'info>,
+    #[account(mut)]
+    pub project: AccountInfo<'info>,
+    #[account(mut)]
+    pub voter: AccountInfo<'info>,
+    #[account(mut)]
+    pub from: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    #[account(mut)]
+    pub vault: AccountInfo<'info>,
+    #[account(signer)]
+    pub from_auth: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    #[account(mut)]
+    pub registry: AccountInfo<'info>,
+}
+
+/// Accounts for a `Withdraw` CPI
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub round: AccountInfo<'info>,
+    #[account(mut)]
+    pub vault: AccountInfo<'info>,
+    /// `[round owner]`
+    pub vault_owner: AccountInfo<'info>,
+    #[account(mut)]
+    pub project: AccountInfo<'info>,
+    #[account(mut, signer)]
+    pub project_owner: AccountInfo<'info>,
+    /// associated token account of the payout wallet for the round's mint
+    #[account(mut)]
+    pub to: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+    pub payout_wallet: AccountInfo<'info>,
+    /// `["config"]`
+    pub config: AccountInfo<'info>,
+    /// `["claim", project]`
+    #[account(mut)]
+    pub claim: AccountInfo<'info>,
+}
+
+/// Accounts for a `Refund` CPI
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    pub round: AccountInfo<'info>,
+    #[account(mut)]
+    pub vault: AccountInfo<'info>,
+    pub vault_owner: AccountInfo<'info>,
+    pub project: AccountInfo<'info>,
+    #[account(mut)]
+    pub voter: AccountInfo<'info>,
+    #[account(mut)]
+    pub to: AccountInfo<'info>,
+    #[account(signer)]
+    pub wallet: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+/// Donates `amount` to the round's matching pool
+pub fn donate<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, Donate<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> ProgramResult {
+    invoke(ctx, QFInstruction::Donate { amount, decimals })
+}
+
+/// Registers a project, the `index`-th of its owner in the round. `proof` is the
+/// owner's allowlist proof, empty for an open round.
+pub fn register_project<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, RegisterProject<'info>>,
+    category: u8,
+    index: u8,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    invoke(
+        ctx,
+        QFInstruction::RegisterProject {
+            category,
+            index,
+            proof,
+        },
+    )
+}
+
+/// Creates the voter of a wallet for a project
+pub fn init_voter<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, InitVoter<'info>>,
+) -> ProgramResult {
+    invoke(ctx, QFInstruction::InitVoter)
+}
+
+/// Votes `amount` to a project
+pub fn vote<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, Vote<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> ProgramResult {
+    invoke(ctx, QFInstruction::Vote { amount, decimals })
+}
+
+/// Pays a project its claim, or its votes back if the round was cancelled
+pub fn withdraw<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, Withdraw<'info>>,
+) -> ProgramResult {
+    invoke(ctx, QFInstruction::Withdraw)
+}
+
+/// Gives the votes of a wallet for a project back, in a cancelled round
+pub fn refund<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, Refund<'info>>,
+) -> ProgramResult {
+    invoke(ctx, QFInstruction::Refund)
+}
+
+fn invoke<'a, 'b, 'c, 'info, T: Accounts<'info>>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, T>,
+    instruction: QFInstruction,
+) -> ProgramResult {
+    // anchor-lang 0.6 has no metas for the context, the remaining accounts follow the struct's
+    let mut accounts = ctx.accounts.to_account_metas(None);
+    for info in ctx.remaining_accounts.iter() {
+        accounts.extend(info.to_account_metas(None));
+    }
+    let instruction = Instruction {
+        program_id: *ctx.program.key,
+        accounts,
+        data: instruction.pack(),
+    };
+    invoke_signed(&instruction, &ctx.to_account_infos(), ctx.signer_seeds)
+}
@@ -0,0 +1,219 @@
+//! Keeper sending CrankEndRound and CrankFinalize for every round of the program once
+//! they are due, with Prometheus metrics for alerting
+
+use clap::{crate_version, value_t, App, Arg};
+use prometheus::{IntCounter, IntCounterVec, IntGauge, Opts, Registry};
+use qf::{
+    instruction,
+    state::{Round, RoundStatus},
+};
+use qf_api::metrics;
+use qf_cli::{accounts::program_accounts, scaffold};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::AccountMeta,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::{
+    env, error::Error, path::PathBuf, process::exit, str::FromStr, thread::sleep, time::Duration,
+};
+
+struct Metrics {
+    /// rounds with a voting end, in Voting or Tallying
+    rounds_watched: IntGauge,
+    cranks_sent: IntCounterVec,
+    crank_failures: IntCounterVec,
+    poll_failures: IntCounter,
+    /// seconds the most overdue crank of the last poll had been due
+    lag: IntGauge,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let metrics = Metrics {
+            rounds_watched: IntGauge::new(
+                "qf_keeper_rounds_watched",
+                "Rounds in Voting or Tallying with a voting end",
+            )?,
+            cranks_sent: IntCounterVec::new(
+                Opts::new("qf_keeper_cranks_sent_total", "Cranks confirmed"),
+                &["crank"],
+            )?,
+            crank_failures: IntCounterVec::new(
+                Opts::new("qf_keeper_crank_failures_total", "Cranks that failed"),
+                &["crank"],
+            )?,
+            poll_failures: IntCounter::new(
+                "qf_keeper_poll_failures_total",
+                "Polls of the rounds that failed",
+            )?,
+            lag: IntGauge::new(
+                "qf_keeper_lag_seconds",
+                "Seconds the most overdue crank had been due at the last poll",
+            )?,
+        };
+        registry.register(Box::new(metrics.rounds_watched.clone()))?;
+        registry.register(Box::new(metrics.cranks_sent.clone()))?;
+        registry.register(Box::new(metrics.crank_failures.clone()))?;
+        registry.register(Box::new(metrics.poll_failures.clone()))?;
+        registry.register(Box::new(metrics.lag.clone()))?;
+        Ok(metrics)
+    }
+}
+
+fn main() {
+    let matches = App::new("qf-keeper")
+        .about("Crank the rounds of a QF program once their voting end and tally period pass")
+        .version(crate_version!())
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("JSON RPC URL of the cluster"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .help("Address the QF program is deployed at"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .long("keypair")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Payer of the cranks [default: ~/.config/solana/id.json]"),
+        )
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value("30")
+                .help("Seconds between polls of the rounds"),
+        )
+        .arg(
+            Arg::with_name("metrics")
+                .long("metrics")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .help("Address to serve Prometheus metrics on at /metrics"),
+        )
+        .get_matches();
+
+    let rpc = RpcClient::new(matches.value_of("url").unwrap().to_string());
+    let interval = value_t!(matches, "interval", u64).unwrap_or_else(|err| err.exit());
+    let result = (|| -> Result<(), Box<dyn Error>> {
+        let program_id = Pubkey::from_str(matches.value_of("program_id").unwrap())
+            .map_err(|_| "invalid program id")?;
+        let keypair = match matches.value_of("keypair") {
+            Some(path) => PathBuf::from(path),
+            None => PathBuf::from(env::var("HOME")?).join(".config/solana/id.json"),
+        };
+        let payer = read_keypair_file(&keypair)
+            .map_err(|err| format!("can not read {}: {}", keypair.display(), err))?;
+
+        let registry = Registry::new();
+        let metrics = Metrics::register(&registry)?;
+        if let Some(listen) = matches.value_of("metrics") {
+            metrics::serve(listen.parse()?, registry)?;
+        }
+
+        // a failed poll is counted and retried, the cluster may only be lagging
+        loop {
+            if let Err(err) = poll(&rpc, &program_id, &payer, &metrics) {
+                metrics.poll_failures.inc();
+                eprintln!("error: {}", err);
+            }
+            sleep(Duration::from_secs(interval));
+        }
+    })();
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
+
+/// Sends the cranks that are due by the cluster's clock, the program rejects them with
+/// CrankNotDue before that
+fn poll(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    metrics: &Metrics,
+) -> Result<(), Box<dyn Error>> {
+    let now = rpc.get_block_time(rpc.get_slot()?)?;
+    let mut watched = 0;
+    let mut lag = 0;
+    for (key, round) in program_accounts::<Round>(rpc, program_id)? {
+        // a fund lent out is recalled by its owner, who knows the reserve
+        if round.config.voting_end == 0 || round.lent > 0 {
+            continue;
+        }
+        let (crank, due, mut crank_instruction) = match round.status {
+            RoundStatus::Voting => (
+                "end_round",
+                round.config.voting_end,
+                instruction::crank_end_round(program_id, &key),
+            ),
+            RoundStatus::Tallying => (
+                "finalize",
+                (round.config.voting_end + round.config.tally_period)
+                    .max(round.voting_ended_at + round.config.dispute_window),
+                instruction::crank_finalize(program_id, &key),
+            ),
+            _ => continue,
+        };
+        watched += 1;
+        if now < due {
+            continue;
+        }
+        lag = lag.max(now - due);
+
+        crank_instruction
+            .accounts
+            .extend(reward_accounts(rpc, program_id, payer, &round)?);
+        match scaffold::send(rpc, payer, &[crank_instruction], &[]) {
+            Ok(()) => {
+                metrics.cranks_sent.with_label_values(&[crank]).inc();
+                println!("{} round {}", crank, key);
+            }
+            Err(err) => {
+                metrics.crank_failures.with_label_values(&[crank]).inc();
+                eprintln!("error: {} round {}: {}", crank, key, err);
+            }
+        }
+    }
+    metrics.rounds_watched.set(watched);
+    metrics.lag.set(lag);
+    Ok(())
+}
+
+/// The accounts the crank reward is paid with, to the payer's associated token account
+/// of the round's mint. None when the round pays no reward or the payer has no such
+/// account, the crank then goes through without the reward.
+fn reward_accounts(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    round: &Round,
+) -> Result<Vec<AccountMeta>, Box<dyn Error>> {
+    let to = get_associated_token_address(&payer.pubkey(), &round.mint);
+    if round.config.crank_reward == 0 || rpc.get_balance(&to)? == 0 {
+        return Ok(vec![]);
+    }
+    let (vault_owner, _) = Pubkey::find_program_address(&[&round.owner.to_bytes()], program_id);
+    Ok(vec![
+        AccountMeta::new(round.vault, false),
+        AccountMeta::new_readonly(vault_owner, false),
+        AccountMeta::new(to, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ])
+}
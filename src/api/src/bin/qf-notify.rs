@@ -0,0 +1,225 @@
+//! Notifier posting the events the program logs as JSON webhooks, so bots and receipts
+//! can be driven without parsing the logs
+
+use clap::{crate_version, App, Arg};
+use prometheus::{IntCounter, IntCounterVec, IntGauge, Opts, Registry};
+use qf_api::metrics;
+use qf_cli::subscribe;
+use serde_json::{Map, Value};
+use solana_client::{
+    pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use std::{process::exit, thread::sleep, time::Duration};
+
+/// tries a webhook gets before the event is dropped for it
+const ATTEMPTS: u32 = 3;
+
+/// what the program logs before an event, see the program's events module
+const EVENT_PREFIX: &str = "Program log: event: ";
+
+struct Metrics {
+    events: IntCounterVec,
+    webhooks_posted: IntCounter,
+    webhook_failures: IntCounter,
+    /// compared with the cluster's slot it tells how far behind the notifier is
+    last_slot: IntGauge,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let metrics = Metrics {
+            events: IntCounterVec::new(
+                Opts::new("qf_notify_events_total", "Events posted, by name"),
+                &["event"],
+            )?,
+            webhooks_posted: IntCounter::new(
+                "qf_notify_webhooks_posted_total",
+                "Events a webhook accepted",
+            )?,
+            webhook_failures: IntCounter::new(
+                "qf_notify_webhook_failures_total",
+                "Events dropped for a webhook after every attempt failed",
+            )?,
+            last_slot: IntGauge::new(
+                "qf_notify_last_slot",
+                "Slot of the last logs received from the cluster",
+            )?,
+        };
+        registry.register(Box::new(metrics.events.clone()))?;
+        registry.register(Box::new(metrics.webhooks_posted.clone()))?;
+        registry.register(Box::new(metrics.webhook_failures.clone()))?;
+        registry.register(Box::new(metrics.last_slot.clone()))?;
+        Ok(metrics)
+    }
+}
+
+fn main() {
+    let matches = App::new("qf-notify")
+        .about("Post the events of a QF program to webhooks")
+        .version(crate_version!())
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("JSON RPC URL of the cluster"),
+        )
+        .arg(
+            Arg::with_name("ws")
+                .long("ws")
+                .value_name("URL")
+                .takes_value(true)
+                .help("Websocket URL of the cluster, derived from --url by default"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .help("Address the QF program is deployed at"),
+        )
+        .arg(
+            Arg::with_name("webhook")
+                .long("webhook")
+                .value_name("URL")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(true)
+                .help("URL every event is posted to, can be repeated"),
+        )
+        .arg(
+            Arg::with_name("event")
+                .long("event")
+                .value_name("NAME")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Only post events with this name, like vote_logged, project_banned, \
+                     round_ended, round_extended, payout_claimed or holdback_released, can be \
+                     repeated",
+                ),
+        )
+        .arg(
+            Arg::with_name("round")
+                .long("round")
+                .value_name("ROUND")
+                .takes_value(true)
+                .help("Only post events of this round"),
+        )
+        .arg(
+            Arg::with_name("metrics")
+                .long("metrics")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .help("Address to serve Prometheus metrics on at /metrics"),
+        )
+        .get_matches();
+
+    let ws_url = matches
+        .value_of("ws")
+        .map(str::to_string)
+        .unwrap_or_else(|| subscribe::websocket_url(matches.value_of("url").unwrap()));
+    let webhooks: Vec<&str> = matches.values_of("webhook").unwrap().collect();
+    let names: Option<Vec<&str>> = matches.values_of("event").map(Iterator::collect);
+    let round = matches.value_of("round");
+
+    let registry = Registry::new();
+    let metrics = Metrics::register(&registry).unwrap();
+    if let Some(listen) = matches.value_of("metrics") {
+        let served = listen
+            .parse()
+            .map_err(|err| format!("invalid metrics address: {}", err))
+            .and_then(|listen| metrics::serve(listen, registry).map_err(|err| err.to_string()));
+        if let Err(err) = served {
+            eprintln!("error: {}", err);
+            exit(1);
+        }
+    }
+
+    let (_subscription, receiver) = match PubsubClient::logs_subscribe(
+        &ws_url,
+        RpcTransactionLogsFilter::Mentions(vec![matches
+            .value_of("program_id")
+            .unwrap()
+            .to_string()]),
+        RpcTransactionLogsConfig { commitment: None },
+    ) {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            exit(1);
+        }
+    };
+
+    while let Ok(response) = receiver.recv() {
+        let logs = response.value;
+        metrics.last_slot.set(response.context.slot as i64);
+        // a failed transaction logs the events it emitted before failing
+        if logs.err.is_some() {
+            continue;
+        }
+        for log in logs.logs.iter() {
+            let mut event = match parse_event(log) {
+                Some(event) => event,
+                None => continue,
+            };
+            let name = event["event"].as_str().unwrap_or_default().to_string();
+            if matches!(&names, Some(names) if !names.contains(&name.as_str()))
+                || matches!(round, Some(round) if event["round"] != round)
+            {
+                continue;
+            }
+            event.insert("signature".to_string(), logs.signature.clone().into());
+            event.insert("slot".to_string(), response.context.slot.into());
+            metrics.events.with_label_values(&[&name]).inc();
+            let body = Value::Object(event).to_string();
+            for webhook in webhooks.iter() {
+                if post(webhook, &body) {
+                    metrics.webhooks_posted.inc();
+                } else {
+                    metrics.webhook_failures.inc();
+                }
+            }
+        }
+    }
+    eprintln!("error: the subscription closed");
+    exit(1);
+}
+
+/// Parses `Program log: event: <name> <key>=<value> ...` into `{"event": <name>,
+/// <key>: <value>, ...}`, the values stay strings as amounts may not fit in a JSON
+/// number
+fn parse_event(log: &str) -> Option<Map<String, Value>> {
+    let mut words = log.strip_prefix(EVENT_PREFIX)?.split_whitespace();
+    let mut event = Map::new();
+    event.insert("event".to_string(), words.next()?.into());
+    for word in words {
+        let (key, value) = word.split_at(word.find('=')?);
+        event.insert(key.to_string(), value[1..].into());
+    }
+    Some(event)
+}
+
+/// Posts the body, retrying with a growing delay, a webhook that keeps failing misses
+/// the event. Returns whether the webhook accepted it.
+fn post(webhook: &str, body: &str) -> bool {
+    for attempt in 1..=ATTEMPTS {
+        match ureq::post(webhook)
+            .set("Content-Type", "application/json")
+            .send_string(body)
+        {
+            Ok(_) => return true,
+            Err(err) if attempt == ATTEMPTS => {
+                eprintln!("webhook {} failed, event dropped: {}", webhook, err)
+            }
+            Err(_) => sleep(Duration::from_secs(1 << attempt)),
+        }
+    }
+    false
+}
@@ -0,0 +1,201 @@
+//! REST API serving rounds as JSON for public round explorers, read over RPC
+
+use clap::{crate_version, App, Arg};
+use qf_cli::export::{self, ProjectResult};
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    convert::Infallible, error::Error, future::Future, net::SocketAddr, process::exit,
+    str::FromStr, sync::Arc,
+};
+use tokio::task;
+use warp::{
+    http::StatusCode,
+    reply::{self, Json, WithStatus},
+    Filter,
+};
+
+struct Api {
+    rpc: RpcClient,
+    program_id: Pubkey,
+}
+
+/// An error as a status and a JSON body with its message
+struct ApiError(StatusCode, String);
+
+type ApiResult = Result<Value, ApiError>;
+
+/// Replies with the JSON a handler returned, or with its error
+async fn respond(handler: impl Future<Output = ApiResult>) -> Result<WithStatus<Json>, Infallible> {
+    Ok(match handler.await {
+        Ok(value) => reply::with_status(reply::json(&value), StatusCode::OK),
+        Err(ApiError(status, message)) => {
+            reply::with_status(reply::json(&json!({ "error": message })), status)
+        }
+    })
+}
+
+/// The round at `round` and the results of its projects, fetched on the blocking pool
+/// as RpcClient is synchronous
+async fn results(
+    api: Arc<Api>,
+    round: &str,
+) -> Result<(Pubkey, Value, Vec<ProjectResult>), ApiError> {
+    let round_key = Pubkey::from_str(round)
+        .map_err(|_| ApiError(StatusCode::BAD_REQUEST, format!("invalid round: {}", round)))?;
+    let (round, results) = task::spawn_blocking(move || {
+        export::results(&api.rpc, &api.program_id, &round_key)
+            .map_err(|err: Box<dyn Error>| err.to_string())
+    })
+    .await
+    .map_err(|err| ApiError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+    .map_err(|err| ApiError(StatusCode::BAD_GATEWAY, err))?;
+    Ok((round_key, export::round_json(&round_key, &round), results))
+}
+
+/// GET /rounds/:round
+async fn round(api: Arc<Api>, round: String) -> ApiResult {
+    let (_, mut round, results) = results(api, &round).await?;
+    round["projects"] = results.len().into();
+    Ok(round)
+}
+
+/// GET /rounds/:round/projects, sorted by address
+async fn projects(api: Arc<Api>, round: String) -> ApiResult {
+    let (_, _, results) = results(api, &round).await?;
+    Ok(results.iter().map(export::project_json).collect())
+}
+
+/// GET /rounds/:round/leaderboard, the projects ranked by their payout if the round
+/// ended now
+async fn leaderboard(api: Arc<Api>, round: String) -> ApiResult {
+    let (_, _, mut results) = results(api, &round).await?;
+    results.sort_by(|a, b| {
+        (b.allocation.payout, b.contributions).cmp(&(a.allocation.payout, a.contributions))
+    });
+    Ok(results
+        .iter()
+        .enumerate()
+        .map(|(rank, result)| {
+            json!({
+                "rank": rank + 1,
+                "project": result.project.to_string(),
+                "owner": result.owner.to_string(),
+                "contributions": result.contributions.to_string(),
+                "contributors": result.contributors,
+                "match": result
+                    .allocation
+                    .payout
+                    .saturating_sub(result.allocation.votes)
+                    .to_string(),
+                "payout": result.allocation.payout.to_string(),
+                "banned": result.banned,
+            })
+        })
+        .collect())
+}
+
+/// GET /rounds/:round/payouts, what Withdraw would pay every project now and the totals,
+/// like simulate-payouts
+async fn payouts(api: Arc<Api>, round: String) -> ApiResult {
+    let (round_key, _, results) = results(api, &round).await?;
+    let allocations = results.iter().map(|result| &result.allocation);
+    let sum = |amount: fn(&qf_cli::simulate::Allocation) -> u64| {
+        allocations.clone().map(amount).sum::<u64>().to_string()
+    };
+    Ok(json!({
+        "round": round_key.to_string(),
+        "payout": sum(|allocation| allocation.payout),
+        "operator_fee": sum(|allocation| allocation.operator_fee),
+        "protocol_fee": sum(|allocation| allocation.protocol_fee),
+        "projects": allocations
+            .clone()
+            .map(|allocation| {
+                json!({
+                    "project": allocation.project.to_string(),
+                    "payout": allocation.payout.to_string(),
+                    "operator_fee": allocation.operator_fee.to_string(),
+                    "protocol_fee": allocation.protocol_fee.to_string(),
+                    "deposit": allocation.deposit.to_string(),
+                    "total": allocation.total().to_string(),
+                    "withdrawn": allocation.withdrawn,
+                })
+            })
+            .collect::<Vec<_>>(),
+    }))
+}
+
+#[tokio::main]
+async fn main() {
+    let matches = App::new("qf-rest")
+        .about("Serve the rounds of a QF program as JSON over HTTP")
+        .version(crate_version!())
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("JSON RPC URL of the cluster"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .help("Address the QF program is deployed at"),
+        )
+        .arg(
+            Arg::with_name("listen")
+                .long("listen")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .default_value("127.0.0.1:8080")
+                .help("Address the HTTP server listens on"),
+        )
+        .get_matches();
+
+    let (program_id, listen) = match (
+        Pubkey::from_str(matches.value_of("program_id").unwrap()),
+        matches.value_of("listen").unwrap().parse::<SocketAddr>(),
+    ) {
+        (Ok(program_id), Ok(listen)) => (program_id, listen),
+        _ => {
+            eprintln!("error: invalid program id or listen address");
+            exit(1);
+        }
+    };
+    let api = Arc::new(Api {
+        rpc: RpcClient::new(matches.value_of("url").unwrap().to_string()),
+        program_id,
+    });
+
+    let api = warp::any().map(move || api.clone());
+    let routes = warp::path!("rounds" / String)
+        .and(api.clone())
+        .and_then(|key, api| respond(round(api, key)))
+        .or(warp::path!("rounds" / String / "projects")
+            .and(api.clone())
+            .and_then(|key, api| respond(projects(api, key))))
+        .or(warp::path!("rounds" / String / "leaderboard")
+            .and(api.clone())
+            .and_then(|key, api| respond(leaderboard(api, key))))
+        .or(warp::path!("rounds" / String / "payouts")
+            .and(api)
+            .and_then(|key, api| respond(payouts(api, key))));
+    // explorers call it from the browser on other origins, it only serves public data
+    let routes = warp::get()
+        .and(routes)
+        .with(warp::cors().allow_any_origin().allow_method("GET"));
+
+    match warp::serve(routes).try_bind_ephemeral(listen) {
+        Ok((_, server)) => server.await,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+//! Pieces shared by the QF services
+
+pub mod metrics;
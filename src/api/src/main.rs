@@ -0,0 +1,252 @@
+//! gRPC service serving the typed data of QF rounds, read over RPC, so backends in any
+//! language can integrate without decoding account layouts
+
+use clap::{crate_version, App, Arg};
+use qf::state::{Round, VoterRegistry};
+use qf_cli::{
+    accounts, export,
+    subscribe::{self, Update},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, program_pack::Pack, pubkey::Pubkey};
+use std::{error::Error, process::exit, str::FromStr, sync::Arc};
+use tokio::{
+    sync::mpsc::{channel, unbounded_channel},
+    task,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{transport::Server, Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("qf");
+}
+
+use proto::qf_server::{Qf, QfServer};
+
+/// updates a StreamVotes client can fall behind by before the stream waits for it
+const STREAM_BUFFER: usize = 64;
+
+struct Service {
+    rpc: Arc<RpcClient>,
+    ws_url: String,
+    program_id: Pubkey,
+}
+
+impl Service {
+    /// Runs `f` with the RPC client on the blocking pool, RpcClient is synchronous
+    async fn rpc<T, F>(&self, f: F) -> Result<T, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&RpcClient, &Pubkey) -> Result<T, Box<dyn Error>> + Send + 'static,
+    {
+        let rpc = self.rpc.clone();
+        let program_id = self.program_id;
+        task::spawn_blocking(move || f(&rpc, &program_id).map_err(|err| err.to_string()))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .map_err(Status::unavailable)
+    }
+}
+
+fn pubkey(name: &str, value: &str) -> Result<Pubkey, Status> {
+    Pubkey::from_str(value)
+        .map_err(|_| Status::invalid_argument(format!("invalid {}: {}", name, value)))
+}
+
+fn round_message(key: &Pubkey, round: &Round) -> proto::Round {
+    proto::Round {
+        address: key.to_string(),
+        status: format!("{:?}", round.status),
+        owner: round.owner.to_string(),
+        mint: round.mint.to_string(),
+        vault: round.vault.to_string(),
+        fund: round.fund,
+        votes: round.votes,
+        voters: round.voters,
+        project_count: round.project_count,
+        voting_started_at: round.voting_started_at,
+        voting_ended_at: round.voting_ended_at,
+        voting_end: round.config.voting_end,
+        previous_round: round.previous_round.to_string(),
+        next_round: round.next_round.to_string(),
+    }
+}
+
+#[tonic::async_trait]
+impl Qf for Service {
+    async fn get_round(
+        &self,
+        request: Request<proto::GetRoundRequest>,
+    ) -> Result<Response<proto::Round>, Status> {
+        let key = pubkey("round", &request.get_ref().round)?;
+        let round = self.rpc(move |rpc, _| accounts::round(rpc, &key)).await?;
+        Ok(Response::new(round_message(&key, &round)))
+    }
+
+    async fn list_projects(
+        &self,
+        request: Request<proto::ListProjectsRequest>,
+    ) -> Result<Response<proto::ListProjectsResponse>, Status> {
+        let key = pubkey("round", &request.get_ref().round)?;
+        let (_, results) = self
+            .rpc(move |rpc, program_id| export::results(rpc, program_id, &key))
+            .await?;
+        let projects = results
+            .into_iter()
+            .map(|result| proto::Project {
+                address: result.project.to_string(),
+                owner: result.owner.to_string(),
+                category: result.category as u32,
+                votes: result.contributions,
+                contributors: result.contributors,
+                area: result.area,
+                payout: result.allocation.payout,
+                banned: result.banned,
+                withdrawn: result.allocation.withdrawn,
+            })
+            .collect();
+        Ok(Response::new(proto::ListProjectsResponse { projects }))
+    }
+
+    async fn get_voter(
+        &self,
+        request: Request<proto::GetVoterRequest>,
+    ) -> Result<Response<proto::Voter>, Status> {
+        let round = pubkey("round", &request.get_ref().round)?;
+        let wallet = pubkey("wallet", &request.get_ref().wallet)?;
+        let registry = self
+            .rpc(move |rpc, program_id| {
+                let (key, _) = Pubkey::find_program_address(
+                    &[b"registry", &round.to_bytes(), &wallet.to_bytes()],
+                    program_id,
+                );
+                match rpc
+                    .get_account_with_commitment(&key, CommitmentConfig::default())?
+                    .value
+                {
+                    Some(account) => Ok(Some(VoterRegistry::unpack(&account.data)?)),
+                    None => Ok(None),
+                }
+            })
+            .await?
+            .ok_or_else(|| Status::not_found("the wallet did not vote in the round"))?;
+        Ok(Response::new(proto::Voter {
+            round: registry.round.to_string(),
+            wallet: registry.wallet.to_string(),
+            projects: registry.projects,
+            votes: registry.votes,
+            weight: registry.weight,
+            last_vote_at: registry.last_vote_at,
+            downvotes: registry.downvotes,
+        }))
+    }
+
+    type StreamVotesStream = ReceiverStream<Result<proto::VoteUpdate, Status>>;
+
+    async fn stream_votes(
+        &self,
+        request: Request<proto::StreamVotesRequest>,
+    ) -> Result<Response<Self::StreamVotesStream>, Status> {
+        let key = pubkey("round", &request.get_ref().round)?;
+        let ws_url = self.ws_url.clone();
+        // the subscriptions stop once the receiver of the updates is dropped, which
+        // happens when the client goes away and the send below fails
+        let (updates, mut receiver) = unbounded_channel();
+        self.rpc(move |rpc, program_id| {
+            for (project, _) in accounts::projects(rpc, program_id, &key)? {
+                subscribe::subscribe_project(&ws_url, &project, updates.clone())?;
+            }
+            Ok(())
+        })
+        .await?;
+
+        let (sender, stream) = channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            while let Some(update) = receiver.recv().await {
+                if let Update::Project { slot, key, project } = update {
+                    let update = proto::VoteUpdate {
+                        slot,
+                        project: key.to_string(),
+                        votes: project.votes,
+                        downvotes: project.downvotes,
+                        banned: project.banned,
+                    };
+                    if sender.send(Ok(update)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(stream)))
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let matches = App::new("qf-api")
+        .about("Serve the rounds of a QF program over gRPC")
+        .version(crate_version!())
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("JSON RPC URL of the cluster"),
+        )
+        .arg(
+            Arg::with_name("ws")
+                .long("ws")
+                .value_name("URL")
+                .takes_value(true)
+                .help("Websocket URL of the cluster, derived from --url by default"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .help("Address the QF program is deployed at"),
+        )
+        .arg(
+            Arg::with_name("listen")
+                .long("listen")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .default_value("127.0.0.1:50051")
+                .help("Address the gRPC server listens on"),
+        )
+        .get_matches();
+
+    let url = matches.value_of("url").unwrap().to_string();
+    let ws_url = matches
+        .value_of("ws")
+        .map(str::to_string)
+        .unwrap_or_else(|| subscribe::websocket_url(&url));
+    let (program_id, listen) = match (
+        Pubkey::from_str(matches.value_of("program_id").unwrap()),
+        matches.value_of("listen").unwrap().parse(),
+    ) {
+        (Ok(program_id), Ok(listen)) => (program_id, listen),
+        _ => {
+            eprintln!("error: invalid program id or listen address");
+            exit(1);
+        }
+    };
+
+    let service = Service {
+        rpc: Arc::new(RpcClient::new(url)),
+        ws_url,
+        program_id,
+    };
+    if let Err(err) = Server::builder()
+        .add_service(QfServer::new(service))
+        .serve(listen)
+        .await
+    {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
@@ -0,0 +1,31 @@
+//! Prometheus metrics of the long running services, served on /metrics
+
+use prometheus::{Encoder, Registry, TextEncoder};
+use std::{error::Error, net::SocketAddr, thread};
+use tokio::runtime::Runtime;
+use warp::Filter;
+
+/// Serves the metrics of `registry` on `listen` from a thread with its own runtime, the
+/// services themselves are synchronous RPC loops. Fails if `listen` can not be bound.
+pub fn serve(listen: SocketAddr, registry: Registry) -> Result<(), Box<dyn Error>> {
+    let runtime = Runtime::new()?;
+    let metrics = warp::path!("metrics")
+        .and(warp::get())
+        .map(move || encode(&registry));
+    let (_, server) = {
+        let _guard = runtime.enter();
+        warp::serve(metrics).try_bind_ephemeral(listen)?
+    };
+    thread::spawn(move || runtime.block_on(server));
+    Ok(())
+}
+
+/// The metrics in the Prometheus text format
+fn encode(registry: &Registry) -> String {
+    let mut buffer = vec![];
+    // encoding into a Vec can not fail
+    TextEncoder::new()
+        .encode(&registry.gather(), &mut buffer)
+        .unwrap();
+    String::from_utf8(buffer).unwrap()
+}
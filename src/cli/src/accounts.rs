@@ -0,0 +1,98 @@
+//! Fetching the accounts of a round over RPC
+
+use qf::state::{Config, Discriminator, Project, Round, Voter, VoterRegistry};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_sdk::{
+    bs58,
+    commitment_config::CommitmentConfig,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+use std::{collections::HashMap, error::Error};
+
+pub fn round(rpc: &RpcClient, round_key: &Pubkey) -> Result<Round, Box<dyn Error>> {
+    let account = rpc.get_account(round_key)?;
+    Ok(Round::unpack(&account.data)?)
+}
+
+/// Accounts of the program that start with the discriminator of `T`
+pub fn program_accounts<T: Pack + IsInitialized + Discriminator>(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<Vec<(Pubkey, T)>, Box<dyn Error>> {
+    let accounts = rpc.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(T::LEN as u64),
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: 0,
+                    bytes: MemcmpEncodedBytes::Binary(bs58::encode(T::DISCRIMINATOR).into_string()),
+                    encoding: None,
+                }),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(key, account)| Some((key, T::unpack(&account.data).ok()?)))
+        .collect())
+}
+
+/// Projects of the round sorted by address, found with getProgramAccounts
+pub fn projects(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    round_key: &Pubkey,
+) -> Result<Vec<(Pubkey, Project)>, Box<dyn Error>> {
+    let mut projects = program_accounts::<Project>(rpc, program_id)?;
+    projects.retain(|(_, project)| project.round == *round_key);
+    projects.sort_by_key(|(key, _)| *key);
+    Ok(projects)
+}
+
+/// Wallets that voted in the round, from their voter registries
+pub fn voter_wallets(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    round_key: &Pubkey,
+) -> Result<Vec<Pubkey>, Box<dyn Error>> {
+    Ok(program_accounts::<VoterRegistry>(rpc, program_id)?
+        .into_iter()
+        .filter(|(_, registry)| registry.round == *round_key)
+        .map(|(_, registry)| registry.wallet)
+        .collect())
+}
+
+/// Every voter account of the program by address
+pub fn voters(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<HashMap<Pubkey, Voter>, Box<dyn Error>> {
+    Ok(program_accounts::<Voter>(rpc, program_id)?
+        .into_iter()
+        .collect())
+}
+
+/// The program config, the default config if it was never initialized
+pub fn config(rpc: &RpcClient, program_id: &Pubkey) -> Result<Config, Box<dyn Error>> {
+    let (config_key, _) = Pubkey::find_program_address(&[b"config"], program_id);
+    match rpc
+        .get_account_with_commitment(&config_key, CommitmentConfig::default())?
+        .value
+    {
+        Some(account) => Ok(Config::unpack(&account.data)?),
+        None => Ok(Config::default()),
+    }
+}
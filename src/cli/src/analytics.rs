@@ -0,0 +1,224 @@
+//! Stats across the rounds of an operator, for grant programs that report on several
+//! seasons
+
+use crate::accounts::program_accounts;
+use qf::state::{Claim, Project, Round, RoundStatus, VoterRegistry};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+};
+
+/// One round of a season
+pub struct RoundStats {
+    pub round: Pubkey,
+    pub status: RoundStatus,
+    pub fund: u64,
+    pub votes: u64,
+    pub voters: u64,
+    pub projects: usize,
+    /// payouts written to claims when the round was finalized, before fees
+    pub allocated: u64,
+    /// the part of `allocated` projects withdrew
+    pub distributed: u64,
+}
+
+/// A project in one of the rounds
+pub struct ProjectSeason {
+    pub round: Pubkey,
+    pub project: Pubkey,
+    pub votes: u64,
+    pub allocated: u64,
+    pub claimed: bool,
+    pub banned: bool,
+}
+
+pub struct SeasonStats {
+    /// linked rounds in order, chains that are not linked follow each other
+    pub rounds: Vec<RoundStats>,
+    pub total_distributed: u64,
+    /// wallets that voted in any of the rounds
+    pub donors: usize,
+    /// wallets that voted in more than one round
+    pub repeat_donors: usize,
+    /// projects by owner, in round order
+    pub projects: BTreeMap<Pubkey, Vec<ProjectSeason>>,
+}
+
+impl SeasonStats {
+    /// Share of the donors that came back for another round
+    pub fn repeat_donor_rate(&self) -> f64 {
+        if self.donors == 0 {
+            return 0.0;
+        }
+        self.repeat_donors as f64 / self.donors as f64
+    }
+}
+
+/// Rounds owned by `operator`, each chain of linked rounds from its first round to its
+/// last, chains in the order their first round started voting
+pub fn linked_rounds(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    operator: &Pubkey,
+) -> Result<Vec<(Pubkey, Round)>, Box<dyn Error>> {
+    let rounds: HashMap<Pubkey, Round> = program_accounts::<Round>(rpc, program_id)?
+        .into_iter()
+        .filter(|(_, round)| round.owner == *operator)
+        .collect();
+
+    // a chain starts at a round whose previous round is not one of the operator's
+    let mut firsts: Vec<(&Pubkey, &Round)> = rounds
+        .iter()
+        .filter(|(_, round)| !rounds.contains_key(&round.previous_round))
+        .collect();
+    firsts.sort_by_key(|(key, round)| (round.voting_started_at, **key));
+
+    let mut ordered = vec![];
+    let mut seen = HashSet::new();
+    for (first, _) in firsts {
+        let mut key = *first;
+        while let Some(round) = rounds.get(&key) {
+            if !seen.insert(key) {
+                break;
+            }
+            ordered.push((key, *round));
+            key = round.next_round;
+        }
+    }
+    Ok(ordered)
+}
+
+/// Stats of every round `operator` owns
+pub fn season_stats(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    operator: &Pubkey,
+) -> Result<SeasonStats, Box<dyn Error>> {
+    let rounds = linked_rounds(rpc, program_id, operator)?;
+    let round_keys: HashSet<Pubkey> = rounds.iter().map(|(key, _)| *key).collect();
+
+    let mut projects_of: HashMap<Pubkey, Vec<(Pubkey, Project)>> = HashMap::new();
+    for (key, project) in program_accounts::<Project>(rpc, program_id)? {
+        if round_keys.contains(&project.round) {
+            projects_of
+                .entry(project.round)
+                .or_default()
+                .push((key, project));
+        }
+    }
+    let claims: HashMap<Pubkey, Claim> = program_accounts::<Claim>(rpc, program_id)?
+        .into_iter()
+        .map(|(_, claim)| (claim.project, claim))
+        .collect();
+
+    // rounds each wallet voted in
+    let mut rounds_of: HashMap<Pubkey, usize> = HashMap::new();
+    for (_, registry) in program_accounts::<VoterRegistry>(rpc, program_id)? {
+        if round_keys.contains(&registry.round) && registry.votes > 0 {
+            *rounds_of.entry(registry.wallet).or_default() += 1;
+        }
+    }
+
+    let mut stats = SeasonStats {
+        rounds: vec![],
+        total_distributed: 0,
+        donors: rounds_of.len(),
+        repeat_donors: rounds_of.values().filter(|rounds| **rounds > 1).count(),
+        projects: BTreeMap::new(),
+    };
+    for (round_key, round) in rounds {
+        let mut round_stats = RoundStats {
+            round: round_key,
+            status: round.status,
+            fund: round.fund,
+            votes: round.votes,
+            voters: round.voters,
+            projects: 0,
+            allocated: 0,
+            distributed: 0,
+        };
+        let mut projects = projects_of.remove(&round_key).unwrap_or_default();
+        projects.sort_by_key(|(key, _)| *key);
+        for (key, project) in projects {
+            let claim = claims.get(&key);
+            let allocated = claim.map_or(0, |claim| claim.amount);
+            let claimed = matches!(claim, Some(claim) if claim.claimed);
+            round_stats.projects += 1;
+            round_stats.allocated += allocated;
+            if claimed {
+                round_stats.distributed += allocated;
+            }
+            stats
+                .projects
+                .entry(project.owner)
+                .or_default()
+                .push(ProjectSeason {
+                    round: round_key,
+                    project: key,
+                    votes: project.votes,
+                    allocated,
+                    claimed,
+                    banned: project.banned,
+                });
+        }
+        stats.total_distributed += round_stats.distributed;
+        stats.rounds.push(round_stats);
+    }
+    Ok(stats)
+}
+
+/// Prints the season stats of `operator`
+pub fn print_season_stats(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    operator: &Pubkey,
+) -> Result<(), Box<dyn Error>> {
+    let stats = season_stats(rpc, program_id, operator)?;
+
+    println!(
+        "{:<44}  {:<12}  {:>16}  {:>16}  {:>8}  {:>8}  {:>16}  {:>16}",
+        "round", "status", "fund", "votes", "voters", "projects", "allocated", "distributed"
+    );
+    for round in stats.rounds.iter() {
+        // the key and status are padded as strings, their own formatting ignores the width
+        let (key, status) = (round.round.to_string(), format!("{:?}", round.status));
+        println!(
+            "{:<44}  {:<12}  {:>16}  {:>16}  {:>8}  {:>8}  {:>16}  {:>16}",
+            key,
+            status,
+            round.fund,
+            round.votes,
+            round.voters,
+            round.projects,
+            round.allocated,
+            round.distributed
+        );
+    }
+    println!();
+    println!("total distributed {}", stats.total_distributed);
+    println!(
+        "donors {}  repeat donors {} ({:.1}%)",
+        stats.donors,
+        stats.repeat_donors,
+        stats.repeat_donor_rate() * 100.0
+    );
+
+    println!();
+    for (owner, seasons) in stats.projects.iter() {
+        println!("owner {}", owner);
+        for season in seasons {
+            println!(
+                "  round {}  project {}  votes {}  allocated {}{}{}",
+                season.round,
+                season.project,
+                season.votes,
+                season.allocated,
+                if season.claimed { "  claimed" } else { "" },
+                if season.banned { "  banned" } else { "" }
+            );
+        }
+    }
+    Ok(())
+}
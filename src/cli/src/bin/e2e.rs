@@ -0,0 +1,443 @@
+//! Runs randomized rounds against a fresh `solana-test-validator` and checks that no
+//! token is created or lost on the way. Build the program with `cargo build-bpf` first.
+
+use clap::{crate_version, value_t, App, Arg};
+use qf::{
+    instruction,
+    state::{Round, RoundConfig, RoundStatus},
+};
+use qf_cli::scaffold::{create_mint, create_round, create_token_account, register_project, send};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::sol_to_lamports,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Account;
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    process::{exit, Child, Command, Stdio},
+    thread::sleep,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const DECIMALS: u8 = 6;
+
+/// Kills the validator when the test ends, whatever way it ends
+struct Validator(Child);
+
+impl Validator {
+    fn start(
+        bin: &str,
+        port: u16,
+        program_id: &Pubkey,
+        program: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let ledger = env::temp_dir().join(format!("qf-e2e-{}", port));
+        let child = Command::new(bin)
+            .arg("--reset")
+            .arg("--quiet")
+            .arg("--rpc-port")
+            .arg(port.to_string())
+            .arg("--ledger")
+            .arg(&ledger)
+            .arg("--bpf-program")
+            .arg(program_id.to_string())
+            .arg(program)
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("can not start {}: {}", bin, err))?;
+        Ok(Self(child))
+    }
+}
+
+impl Drop for Validator {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+struct Args {
+    rounds: usize,
+    voters: usize,
+    projects: usize,
+    seed: u64,
+}
+
+fn main() {
+    let matches = App::new("e2e")
+        .about("Drive randomized rounds on a local validator and check token conservation")
+        .version(crate_version!())
+        .arg(
+            Arg::with_name("program")
+                .long("program")
+                .value_name("PATH")
+                .takes_value(true)
+                .default_value("../../target/deploy/qf.so")
+                .help("BPF build of the program"),
+        )
+        .arg(
+            Arg::with_name("validator")
+                .long("validator")
+                .value_name("PATH")
+                .takes_value(true)
+                .default_value("solana-test-validator")
+                .help("Validator binary"),
+        )
+        .arg(
+            Arg::with_name("port")
+                .long("port")
+                .value_name("PORT")
+                .takes_value(true)
+                .default_value("8899")
+                .help("JSON RPC port of the validator"),
+        )
+        .arg(
+            Arg::with_name("rounds")
+                .long("rounds")
+                .value_name("N")
+                .takes_value(true)
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("voters")
+                .long("voters")
+                .value_name("N")
+                .takes_value(true)
+                .default_value("200"),
+        )
+        .arg(
+            Arg::with_name("projects")
+                .long("projects")
+                .value_name("N")
+                .takes_value(true)
+                .default_value("5"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .help("Seed of the scenario, random by default"),
+        )
+        .get_matches();
+
+    let seed = match matches.value_of("seed") {
+        Some(_) => value_t!(matches, "seed", u64).unwrap_or_else(|err| err.exit()),
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    let args = Args {
+        rounds: value_t!(matches, "rounds", usize).unwrap_or_else(|err| err.exit()),
+        voters: value_t!(matches, "voters", usize).unwrap_or_else(|err| err.exit()),
+        projects: value_t!(matches, "projects", usize).unwrap_or_else(|err| err.exit()),
+        seed,
+    };
+    let port = value_t!(matches, "port", u16).unwrap_or_else(|err| err.exit());
+
+    // printed first so a failing scenario can be replayed
+    println!("seed {}", args.seed);
+    let program_id = Keypair::new().pubkey();
+    let result = Validator::start(
+        matches.value_of("validator").unwrap(),
+        port,
+        &program_id,
+        matches.value_of("program").unwrap(),
+    )
+    .and_then(|_validator| {
+        let rpc = RpcClient::new(format!("http://127.0.0.1:{}", port));
+        wait_for(&rpc)?;
+        run(&rpc, &program_id, &args)
+    });
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+    println!("ok");
+}
+
+fn wait_for(rpc: &RpcClient) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    while rpc.get_version().is_err() {
+        if start.elapsed() > Duration::from_secs(60) {
+            return Err("the validator did not start".into());
+        }
+        sleep(Duration::from_millis(500));
+    }
+    Ok(())
+}
+
+fn run(rpc: &RpcClient, program_id: &Pubkey, args: &Args) -> Result<(), Box<dyn Error>> {
+    let payer = Keypair::new();
+    let signature = rpc.request_airdrop(&payer.pubkey(), sol_to_lamports(1_000.0))?;
+    rpc.poll_for_signature(&signature)?;
+
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    for i in 0..args.rounds {
+        run_round(rpc, program_id, &payer, &mut rng, args)
+            .map_err(|err| format!("round {}: {}", i, err))?;
+        println!("round {} ok", i);
+    }
+    Ok(())
+}
+
+struct VoterWallet {
+    wallet: Keypair,
+    from: Pubkey,
+    initial: u64,
+    balance: u64,
+    /// projects it voted for
+    projects: Vec<Pubkey>,
+}
+
+/// One round: votes from every voter, maybe a ban, then payouts, or refunds when the
+/// quorum is missed on purpose
+fn run_round(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    rng: &mut StdRng,
+    args: &Args,
+) -> Result<(), Box<dyn Error>> {
+    let unit = 10u64.pow(DECIMALS as u32);
+    let cancel = rng.gen_bool(0.3);
+    let config = RoundConfig {
+        min_unique_voters: if cancel { args.voters as u64 + 1 } else { 0 },
+        ..RoundConfig::default()
+    };
+
+    let mint = create_mint(rpc, payer, DECIMALS)?;
+    let fund = rng.gen_range(1_000, 100_000) * unit;
+    let (round_key, vault) = create_round(rpc, program_id, payer, &mint, fund, config)?;
+    let mut accounts = vec![vault];
+
+    let mut projects = vec![];
+    for _ in 0..args.projects {
+        projects.push(register_project(rpc, program_id, payer, &round_key)?);
+    }
+    let project_keys: Vec<Pubkey> = projects.iter().map(|(key, _)| *key).collect();
+
+    let mut voters = vec![];
+    for _ in 0..args.voters {
+        let wallet = Keypair::new();
+        let balance = rng.gen_range(1, 1_000) * unit;
+        let from = create_token_account(rpc, payer, &mint, &wallet.pubkey(), balance)?;
+        accounts.push(from);
+        voters.push(VoterWallet {
+            wallet,
+            from,
+            initial: balance,
+            balance,
+            projects: vec![],
+        });
+    }
+    check_supply(rpc, &mint, &accounts)?;
+
+    send(
+        rpc,
+        payer,
+        &[instruction::start_voting(
+            program_id,
+            &round_key,
+            &payer.pubkey(),
+        )],
+        &[],
+    )?;
+
+    // the registry of the wallet in the round and its voter for the project
+    let derive = |wallet: &Pubkey, project: &Pubkey| {
+        (
+            Pubkey::find_program_address(
+                &[b"registry", &round_key.to_bytes(), &wallet.to_bytes()],
+                program_id,
+            )
+            .0,
+            Pubkey::find_program_address(&[&project.to_bytes(), &wallet.to_bytes()], program_id).0,
+        )
+    };
+    for voter in voters.iter_mut() {
+        let count = rng.gen_range(1, 4.min(project_keys.len() + 1));
+        for project in project_keys.choose_multiple(rng, count) {
+            if voter.balance == 0 {
+                break;
+            }
+            let amount = rng
+                .gen_range(1, voter.balance / count as u64 + 2)
+                .min(voter.balance);
+            let wallet = voter.wallet.pubkey();
+            let (registry, voter_key) = derive(&wallet, project);
+            send(
+                rpc,
+                payer,
+                &[
+                    instruction::init_voter(
+                        program_id,
+                        &round_key,
+                        project,
+                        &wallet,
+                        &payer.pubkey(),
+                    ),
+                    instruction::vote(
+                        program_id,
+                        &round_key,
+                        project,
+                        &voter_key,
+                        &voter.from,
+                        &mint,
+                        &vault,
+                        &wallet,
+                        &registry,
+                        amount,
+                        DECIMALS,
+                    ),
+                ],
+                &[&voter.wallet],
+            )?;
+            voter.balance -= amount;
+            voter.projects.push(*project);
+        }
+    }
+    check_supply(rpc, &mint, &accounts)?;
+
+    if rng.gen_bool(0.5) {
+        let banned = project_keys.choose(rng).unwrap();
+        send(
+            rpc,
+            payer,
+            &[instruction::ban_project(
+                program_id,
+                &round_key,
+                &payer.pubkey(),
+                banned,
+            )],
+            &[],
+        )?;
+    }
+
+    send(
+        rpc,
+        payer,
+        &[instruction::end_round(
+            program_id,
+            &round_key,
+            &payer.pubkey(),
+        )],
+        &[],
+    )?;
+    send(
+        rpc,
+        payer,
+        &[instruction::finalize(
+            program_id,
+            &round_key,
+            &payer.pubkey(),
+            &project_keys,
+        )],
+        &[],
+    )?;
+
+    let round = Round::unpack(&rpc.get_account(&round_key)?.data)?;
+    match round.status {
+        RoundStatus::Cancelled if cancel => {
+            for voter in voters.iter() {
+                for project in voter.projects.iter() {
+                    send(
+                        rpc,
+                        payer,
+                        &[instruction::refund(
+                            program_id,
+                            &round_key,
+                            &payer.pubkey(),
+                            &vault,
+                            project,
+                            &voter.from,
+                            &voter.wallet.pubkey(),
+                        )],
+                        &[&voter.wallet],
+                    )?;
+                }
+            }
+            check_supply(rpc, &mint, &accounts)?;
+            // every voter has what it started with, the vault only the matching fund
+            let balances = balances(rpc, &accounts)?;
+            if let Some(voter) = voters
+                .iter()
+                .find(|voter| balances[&voter.from] != voter.initial)
+            {
+                return Err(format!("{} not refunded", voter.wallet.pubkey()).into());
+            }
+            if balances[&vault] != fund {
+                return Err(format!(
+                    "vault holds {} after refunds, not {}",
+                    balances[&vault], fund
+                )
+                .into());
+            }
+        }
+        RoundStatus::Claim if !cancel => {
+            for (project, owner) in projects.iter() {
+                send(
+                    rpc,
+                    payer,
+                    &[instruction::withdraw(
+                        program_id,
+                        &round_key,
+                        &payer.pubkey(),
+                        &vault,
+                        &mint,
+                        project,
+                        &owner.pubkey(),
+                        &owner.pubkey(),
+                    )],
+                    &[owner],
+                )?;
+                accounts.push(get_associated_token_address(&owner.pubkey(), &mint));
+            }
+            check_supply(rpc, &mint, &accounts)?;
+            // what is left in the vault covers the fees and the rounding dust
+            let round = Round::unpack(&rpc.get_account(&round_key)?.data)?;
+            let left = balances(rpc, &[vault])?[&vault];
+            if left < round.fees().checked_add(round.fund_remaining).unwrap() {
+                return Err(format!(
+                    "vault holds {} after payouts, fees {} and remaining fund {}",
+                    left,
+                    round.fees(),
+                    round.fund_remaining
+                )
+                .into());
+            }
+        }
+        status => return Err(format!("round is {:?} after Finalize", status).into()),
+    }
+    Ok(())
+}
+
+fn balances(rpc: &RpcClient, keys: &[Pubkey]) -> Result<HashMap<Pubkey, u64>, Box<dyn Error>> {
+    let mut balances = HashMap::new();
+    for key in keys {
+        let account = rpc.get_account(key)?;
+        balances.insert(*key, Account::unpack(&account.data)?.amount);
+    }
+    Ok(balances)
+}
+
+/// Fails unless the accounts hold the whole supply of the mint
+fn check_supply(rpc: &RpcClient, mint: &Pubkey, accounts: &[Pubkey]) -> Result<(), Box<dyn Error>> {
+    let supply = spl_token::state::Mint::unpack(&rpc.get_account(mint)?.data)?.supply;
+    let held = balances(rpc, accounts)?
+        .values()
+        .try_fold(0u64, |sum, amount| sum.checked_add(*amount))
+        .ok_or("balances overflow")?;
+    if held != supply {
+        return Err(format!("accounts hold {} of a supply of {}", held, supply).into());
+    }
+    Ok(())
+}
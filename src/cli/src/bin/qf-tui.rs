@@ -0,0 +1,154 @@
+//! Terminal dashboard of a live round: the project standings, pool size, contributor
+//! counts and time left, redrawn from RPC every few seconds
+
+use clap::{crate_version, value_t, App, Arg};
+use qf::state::{Round, RoundStatus};
+use qf_cli::export::{self, ProjectResult};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    fmt::Write as _,
+    io::{stdout, Write},
+    process::exit,
+    str::FromStr,
+    thread::sleep,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// clears the screen and moves the cursor home
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+fn main() {
+    let matches = App::new("qf-tui")
+        .about("Follow the standings of a round in the terminal")
+        .version(crate_version!())
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("JSON RPC URL of the cluster"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .help("Address the QF program is deployed at"),
+        )
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value("5")
+                .help("Seconds between refreshes"),
+        )
+        .arg(
+            Arg::with_name("round")
+                .value_name("ROUND")
+                .required(true)
+                .help("Address of the round"),
+        )
+        .get_matches();
+
+    let rpc = RpcClient::new(matches.value_of("url").unwrap().to_string());
+    let interval = value_t!(matches, "interval", u64).unwrap_or_else(|err| err.exit());
+    let (program_id, round_key) = match (
+        Pubkey::from_str(matches.value_of("program_id").unwrap()),
+        Pubkey::from_str(matches.value_of("round").unwrap()),
+    ) {
+        (Ok(program_id), Ok(round_key)) => (program_id, round_key),
+        _ => {
+            eprintln!("error: invalid address");
+            exit(1);
+        }
+    };
+
+    // a failed refresh is shown and retried, the cluster may only be lagging
+    loop {
+        let frame = match export::results(&rpc, &program_id, &round_key) {
+            Ok((round, results)) => render(&round_key, &round, results),
+            Err(err) => format!("error: {}\n", err),
+        };
+        print!("{}{}", CLEAR, frame);
+        let _ = stdout().flush();
+        sleep(Duration::from_secs(interval));
+    }
+}
+
+fn render(round_key: &Pubkey, round: &Round, mut results: Vec<ProjectResult>) -> String {
+    let mut frame = String::new();
+    let _ = writeln!(frame, "round {}  {:?}", round_key, round.status);
+    let _ = writeln!(
+        frame,
+        "pool {}  votes {}  voters {}  projects {}",
+        round.fund,
+        round.votes,
+        round.voters,
+        results.len()
+    );
+    let _ = writeln!(frame, "{}", time_left(round));
+    let _ = writeln!(frame);
+
+    results.sort_by(|a, b| {
+        (b.allocation.payout, b.contributions).cmp(&(a.allocation.payout, a.contributions))
+    });
+    let _ = writeln!(
+        frame,
+        "{:>4}  {:<44}  {:>16}  {:>12}  {:>16}  {:>16}",
+        "#", "project", "votes", "contributors", "match", "payout"
+    );
+    for (rank, result) in results.iter().enumerate() {
+        let payout = &result.allocation;
+        let state = if result.banned {
+            "  banned"
+        } else if payout.withdrawn {
+            "  withdrawn"
+        } else {
+            ""
+        };
+        // a key ignores the width, so it is padded as a string
+        let project = result.project.to_string();
+        let _ = writeln!(
+            frame,
+            "{:>4}  {:<44}  {:>16}  {:>12}  {:>16}  {:>16}{}",
+            rank + 1,
+            project,
+            result.contributions,
+            result.contributors,
+            payout.payout.saturating_sub(payout.votes),
+            payout.total(),
+            state
+        );
+    }
+    frame
+}
+
+/// What is left of the voting, or of the round once voting ended
+fn time_left(round: &Round) -> String {
+    match round.status {
+        RoundStatus::Registration => "registration open".to_string(),
+        RoundStatus::Voting if round.config.voting_end == 0 => {
+            "voting open, ended by the owner".to_string()
+        }
+        RoundStatus::Voting => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            let left = (round.config.voting_end - now).max(0);
+            format!(
+                "voting ends in {}d {:02}:{:02}:{:02}",
+                left / 86_400,
+                left % 86_400 / 3_600,
+                left % 3_600 / 60,
+                left % 60
+            )
+        }
+        status => format!("voting ended, round {:?}", status),
+    }
+}
@@ -0,0 +1,151 @@
+//! Results of a round for reporting, as CSV or JSON
+
+use crate::{accounts, simulate};
+use qf::state::{AppealStatus, Round};
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::error::Error;
+
+/// Contributions, contributors, area and payout of a project
+pub struct ProjectResult {
+    pub project: Pubkey,
+    pub owner: Pubkey,
+    pub category: u8,
+    pub contributions: u64,
+    /// wallets whose voter account for the project holds votes
+    pub contributors: u64,
+    pub area: String,
+    pub allocation: simulate::Allocation,
+    pub banned: bool,
+    pub appeal: AppealStatus,
+    /// registration deposit forfeited by the ban
+    pub banned_deposit: u64,
+}
+
+pub fn export_round(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    round_key: &Pubkey,
+    format: &str,
+) -> Result<(), Box<dyn Error>> {
+    let (round, results) = results(rpc, program_id, round_key)?;
+    match format {
+        "csv" => print_csv(&results),
+        "json" => print_json(round_key, &round, &results)?,
+        _ => return Err(format!("unknown format: {}", format).into()),
+    }
+    Ok(())
+}
+
+/// The round and the results of its projects, sorted by address
+pub fn results(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    round_key: &Pubkey,
+) -> Result<(Round, Vec<ProjectResult>), Box<dyn Error>> {
+    let round = accounts::round(rpc, round_key)?;
+    let config = accounts::config(rpc, program_id)?;
+    let projects = accounts::projects(rpc, program_id, round_key)?;
+    let wallets = accounts::voter_wallets(rpc, program_id, round_key)?;
+    let voters = accounts::voters(rpc, program_id)?;
+    let allocations = simulate::allocations(round_key, &round, &config, &projects);
+
+    let results = projects
+        .iter()
+        .zip(allocations)
+        .map(|((key, project), allocation)| {
+            let contributors = wallets
+                .iter()
+                .filter(|wallet| {
+                    let (voter, _) = Pubkey::find_program_address(
+                        &[&key.to_bytes(), &wallet.to_bytes()],
+                        program_id,
+                    );
+                    matches!(voters.get(&voter), Some(voter) if voter.votes > 0)
+                })
+                .count() as u64;
+            ProjectResult {
+                project: *key,
+                owner: project.owner,
+                category: project.category,
+                contributions: project.votes,
+                contributors,
+                area: project.area.to_string(),
+                allocation,
+                banned: project.banned,
+                appeal: project.appeal,
+                banned_deposit: project.banned_deposit,
+            }
+        })
+        .collect();
+    Ok((round, results))
+}
+
+fn print_csv(results: &[ProjectResult]) {
+    println!("project,owner,category,contributions,contributors,area,payout,operator_fee,protocol_fee,deposit,total,banned,withdrawn");
+    for result in results {
+        let allocation = &result.allocation;
+        println!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            result.project,
+            result.owner,
+            result.category,
+            result.contributions,
+            result.contributors,
+            result.area,
+            allocation.payout,
+            allocation.operator_fee,
+            allocation.protocol_fee,
+            allocation.deposit,
+            allocation.total(),
+            result.banned,
+            allocation.withdrawn,
+        );
+    }
+}
+
+fn print_json(
+    round_key: &Pubkey,
+    round: &Round,
+    results: &[ProjectResult],
+) -> Result<(), Box<dyn Error>> {
+    let mut report = round_json(round_key, round);
+    report["projects"] = results.iter().map(project_json).collect();
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+// amounts are strings, the area does not fit in a JSON number and the others may not
+
+/// The round as it is reported in JSON
+pub fn round_json(round_key: &Pubkey, round: &Round) -> Value {
+    json!({
+        "round": round_key.to_string(),
+        "status": format!("{:?}", round.status),
+        "mint": round.mint.to_string(),
+        "fund": round.fund.to_string(),
+        "votes": round.votes.to_string(),
+        "voters": round.voters,
+    })
+}
+
+/// The result of a project as it is reported in JSON
+pub fn project_json(result: &ProjectResult) -> Value {
+    let allocation = &result.allocation;
+    json!({
+        "project": result.project.to_string(),
+        "owner": result.owner.to_string(),
+        "category": result.category,
+        "contributions": result.contributions.to_string(),
+        "contributors": result.contributors,
+        "area": result.area,
+        "payout": allocation.payout.to_string(),
+        "operator_fee": allocation.operator_fee.to_string(),
+        "protocol_fee": allocation.protocol_fee.to_string(),
+        "deposit": allocation.deposit.to_string(),
+        "total": allocation.total().to_string(),
+        "banned": result.banned,
+        "withdrawn": allocation.withdrawn,
+    })
+}
@@ -0,0 +1,10 @@
+//! Reading and setting up QF rounds over RPC, shared by the `qf-cli` commands and the
+//! end to end test
+
+pub mod accounts;
+pub mod analytics;
+pub mod export;
+pub mod report;
+pub mod scaffold;
+pub mod simulate;
+pub mod subscribe;
@@ -0,0 +1,234 @@
+//! Command line tools for operators of QF rounds
+
+use clap::{crate_description, crate_name, crate_version, App, AppSettings, Arg, SubCommand};
+use qf_cli::{analytics, export, report, scaffold, simulate, subscribe};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::read_keypair_file};
+use std::{
+    env,
+    error::Error,
+    path::{Path, PathBuf},
+    process::exit,
+    str::FromStr,
+};
+
+fn main() {
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("JSON RPC URL of the cluster"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .help("Address the QF program is deployed at"),
+        )
+        .subcommand(
+            SubCommand::with_name("simulate-payouts")
+                .about("Print what every project of a round would be paid if it ended now")
+                .arg(
+                    Arg::with_name("round")
+                        .value_name("ROUND")
+                        .required(true)
+                        .help("Address of the round"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export-round")
+                .about("Print the contributions, contributors, area and payout of every project")
+                .arg(
+                    Arg::with_name("round")
+                        .value_name("ROUND")
+                        .required(true)
+                        .help("Address of the round"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .takes_value(true)
+                        .possible_values(&["csv", "json"])
+                        .default_value("csv")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("generate-report")
+                .about("Print a summary of a finished round from on-chain data")
+                .arg(
+                    Arg::with_name("round")
+                        .value_name("ROUND")
+                        .required(true)
+                        .help("Address of the round"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .takes_value(true)
+                        .possible_values(&["markdown", "html"])
+                        .default_value("markdown")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("season-stats")
+                .about("Print stats across the linked rounds of an operator")
+                .arg(
+                    Arg::with_name("operator")
+                        .value_name("OPERATOR")
+                        .required(true)
+                        .help("Owner of the rounds"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("scaffold-devnet")
+                .about("Create a test mint, funded voters and a round with sample projects")
+                .arg(
+                    Arg::with_name("keypair")
+                        .long("keypair")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Payer and round owner [default: ~/.config/solana/id.json]"),
+                )
+                .arg(
+                    Arg::with_name("voters")
+                        .long("voters")
+                        .value_name("N")
+                        .takes_value(true)
+                        .default_value("10")
+                        .help("Number of voter wallets to generate"),
+                )
+                .arg(
+                    Arg::with_name("projects")
+                        .long("projects")
+                        .value_name("N")
+                        .takes_value(true)
+                        .default_value("3")
+                        .help("Number of projects to register"),
+                )
+                .arg(
+                    Arg::with_name("tokens")
+                        .long("tokens")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("Whole tokens minted to every voter"),
+                )
+                .arg(
+                    Arg::with_name("airdrop")
+                        .long("airdrop")
+                        .value_name("SOL")
+                        .takes_value(true)
+                        .default_value("2")
+                        .help("SOL airdropped to the payer first, 0 to skip"),
+                )
+                .arg(
+                    Arg::with_name("out_dir")
+                        .long("out-dir")
+                        .value_name("DIR")
+                        .takes_value(true)
+                        .default_value("scaffold")
+                        .help("Directory the generated keypairs are written to"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("watch-round")
+                .about("Print the votes of a round and its projects every time they change")
+                .arg(
+                    Arg::with_name("round")
+                        .value_name("ROUND")
+                        .required(true)
+                        .help("Address of the round"),
+                )
+                .arg(
+                    Arg::with_name("ws")
+                        .long("ws")
+                        .value_name("URL")
+                        .takes_value(true)
+                        .help("Websocket URL of the cluster, derived from --url by default"),
+                ),
+        )
+        .get_matches();
+
+    let url = matches.value_of("url").unwrap().to_string();
+    let rpc = RpcClient::new(url.clone());
+    let result =
+        pubkey_of(&matches, "program_id").and_then(|program_id| match matches.subcommand() {
+            ("simulate-payouts", Some(matches)) => {
+                simulate::simulate_payouts(&rpc, &program_id, &pubkey_of(matches, "round")?)
+            }
+            ("export-round", Some(matches)) => export::export_round(
+                &rpc,
+                &program_id,
+                &pubkey_of(matches, "round")?,
+                matches.value_of("format").unwrap(),
+            ),
+            ("generate-report", Some(matches)) => report::generate_report(
+                &rpc,
+                &program_id,
+                &pubkey_of(matches, "round")?,
+                matches.value_of("format").unwrap(),
+            ),
+            ("season-stats", Some(matches)) => {
+                analytics::print_season_stats(&rpc, &program_id, &pubkey_of(matches, "operator")?)
+            }
+            ("scaffold-devnet", Some(matches)) => {
+                let keypair = match matches.value_of("keypair") {
+                    Some(path) => PathBuf::from(path),
+                    None => PathBuf::from(env::var("HOME")?).join(".config/solana/id.json"),
+                };
+                let payer = read_keypair_file(&keypair)
+                    .map_err(|err| format!("can not read {}: {}", keypair.display(), err))?;
+                scaffold::scaffold_devnet(
+                    &rpc,
+                    &program_id,
+                    &payer,
+                    &scaffold::Scaffold {
+                        voters: value_of(matches, "voters")?,
+                        projects: value_of(matches, "projects")?,
+                        tokens: value_of(matches, "tokens")?,
+                        airdrop: value_of(matches, "airdrop")?,
+                        out_dir: Path::new(matches.value_of("out_dir").unwrap()),
+                    },
+                )
+            }
+            ("watch-round", Some(matches)) => subscribe::watch_round(
+                &rpc,
+                &matches
+                    .value_of("ws")
+                    .map(str::to_string)
+                    .unwrap_or_else(|| subscribe::websocket_url(&url)),
+                &program_id,
+                &pubkey_of(matches, "round")?,
+            ),
+            _ => unreachable!(),
+        });
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
+
+fn pubkey_of(matches: &clap::ArgMatches, name: &str) -> Result<Pubkey, Box<dyn Error>> {
+    let value = matches.value_of(name).unwrap();
+    Pubkey::from_str(value).map_err(|_| format!("invalid {}: {}", name, value).into())
+}
+
+fn value_of<T: FromStr>(matches: &clap::ArgMatches, name: &str) -> Result<T, Box<dyn Error>> {
+    let value = matches.value_of(name).unwrap();
+    value
+        .parse()
+        .map_err(|_| format!("invalid {}: {}", name, value).into())
+}
@@ -0,0 +1,279 @@
+//! A readable summary of a finished round, in Markdown or HTML, from on-chain data only
+
+use crate::{
+    accounts::program_accounts,
+    export::{self, ProjectResult},
+};
+use qf::state::{AppealStatus, Claim, Round, RoundStatus};
+use serde_json::json;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, error::Error};
+
+/// projects listed in the top projects table
+const TOP_PROJECTS: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    fn heading(self, out: &mut String, level: usize, text: &str) {
+        match self {
+            Format::Markdown => out.push_str(&format!("{} {}\n\n", "#".repeat(level), text)),
+            Format::Html => out.push_str(&format!("<h{0}>{1}</h{0}>\n", level, text)),
+        }
+    }
+
+    fn paragraph(self, out: &mut String, text: &str) {
+        match self {
+            Format::Markdown => out.push_str(&format!("{}\n\n", text)),
+            Format::Html => out.push_str(&format!("<p>{}</p>\n", text)),
+        }
+    }
+
+    fn table(self, out: &mut String, headers: &[&str], rows: &[Vec<String>]) {
+        match self {
+            Format::Markdown => {
+                out.push_str(&format!("| {} |\n", headers.join(" | ")));
+                out.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+                for row in rows {
+                    out.push_str(&format!("| {} |\n", row.join(" | ")));
+                }
+                out.push('\n');
+            }
+            Format::Html => {
+                out.push_str("<table>\n<tr>");
+                for header in headers {
+                    out.push_str(&format!("<th>{}</th>", header));
+                }
+                out.push_str("</tr>\n");
+                for row in rows {
+                    out.push_str("<tr>");
+                    for cell in row {
+                        out.push_str(&format!("<td>{}</td>", cell));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</table>\n");
+            }
+        }
+    }
+
+    /// JSON a chart can be drawn from
+    fn data(self, out: &mut String, id: &str, data: &serde_json::Value) {
+        match self {
+            Format::Markdown => out.push_str(&format!("```json\n{}\n```\n\n", data)),
+            Format::Html => out.push_str(&format!(
+                "<script type=\"application/json\" id=\"{}\">{}</script>\n",
+                id, data
+            )),
+        }
+    }
+}
+
+/// What a project was paid or will be paid before fees: its claim once the round is
+/// finalized, as Withdraw pays the claim
+fn payout(result: &ProjectResult, claims: &HashMap<Pubkey, Claim>) -> u64 {
+    claims
+        .get(&result.project)
+        .map_or(result.allocation.payout, |claim| claim.amount)
+}
+
+pub fn generate_report(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    round_key: &Pubkey,
+    format: &str,
+) -> Result<(), Box<dyn Error>> {
+    let format = match format {
+        "markdown" => Format::Markdown,
+        "html" => Format::Html,
+        _ => return Err(format!("unknown format: {}", format).into()),
+    };
+    let (round, mut results) = export::results(rpc, program_id, round_key)?;
+    if !matches!(
+        round.status,
+        RoundStatus::Claim | RoundStatus::Closed | RoundStatus::Cancelled
+    ) {
+        return Err(format!("round is {:?}, it is not finished", round.status).into());
+    }
+    let claims: HashMap<Pubkey, Claim> = program_accounts::<Claim>(rpc, program_id)?
+        .into_iter()
+        .map(|(_, claim)| (claim.project, claim))
+        .collect();
+    results.sort_by_key(|result| std::cmp::Reverse(payout(result, &claims)));
+
+    let mut out = String::new();
+    if format == Format::Html {
+        out.push_str("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\">");
+        out.push_str(&format!(
+            "<title>Round {}</title></head>\n<body>\n",
+            round_key
+        ));
+    }
+    summary(format, &mut out, round_key, &round, &results, &claims);
+    top_projects(format, &mut out, &results, &claims);
+    distribution(format, &mut out, &results, &claims);
+    fees(format, &mut out, &round, &results);
+    banned(format, &mut out, &results);
+    if format == Format::Html {
+        out.push_str("</body>\n</html>\n");
+    }
+    print!("{}", out);
+    Ok(())
+}
+
+fn summary(
+    format: Format,
+    out: &mut String,
+    round_key: &Pubkey,
+    round: &Round,
+    results: &[ProjectResult],
+    claims: &HashMap<Pubkey, Claim>,
+) {
+    let paid: u64 = results.iter().map(|result| payout(result, claims)).sum();
+    format.heading(out, 1, &format!("Round {}", round_key));
+    format.table(
+        out,
+        &["", ""],
+        &[
+            vec!["Status".to_string(), format!("{:?}", round.status)],
+            vec!["Mint".to_string(), round.mint.to_string()],
+            vec!["Matching fund".to_string(), round.fund.to_string()],
+            vec!["Votes".to_string(), round.votes.to_string()],
+            vec!["Voters".to_string(), round.voters.to_string()],
+            vec!["Projects".to_string(), results.len().to_string()],
+            vec!["Paid to projects before fees".to_string(), paid.to_string()],
+        ],
+    );
+    if round.status == RoundStatus::Cancelled {
+        format.paragraph(
+            out,
+            "The round was cancelled, the votes are refunded to the voters and nothing is matched.",
+        );
+    }
+}
+
+fn top_projects(
+    format: Format,
+    out: &mut String,
+    results: &[ProjectResult],
+    claims: &HashMap<Pubkey, Claim>,
+) {
+    format.heading(out, 2, "Top projects");
+    let rows = results
+        .iter()
+        .take(TOP_PROJECTS)
+        .enumerate()
+        .map(|(rank, result)| {
+            let payout = payout(result, claims);
+            vec![
+                (rank + 1).to_string(),
+                result.project.to_string(),
+                result.contributions.to_string(),
+                result.contributors.to_string(),
+                payout.saturating_sub(result.contributions).to_string(),
+                payout.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    format.table(
+        out,
+        &["#", "Project", "Votes", "Contributors", "Match", "Payout"],
+        &rows,
+    );
+}
+
+fn distribution(
+    format: Format,
+    out: &mut String,
+    results: &[ProjectResult],
+    claims: &HashMap<Pubkey, Claim>,
+) {
+    format.heading(out, 2, "Distribution");
+    format.paragraph(
+        out,
+        "Share of the payouts of every project, the data below can be fed to a chart.",
+    );
+    let payouts: Vec<u64> = results
+        .iter()
+        .map(|result| payout(result, claims))
+        .collect();
+    let total: u64 = payouts.iter().sum();
+    // amounts are strings like in export-round, they may not fit in a JSON number
+    let data = json!({
+        "labels": results
+            .iter()
+            .map(|result| result.project.to_string())
+            .collect::<Vec<_>>(),
+        "payouts": payouts.iter().map(u64::to_string).collect::<Vec<_>>(),
+        "shares": payouts
+            .iter()
+            .map(|payout| if total == 0 { 0.0 } else { *payout as f64 / total as f64 })
+            .collect::<Vec<_>>(),
+    });
+    format.data(out, "distribution", &data);
+}
+
+fn fees(format: Format, out: &mut String, round: &Round, results: &[ProjectResult]) {
+    // fees of the projects that withdrew are in the round, the others still to come
+    let pending_operator: u64 = results
+        .iter()
+        .map(|result| result.allocation.operator_fee)
+        .sum();
+    let pending_protocol: u64 = results
+        .iter()
+        .map(|result| result.allocation.protocol_fee)
+        .sum();
+    format.heading(out, 2, "Fees");
+    format.table(
+        out,
+        &["Fee", "Taken", "Still to take"],
+        &[
+            vec![
+                "Operator".to_string(),
+                round.operator_fee.to_string(),
+                pending_operator.to_string(),
+            ],
+            vec![
+                "Protocol".to_string(),
+                round.protocol_fee.to_string(),
+                pending_protocol.to_string(),
+            ],
+        ],
+    );
+}
+
+fn banned(format: Format, out: &mut String, results: &[ProjectResult]) {
+    format.heading(out, 2, "Banned projects");
+    let rows = results
+        .iter()
+        .filter(|result| result.banned)
+        .map(|result| {
+            let reason = match result.appeal {
+                AppealStatus::None => "banned by the round owner, not appealed",
+                AppealStatus::Pending => "banned by the round owner, appeal pending",
+                AppealStatus::Upheld => "banned, the appeal or a flag was upheld",
+                AppealStatus::Overturned => "ban overturned on appeal",
+            };
+            vec![
+                result.project.to_string(),
+                result.owner.to_string(),
+                reason.to_string(),
+                result.banned_deposit.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    if rows.is_empty() {
+        format.paragraph(out, "No project was banned.");
+    } else {
+        format.table(
+            out,
+            &["Project", "Owner", "Reason", "Forfeited deposit"],
+            &rows,
+        );
+    }
+}
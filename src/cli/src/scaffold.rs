@@ -0,0 +1,259 @@
+//! A sandbox round on devnet: a test mint, funded voters and registered projects
+
+use qf::{
+    instruction,
+    state::{Round, RoundConfig},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::sol_to_lamports,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{write_keypair_file, Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::{error::Error, fs, path::Path};
+
+/// SOL each generated voter and project owner gets for fees and rent
+const WALLET_SOL: f64 = 0.1;
+
+/// decimals of the test mint
+const DECIMALS: u8 = 9;
+
+pub struct Scaffold<'a> {
+    pub voters: usize,
+    pub projects: usize,
+    /// whole tokens minted to every voter
+    pub tokens: u64,
+    /// SOL airdropped to the payer first, none when 0
+    pub airdrop: f64,
+    /// directory the generated keypairs are written to
+    pub out_dir: &'a Path,
+}
+
+/// Sets up a round the payer owns and prints the addresses of everything created
+pub fn scaffold_devnet(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    scaffold: &Scaffold,
+) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(scaffold.out_dir)?;
+
+    if scaffold.airdrop > 0.0 {
+        let signature = rpc.request_airdrop(&payer.pubkey(), sol_to_lamports(scaffold.airdrop))?;
+        rpc.poll_for_signature(&signature)?;
+        println!("airdropped {} SOL to {}", scaffold.airdrop, payer.pubkey());
+    }
+
+    let mint = create_mint(rpc, payer, DECIMALS)?;
+    println!("mint {}", mint);
+
+    let (round, vault) = create_round(rpc, program_id, payer, &mint, 0, RoundConfig::default())?;
+    println!("round {}", round);
+    println!("vault {}", vault);
+
+    let amount = scaffold.tokens * 10u64.pow(DECIMALS as u32);
+    for i in 0..scaffold.voters {
+        let voter = wallet(rpc, payer, scaffold.out_dir, &format!("voter-{}.json", i))?;
+        let from = create_token_account(rpc, payer, &mint, &voter, amount)?;
+        println!("voter {} token account {}", voter, from);
+    }
+
+    for i in 0..scaffold.projects {
+        let (project, owner) = register_project(rpc, program_id, payer, &round)?;
+        write_keypair_file(
+            &owner,
+            scaffold.out_dir.join(format!("project-owner-{}.json", i)),
+        )?;
+        println!("project {} owner {}", project, owner.pubkey());
+    }
+
+    println!("keypairs written to {}", scaffold.out_dir.display());
+    Ok(())
+}
+
+/// Generates a keypair, writes it to `name` in `out_dir` and funds it for fees
+fn wallet(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    out_dir: &Path,
+    name: &str,
+) -> Result<Pubkey, Box<dyn Error>> {
+    let keypair = Keypair::new();
+    write_keypair_file(&keypair, out_dir.join(name))?;
+    fund(rpc, payer, &keypair.pubkey())?;
+    Ok(keypair.pubkey())
+}
+
+/// Sends the payer's SOL for fees and rent to `to`
+pub fn fund(rpc: &RpcClient, payer: &Keypair, to: &Pubkey) -> Result<(), Box<dyn Error>> {
+    send(
+        rpc,
+        payer,
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            to,
+            sol_to_lamports(WALLET_SOL),
+        )],
+        &[],
+    )
+}
+
+/// Creates a mint the payer is the authority of
+pub fn create_mint(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    decimals: u8,
+) -> Result<Pubkey, Box<dyn Error>> {
+    let mint = Keypair::new();
+    let rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
+    send(
+        rpc,
+        payer,
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                decimals,
+            )?,
+        ],
+        &[&mint],
+    )?;
+    Ok(mint.pubkey())
+}
+
+/// Starts a round owned by the payer with a new vault holding `fund` tokens, returns
+/// the round and the vault
+pub fn create_round(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    mint: &Pubkey,
+    fund: u64,
+    config: RoundConfig,
+) -> Result<(Pubkey, Pubkey), Box<dyn Error>> {
+    // the vault is owned by the program address of the round owner
+    let (vault_owner, _) = Pubkey::find_program_address(&[&payer.pubkey().to_bytes()], program_id);
+    let vault = create_token_account(rpc, payer, mint, &vault_owner, fund)?;
+    let round = Keypair::new();
+    let rent = rpc.get_minimum_balance_for_rent_exemption(Round::LEN)?;
+    send(
+        rpc,
+        payer,
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &round.pubkey(),
+                rent,
+                Round::LEN as u64,
+                program_id,
+            ),
+            instruction::start_round(program_id, &round.pubkey(), &payer.pubkey(), &vault, config),
+        ],
+        &[&round],
+    )?;
+    Ok((round.pubkey(), vault))
+}
+
+/// Registers a project for a new funded owner, who is also its payout wallet. Returns
+/// the project and its owner.
+pub fn register_project(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    round: &Pubkey,
+) -> Result<(Pubkey, Keypair), Box<dyn Error>> {
+    let owner = Keypair::new();
+    fund(rpc, payer, &owner.pubkey())?;
+    let (project, _) = Pubkey::find_program_address(
+        &[&round.to_bytes(), &owner.pubkey().to_bytes(), &[0]],
+        program_id,
+    );
+    send(
+        rpc,
+        payer,
+        &[instruction::register_project(
+            program_id,
+            round,
+            &owner.pubkey(),
+            &owner.pubkey(),
+            0,
+            0,
+            vec![],
+        )],
+        &[&owner],
+    )?;
+    Ok((project, owner))
+}
+
+/// Creates a token account of `mint` for `owner` and mints `amount` to it, the payer
+/// is the mint authority
+pub fn create_token_account(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Result<Pubkey, Box<dyn Error>> {
+    let account = Keypair::new();
+    let rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
+    let mut instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &account.pubkey(),
+            rent,
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            &account.pubkey(),
+            mint,
+            owner,
+        )?,
+    ];
+    if amount > 0 {
+        instructions.push(spl_token::instruction::mint_to(
+            &spl_token::id(),
+            mint,
+            &account.pubkey(),
+            &payer.pubkey(),
+            &[],
+            amount,
+        )?);
+    }
+    send(rpc, payer, &instructions, &[&account])?;
+    Ok(account.pubkey())
+}
+
+/// Sends the instructions in one transaction paid and signed by the payer and `signers`
+pub fn send(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), Box<dyn Error>> {
+    let (blockhash, _) = rpc.get_recent_blockhash()?;
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    rpc.send_and_confirm_transaction(&transaction)?;
+    Ok(())
+}
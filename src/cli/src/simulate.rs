@@ -0,0 +1,126 @@
+//! Payouts of a round computed with the program's own math
+
+use crate::accounts;
+use qf::{
+    processor::Processor,
+    state::{Config, Project, Round, RoundStatus},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::error::Error;
+
+/// What Withdraw would pay a project
+pub struct Allocation {
+    pub project: Pubkey,
+    pub votes: u64,
+    /// votes and match before fees
+    pub payout: u64,
+    pub operator_fee: u64,
+    pub protocol_fee: u64,
+    /// registration deposit paid back with the payout
+    pub deposit: u64,
+    pub withdrawn: bool,
+}
+
+impl Allocation {
+    /// What reaches the payout wallet
+    pub fn total(&self) -> u64 {
+        self.payout - self.operator_fee - self.protocol_fee + self.deposit
+    }
+}
+
+/// Runs Withdraw for every project in turn on a copy of the round. A round that is not
+/// finalized yet is allocated as Finalize would allocate it now.
+pub fn allocations(
+    round_key: &Pubkey,
+    round: &Round,
+    config: &Config,
+    projects: &[(Pubkey, Project)],
+) -> Vec<Allocation> {
+    let mut round = *round;
+    if matches!(
+        round.status,
+        RoundStatus::Registration | RoundStatus::Voting | RoundStatus::Tallying
+    ) {
+        round.lock_payouts();
+    }
+
+    projects
+        .iter()
+        .map(|(key, project)| {
+            let mut project = *project;
+            let payout = if project.withdraw {
+                0
+            } else {
+                Processor::payout(&round, &mut project)
+            };
+            round.fund_remaining -= payout;
+
+            let exempt = config.is_fee_exempt(round_key) || config.is_fee_exempt(&project.owner);
+            let (operator_fee, protocol_fee) = (round.operator_fee, round.protocol_fee);
+            Processor::charge_fees(&mut round, config, payout, exempt);
+
+            Allocation {
+                project: *key,
+                votes: project.votes,
+                payout,
+                operator_fee: round.operator_fee - operator_fee,
+                protocol_fee: round.protocol_fee - protocol_fee,
+                deposit: if project.banned || project.withdraw {
+                    0
+                } else {
+                    project.deposit
+                },
+                withdrawn: project.withdraw,
+            }
+        })
+        .collect()
+}
+
+pub fn simulate_payouts(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    round_key: &Pubkey,
+) -> Result<(), Box<dyn Error>> {
+    let round = accounts::round(rpc, round_key)?;
+    let config = accounts::config(rpc, program_id)?;
+    let projects = accounts::projects(rpc, program_id, round_key)?;
+    let allocations = allocations(round_key, &round, &config, &projects);
+
+    println!(
+        "Round {} ({:?}), fund {}, votes {}, {} projects",
+        round_key,
+        round.status,
+        round.fund,
+        round.votes,
+        projects.len()
+    );
+    println!(
+        "{:<44} {:>20} {:>20} {:>20} {:>20} {:>20} {:>20}",
+        "Project", "Votes", "Payout", "Operator fee", "Protocol fee", "Deposit", "Total"
+    );
+    for allocation in &allocations {
+        println!(
+            "{:<44} {:>20} {:>20} {:>20} {:>20} {:>20} {:>20}{}",
+            allocation.project,
+            allocation.votes,
+            allocation.payout,
+            allocation.operator_fee,
+            allocation.protocol_fee,
+            allocation.deposit,
+            allocation.total(),
+            if allocation.withdrawn {
+                " (withdrawn)"
+            } else {
+                ""
+            }
+        );
+    }
+    println!(
+        "Payouts {}, operator fees {}, protocol fees {}",
+        allocations.iter().map(|a| a.payout).sum::<u64>(),
+        allocations.iter().map(|a| a.operator_fee).sum::<u64>(),
+        allocations.iter().map(|a| a.protocol_fee).sum::<u64>(),
+    );
+    Ok(())
+}
@@ -0,0 +1,174 @@
+//! Live updates of rounds and projects over the websocket RPC, so a dashboard can follow
+//! the vote totals without polling
+
+use crate::accounts;
+use qf::state::{Project, Round};
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccount;
+use solana_client::{rpc_client::RpcClient, rpc_response::Response};
+use solana_sdk::{
+    account::Account,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+use std::{error::Error, thread};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tungstenite::{connect, Message};
+use url::Url;
+
+/// A decoded account update and the slot it was seen at
+#[derive(Debug)]
+pub enum Update {
+    Round {
+        slot: u64,
+        key: Pubkey,
+        round: Box<Round>,
+    },
+    Project {
+        slot: u64,
+        key: Pubkey,
+        project: Box<Project>,
+    },
+}
+
+/// Sends an update down `sender` every time the round changes
+pub fn subscribe_round(
+    ws_url: &str,
+    round_key: &Pubkey,
+    sender: UnboundedSender<Update>,
+) -> Result<(), Box<dyn Error>> {
+    subscribe(ws_url, round_key, sender, |slot, key, round| {
+        Update::Round {
+            slot,
+            key,
+            round: Box::new(round),
+        }
+    })
+}
+
+/// Sends an update down `sender` every time the project changes
+pub fn subscribe_project(
+    ws_url: &str,
+    project_key: &Pubkey,
+    sender: UnboundedSender<Update>,
+) -> Result<(), Box<dyn Error>> {
+    subscribe(ws_url, project_key, sender, |slot, key, project| {
+        Update::Project {
+            slot,
+            key,
+            project: Box::new(project),
+        }
+    })
+}
+
+/// Subscribes to the account and forwards every update that unpacks as `T` from a
+/// thread, which unsubscribes once the channel is closed. The `PubsubClient` of
+/// solana-client 1.6 has no accountSubscribe, so the subscription is sent over the
+/// websocket the way it sends the others.
+fn subscribe<T: Pack + IsInitialized + 'static>(
+    ws_url: &str,
+    key: &Pubkey,
+    sender: UnboundedSender<Update>,
+    update: fn(u64, Pubkey, T) -> Update,
+) -> Result<(), Box<dyn Error>> {
+    let (mut socket, _) = connect(Url::parse(ws_url)?)?;
+    socket.write_message(Message::Text(
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "accountSubscribe",
+            "params": [key.to_string(), {"encoding": "base64"}],
+        })
+        .to_string(),
+    ))?;
+    let reply: Value = serde_json::from_str(&socket.read_message()?.into_text()?)?;
+    let subscription = match reply["result"].as_u64() {
+        Some(subscription) => subscription,
+        None => return Err(format!("accountSubscribe failed: {}", reply).into()),
+    };
+
+    let key = *key;
+    thread::spawn(move || {
+        while let Ok(message) = socket.read_message() {
+            let mut notification: Value = match message
+                .into_text()
+                .ok()
+                .and_then(|text| serde_json::from_str(&text).ok())
+            {
+                Some(notification) => notification,
+                None => continue,
+            };
+            let response: Response<UiAccount> =
+                match serde_json::from_value(notification["params"]["result"].take()) {
+                    Ok(response) => response,
+                    Err(_) => continue,
+                };
+            let account = match response.value.decode::<Account>() {
+                Some(account) => T::unpack(&account.data),
+                None => continue,
+            };
+            // closed accounts and accounts of another kind are skipped
+            if let Ok(account) = account {
+                if sender
+                    .send(update(response.context.slot, key, account))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+        let _ = socket.write_message(Message::Text(
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "accountUnsubscribe",
+                "params": [subscription],
+            })
+            .to_string(),
+        ));
+        let _ = socket.close(None);
+    });
+    Ok(())
+}
+
+/// Prints the fund and votes of the round and the votes of its projects as they change
+pub fn watch_round(
+    rpc: &RpcClient,
+    ws_url: &str,
+    program_id: &Pubkey,
+    round_key: &Pubkey,
+) -> Result<(), Box<dyn Error>> {
+    let (sender, mut receiver) = unbounded_channel();
+    subscribe_round(ws_url, round_key, sender.clone())?;
+    for (key, _) in accounts::projects(rpc, program_id, round_key)? {
+        subscribe_project(ws_url, &key, sender.clone())?;
+    }
+    drop(sender);
+
+    while let Some(update) = receiver.blocking_recv() {
+        match update {
+            Update::Round { slot, key, round } => println!(
+                "{} round {} fund {} votes {} voters {}",
+                slot, key, round.fund, round.votes, round.voters
+            ),
+            Update::Project { slot, key, project } => println!(
+                "{} project {} votes {} area {}",
+                slot, key, project.votes, project.area
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// The websocket endpoint of a validator serving JSON RPC at `url`, which listens on the
+/// next port
+pub fn websocket_url(url: &str) -> String {
+    let url = url.replacen("http", "ws", 1);
+    match url.rfind(':') {
+        Some(colon) if url[colon + 1..].parse::<u16>().is_ok() => {
+            let port: u16 = url[colon + 1..].parse().unwrap();
+            format!("{}:{}", &url[..colon], port + 1)
+        }
+        _ => url,
+    }
+}
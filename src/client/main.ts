@@ -11,6 +11,10 @@ import {
   sendAndConfirmTransaction,
   TransactionInstruction,
   SYSVAR_RENT_PUBKEY,
+  AddressLookupTableProgram,
+  ComputeBudgetProgram,
+  TransactionMessage,
+  VersionedTransaction,
 } from "@solana/web3.js";
 import * as SPLToken from "@solana/spl-token";
 import { newAccountWithLamports } from "./util/new-account-with-lamports";
@@ -93,7 +97,7 @@ async function main() {
     feePayer,
     feePayer,
     project1Pubkey,
-    AliceTokenHolderPubkey,
+    Alice.publicKey,
     QFProgramID
   );
   console.log("=> Init Alice Projcet 1 Voter", initAliceProject1VoterTxHash);
@@ -108,7 +112,7 @@ async function main() {
     feePayer,
     feePayer,
     project2Pubkey,
-    AliceTokenHolderPubkey,
+    Alice.publicKey,
     QFProgramID
   );
   console.log("=> Init Alice Projcet 2 Voter", initAliceProject2VoterTxHash);
@@ -175,7 +179,7 @@ async function main() {
       feePayer,
       feePayer,
       project1Pubkey,
-      bobTokenHolderPubkey,
+      bob.publicKey,
       QFProgramID
     );
   console.log("=> Init Bob Projcet 1 Voter", initBobProject1Voter);
@@ -385,11 +389,106 @@ enum Instruction {
   WithdrawFee,
 }
 
+// compute units of each instruction with some headroom. Vote, InitVoter and
+// Withdraw follow `benches/compute_units.rs`, run it again after changing them.
+const COMPUTE_UNITS: { [instruction: number]: number } = {
+  [Instruction.StartRound]: 30000,
+  [Instruction.Donate]: 40000,
+  [Instruction.RegisterProject]: 30000,
+  [Instruction.InitVoter]: 40000,
+  [Instruction.Vote]: 120000,
+  [Instruction.Withdraw]: 120000,
+  [Instruction.EndRound]: 20000,
+  [Instruction.WithdrawFee]: 60000,
+};
+
+// budget of an instruction without a measured entry above, the default
+// budget the runtime gives an instruction
+const DEFAULT_COMPUTE_UNITS = 200000;
+const MAX_TRANSACTION_COMPUTE_UNITS = 1400000;
+
+// budget of an instruction of another program, e.g. creating an account
+const OTHER_PROGRAM_COMPUTE_UNITS = 10000;
+
+// how the priority fee is picked: none, a fixed price, or a percentile of the
+// fees recently paid to write the same accounts, capped at `maxMicroLamports`
+type FeeStrategy =
+  | { kind: "none" }
+  | { kind: "fixed"; microLamports: number }
+  | { kind: "recent"; percentile: number; maxMicroLamports: number };
+
+const DEFAULT_FEE_STRATEGY: FeeStrategy = { kind: "none" };
+
+// prepends a compute unit limit covering every instruction of the transaction
+// and a compute unit price picked by the fee strategy
+async function addComputeBudget(
+  connection: Connection,
+  tx: Transaction,
+  programId: PublicKey,
+  feeStrategy: FeeStrategy
+): Promise<void> {
+  let units = 0;
+  for (const instruction of tx.instructions) {
+    units += instruction.programId.equals(programId)
+      ? COMPUTE_UNITS[instruction.data[0]] ?? DEFAULT_COMPUTE_UNITS
+      : OTHER_PROGRAM_COMPUTE_UNITS;
+  }
+  // a transaction can not ask for more than the runtime's maximum
+  units = Math.min(units, MAX_TRANSACTION_COMPUTE_UNITS);
+  const budget = [ComputeBudgetProgram.setComputeUnitLimit({ units })];
+
+  const microLamports = await priorityFee(connection, tx, feeStrategy);
+  if (microLamports > 0) {
+    budget.push(ComputeBudgetProgram.setComputeUnitPrice({ microLamports }));
+  }
+
+  tx.instructions.unshift(...budget);
+}
+
+async function priorityFee(
+  connection: Connection,
+  tx: Transaction,
+  feeStrategy: FeeStrategy
+): Promise<number> {
+  switch (feeStrategy.kind) {
+    case "none":
+      return 0;
+    case "fixed":
+      return feeStrategy.microLamports;
+    case "recent": {
+      const writable: PublicKey[] = [];
+      for (const instruction of tx.instructions) {
+        for (const key of instruction.keys) {
+          if (key.isWritable) {
+            writable.push(key.pubkey);
+          }
+        }
+      }
+      const fees = (
+        await connection.getRecentPrioritizationFees({
+          lockedWritableAccounts: writable,
+        })
+      )
+        .map((fee) => fee.prioritizationFee)
+        .sort((a, b) => a - b);
+      if (fees.length === 0) {
+        return 0;
+      }
+      const index = Math.min(
+        fees.length - 1,
+        Math.floor((fees.length * feeStrategy.percentile) / 100)
+      );
+      return Math.min(fees[index], feeStrategy.maxMicroLamports);
+    }
+  }
+}
+
 function createStartRoundInstruction(
   programId: PublicKey,
   newRoundPubkey: PublicKey,
   ownerPubkey: PublicKey,
-  vaultPubkey: PublicKey
+  vaultPubkey: PublicKey,
+  configPubkey: PublicKey
 ): TransactionInstruction {
   const dataLayout = BufferLayout.struct([BufferLayout.u8("instruction")]);
 
@@ -422,6 +521,11 @@ function createStartRoundInstruction(
       isSigner: false,
       isWritable: false,
     },
+    {
+      pubkey: configPubkey,
+      isSigner: false,
+      isWritable: false,
+    },
   ];
 
   return new TransactionInstruction({
@@ -431,18 +535,39 @@ function createStartRoundInstruction(
   });
 }
 
+// the tokens a project deposits to register when the round asks for a deposit
+type RegistrationDeposit = {
+  fromPubkey: PublicKey;
+  mintPubkey: PublicKey;
+  vaultPubkey: PublicKey;
+  fromAuth: Account;
+};
+
 function registerProjectInstruction(
   programId: PublicKey,
   newProjectPubkey: PublicKey,
   roundPubkey: PublicKey,
-  projectOwnerPubkey: PublicKey
+  projectOwnerPubkey: PublicKey,
+  payoutWalletPubkey: PublicKey,
+  category: number,
+  index: number,
+  deposit?: RegistrationDeposit
 ): TransactionInstruction {
-  const dataLayout = BufferLayout.struct([BufferLayout.u8("instruction")]);
+  // the round has no allowlist, so the proof is empty
+  const dataLayout = BufferLayout.struct([
+    BufferLayout.u8("instruction"),
+    BufferLayout.u8("category"),
+    BufferLayout.u8("index"),
+    BufferLayout.u8("proofLength"),
+  ]);
 
   const data = Buffer.alloc(dataLayout.span);
   dataLayout.encode(
     {
       instruction: Instruction.RegisterProject,
+      category,
+      index,
+      proofLength: 0,
     },
     data
   );
@@ -456,19 +581,58 @@ function registerProjectInstruction(
     {
       pubkey: roundPubkey,
       isSigner: false,
-      isWritable: false,
+      isWritable: true,
     },
     {
       pubkey: projectOwnerPubkey,
+      isSigner: true,
+      isWritable: true,
+    },
+    {
+      pubkey: SYSVAR_RENT_PUBKEY,
       isSigner: false,
       isWritable: false,
     },
     {
-      pubkey: SYSVAR_RENT_PUBKEY,
+      pubkey: payoutWalletPubkey,
+      isSigner: false,
+      isWritable: false,
+    },
+    {
+      pubkey: SystemProgram.programId,
       isSigner: false,
       isWritable: false,
     },
   ];
+  if (deposit) {
+    keys.push(
+      {
+        pubkey: deposit.fromPubkey,
+        isSigner: false,
+        isWritable: true,
+      },
+      {
+        pubkey: deposit.mintPubkey,
+        isSigner: false,
+        isWritable: false,
+      },
+      {
+        pubkey: deposit.vaultPubkey,
+        isSigner: false,
+        isWritable: true,
+      },
+      {
+        pubkey: deposit.fromAuth.publicKey,
+        isSigner: true,
+        isWritable: false,
+      },
+      {
+        pubkey: SPLToken.TOKEN_PROGRAM_ID,
+        isSigner: false,
+        isWritable: false,
+      }
+    );
+  }
 
   return new TransactionInstruction({
     keys,
@@ -480,7 +644,7 @@ function registerProjectInstruction(
 function initVoterInstruction(
   programId: PublicKey,
   voterPubkey: PublicKey,
-  voterTokenHolderPubkey: PublicKey,
+  voterWalletPubkey: PublicKey,
   projectPubkey: PublicKey,
   fromPubkey: PublicKey
 ): TransactionInstruction {
@@ -501,7 +665,7 @@ function initVoterInstruction(
       isWritable: true,
     },
     {
-      pubkey: voterTokenHolderPubkey,
+      pubkey: voterWalletPubkey,
       isSigner: false,
       isWritable: false,
     },
@@ -843,13 +1007,36 @@ async function getVaultOwnerPubkey(
   return pda;
 }
 
+async function getConfigPubkey(programId: PublicKey): Promise<PublicKey> {
+  let [pda] = await PublicKey.findProgramAddress(
+    [Buffer.from("config")],
+    programId
+  );
+  return pda;
+}
+
+// a project of a round, an owner registers up to a few projects per round by index
+async function getProjectPubkey(
+  round: PublicKey,
+  owner: PublicKey,
+  index: number,
+  programId: PublicKey
+): Promise<PublicKey> {
+  let [pda] = await PublicKey.findProgramAddress(
+    [round.toBuffer(), owner.toBuffer(), Buffer.from([index])],
+    programId
+  );
+  return pda;
+}
+
+// the voter of a wallet for a project, derived from the wallet that votes
 async function getVoterPubkey(
   project: PublicKey,
-  voterTokenAccountPubkey: PublicKey,
+  voterWalletPubkey: PublicKey,
   programId: PublicKey
 ): Promise<PublicKey> {
   let [pda] = await PublicKey.findProgramAddress(
-    [project.toBuffer(), voterTokenAccountPubkey.toBuffer()],
+    [project.toBuffer(), voterWalletPubkey.toBuffer()],
     programId
   );
   return pda;
@@ -858,7 +1045,8 @@ async function getVoterPubkey(
 async function CreateNewRound(
   connection: Connection,
   feePayer: Account,
-  programId: PublicKey
+  programId: PublicKey,
+  feeStrategy: FeeStrategy = DEFAULT_FEE_STRATEGY
 ): Promise<any> {
   let owner = await newAccountWithLamports(connection, 10000000000);
   let vault = new Account();
@@ -866,6 +1054,7 @@ async function CreateNewRound(
     SPLToken.AccountLayout.span
   );
   let vaultOwnerPubkey = await getVaultOwnerPubkey(owner.publicKey, programId);
+  let configPubkey = await getConfigPubkey(programId);
   let round = new Account();
   const roundRentExemption = await connection.getMinimumBalanceForRentExemption(
     RoundAccountDataLayout.span
@@ -903,10 +1092,13 @@ async function CreateNewRound(
         programId,
         round.publicKey,
         owner.publicKey,
-        vault.publicKey
+        vault.publicKey,
+        configPubkey
       )
     );
 
+  await addComputeBudget(connection, tx, programId, feeStrategy);
+
   let txHash = await sendAndConfirmTransaction(
     connection,
     tx,
@@ -930,24 +1122,27 @@ async function InitVoter(
   feePayer: Account,
   from: Account,
   projectPubkey: PublicKey,
-  voterTokenAccountPubkey: PublicKey,
-  programId: PublicKey
+  voterWalletPubkey: PublicKey,
+  programId: PublicKey,
+  feeStrategy: FeeStrategy = DEFAULT_FEE_STRATEGY
 ): Promise<any> {
   let voterPubkey = await getVoterPubkey(
     projectPubkey,
-    voterTokenAccountPubkey,
+    voterWalletPubkey,
     programId
   );
   const tx = new Transaction().add(
     initVoterInstruction(
       programId,
       voterPubkey,
-      voterTokenAccountPubkey,
+      voterWalletPubkey,
       projectPubkey,
       from.publicKey
     )
   );
 
+  await addComputeBudget(connection, tx, programId, feeStrategy);
+
   let txHash = await sendAndConfirmTransaction(
     connection,
     tx,
@@ -968,45 +1163,47 @@ async function RegisterProject(
   connection: Connection,
   feePayer: Account,
   roundPubkey: PublicKey,
-  programId: PublicKey
+  programId: PublicKey,
+  index: number = 0,
+  deposit?: RegistrationDeposit,
+  feeStrategy: FeeStrategy = DEFAULT_FEE_STRATEGY
 ): Promise<any> {
-  let owner = new Account();
-  let project = new Account();
+  // the owner pays for the project account
+  let owner = await newAccountWithLamports(connection, 1000000000);
+  let projectPubkey = await getProjectPubkey(
+    roundPubkey,
+    owner.publicKey,
+    index,
+    programId
+  );
 
-  const tx = new Transaction()
-    .add(
-      SystemProgram.createAccount({
-        fromPubkey: feePayer.publicKey,
-        newAccountPubkey: project.publicKey,
-        lamports: await connection.getMinimumBalanceForRentExemption(
-          ProjectAccountDataLayout.span
-        ),
-        space: ProjectAccountDataLayout.span,
-        programId: programId,
-      })
+  const tx = new Transaction().add(
+    registerProjectInstruction(
+      programId,
+      projectPubkey,
+      roundPubkey,
+      owner.publicKey,
+      owner.publicKey,
+      0,
+      index,
+      deposit
     )
-    .add(
-      registerProjectInstruction(
-        programId,
-        project.publicKey,
-        roundPubkey,
-        owner.publicKey
-      )
-    );
-
-  let txHash = await sendAndConfirmTransaction(
-    connection,
-    tx,
-    [feePayer, project],
-    {
-      commitment: "singleGossip",
-      preflightCommitment: "singleGossip",
-    }
   );
 
+  await addComputeBudget(connection, tx, programId, feeStrategy);
+
+  let signers = [feePayer, owner];
+  if (deposit) {
+    signers.push(deposit.fromAuth);
+  }
+  let txHash = await sendAndConfirmTransaction(connection, tx, signers, {
+    commitment: "singleGossip",
+    preflightCommitment: "singleGossip",
+  });
+
   return {
     txHash: txHash,
-    projectPubkey: project.publicKey,
+    projectPubkey: projectPubkey,
     owner: owner,
   };
 }
@@ -1021,7 +1218,8 @@ async function Donate(
   toPubkey: PublicKey,
   fromAuth: Account,
   amount: number,
-  decimals: number
+  decimals: number,
+  feeStrategy: FeeStrategy = DEFAULT_FEE_STRATEGY
 ): Promise<any> {
   const tx = new Transaction().add(
     donateInstruction(
@@ -1036,6 +1234,8 @@ async function Donate(
     )
   );
 
+  await addComputeBudget(connection, tx, programId, feeStrategy);
+
   let txHash = await sendAndConfirmTransaction(
     connection,
     tx,
@@ -1063,7 +1263,8 @@ async function Vote(
   toPubkey: PublicKey,
   fromAuth: Account,
   amount: number,
-  decimals: number
+  decimals: number,
+  feeStrategy: FeeStrategy = DEFAULT_FEE_STRATEGY
 ): Promise<any> {
   const tx = new Transaction().add(
     voteInstruction(
@@ -1080,6 +1281,8 @@ async function Vote(
     )
   );
 
+  await addComputeBudget(connection, tx, programId, feeStrategy);
+
   let txHash = await sendAndConfirmTransaction(
     connection,
     tx,
@@ -1104,7 +1307,8 @@ async function Withdraw(
   vaultOwnerPubkey: PublicKey,
   projectPubkey: PublicKey,
   projectOwner: Account,
-  toPubkey: PublicKey
+  toPubkey: PublicKey,
+  feeStrategy: FeeStrategy = DEFAULT_FEE_STRATEGY
 ): Promise<any> {
   const tx = new Transaction().add(
     withdrawInstruction(
@@ -1118,6 +1322,8 @@ async function Withdraw(
     )
   );
 
+  await addComputeBudget(connection, tx, programId, feeStrategy);
+
   let txHash = await sendAndConfirmTransaction(
     connection,
     tx,
@@ -1137,12 +1343,15 @@ async function EndRound(
   connection: Connection,
   roundPubkey: PublicKey,
   owner: Account,
-  programId: PublicKey
+  programId: PublicKey,
+  feeStrategy: FeeStrategy = DEFAULT_FEE_STRATEGY
 ): Promise<any> {
   const tx = new Transaction().add(
     endRoundInstruction(programId, roundPubkey, owner.publicKey)
   );
 
+  await addComputeBudget(connection, tx, programId, feeStrategy);
+
   let txHash = await sendAndConfirmTransaction(connection, tx, [owner], {
     commitment: "singleGossip",
     preflightCommitment: "singleGossip",
@@ -1161,7 +1370,8 @@ async function WithdrawFee(
   owner: Account,
   vaultPubkey: PublicKey,
   vaultOwnerPubkey: PublicKey,
-  toPubkey: PublicKey
+  toPubkey: PublicKey,
+  feeStrategy: FeeStrategy = DEFAULT_FEE_STRATEGY
 ): Promise<any> {
   const tx = new Transaction().add(
     withdrawFeeInstruction(
@@ -1174,6 +1384,8 @@ async function WithdrawFee(
     )
   );
 
+  await addComputeBudget(connection, tx, programId, feeStrategy);
+
   let txHash = await sendAndConfirmTransaction(
     connection,
     tx,
@@ -1189,6 +1401,116 @@ async function WithdrawFee(
   };
 }
 
+// an extend instruction has to fit a legacy transaction, so addresses are added
+// in chunks
+const LOOKUP_TABLE_EXTEND_CHUNK = 20;
+
+// creates a lookup table holding the round, its vault and projects, so batches
+// over many projects (SubmitVoteBatch, crank instructions) fit a v0 transaction
+async function CreateRoundLookupTable(
+  connection: Connection,
+  feePayer: Account,
+  authority: Account,
+  addresses: PublicKey[]
+): Promise<PublicKey> {
+  const slot = await connection.getSlot("finalized");
+  const [
+    createInstruction,
+    lookupTablePubkey,
+  ] = AddressLookupTableProgram.createLookupTable({
+    authority: authority.publicKey,
+    payer: feePayer.publicKey,
+    recentSlot: slot,
+  });
+
+  await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(createInstruction),
+    [feePayer, authority],
+    {
+      commitment: "singleGossip",
+      preflightCommitment: "singleGossip",
+    }
+  );
+  await ExtendRoundLookupTable(
+    connection,
+    feePayer,
+    authority,
+    lookupTablePubkey,
+    addresses
+  );
+
+  return lookupTablePubkey;
+}
+
+// adds addresses to a lookup table, e.g. projects registered after it was made
+async function ExtendRoundLookupTable(
+  connection: Connection,
+  feePayer: Account,
+  authority: Account,
+  lookupTablePubkey: PublicKey,
+  addresses: PublicKey[]
+): Promise<void> {
+  for (let i = 0; i < addresses.length; i += LOOKUP_TABLE_EXTEND_CHUNK) {
+    const tx = new Transaction().add(
+      AddressLookupTableProgram.extendLookupTable({
+        lookupTable: lookupTablePubkey,
+        authority: authority.publicKey,
+        payer: feePayer.publicKey,
+        addresses: addresses.slice(i, i + LOOKUP_TABLE_EXTEND_CHUNK),
+      })
+    );
+    await sendAndConfirmTransaction(connection, tx, [feePayer, authority], {
+      commitment: "singleGossip",
+      preflightCommitment: "singleGossip",
+    });
+  }
+}
+
+// sends the instructions as a v0 transaction, accounts in the lookup tables
+// are referenced by index instead of by address. A table can only be used from
+// the slot after it was last extended.
+async function SendV0Transaction(
+  connection: Connection,
+  feePayer: Account,
+  instructions: TransactionInstruction[],
+  lookupTablePubkeys: PublicKey[],
+  signers: Account[]
+): Promise<any> {
+  const lookupTables = [];
+  for (const lookupTablePubkey of lookupTablePubkeys) {
+    const lookupTable = (
+      await connection.getAddressLookupTable(lookupTablePubkey)
+    ).value;
+    if (lookupTable === null) {
+      throw new Error(`lookup table ${lookupTablePubkey} not found`);
+    }
+    lookupTables.push(lookupTable);
+  }
+
+  const {
+    blockhash,
+    lastValidBlockHeight,
+  } = await connection.getLatestBlockhash();
+  const message = new TransactionMessage({
+    payerKey: feePayer.publicKey,
+    recentBlockhash: blockhash,
+    instructions,
+  }).compileToV0Message(lookupTables);
+  const tx = new VersionedTransaction(message);
+  tx.sign([feePayer, ...signers]);
+
+  let txHash = await connection.sendTransaction(tx);
+  await connection.confirmTransaction(
+    { signature: txHash, blockhash, lastValidBlockHeight },
+    "confirmed"
+  );
+
+  return {
+    txHash: txHash,
+  };
+}
+
 type Round = {
   roundStatus: number; // u8
   fund: BN; // u64
@@ -1199,6 +1521,7 @@ type Round = {
 };
 
 const RoundAccountDataLayout = BufferLayout.struct([
+  BufferLayout.blob(8, "discriminator"),
   BufferLayout.u8("roundStatus"),
   BufferLayout.blob(8, "fund"),
   BufferLayout.blob(8, "fee"),
@@ -1253,6 +1576,7 @@ type Project = {
 };
 
 const ProjectAccountDataLayout = BufferLayout.struct([
+  BufferLayout.blob(8, "discriminator"),
   BufferLayout.blob(32, "round"),
   BufferLayout.blob(32, "owner"),
   BufferLayout.u8("withdraw"),
@@ -1305,6 +1629,7 @@ type Voter = {
 };
 
 const VoterAccountDataLayout = BufferLayout.struct([
+  BufferLayout.blob(8, "discriminator"),
   BufferLayout.u8("isInit"),
   BufferLayout.blob(8, "votes"),
   BufferLayout.blob(8, "votes_sqrt"),
@@ -1408,7 +1733,7 @@ async function DeployProgram(
   connection: Connection,
   feePayer: Account
 ): Promise<PublicKey> {
-  const data = await fs.readFile("src/program/target/deploy/qf.so");
+  const data = await fs.readFile("target/deploy/qf.so");
   const programAccount = new Account();
   await BpfLoader.load(
     connection,
@@ -53,6 +53,7 @@ export default () => {
   const [output, setOutput] = useState("");
 
   const RoundAccountDataLayout = BufferLayout.struct([
+    BufferLayout.blob(8, "discriminator"),
     BufferLayout.u8("roundStatus"),
     BufferLayout.blob(8, "fund"),
     BufferLayout.blob(8, "fee"),
@@ -63,6 +64,7 @@ export default () => {
   const [getRoundInfoPubkey, setGetRoundInfoPubkey] = useState("");
 
   const ProjectAccountDataLayout = BufferLayout.struct([
+    BufferLayout.blob(8, "discriminator"),
     BufferLayout.blob(32, "round"),
     BufferLayout.blob(32, "owner"),
     BufferLayout.u8("withdraw"),
@@ -73,6 +75,7 @@ export default () => {
   const [getProjectInfoPubkey, setGetProjectInfoPubkey] = useState("");
 
   const VoterAccountDataLayout = BufferLayout.struct([
+    BufferLayout.blob(8, "discriminator"),
     BufferLayout.u8("isInit"),
     BufferLayout.blob(8, "votes"),
     BufferLayout.blob(8, "votes_sqrt"),
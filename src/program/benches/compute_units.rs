@@ -0,0 +1,399 @@
+//! Compute units of Vote, InitVoter and Withdraw, measured on the BPF build.
+//!
+//! Build the program with `cargo build-bpf` first, then run
+//! `BPF_OUT_DIR=../../target/deploy cargo bench --bench compute_units`. Each case is
+//! run with a shrinking compute budget until it fails, so the report holds the
+//! exact units the instruction needs. Every run starts from accounts written
+//! straight into the bank, so only the measured instruction is executed.
+
+use qf::{
+    instruction::{self, QFInstruction},
+    state::{Category, Claim, Project, Round, RoundStatus, Voter, VoterRegistry, MAX_CATEGORIES},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_math::uint::U256;
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+/// Default compute budget of an instruction
+const MAX_UNITS: u64 = 200_000;
+
+#[derive(Clone, Copy)]
+enum Kind {
+    InitVoter,
+    Vote,
+    Withdraw,
+}
+
+/// What the measured instruction runs against
+#[derive(Clone, Copy)]
+struct Case {
+    kind: Kind,
+    /// categories of the round, the project is in the last one
+    categories: u8,
+    /// tokens the voter already voted for the project
+    prior_votes: u64,
+    /// tokens the vote moves
+    amount: u64,
+}
+
+/// Keys and signers shared by every run of a case
+struct Fixture {
+    program_id: Pubkey,
+    round: Pubkey,
+    owner: Pubkey,
+    vault: Pubkey,
+    vault_owner: Pubkey,
+    mint: Pubkey,
+    project: Pubkey,
+    project_owner: Keypair,
+    payout_wallet: Pubkey,
+    voter_wallet: Keypair,
+    from: Pubkey,
+    voter: Pubkey,
+    registry: Pubkey,
+    claim: Pubkey,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let round = Pubkey::new_unique();
+        let project = Pubkey::new_unique();
+        let voter_wallet = Keypair::new();
+        let (vault_owner, _) = Pubkey::find_program_address(&[&owner.to_bytes()], &program_id);
+        let (voter, _) = Pubkey::find_program_address(
+            &[&project.to_bytes(), &voter_wallet.pubkey().to_bytes()],
+            &program_id,
+        );
+        let (registry, _) = Pubkey::find_program_address(
+            &[
+                b"registry",
+                &round.to_bytes(),
+                &voter_wallet.pubkey().to_bytes(),
+            ],
+            &program_id,
+        );
+        let (claim, _) =
+            Pubkey::find_program_address(&[b"claim", &project.to_bytes()], &program_id);
+        Fixture {
+            program_id,
+            round,
+            owner,
+            vault: Pubkey::new_unique(),
+            vault_owner,
+            mint: Pubkey::new_unique(),
+            project,
+            project_owner: Keypair::new(),
+            payout_wallet: Pubkey::new_unique(),
+            voter_wallet,
+            from: Pubkey::new_unique(),
+            voter,
+            registry,
+            claim,
+        }
+    }
+}
+
+fn account<T: Pack>(state: T, owner: &Pubkey) -> Account {
+    let mut data = vec![0; T::LEN];
+    state.pack_into_slice(&mut data);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    account(
+        TokenAccount {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        },
+        &spl_token::id(),
+    )
+}
+
+/// Sqrt of `votes` the way the program keeps it, scaled by 10^12
+fn votes_sqrt(votes: u64) -> U256 {
+    U256::from(((votes as f64).sqrt() * 1e12) as u128)
+}
+
+fn program_test(fixture: &Fixture, case: &Case, max_units: u64) -> ProgramTest {
+    let mut program_test = ProgramTest::new("qf", fixture.program_id, None);
+    program_test.set_bpf_compute_max_units(max_units);
+
+    let sqrt = votes_sqrt(case.prior_votes);
+    let area = sqrt * sqrt / U256::from(1_000_000_000_000u64);
+    let category = case.categories.saturating_sub(1);
+
+    let mut round = Round {
+        status: match case.kind {
+            Kind::Withdraw => RoundStatus::Claim,
+            _ => RoundStatus::Voting,
+        },
+        fund: 1_000_000_000_000,
+        vault: fixture.vault,
+        owner: fixture.owner,
+        mint: fixture.mint,
+        area,
+        votes: case.prior_votes,
+        voters: (case.prior_votes > 0) as u64,
+        category_count: case.categories,
+        ..Round::default()
+    };
+    round.fund_remaining = round.fund + round.votes;
+    if case.categories > 0 {
+        let share_bps = 10_000 / case.categories as u16;
+        for category in round.categories[..case.categories as usize].iter_mut() {
+            *category = Category {
+                share_bps,
+                area: U256::zero(),
+            };
+        }
+        round.categories[category as usize].area = area;
+    }
+    assert!(case.categories as usize <= MAX_CATEGORIES);
+    program_test.add_account(fixture.round, account(round, &fixture.program_id));
+
+    let project = Project {
+        round: fixture.round,
+        owner: fixture.project_owner.pubkey(),
+        votes: case.prior_votes,
+        area,
+        area_sqrt: sqrt,
+        payout_wallet: fixture.payout_wallet,
+        category,
+        ..Project::default()
+    };
+    program_test.add_account(fixture.project, account(project, &fixture.program_id));
+
+    if matches!(case.kind, Kind::Withdraw) {
+        let claim = Claim {
+            project: fixture.project,
+            amount: case.prior_votes,
+            claimed: false,
+        };
+        program_test.add_account(fixture.claim, account(claim, &fixture.program_id));
+    }
+
+    if !matches!(case.kind, Kind::InitVoter) {
+        let voter = Voter {
+            is_initialized: true,
+            votes: case.prior_votes,
+            weight: case.prior_votes,
+            votes_sqrt: sqrt,
+            ..Voter::default()
+        };
+        program_test.add_account(fixture.voter, account(voter, &fixture.program_id));
+        let registry = VoterRegistry {
+            round: fixture.round,
+            wallet: fixture.voter_wallet.pubkey(),
+            projects: 1,
+            votes: case.prior_votes,
+            weight: case.prior_votes,
+            ..VoterRegistry::default()
+        };
+        program_test.add_account(fixture.registry, account(registry, &fixture.program_id));
+    }
+
+    program_test.add_account(
+        fixture.mint,
+        account(
+            Mint {
+                mint_authority: COption::None,
+                supply: u64::MAX,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+            &spl_token::id(),
+        ),
+    );
+    program_test.add_account(
+        fixture.vault,
+        token_account(&fixture.mint, &fixture.vault_owner, round.fund_remaining),
+    );
+    program_test.add_account(
+        fixture.from,
+        token_account(&fixture.mint, &fixture.voter_wallet.pubkey(), u64::MAX / 2),
+    );
+    program_test.add_account(
+        get_associated_token_address(&fixture.payout_wallet, &fixture.mint),
+        token_account(&fixture.mint, &fixture.payout_wallet, 0),
+    );
+    program_test
+}
+
+fn measured_instruction(fixture: &Fixture, case: &Case, payer: &Pubkey) -> Instruction {
+    match case.kind {
+        Kind::InitVoter => Instruction {
+            program_id: fixture.program_id,
+            accounts: vec![
+                AccountMeta::new(fixture.voter, false),
+                AccountMeta::new_readonly(fixture.voter_wallet.pubkey(), false),
+                AccountMeta::new_readonly(fixture.project, false),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(fixture.round, false),
+                AccountMeta::new(fixture.registry, false),
+            ],
+            data: QFInstruction::InitVoter.pack(),
+        },
+        Kind::Vote => instruction::vote(
+            &fixture.program_id,
+            &fixture.round,
+            &fixture.project,
+            &fixture.voter,
+            &fixture.from,
+            &fixture.mint,
+            &fixture.vault,
+            &fixture.voter_wallet.pubkey(),
+            &fixture.registry,
+            case.amount,
+            6,
+        ),
+        Kind::Withdraw => {
+            let (config, _) = Pubkey::find_program_address(&[b"config"], &fixture.program_id);
+            Instruction {
+                program_id: fixture.program_id,
+                accounts: vec![
+                    AccountMeta::new(fixture.round, false),
+                    AccountMeta::new(fixture.vault, false),
+                    AccountMeta::new_readonly(fixture.vault_owner, false),
+                    AccountMeta::new(fixture.project, false),
+                    AccountMeta::new(fixture.project_owner.pubkey(), true),
+                    AccountMeta::new(
+                        get_associated_token_address(&fixture.payout_wallet, &fixture.mint),
+                        false,
+                    ),
+                    AccountMeta::new_readonly(spl_token::id(), false),
+                    AccountMeta::new_readonly(fixture.mint, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                    AccountMeta::new_readonly(sysvar::rent::id(), false),
+                    AccountMeta::new_readonly(fixture.payout_wallet, false),
+                    AccountMeta::new_readonly(config, false),
+                    AccountMeta::new(fixture.claim, false),
+                ],
+                data: QFInstruction::Withdraw.pack(),
+            }
+        }
+    }
+}
+
+/// Whether the case runs within `max_units`
+async fn fits(fixture: &Fixture, case: &Case, max_units: u64) -> bool {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test(fixture, case, max_units).start().await;
+    let instruction = measured_instruction(fixture, case, &payer.pubkey());
+
+    let mut signers = vec![&payer];
+    match case.kind {
+        Kind::Vote => signers.push(&fixture.voter_wallet),
+        Kind::Withdraw => signers.push(&fixture.project_owner),
+        Kind::InitVoter => {}
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &signers,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.is_ok()
+}
+
+/// Fewest compute units the case runs with, None if it fails within the default budget
+async fn compute_units(case: &Case) -> Option<u64> {
+    let fixture = Fixture::new();
+    if !fits(&fixture, case, MAX_UNITS).await {
+        return None;
+    }
+    let (mut low, mut high) = (0, MAX_UNITS);
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        if fits(&fixture, case, mid).await {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Some(high)
+}
+
+fn cases() -> Vec<(&'static str, Case)> {
+    let case = |kind, categories, prior_votes, amount| Case {
+        kind,
+        categories,
+        prior_votes,
+        amount,
+    };
+    vec![
+        ("InitVoter", case(Kind::InitVoter, 0, 0, 0)),
+        ("InitVoter, 8 categories", case(Kind::InitVoter, 8, 0, 0)),
+        (
+            "Vote, first vote, 1 token",
+            case(Kind::Vote, 0, 0, 1_000_000),
+        ),
+        (
+            "Vote, first vote, 10^9 tokens",
+            case(Kind::Vote, 0, 0, 1_000_000_000_000_000),
+        ),
+        (
+            "Vote, after 10^6 tokens",
+            case(Kind::Vote, 0, 1_000_000_000_000, 1_000_000),
+        ),
+        (
+            "Vote, 4 categories",
+            case(Kind::Vote, 4, 1_000_000_000_000, 1_000_000),
+        ),
+        (
+            "Vote, 8 categories",
+            case(Kind::Vote, 8, 1_000_000_000_000, 1_000_000),
+        ),
+        ("Withdraw, no votes", case(Kind::Withdraw, 0, 0, 0)),
+        (
+            "Withdraw, 10^6 tokens voted",
+            case(Kind::Withdraw, 0, 1_000_000_000_000, 0),
+        ),
+        (
+            "Withdraw, 8 categories",
+            case(Kind::Withdraw, 8, 1_000_000_000_000, 0),
+        ),
+    ]
+}
+
+#[tokio::main]
+async fn main() {
+    println!("| case | compute units |");
+    println!("| --- | --- |");
+    for (name, case) in cases() {
+        match compute_units(&case).await {
+            Some(units) => println!("| {} | {} |", name, units),
+            None => println!("| {} | fails within {} |", name, MAX_UNITS),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use qf::instruction::QFInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    // short or malformed input has to come back as an error, not a panic
+    if let Ok(instruction) = QFInstruction::unpack(data) {
+        // whatever unpacks has to pack back to something that unpacks the same
+        let packed = instruction.pack();
+        assert_eq!(QFInstruction::unpack(&packed).unwrap(), instruction);
+    }
+});
@@ -0,0 +1,24 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use qf::state::{Project, Round, Voter};
+use solana_program::program_pack::Pack;
+
+fn check<T: Pack + PartialEq + std::fmt::Debug>(data: &[u8]) {
+    // account data of the wrong size is rejected before it is read
+    let _ = T::unpack_unchecked(data);
+
+    // any bytes of the right size either unpack or fail, and unpack back the same
+    let mut src = data.to_vec();
+    src.resize(T::LEN, 0);
+    if let Ok(state) = T::unpack_from_slice(&src) {
+        let mut dst = vec![0; T::LEN];
+        state.pack_into_slice(&mut dst);
+        assert_eq!(T::unpack_from_slice(&dst).unwrap(), state);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    check::<Round>(data);
+    check::<Project>(data);
+    check::<Voter>(data);
+});
@@ -0,0 +1,71 @@
+//! Merkle allowlists of the wallets that may register projects in a round
+//!
+//! A leaf is the hash of the wallet and a node the hash of its two children in sorted
+//! order, so a proof is only the list of siblings from the leaf up. An odd node is
+//! carried up as it is.
+
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+/// deepest proof RegisterProject takes, enough for a million wallets
+pub const MAX_PROOF_LEN: usize = 20;
+
+/// The leaf of `wallet`, prefixed so it can not be mistaken for a node
+pub fn leaf(wallet: &Pubkey) -> [u8; 32] {
+    hashv(&[b"allowlist", wallet.as_ref()]).to_bytes()
+}
+
+fn node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[a, b]).to_bytes()
+    } else {
+        hashv(&[b, a]).to_bytes()
+    }
+}
+
+/// Whether `proof` leads from the leaf of `wallet` to `root`
+pub fn verify(root: &[u8; 32], wallet: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    proof
+        .iter()
+        .fold(leaf(wallet), |hash, sibling| node(&hash, sibling))
+        == *root
+}
+
+/// The levels of the tree over `wallets`, leaves first and the root last
+fn levels(wallets: &[Pubkey]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![wallets.iter().map(leaf).collect::<Vec<_>>()];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => node(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// The root of the allowlist of `wallets`, all zeros for an empty list
+pub fn root(wallets: &[Pubkey]) -> [u8; 32] {
+    levels(wallets)
+        .last()
+        .and_then(|level| level.first().copied())
+        .unwrap_or_default()
+}
+
+/// The proof of `wallet` in the allowlist of `wallets`, None if it is not in it
+pub fn proof(wallets: &[Pubkey], wallet: &Pubkey) -> Option<Vec<[u8; 32]>> {
+    let mut index = wallets.iter().position(|key| key == wallet)?;
+    let mut proof = vec![];
+    for level in levels(wallets).iter() {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    Some(proof)
+}
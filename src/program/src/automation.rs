@@ -0,0 +1,36 @@
+//! Helpers to run the phase transitions of a round from an automation network
+//!
+//! The crank instructions need no signer and do nothing once the round moved on,
+//! so a thread (for example a Clockwork thread with a timestamp trigger) can
+//! run them at the scheduled time and retry them safely, whoever pays for it.
+
+use crate::{instruction, state::Round};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+/// An instruction and the unix timestamp from which it can run
+pub struct ScheduledTask {
+    pub trigger_at: i64,
+    pub instruction: Instruction,
+}
+
+/// The transitions of a round that can be scheduled, empty if the round config
+/// does not set `voting_end`
+pub fn round_tasks(program_id: &Pubkey, round_key: &Pubkey, round: &Round) -> Vec<ScheduledTask> {
+    let voting_end = round.config.voting_end;
+    if voting_end == 0 {
+        return vec![];
+    }
+
+    vec![
+        ScheduledTask {
+            trigger_at: voting_end,
+            instruction: instruction::crank_end_round(program_id, round_key),
+        },
+        ScheduledTask {
+            // the voting ends at `voting_end` when the crank runs on time
+            trigger_at: voting_end
+                .saturating_add(round.config.tally_period.max(round.config.dispute_window)),
+            instruction: instruction::crank_finalize(program_id, round_key),
+        },
+    ]
+}
@@ -0,0 +1,113 @@
+//! Typed contexts for programs invoking QF through CPI
+//!
+//! `from_auth` may be a wallet that signed the outer transaction or a program
+//! derived address of the calling program, in which case its seeds are passed
+//! as `signer_seeds` and the signature is forwarded to the token program.
+
+use crate::instruction;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::invoke_signed,
+};
+
+/// Accounts for a `Donate` CPI
+pub struct Donate<'a> {
+    pub round: AccountInfo<'a>,
+    pub from: AccountInfo<'a>,
+    pub mint: AccountInfo<'a>,
+    pub vault: AccountInfo<'a>,
+    pub from_auth: AccountInfo<'a>,
+    pub token_program: AccountInfo<'a>,
+    pub sponsor: AccountInfo<'a>,
+    pub payer: AccountInfo<'a>,
+    pub system_program: AccountInfo<'a>,
+    pub rent: AccountInfo<'a>,
+}
+
+/// Accounts for a `Vote` CPI
+pub struct Vote<'a> {
+    pub round: AccountInfo<'a>,
+    pub project: AccountInfo<'a>,
+    pub voter: AccountInfo<'a>,
+    pub from: AccountInfo<'a>,
+    pub mint: AccountInfo<'a>,
+    pub vault: AccountInfo<'a>,
+    pub from_auth: AccountInfo<'a>,
+    pub token_program: AccountInfo<'a>,
+    pub registry: AccountInfo<'a>,
+}
+
+/// Donates `amount` to the round's matching pool.
+pub fn donate<'a>(
+    qf_program: &AccountInfo<'a>,
+    accounts: Donate<'a>,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &instruction::donate(
+            qf_program.key,
+            accounts.round.key,
+            accounts.from.key,
+            accounts.mint.key,
+            accounts.vault.key,
+            accounts.from_auth.key,
+            accounts.payer.key,
+            &[],
+            amount,
+            decimals,
+        ),
+        &[
+            accounts.round,
+            accounts.from,
+            accounts.mint,
+            accounts.vault,
+            accounts.from_auth,
+            accounts.token_program,
+            accounts.sponsor,
+            accounts.payer,
+            accounts.system_program,
+            accounts.rent,
+            qf_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Votes `amount` to a project.
+pub fn vote<'a>(
+    qf_program: &AccountInfo<'a>,
+    accounts: Vote<'a>,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &instruction::vote(
+            qf_program.key,
+            accounts.round.key,
+            accounts.project.key,
+            accounts.voter.key,
+            accounts.from.key,
+            accounts.mint.key,
+            accounts.vault.key,
+            accounts.from_auth.key,
+            accounts.registry.key,
+            amount,
+            decimals,
+        ),
+        &[
+            accounts.round,
+            accounts.project,
+            accounts.voter,
+            accounts.from,
+            accounts.mint,
+            accounts.vault,
+            accounts.from_auth,
+            accounts.token_program,
+            accounts.registry,
+            qf_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
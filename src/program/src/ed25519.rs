@@ -0,0 +1,65 @@
+//! Checks on signatures verified by the native ed25519 program
+
+use crate::error::QFError;
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, sysvar::instructions,
+};
+
+solana_program::declare_id!("Ed25519SigVerify111111111111111111111111111");
+
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
+const PUBKEY_SERIALIZED_SIZE: usize = 32;
+
+/// Makes sure the instruction right before the current one is an ed25519
+/// program instruction verifying that `signer` signed `message`.
+///
+/// Only a single signature whose key and message live in the ed25519
+/// instruction data itself is accepted.
+pub fn verify_previous_instruction(
+    instructions_info: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<(), ProgramError> {
+    if !instructions::check_id(instructions_info.key) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let instructions_data = instructions_info.data.borrow();
+    let current_index = instructions::load_current_index(&instructions_data) as usize;
+    if current_index == 0 {
+        return Err(QFError::SignatureVerificationFailed.into());
+    }
+    let instruction = instructions::load_instruction_at(current_index - 1, &instructions_data)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    if instruction.program_id != id() {
+        return Err(QFError::SignatureVerificationFailed.into());
+    }
+
+    let data = instruction.data;
+    if data.len() < SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SERIALIZED_SIZE || data[0] != 1 {
+        return Err(QFError::SignatureVerificationFailed.into());
+    }
+    let read_u16 = |offset: usize| {
+        let start = SIGNATURE_OFFSETS_START + offset;
+        u16::from_le_bytes([data[start], data[start + 1]])
+    };
+    // the instruction indexes must point at the ed25519 instruction itself
+    if read_u16(2) != u16::MAX || read_u16(6) != u16::MAX || read_u16(12) != u16::MAX {
+        return Err(QFError::SignatureVerificationFailed.into());
+    }
+
+    let pubkey_offset = read_u16(4) as usize;
+    let message_offset = read_u16(8) as usize;
+    let message_size = read_u16(10) as usize;
+    let pubkey = data
+        .get(pubkey_offset..pubkey_offset + PUBKEY_SERIALIZED_SIZE)
+        .ok_or(QFError::SignatureVerificationFailed)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(QFError::SignatureVerificationFailed)?;
+    if pubkey != signer.as_ref() || signed_message != message {
+        return Err(QFError::SignatureVerificationFailed.into());
+    }
+
+    Ok(())
+}
@@ -1,8 +1,24 @@
 use crate::{error::QFError, processor::Processor};
+use default_env::default_env;
 use solana_program::{
     account_info::AccountInfo, entrypoint, entrypoint::ProgramResult,
     program_error::PrintProgramError, pubkey::Pubkey,
 };
+use solana_security_txt::security_txt;
+
+// the program custodies matching pools, this gives auditors and white hats a contact
+// from the deployed binary. The revision and release are those of the CI build, empty
+// for a local build.
+security_txt! {
+    name: "QF",
+    project_url: "https://github.com/brucx/QF",
+    contacts: "email:a122092487@gmail.com,link:https://github.com/brucx/QF/security/advisories/new",
+    policy: "https://github.com/brucx/QF/blob/main/SECURITY.md",
+    preferred_languages: "en",
+    source_code: "https://github.com/brucx/QF",
+    source_revision: default_env!("GITHUB_SHA", ""),
+    source_release: default_env!("GITHUB_REF_NAME", "")
+}
 
 entrypoint!(process_instruction);
 fn process_instruction(
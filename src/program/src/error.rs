@@ -2,28 +2,335 @@ use num_derive::FromPrimitive;
 use solana_program::{decode_error::DecodeError, program_error::ProgramError};
 use thiserror::Error;
 
+/// Errors of the program, the hundreds of the code give the kind of the error
 #[derive(Error, Debug, Clone, PartialEq, FromPrimitive)]
 pub enum QFError {
+    // 100..199: accounts that do not match what the instruction expects
     #[error("owner mismatch")]
-    OwnerMismatch,
-
-    #[error("round status does not expected")]
-    RoundStatusError,
+    OwnerMismatch = 100,
 
     #[error("vault does not match")]
     VaultMismatch,
 
+    #[error("vault authority does not match")]
+    VaultAuthorityMismatch,
+
     #[error("round does not match")]
     RoundMismatch,
 
-    #[error("project has already withdraw")]
-    ProjectAlreadyWithdraw,
+    #[error("project does not match")]
+    ProjectMismatch,
+
+    #[error("voter mismatch")]
+    VoterMismatch,
+
+    #[error("voter registry does not match")]
+    RegistryMismatch,
+
+    #[error("mint does not match")]
+    MintMismatch,
+
+    #[error("recipient does not match")]
+    RecipientMismatch,
+
+    #[error("sponsor does not match")]
+    SponsorMismatch,
+
+    #[error("pledge does not match")]
+    PledgeMismatch,
+
+    #[error("subscription mismatch")]
+    SubscriptionMismatch,
+
+    #[error("receipt mismatch")]
+    ReceiptMismatch,
+
+    #[error("airdrop mismatch")]
+    AirdropMismatch,
+
+    #[error("stake pool mismatch")]
+    StakePoolMismatch,
+
+    #[error("collection mismatch")]
+    CollectionMismatch,
+
+    #[error("curator mismatch")]
+    CuratorMismatch,
+
+    #[error("session does not match")]
+    SessionMismatch,
+
+    #[error("delegation does not match")]
+    DelegationMismatch,
+
+    #[error("config mismatch")]
+    ConfigMismatch,
+
+    #[error("admin mismatch")]
+    AdminMismatch,
+
+    #[error("fee recipient mismatch")]
+    FeeRecipientMismatch,
+
+    #[error("pending action mismatch")]
+    ActionMismatch,
+
+    #[error("program data account does not match")]
+    ProgramDataMismatch,
+
+    #[error("invalid token authority")]
+    InvalidTokenAuthority,
 
     #[error("unexpected token program id")]
     UnexpectedTokenProgramID,
 
-    #[error("voter mismatch")]
-    VoterMismatch,
+    #[error("unexpected associated token program id")]
+    UnexpectedAssociatedTokenProgramID,
+
+    #[error("archive does not match")]
+    ArchiveMismatch,
+
+    #[error("claim does not match")]
+    ClaimMismatch,
+
+    #[error("flag does not match")]
+    FlagMismatch,
+
+    #[error("project update does not match")]
+    UpdateMismatch,
+
+    #[error("voter tree does not match")]
+    VoterTreeMismatch,
+
+    #[error("collateral account does not match")]
+    CollateralMismatch,
+
+    // 200..299: round phases, modes and deadlines
+    #[error("round status does not expected")]
+    RoundStatusError = 200,
+
+    #[error("not allowed in this round mode")]
+    RoundModeMismatch,
+
+    #[error("round already linked")]
+    RoundAlreadyLinked,
+
+    #[error("invalid category")]
+    InvalidCategory,
+
+    #[error("committee share exceeded")]
+    CommitteeShareExceeded,
+
+    #[error("invalid epoch")]
+    InvalidEpoch,
+
+    #[error("invalid deadline")]
+    InvalidDeadline,
+
+    #[error("crank is not due")]
+    CrankNotDue,
+
+    #[error("grace period is over")]
+    GracePeriodOver,
+
+    #[error("dispute window is still open")]
+    DisputeWindowOpen,
+
+    #[error("claim period is still open")]
+    ClaimPeriodOpen,
+
+    #[error("appeals are pending")]
+    AppealsPending,
+
+    #[error("pledges are not executed")]
+    PledgesNotExecuted,
+
+    #[error("flags are pending")]
+    FlagsPending,
+
+    #[error("round can not be extended to this voting end")]
+    InvalidExtension,
+
+    #[error("round fund is already lent out")]
+    FundLent,
+
+    // 300..399: projects
+    #[error("project is banned")]
+    ProjectBanned = 300,
+
+    #[error("project already verified")]
+    ProjectAlreadyVerified,
+
+    #[error("invalid appeal")]
+    InvalidAppeal,
+
+    #[error("duplicate project")]
+    DuplicateProject,
+
+    #[error("invalid project index")]
+    InvalidProjectIndex,
+
+    #[error("round has reached its project limit")]
+    ProjectLimitReached,
+
+    #[error("flagging is disabled in this round")]
+    FlaggingDisabled,
+
+    #[error("invalid flag")]
+    InvalidFlag,
+
+    #[error("wallet is not on the registration allowlist")]
+    NotAllowlisted,
+
+    #[error("invalid donation tiers")]
+    InvalidTiers,
+
+    // 400..499: votes and voters
+    #[error("voter is blacklisted")]
+    VoterBlacklisted = 400,
+
+    #[error("voter has no votes")]
+    NoVotes,
+
+    #[error("credit budget exceeded")]
+    CreditBudgetExceeded,
+
+    #[error("invalid batch nonce")]
+    InvalidBatchNonce,
+
+    #[error("signature verification failed")]
+    SignatureVerificationFailed,
+
+    #[error("permit has expired")]
+    PermitExpired,
+
+    #[error("invalid permit nonce")]
+    InvalidPermitNonce,
+
+    #[error("session has expired")]
+    SessionExpired,
+
+    #[error("session limit exceeded")]
+    SessionLimitExceeded,
+
+    #[error("delegation budget exceeded")]
+    DelegationBudgetExceeded,
+
+    #[error("insufficient delegated amount")]
+    InsufficientDelegatedAmount,
+
+    #[error("invalid subscription")]
+    InvalidSubscription,
+
+    #[error("subscription is still active")]
+    SubscriptionActive,
+
+    #[error("subscription vote is not due")]
+    SubscriptionNotDue,
+
+    #[error("downvote budget exceeded")]
+    DownvoteBudgetExceeded,
+
+    #[error("vote cooldown has not passed")]
+    VoteCooldown,
+
+    #[error("voter already endorsed the project")]
+    AlreadyEndorsed,
+
+    #[error("round has no aggregator")]
+    AggregatorNotSet,
+
+    #[error("vote batch total overflows")]
+    BatchTotalOverflow,
+
+    #[error("vote log is full")]
+    VoteLogFull,
+
+    #[error("voter tree is full")]
+    VoterTreeFull,
+
+    #[error("voter tree root is not in its change logs")]
+    StaleVoterTreeRoot,
+
+    #[error("invalid voter tree proof")]
+    InvalidVoterProof,
+
+    #[error("wallet already votes through voter accounts or the voter tree")]
+    VoterKindMismatch,
+
+    #[error("votes of the voter were paid by another wallet")]
+    CustodianMismatch,
+
+    #[error("participation mint mismatch")]
+    ParticipationMintMismatch,
+
+    // 500..599: payouts and refunds
+    #[error("project has already withdraw")]
+    ProjectAlreadyWithdraw = 500,
+
+    #[error("pledge already executed")]
+    PledgeAlreadyExecuted,
+
+    #[error("matching already reclaimed")]
+    MatchingAlreadyReclaimed,
+
+    #[error("receipt already minted")]
+    ReceiptAlreadyMinted,
+
+    #[error("airdrop already claimed")]
+    AirdropAlreadyClaimed,
+
+    #[error("insufficient vault balance")]
+    InsufficientVaultBalance,
+
+    #[error("vault is not empty")]
+    VaultNotEmpty,
+
+    #[error("claim already paid")]
+    ClaimAlreadyPaid,
+
+    #[error("holdback exceeds 10000 basis points")]
+    InvalidHoldback,
+
+    #[error("no holdback to release")]
+    NoHoldback,
+
+    #[error("loan exceeds the round fund")]
+    LoanExceedsFund,
+
+    #[error("claim period is over")]
+    ClaimPeriodOver,
+
+    // 600..699: the program config, fees and the build
+    #[error("fee exceeds 10000 basis points")]
+    InvalidFeeBps = 600,
+
+    #[error("no fees due")]
+    NoFeesDue,
+
+    #[error("invalid fee exempt key")]
+    InvalidFeeExemptKey,
+
+    #[error("fee exemption list is full")]
+    FeeExemptFull,
+
+    #[error("invalid timelock")]
+    InvalidTimelock,
+
+    #[error("timelock has not elapsed")]
+    TimelockNotElapsed,
+
+    #[error("instruction is not enabled in this build")]
+    InstructionDisabled,
+
+    #[error("fees are not enabled in this build")]
+    FeesDisabled,
+
+    #[error("mint is not approved by the config")]
+    MintNotApproved,
+
+    #[error("approved mint list is full")]
+    ApprovedMintsFull,
 }
 impl From<QFError> for ProgramError {
     fn from(e: QFError) -> Self {
@@ -35,3 +342,34 @@ impl<E> DecodeError<E> for QFError {
         "QFError"
     }
 }
+
+/// What a [`QFError`] is about, the hundreds of its code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QFErrorKind {
+    Account,
+    Round,
+    Project,
+    Vote,
+    Payout,
+    Config,
+}
+
+impl QFError {
+    /// Kind of the error, clients can map codes they do not know yet with it
+    pub fn kind(&self) -> QFErrorKind {
+        Self::kind_of(self.clone() as u32).unwrap()
+    }
+
+    /// Kind of an error code, None if it is not in a range of the program
+    pub fn kind_of(code: u32) -> Option<QFErrorKind> {
+        match code / 100 {
+            1 => Some(QFErrorKind::Account),
+            2 => Some(QFErrorKind::Round),
+            3 => Some(QFErrorKind::Project),
+            4 => Some(QFErrorKind::Vote),
+            5 => Some(QFErrorKind::Payout),
+            6 => Some(QFErrorKind::Config),
+            _ => None,
+        }
+    }
+}
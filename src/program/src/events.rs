@@ -0,0 +1,182 @@
+//! Events logged by the program, one line each so indexers can parse them from the logs
+
+use solana_program::{hash::Hash, msg, pubkey::Pubkey};
+use spl_math::uint::U256;
+
+/// An event of a round, the first three are broken invariants found by VerifyInvariants
+pub enum Event {
+    /// the project areas do not add up to the round area
+    AreaMismatch { expected: U256, actual: U256 },
+    /// the project areas of a category do not add up to the category area
+    CategoryAreaMismatch {
+        category: u8,
+        expected: U256,
+        actual: U256,
+    },
+    /// what the projects can still withdraw is more than the vault holds
+    PayoutsExceedVault { committed: u64, vault: u64 },
+    /// the owner took what was left in the vault after the recovery grace
+    FundsRecovered { amount: u64, to: Pubkey },
+    /// an entry was appended to the vote log, `index` is its position
+    VoteLogged {
+        index: u64,
+        wallet: Pubkey,
+        project: Pubkey,
+        amount: u64,
+        kind: VoteKind,
+        slot: u64,
+    },
+    /// a project account was closed and folded into the round archive
+    ProjectArchived {
+        project: Pubkey,
+        votes: u64,
+        area: U256,
+        discretionary: u64,
+        banned: bool,
+        withdraw: bool,
+    },
+    /// every project is archived and the round account is closed
+    RoundArchived { projects: u64, hash: Hash },
+    /// the round owner banned a project, `deposit` is its forfeited registration deposit
+    ProjectBanned { project: Pubkey, deposit: u64 },
+    /// the voting ended, by the owner or by the crank
+    RoundEnded { votes: u64, voters: u64, at: i64 },
+    /// a project withdrew, `amount` reached `to` after fees with the deposit refunded
+    PayoutClaimed {
+        project: Pubkey,
+        to: Pubkey,
+        amount: u64,
+    },
+    /// the round owner pushed the voting end from `from` to `to`
+    RoundExtended { from: i64, to: i64 },
+    /// the round owner released the holdback of a project's payout
+    HoldbackReleased {
+        project: Pubkey,
+        to: Pubkey,
+        amount: u64,
+    },
+}
+
+/// What an entry of the vote log records, hashed as its `u8` value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VoteKind {
+    Vote,
+    Revoke,
+    Downvote,
+}
+
+impl VoteKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoteKind::Vote => "vote",
+            VoteKind::Revoke => "revoke",
+            VoteKind::Downvote => "downvote",
+        }
+    }
+}
+
+/// Logs the event as `event: invariant_violation round=<round> kind=<kind> ...`, or
+/// `event: <name> round=<round> ...` for the others. A format and four arguments are
+/// formatted here, `msg!` would log five arguments as numbers.
+pub fn emit(round: &Pubkey, event: &Event) {
+    match event {
+        Event::AreaMismatch { expected, actual } => msg!(
+            "event: invariant_violation round={} kind=area_mismatch expected={} actual={}",
+            round,
+            expected,
+            actual
+        ),
+        Event::CategoryAreaMismatch {
+            category,
+            expected,
+            actual,
+        } => msg!(&format!(
+            "event: invariant_violation round={} kind=category_area_mismatch category={} expected={} actual={}",
+            round, category, expected, actual
+        )),
+        Event::PayoutsExceedVault { committed, vault } => msg!(
+            "event: invariant_violation round={} kind=payouts_exceed_vault committed={} vault={}",
+            round,
+            committed,
+            vault
+        ),
+        Event::FundsRecovered { amount, to } => msg!(
+            "event: funds_recovered round={} amount={} to={}",
+            round,
+            amount,
+            to
+        ),
+        Event::VoteLogged {
+            index,
+            wallet,
+            project,
+            amount,
+            kind,
+            slot,
+        } => msg!(
+            "event: vote_logged round={} index={} wallet={} project={} amount={} kind={} slot={}",
+            round,
+            index,
+            wallet,
+            project,
+            amount,
+            kind.name(),
+            slot
+        ),
+        Event::ProjectArchived {
+            project,
+            votes,
+            area,
+            discretionary,
+            banned,
+            withdraw,
+        } => msg!(
+            "event: project_archived round={} project={} votes={} area={} discretionary={} banned={} withdraw={}",
+            round,
+            project,
+            votes,
+            area,
+            discretionary,
+            banned,
+            withdraw
+        ),
+        Event::RoundArchived { projects, hash } => msg!(
+            "event: round_archived round={} projects={} hash={}",
+            round,
+            projects,
+            hash
+        ),
+        Event::ProjectBanned { project, deposit } => msg!(
+            "event: project_banned round={} project={} deposit={}",
+            round,
+            project,
+            deposit
+        ),
+        Event::RoundEnded { votes, voters, at } => msg!(&format!(
+            "event: round_ended round={} votes={} voters={} at={}",
+            round, votes, voters, at
+        )),
+        Event::PayoutClaimed {
+            project,
+            to,
+            amount,
+        } => msg!(&format!(
+            "event: payout_claimed round={} project={} to={} amount={}",
+            round, project, to, amount
+        )),
+        Event::RoundExtended { from, to } => msg!(
+            "event: round_extended round={} from={} to={}",
+            round,
+            from,
+            to
+        ),
+        Event::HoldbackReleased {
+            project,
+            to,
+            amount,
+        } => msg!(&format!(
+            "event: holdback_released round={} project={} to={} amount={}",
+            round, project, to, amount
+        )),
+    }
+}
@@ -1,22 +1,166 @@
-use solana_program::program_error::ProgramError;
+use crate::{
+    allowlist::MAX_PROOF_LEN,
+    lending, metaplex,
+    state::{ActionKind, RoundConfig, Tier, MAX_UPDATE_LEN, VOTER_TREE_DEPTH},
+    token_2022,
+};
+use num_enum::TryFromPrimitive;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
 use std::convert::TryInto;
 use std::mem::size_of;
-use spl_math::{
-    uint::U256,
-};
+
+/// A single vote inside a vote batch
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VoteBatchEntry {
+    /// wallet the vote is counted for
+    pub voter: Pubkey,
+    pub project: Pubkey,
+    pub amount: u64,
+}
+impl VoteBatchEntry {
+    pub const LEN: usize = 72;
+
+    fn unpack(input: &[u8]) -> Result<(Self, &[u8]), ProgramError> {
+        let (voter, rest) = unpack_pubkey(input)?;
+        let (project, rest) = unpack_pubkey(rest)?;
+        let (amount, rest) = unpack_u64(rest)?;
+        Ok((
+            VoteBatchEntry {
+                voter,
+                project,
+                amount,
+            },
+            rest,
+        ))
+    }
+
+    fn pack_into(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.voter.as_ref());
+        buf.extend_from_slice(self.project.as_ref());
+        buf.extend_from_slice(&self.amount.to_le_bytes());
+    }
+}
+
+/// The message the round aggregator signs for a vote batch
+pub fn vote_batch_message(round: &Pubkey, nonce: u64, entries: &[VoteBatchEntry]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(40 + entries.len() * VoteBatchEntry::LEN);
+    message.extend_from_slice(round.as_ref());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    for entry in entries {
+        entry.pack_into(&mut message);
+    }
+    message
+}
+
+/// The message a voter signs to let a relayer vote on their behalf
+pub fn permit_message(project: &Pubkey, amount: u64, expiry: i64, nonce: u64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(56);
+    message.extend_from_slice(project.as_ref());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.extend_from_slice(&expiry.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum QFInstruction {
-    StartRound,
+    StartRound { config: RoundConfig },
     Donate { amount: u64, decimals: u8 },
-    RegisterProject,
+    RegisterProject { category: u8, index: u8, proof: Vec<[u8; 32]> },
     InitVoter,
     Vote { amount: u64, decimals: u8 },
     Withdraw,
     EndRound,
     WithdrawFee,
-    BanProject { ban_amount: U256 },
+    BanProject,
+    SubmitVoteBatch { nonce: u64, entries: Vec<VoteBatchEntry> },
+    VoteWithPermit { amount: u64, decimals: u8, expiry: i64, nonce: u64 },
+    CreateSession { max_amount: u64, expiry: i64 },
+    SessionVote { amount: u64, decimals: u8 },
+    DelegateVotes { budget: u64 },
+    VoteAsDelegate { amount: u64, decimals: u8 },
+    SetPayoutWallet,
+    StartVoting,
+    Finalize,
+    CloseRound,
+    Refund,
+    RefundDonation,
+    Pledge { amount: u64, decimals: u8 },
+    ExecutePledge,
+    ReclaimPledge,
+    RevokeVote,
+    ReclaimMatching,
+    RolloverFunds,
+    LinkRounds,
+    CreateRoundTemplate { id: u64, config: RoundConfig },
+    StartRoundFromTemplate,
+    SetCategories { shares: Vec<u16> },
+    SetProjectCategory { category: u8 },
+    AllocateDiscretionary { amount: u64 },
+    CastCredits { amount: u64 },
+    EpochVote { amount: u64, decimals: u8 },
+    WithdrawEpoch { epoch: u64 },
+    CreateRecurringVote { amount: u64, interval: i64, total: u64, decimals: u8 },
+    ExecuteRecurringVote,
+    CancelRecurringVote,
+    CrankEndRound,
+    CrankFinalize,
+    MintReceipt,
+    CreateAirdrop { amount: u64, decimals: u8 },
+    ClaimAirdrop,
+    StakePoolVote { amount: u64, decimals: u8 },
+    AddCurator,
+    AttestProject,
+    BlacklistVoter { reverse: bool },
+    AppealBan,
+    ResolveAppeal { overturn: bool },
+    VerifyInvariants,
+    SweepDust,
+    InitConfig { protocol_fee_bps: u16, fee_recipient: Pubkey },
+    SetProtocolFee { protocol_fee_bps: u16 },
+    SetFeeExempt { key: Pubkey, exempt: bool },
+    SetFeeRecipient { fee_recipient: Pubkey },
+    AcceptFeeRecipient,
+    RecoverAbandonedFunds,
+    ProposeAction { kind: ActionKind },
+    CancelAction,
+    SetTimelock { timelock: i64 },
+    MigrateVoter,
+    ArchiveRound,
+    WriteClaims,
+    Downvote { amount: u64, decimals: u8 },
+    FlagProject,
+    ResolveFlag { uphold: bool },
+    SetRegistrationAllowlist { root: [u8; 32] },
+    SetProjectTiers { tiers: Vec<Tier> },
+    PostProjectUpdate { hash: [u8; 32], body: Vec<u8> },
+    Endorse,
+    SetMaxRoundDuration { max_round_duration: i64 },
+    ExtendRound { voting_end: i64 },
+    VoteWithSwap { decimals: u8, route: Vec<u8> },
+    SetApprovedMint { mint: Pubkey, approved: bool },
+    ReleaseHoldback,
+    InitVoterTree,
+    CompressedVote {
+        amount: u64,
+        decimals: u8,
+        root: [u8; 32],
+        votes: u64,
+        last_vote_slot: u64,
+        proof: Vec<[u8; 32]>,
+    },
+    CompressedRefund { root: [u8; 32], votes: u64, last_vote_slot: u64, proof: Vec<[u8; 32]> },
+    LendFund { amount: u64 },
+    MintParticipation,
 }
 
 impl QFInstruction {
@@ -25,14 +169,15 @@ impl QFInstruction {
             .split_first()
             .ok_or(ProgramError::InvalidInstructionData)?;
         Ok(match tag {
-            0 => Self::StartRound,
-            1 | 4 => {
-                let (amount, rest) = rest.split_at(8);
-                let amount = amount
-                    .try_into()
-                    .ok()
-                    .map(u64::from_le_bytes)
+            0 => {
+                let config = rest
+                    .get(..RoundConfig::LEN)
                     .ok_or(ProgramError::InvalidInstructionData)?;
+                let config = RoundConfig::unpack_from_slice(config)?;
+                Self::StartRound { config }
+            }
+            1 | 4 => {
+                let (amount, rest) = unpack_u64(rest)?;
                 let (&decimals, _rest) = rest
                     .split_first()
                     .ok_or(ProgramError::InvalidInstructionData)?;
@@ -42,20 +187,332 @@ impl QFInstruction {
                     _ => unreachable!(),
                 }
             }
-            2 => Self::RegisterProject,
+            2 => {
+                // rounds without categories register projects with the bare tag, and
+                // rounds without an allowlist without a proof
+                let category = rest.first().copied().unwrap_or(0);
+                let index = rest.get(1).copied().unwrap_or(0);
+                let count = rest.get(2).copied().unwrap_or(0) as usize;
+                if count > MAX_PROOF_LEN {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let proof = rest
+                    .get(3..3 + count * 32)
+                    .ok_or(ProgramError::InvalidInstructionData)?
+                    .chunks(32)
+                    .map(|node| node.try_into().unwrap())
+                    .collect();
+                Self::RegisterProject {
+                    category,
+                    index,
+                    proof,
+                }
+            }
             3 => Self::InitVoter,
             5 => Self::Withdraw,
             6 => Self::EndRound,
             7 => Self::WithdrawFee,
-            8 => {
-                let (ban_amount, _rest) = rest.split_at(32);
-                let ban_amount = ban_amount
-                    .try_into()
-                    .ok()
-                    .map(U256::from_little_endian)
-                    .ok_or(ProgramError::InvalidInstructionData)?;
-                Self::BanProject { ban_amount }
-            },
+            // older clients still send a ban amount, the whole area is banned now
+            8 => Self::BanProject,
+            9 => {
+                let (nonce, rest) = unpack_u64(rest)?;
+                let (&count, mut rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let mut entries = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let (entry, next) = VoteBatchEntry::unpack(rest)?;
+                    entries.push(entry);
+                    rest = next;
+                }
+                Self::SubmitVoteBatch { nonce, entries }
+            }
+            10 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (&decimals, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (expiry, rest) = unpack_u64(rest)?;
+                let (nonce, _rest) = unpack_u64(rest)?;
+                Self::VoteWithPermit {
+                    amount,
+                    decimals,
+                    expiry: expiry as i64,
+                    nonce,
+                }
+            }
+            11 => {
+                let (max_amount, rest) = unpack_u64(rest)?;
+                let (expiry, _rest) = unpack_u64(rest)?;
+                Self::CreateSession {
+                    max_amount,
+                    expiry: expiry as i64,
+                }
+            }
+            12 | 14 | 21 | 34 | 42 | 44 | 64 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (&decimals, _rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                match tag {
+                    12 => Self::SessionVote { amount, decimals },
+                    14 => Self::VoteAsDelegate { amount, decimals },
+                    21 => Self::Pledge { amount, decimals },
+                    34 => Self::EpochVote { amount, decimals },
+                    42 => Self::CreateAirdrop { amount, decimals },
+                    44 => Self::StakePoolVote { amount, decimals },
+                    64 => Self::Downvote { amount, decimals },
+                    _ => unreachable!(),
+                }
+            }
+            13 => {
+                let (budget, _rest) = unpack_u64(rest)?;
+                Self::DelegateVotes { budget }
+            }
+            15 => Self::SetPayoutWallet,
+            16 => Self::StartVoting,
+            17 => Self::Finalize,
+            18 => Self::CloseRound,
+            19 => Self::Refund,
+            20 => Self::RefundDonation,
+            22 => Self::ExecutePledge,
+            23 => Self::ReclaimPledge,
+            24 => Self::RevokeVote,
+            25 => Self::ReclaimMatching,
+            26 => Self::RolloverFunds,
+            27 => Self::LinkRounds,
+            28 => {
+                let (id, rest) = unpack_u64(rest)?;
+                let config = rest
+                    .get(..RoundConfig::LEN)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let config = RoundConfig::unpack_from_slice(config)?;
+                Self::CreateRoundTemplate { id, config }
+            }
+            29 => Self::StartRoundFromTemplate,
+            30 => {
+                let (&count, mut rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let mut shares = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let share = rest
+                        .get(..2)
+                        .and_then(|slice| slice.try_into().ok())
+                        .map(u16::from_le_bytes)
+                        .ok_or(ProgramError::InvalidInstructionData)?;
+                    shares.push(share);
+                    rest = &rest[2..];
+                }
+                Self::SetCategories { shares }
+            }
+            31 => {
+                let (&category, _rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                Self::SetProjectCategory { category }
+            }
+            32 | 33 => {
+                let (amount, _rest) = unpack_u64(rest)?;
+                match tag {
+                    32 => Self::AllocateDiscretionary { amount },
+                    33 => Self::CastCredits { amount },
+                    _ => unreachable!(),
+                }
+            }
+            35 => {
+                let (epoch, _rest) = unpack_u64(rest)?;
+                Self::WithdrawEpoch { epoch }
+            }
+            36 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (interval, rest) = unpack_u64(rest)?;
+                let (total, rest) = unpack_u64(rest)?;
+                let (&decimals, _rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                Self::CreateRecurringVote {
+                    amount,
+                    interval: interval as i64,
+                    total,
+                    decimals,
+                }
+            }
+            37 => Self::ExecuteRecurringVote,
+            38 => Self::CancelRecurringVote,
+            39 => Self::CrankEndRound,
+            40 => Self::CrankFinalize,
+            41 => Self::MintReceipt,
+            43 => Self::ClaimAirdrop,
+            45 => Self::AddCurator,
+            46 => Self::AttestProject,
+            47 | 49 | 66 => {
+                let flag = match rest.first() {
+                    None | Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                match tag {
+                    47 => Self::BlacklistVoter { reverse: flag },
+                    49 => Self::ResolveAppeal { overturn: flag },
+                    66 => Self::ResolveFlag { uphold: flag },
+                    _ => unreachable!(),
+                }
+            }
+            48 => Self::AppealBan,
+            50 => Self::VerifyInvariants,
+            51 => Self::SweepDust,
+            52 | 53 => {
+                let protocol_fee_bps = rest
+                    .get(..2)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                match tag {
+                    52 => {
+                        let (fee_recipient, _rest) = unpack_pubkey(&rest[2..])?;
+                        Self::InitConfig {
+                            protocol_fee_bps,
+                            fee_recipient,
+                        }
+                    }
+                    53 => Self::SetProtocolFee { protocol_fee_bps },
+                    _ => unreachable!(),
+                }
+            }
+            54 => {
+                let (key, rest) = unpack_pubkey(rest)?;
+                let exempt = match rest.first() {
+                    None | Some(1) => true,
+                    Some(0) => false,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                Self::SetFeeExempt { key, exempt }
+            }
+            55 => {
+                let (fee_recipient, _rest) = unpack_pubkey(rest)?;
+                Self::SetFeeRecipient { fee_recipient }
+            }
+            56 => Self::AcceptFeeRecipient,
+            57 => Self::RecoverAbandonedFunds,
+            58 => {
+                let kind = rest
+                    .first()
+                    .and_then(|&kind| ActionKind::try_from_primitive(kind).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                Self::ProposeAction { kind }
+            }
+            59 => Self::CancelAction,
+            60 => {
+                let (timelock, _rest) = unpack_u64(rest)?;
+                Self::SetTimelock {
+                    timelock: timelock as i64,
+                }
+            }
+            61 => Self::MigrateVoter,
+            62 => Self::ArchiveRound,
+            63 => Self::WriteClaims,
+            65 => Self::FlagProject,
+            67 => {
+                let root = rest
+                    .get(..32)
+                    .and_then(|root| root.try_into().ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                Self::SetRegistrationAllowlist { root }
+            }
+            68 => {
+                let (&count, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let tiers = rest
+                    .get(..count as usize * Tier::LEN)
+                    .ok_or(ProgramError::InvalidInstructionData)?
+                    .chunks(Tier::LEN)
+                    .map(Tier::unpack_from_slice)
+                    .collect::<Result<_, _>>()?;
+                Self::SetProjectTiers { tiers }
+            }
+            69 => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|hash| hash.try_into().ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let len = rest
+                    .get(32..34)
+                    .and_then(|len| len.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)? as usize;
+                if len > MAX_UPDATE_LEN {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let body = rest
+                    .get(34..34 + len)
+                    .ok_or(ProgramError::InvalidInstructionData)?
+                    .to_vec();
+                Self::PostProjectUpdate { hash, body }
+            }
+            70 => Self::Endorse,
+            71 | 72 => {
+                let (seconds, _rest) = unpack_u64(rest)?;
+                match tag {
+                    71 => Self::SetMaxRoundDuration {
+                        max_round_duration: seconds as i64,
+                    },
+                    72 => Self::ExtendRound {
+                        voting_end: seconds as i64,
+                    },
+                    _ => unreachable!(),
+                }
+            }
+            73 => {
+                let (&decimals, route) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                Self::VoteWithSwap {
+                    decimals,
+                    route: route.to_vec(),
+                }
+            }
+            74 => {
+                let (mint, rest) = unpack_pubkey(rest)?;
+                let approved = match rest.first() {
+                    None | Some(1) => true,
+                    Some(0) => false,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                Self::SetApprovedMint { mint, approved }
+            }
+            75 => Self::ReleaseHoldback,
+            76 => Self::InitVoterTree,
+            77 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (&decimals, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (root, votes, last_vote_slot, proof) = unpack_voter_leaf(rest)?;
+                Self::CompressedVote {
+                    amount,
+                    decimals,
+                    root,
+                    votes,
+                    last_vote_slot,
+                    proof,
+                }
+            }
+            78 => {
+                let (root, votes, last_vote_slot, proof) = unpack_voter_leaf(rest)?;
+                Self::CompressedRefund {
+                    root,
+                    votes,
+                    last_vote_slot,
+                    proof,
+                }
+            }
+            79 => {
+                let (amount, _rest) = unpack_u64(rest)?;
+                Self::LendFund { amount }
+            }
+            80 => Self::MintParticipation,
             _ => return Err(ProgramError::InvalidInstructionData),
         })
     }
@@ -63,13 +520,30 @@ impl QFInstruction {
     pub fn pack(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
         match self {
-            Self::StartRound => buf.push(0),
+            Self::StartRound { config } => {
+                buf.push(0);
+                let mut dst = [0; RoundConfig::LEN];
+                config.pack_into_slice(&mut dst);
+                buf.extend_from_slice(&dst);
+            }
             &Self::Donate { amount, decimals } => {
                 buf.push(1);
                 buf.extend_from_slice(&amount.to_le_bytes());
                 buf.push(decimals);
             }
-            Self::RegisterProject => buf.push(2),
+            Self::RegisterProject {
+                category,
+                index,
+                proof,
+            } => {
+                buf.push(2);
+                buf.push(*category);
+                buf.push(*index);
+                buf.push(proof.len() as u8);
+                for node in proof {
+                    buf.extend_from_slice(node);
+                }
+            }
             Self::InitVoter => buf.push(3),
             &Self::Vote { amount, decimals } => {
                 buf.push(4);
@@ -79,13 +553,1143 @@ impl QFInstruction {
             Self::Withdraw => buf.push(5),
             Self::EndRound => buf.push(6),
             Self::WithdrawFee => buf.push(7),
-            Self::BanProject { ban_amount } => {
-                buf.push(8);
-                let mut dst: [u8; 32] = [0; 32];
-                ban_amount.to_little_endian(&mut dst);
+            Self::BanProject => buf.push(8),
+            Self::SubmitVoteBatch { nonce, entries } => {
+                buf.push(9);
+                buf.extend_from_slice(&nonce.to_le_bytes());
+                buf.push(entries.len() as u8);
+                for entry in entries {
+                    entry.pack_into(&mut buf);
+                }
+            }
+            &Self::VoteWithPermit {
+                amount,
+                decimals,
+                expiry,
+                nonce,
+            } => {
+                buf.push(10);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(decimals);
+                buf.extend_from_slice(&expiry.to_le_bytes());
+                buf.extend_from_slice(&nonce.to_le_bytes());
+            }
+            &Self::CreateSession { max_amount, expiry } => {
+                buf.push(11);
+                buf.extend_from_slice(&max_amount.to_le_bytes());
+                buf.extend_from_slice(&expiry.to_le_bytes());
+            }
+            &Self::SessionVote { amount, decimals } => {
+                buf.push(12);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(decimals);
+            }
+            &Self::DelegateVotes { budget } => {
+                buf.push(13);
+                buf.extend_from_slice(&budget.to_le_bytes());
+            }
+            &Self::VoteAsDelegate { amount, decimals } => {
+                buf.push(14);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(decimals);
+            }
+            Self::SetPayoutWallet => buf.push(15),
+            Self::StartVoting => buf.push(16),
+            Self::Finalize => buf.push(17),
+            Self::CloseRound => buf.push(18),
+            Self::Refund => buf.push(19),
+            Self::RefundDonation => buf.push(20),
+            &Self::Pledge { amount, decimals } => {
+                buf.push(21);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(decimals);
+            }
+            Self::ExecutePledge => buf.push(22),
+            Self::ReclaimPledge => buf.push(23),
+            Self::RevokeVote => buf.push(24),
+            Self::ReclaimMatching => buf.push(25),
+            Self::RolloverFunds => buf.push(26),
+            Self::LinkRounds => buf.push(27),
+            Self::CreateRoundTemplate { id, config } => {
+                buf.push(28);
+                buf.extend_from_slice(&id.to_le_bytes());
+                let mut dst = [0; RoundConfig::LEN];
+                config.pack_into_slice(&mut dst);
                 buf.extend_from_slice(&dst);
-            },
+            }
+            Self::StartRoundFromTemplate => buf.push(29),
+            Self::SetCategories { shares } => {
+                buf.push(30);
+                buf.push(shares.len() as u8);
+                for share in shares {
+                    buf.extend_from_slice(&share.to_le_bytes());
+                }
+            }
+            &Self::SetProjectCategory { category } => {
+                buf.push(31);
+                buf.push(category);
+            }
+            &Self::AllocateDiscretionary { amount } => {
+                buf.push(32);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            &Self::CastCredits { amount } => {
+                buf.push(33);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            &Self::EpochVote { amount, decimals } => {
+                buf.push(34);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(decimals);
+            }
+            &Self::WithdrawEpoch { epoch } => {
+                buf.push(35);
+                buf.extend_from_slice(&epoch.to_le_bytes());
+            }
+            &Self::CreateRecurringVote {
+                amount,
+                interval,
+                total,
+                decimals,
+            } => {
+                buf.push(36);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&interval.to_le_bytes());
+                buf.extend_from_slice(&total.to_le_bytes());
+                buf.push(decimals);
+            }
+            Self::ExecuteRecurringVote => buf.push(37),
+            Self::CancelRecurringVote => buf.push(38),
+            Self::CrankEndRound => buf.push(39),
+            Self::CrankFinalize => buf.push(40),
+            Self::MintReceipt => buf.push(41),
+            &Self::CreateAirdrop { amount, decimals } => {
+                buf.push(42);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(decimals);
+            }
+            Self::ClaimAirdrop => buf.push(43),
+            &Self::StakePoolVote { amount, decimals } => {
+                buf.push(44);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(decimals);
+            }
+            Self::AddCurator => buf.push(45),
+            Self::AttestProject => buf.push(46),
+            &Self::BlacklistVoter { reverse } => {
+                buf.push(47);
+                buf.push(reverse as u8);
+            }
+            Self::AppealBan => buf.push(48),
+            Self::VerifyInvariants => buf.push(50),
+            Self::SweepDust => buf.push(51),
+            &Self::ResolveAppeal { overturn } => {
+                buf.push(49);
+                buf.push(overturn as u8);
+            }
+            &Self::InitConfig {
+                protocol_fee_bps,
+                ref fee_recipient,
+            } => {
+                buf.push(52);
+                buf.extend_from_slice(&protocol_fee_bps.to_le_bytes());
+                buf.extend_from_slice(fee_recipient.as_ref());
+            }
+            &Self::SetProtocolFee { protocol_fee_bps } => {
+                buf.push(53);
+                buf.extend_from_slice(&protocol_fee_bps.to_le_bytes());
+            }
+            &Self::SetFeeExempt { ref key, exempt } => {
+                buf.push(54);
+                buf.extend_from_slice(key.as_ref());
+                buf.push(exempt as u8);
+            }
+            Self::SetFeeRecipient { fee_recipient } => {
+                buf.push(55);
+                buf.extend_from_slice(fee_recipient.as_ref());
+            }
+            Self::AcceptFeeRecipient => buf.push(56),
+            Self::RecoverAbandonedFunds => buf.push(57),
+            &Self::ProposeAction { kind } => {
+                buf.push(58);
+                buf.push(kind as u8);
+            }
+            Self::CancelAction => buf.push(59),
+            &Self::SetTimelock { timelock } => {
+                buf.push(60);
+                buf.extend_from_slice(&timelock.to_le_bytes());
+            }
+            Self::MigrateVoter => buf.push(61),
+            Self::ArchiveRound => buf.push(62),
+            Self::WriteClaims => buf.push(63),
+            &Self::Downvote { amount, decimals } => {
+                buf.push(64);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(decimals);
+            }
+            Self::FlagProject => buf.push(65),
+            &Self::ResolveFlag { uphold } => {
+                buf.push(66);
+                buf.push(uphold as u8);
+            }
+            Self::SetRegistrationAllowlist { root } => {
+                buf.push(67);
+                buf.extend_from_slice(root);
+            }
+            Self::SetProjectTiers { tiers } => {
+                buf.push(68);
+                buf.push(tiers.len() as u8);
+                for tier in tiers {
+                    let mut dst = [0; Tier::LEN];
+                    tier.pack_into_slice(&mut dst);
+                    buf.extend_from_slice(&dst);
+                }
+            }
+            Self::PostProjectUpdate { hash, body } => {
+                buf.push(69);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(&(body.len() as u16).to_le_bytes());
+                buf.extend_from_slice(body);
+            }
+            Self::Endorse => buf.push(70),
+            &Self::SetMaxRoundDuration { max_round_duration } => {
+                buf.push(71);
+                buf.extend_from_slice(&max_round_duration.to_le_bytes());
+            }
+            &Self::ExtendRound { voting_end } => {
+                buf.push(72);
+                buf.extend_from_slice(&voting_end.to_le_bytes());
+            }
+            Self::VoteWithSwap { decimals, route } => {
+                buf.push(73);
+                buf.push(*decimals);
+                buf.extend_from_slice(route);
+            }
+            &Self::SetApprovedMint { ref mint, approved } => {
+                buf.push(74);
+                buf.extend_from_slice(mint.as_ref());
+                buf.push(approved as u8);
+            }
+            Self::ReleaseHoldback => buf.push(75),
+            Self::InitVoterTree => buf.push(76),
+            Self::CompressedVote {
+                amount,
+                decimals,
+                root,
+                votes,
+                last_vote_slot,
+                proof,
+            } => {
+                buf.push(77);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*decimals);
+                pack_voter_leaf(&mut buf, root, *votes, *last_vote_slot, proof);
+            }
+            Self::CompressedRefund {
+                root,
+                votes,
+                last_vote_slot,
+                proof,
+            } => {
+                buf.push(78);
+                pack_voter_leaf(&mut buf, root, *votes, *last_vote_slot, proof);
+            }
+            &Self::LendFund { amount } => {
+                buf.push(79);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::MintParticipation => buf.push(80),
         };
         buf
     }
 }
+
+fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    if input.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (amount, rest) = input.split_at(8);
+    let amount = amount
+        .try_into()
+        .ok()
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((amount, rest))
+}
+
+/// A root of the voter tree, the votes and last vote slot of a leaf and its proof
+type VoterLeaf = ([u8; 32], u64, u64, Vec<[u8; 32]>);
+
+/// Unpacks a voter tree leaf, the proof is a count then the nodes
+fn unpack_voter_leaf(input: &[u8]) -> Result<VoterLeaf, ProgramError> {
+    let root = input
+        .get(..32)
+        .and_then(|root| root.try_into().ok())
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let (votes, rest) = unpack_u64(&input[32..])?;
+    let (last_vote_slot, rest) = unpack_u64(rest)?;
+    let (&count, rest) = rest
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if count as usize > VOTER_TREE_DEPTH {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let proof = rest
+        .get(..count as usize * 32)
+        .ok_or(ProgramError::InvalidInstructionData)?
+        .chunks(32)
+        .map(|node| node.try_into().unwrap())
+        .collect();
+    Ok((root, votes, last_vote_slot, proof))
+}
+
+fn pack_voter_leaf(
+    buf: &mut Vec<u8>,
+    root: &[u8; 32],
+    votes: u64,
+    last_vote_slot: u64,
+    proof: &[[u8; 32]],
+) {
+    buf.extend_from_slice(root);
+    buf.extend_from_slice(&votes.to_le_bytes());
+    buf.extend_from_slice(&last_vote_slot.to_le_bytes());
+    buf.push(proof.len() as u8);
+    for node in proof {
+        buf.extend_from_slice(node);
+    }
+}
+
+fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+    if input.len() < 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (key, rest) = input.split_at(32);
+    let key = Pubkey::new(key);
+    Ok((key, rest))
+}
+
+/// Creates a `StartRound` instruction. `round` has to be created for the program with
+/// `Round::LEN` bytes and `vault` has to be owned by the program address of `owner`,
+/// of a mint the program config approves.
+pub fn start_round(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    owner: &Pubkey,
+    vault: &Pubkey,
+    config: RoundConfig,
+) -> Instruction {
+    let (program_config, _) = Pubkey::find_program_address(&[b"config"], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*vault, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(program_config, false),
+        ],
+        data: QFInstruction::StartRound { config }.pack(),
+    }
+}
+
+/// Creates a `RegisterProject` instruction for a round without a registration deposit.
+/// The project is the `index`-th address of `owner` in the round, `owner` signs and
+/// pays for it. `proof` is the allowlist proof of `owner`, empty for an open round.
+pub fn register_project(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    owner: &Pubkey,
+    payout_wallet: &Pubkey,
+    category: u8,
+    index: u8,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (project, _) =
+        Pubkey::find_program_address(&[&round.to_bytes(), &owner.to_bytes(), &[index]], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(project, false),
+            AccountMeta::new(*round, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(*payout_wallet, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: QFInstruction::RegisterProject {
+            category,
+            index,
+            proof,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `InitVoter` instruction for `wallet`, the voter and the wallet's round
+/// registry are derived from it. `payer` signs and pays for them.
+pub fn init_voter(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    project: &Pubkey,
+    wallet: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (voter, _) =
+        Pubkey::find_program_address(&[&project.to_bytes(), &wallet.to_bytes()], program_id);
+    let (registry, _) = Pubkey::find_program_address(
+        &[b"registry", &round.to_bytes(), &wallet.to_bytes()],
+        program_id,
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(voter, false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*project, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(*round, false),
+            AccountMeta::new(registry, false),
+        ],
+        data: QFInstruction::InitVoter.pack(),
+    }
+}
+
+/// Creates a `StartVoting` instruction, the round owner signs
+pub fn start_voting(program_id: &Pubkey, round: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: QFInstruction::StartVoting.pack(),
+    }
+}
+
+/// Creates an `EndRound` instruction, the round owner signs
+pub fn end_round(program_id: &Pubkey, round: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: QFInstruction::EndRound.pack(),
+    }
+}
+
+/// Accounts of a Solend reserve the fund of a round is lent to
+pub struct LendingReserve {
+    pub reserve: Pubkey,
+    pub liquidity_supply: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub lending_market: Pubkey,
+}
+
+impl LendingReserve {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let (lending_market_authority, _) =
+            Pubkey::find_program_address(&[self.lending_market.as_ref()], &lending::id());
+        vec![
+            AccountMeta::new(self.reserve, false),
+            AccountMeta::new(self.liquidity_supply, false),
+            AccountMeta::new(self.collateral_mint, false),
+            AccountMeta::new_readonly(self.lending_market, false),
+            AccountMeta::new_readonly(lending_market_authority, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(lending::id(), false),
+        ]
+    }
+}
+
+/// Creates a `LendFund` instruction lending `amount` of the fund to `reserve`, the
+/// round owner signs. `collateral` is a token account of the vault authority for the
+/// reserve's collateral mint. The reserve has to be refreshed in the same transaction.
+pub fn lend_fund(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    owner: &Pubkey,
+    vault: &Pubkey,
+    collateral: &Pubkey,
+    reserve: &LendingReserve,
+    amount: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*round, false),
+        AccountMeta::new_readonly(*owner, true),
+    ];
+    accounts.extend(recall_fund_accounts(
+        program_id, owner, vault, collateral, reserve,
+    ));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: QFInstruction::LendFund { amount }.pack(),
+    }
+}
+
+/// The accounts EndRound and CrankEndRound take after their own while the fund of the
+/// round is lent out, before those of the crank reward. The reserve has to be refreshed
+/// in the same transaction.
+pub fn recall_fund_accounts(
+    program_id: &Pubkey,
+    round_owner: &Pubkey,
+    vault: &Pubkey,
+    collateral: &Pubkey,
+    reserve: &LendingReserve,
+) -> Vec<AccountMeta> {
+    let (vault_owner, _) = Pubkey::find_program_address(&[&round_owner.to_bytes()], program_id);
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(vault_owner, false),
+        AccountMeta::new(*collateral, false),
+    ];
+    accounts.extend(reserve.accounts());
+    accounts
+}
+
+/// Creates a `Finalize` instruction that also writes the claims of `projects`, the
+/// round owner signs and pays for the claims
+pub fn finalize(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    owner: &Pubkey,
+    projects: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*round, false),
+        AccountMeta::new(*owner, true),
+    ];
+    if !projects.is_empty() {
+        accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+        accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+        for project in projects {
+            let (claim, _) =
+                Pubkey::find_program_address(&[b"claim", &project.to_bytes()], program_id);
+            accounts.push(AccountMeta::new_readonly(*project, false));
+            accounts.push(AccountMeta::new(claim, false));
+        }
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: QFInstruction::Finalize.pack(),
+    }
+}
+
+/// Creates a `BanProject` instruction, the round owner signs
+pub fn ban_project(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    owner: &Pubkey,
+    project: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*project, false),
+        ],
+        data: QFInstruction::BanProject.pack(),
+    }
+}
+
+/// Creates a `SetRegistrationAllowlist` instruction, the round owner signs
+pub fn set_registration_allowlist(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    owner: &Pubkey,
+    root: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: QFInstruction::SetRegistrationAllowlist { root }.pack(),
+    }
+}
+
+/// Creates a `SetProjectTiers` instruction, the project owner signs
+pub fn set_project_tiers(
+    program_id: &Pubkey,
+    project: &Pubkey,
+    round: &Pubkey,
+    owner: &Pubkey,
+    tiers: Vec<Tier>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*project, false),
+            AccountMeta::new_readonly(*round, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: QFInstruction::SetProjectTiers { tiers }.pack(),
+    }
+}
+
+/// Creates a `PostProjectUpdate` instruction for the `index`-th update of the project,
+/// its `updates` count. The project owner signs and pays for the update.
+pub fn post_project_update(
+    program_id: &Pubkey,
+    project: &Pubkey,
+    owner: &Pubkey,
+    index: u64,
+    hash: [u8; 32],
+    body: Vec<u8>,
+) -> Instruction {
+    let (update, _) = Pubkey::find_program_address(
+        &[b"update", &project.to_bytes(), &index.to_le_bytes()],
+        program_id,
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*project, false),
+            AccountMeta::new(update, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: QFInstruction::PostProjectUpdate { hash, body }.pack(),
+    }
+}
+
+/// Creates an `Endorse` instruction, `wallet` signs and endorses with its voter of the
+/// project
+pub fn endorse(program_id: &Pubkey, round: &Pubkey, project: &Pubkey, wallet: &Pubkey) -> Instruction {
+    let (voter, _) =
+        Pubkey::find_program_address(&[&project.to_bytes(), &wallet.to_bytes()], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new(*project, false),
+            AccountMeta::new(voter, false),
+            AccountMeta::new_readonly(*wallet, true),
+        ],
+        data: QFInstruction::Endorse.pack(),
+    }
+}
+
+/// Creates an `ExtendRound` instruction moving the voting end to `voting_end`, the
+/// round owner signs
+pub fn extend_round(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    owner: &Pubkey,
+    voting_end: i64,
+) -> Instruction {
+    let (config, _) = Pubkey::find_program_address(&[b"config"], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(config, false),
+        ],
+        data: QFInstruction::ExtendRound { voting_end }.pack(),
+    }
+}
+
+/// Creates a `Withdraw` instruction paying the project to the associated token account
+/// of its payout wallet. The project owner signs and pays for that account if it does
+/// not exist yet.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    round_owner: &Pubkey,
+    vault: &Pubkey,
+    mint: &Pubkey,
+    project: &Pubkey,
+    project_owner: &Pubkey,
+    payout_wallet: &Pubkey,
+) -> Instruction {
+    let (vault_owner, _) = Pubkey::find_program_address(&[&round_owner.to_bytes()], program_id);
+    let (config, _) = Pubkey::find_program_address(&[b"config"], program_id);
+    let (claim, _) = Pubkey::find_program_address(&[b"claim", &project.to_bytes()], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(vault_owner, false),
+            AccountMeta::new(*project, false),
+            AccountMeta::new(*project_owner, true),
+            AccountMeta::new(get_associated_token_address(payout_wallet, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(*payout_wallet, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(claim, false),
+        ],
+        data: QFInstruction::Withdraw.pack(),
+    }
+}
+
+/// Creates a `ReleaseHoldback` instruction paying the project the holdback of its payout,
+/// to the associated token account of its payout wallet. The round owner signs.
+pub fn release_holdback(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    round_owner: &Pubkey,
+    vault: &Pubkey,
+    mint: &Pubkey,
+    project: &Pubkey,
+    payout_wallet: &Pubkey,
+) -> Instruction {
+    let (vault_owner, _) = Pubkey::find_program_address(&[&round_owner.to_bytes()], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new_readonly(*round_owner, true),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(vault_owner, false),
+            AccountMeta::new(*project, false),
+            AccountMeta::new(get_associated_token_address(payout_wallet, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: QFInstruction::ReleaseHoldback.pack(),
+    }
+}
+
+/// Creates a `Refund` instruction giving the votes of `wallet` for the project back to
+/// its token account `to`, the wallet signs
+pub fn refund(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    round_owner: &Pubkey,
+    vault: &Pubkey,
+    project: &Pubkey,
+    to: &Pubkey,
+    wallet: &Pubkey,
+) -> Instruction {
+    let (vault_owner, _) = Pubkey::find_program_address(&[&round_owner.to_bytes()], program_id);
+    let (voter, _) =
+        Pubkey::find_program_address(&[&project.to_bytes(), &wallet.to_bytes()], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*round, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(vault_owner, false),
+            AccountMeta::new_readonly(*project, false),
+            AccountMeta::new(voter, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*wallet, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: QFInstruction::Refund.pack(),
+    }
+}
+
+/// Creates a `Refund` instruction giving the votes `custodian` paid for `wallet` in a
+/// batch back to the custodian's token account `to`, the custodian signs
+#[allow(clippy::too_many_arguments)]
+pub fn refund_to_custodian(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    round_owner: &Pubkey,
+    vault: &Pubkey,
+    project: &Pubkey,
+    to: &Pubkey,
+    custodian: &Pubkey,
+    wallet: &Pubkey,
+) -> Instruction {
+    let mut instruction = refund(program_id, round, round_owner, vault, project, to, wallet);
+    instruction.accounts[6] = AccountMeta::new_readonly(*custodian, true);
+    instruction.accounts.push(AccountMeta::new_readonly(*wallet, false));
+    instruction
+}
+
+/// Creates a `Donate` instruction. `signer_pubkeys` are only needed when
+/// `from_auth` is a multisig.
+#[allow(clippy::too_many_arguments)]
+pub fn donate(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    from: &Pubkey,
+    mint: &Pubkey,
+    vault: &Pubkey,
+    from_auth: &Pubkey,
+    payer: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let (sponsor, _) = Pubkey::find_program_address(
+        &[b"sponsor", &round.to_bytes(), &from.to_bytes()],
+        program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(*round, false),
+        AccountMeta::new(*from, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(*from_auth, signer_pubkeys.is_empty()),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(sponsor, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: QFInstruction::Donate { amount, decimals }.pack(),
+    }
+}
+
+/// Creates a `Vote` instruction. `voter` is derived from the project and `from_auth`,
+/// which signs, and `registry` is the round registry of `from_auth`. A multisig
+/// authority can not vote, extra signer accounts are ignored.
+#[allow(clippy::too_many_arguments)]
+pub fn vote(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    project: &Pubkey,
+    voter: &Pubkey,
+    from: &Pubkey,
+    mint: &Pubkey,
+    vault: &Pubkey,
+    from_auth: &Pubkey,
+    registry: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new(*project, false),
+            AccountMeta::new(*voter, false),
+            AccountMeta::new(*from, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*from_auth, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(*registry, false),
+        ],
+        data: QFInstruction::Vote { amount, decimals }.pack(),
+    }
+}
+
+/// The accounts Vote takes after its own to mint the receipt of `voter`, derived from
+/// `wallet`, if it was not minted yet. `payer` signs and pays for the receipt.
+pub fn receipt_accounts(
+    program_id: &Pubkey,
+    voter: &Pubkey,
+    wallet: &Pubkey,
+    payer: &Pubkey,
+) -> Vec<AccountMeta> {
+    let (receipt_mint, _) =
+        Pubkey::find_program_address(&[b"receipt", &voter.to_bytes()], program_id);
+    vec![
+        AccountMeta::new(receipt_mint, false),
+        AccountMeta::new(metaplex::find_metadata_address(&receipt_mint), false),
+        AccountMeta::new(get_associated_token_address(wallet, &receipt_mint), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(metaplex::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ]
+}
+
+/// Creates a `MintReceipt` instruction for the voter of `wallet` on `project`
+pub fn mint_receipt(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    project: &Pubkey,
+    wallet: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (voter, _) =
+        Pubkey::find_program_address(&[&project.to_bytes(), &wallet.to_bytes()], program_id);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*round, false),
+        AccountMeta::new_readonly(*project, false),
+        AccountMeta::new_readonly(voter, false),
+        AccountMeta::new_readonly(*wallet, false),
+    ];
+    accounts.extend(receipt_accounts(program_id, &voter, wallet, payer));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: QFInstruction::MintReceipt.pack(),
+    }
+}
+
+/// Creates a `MintParticipation` instruction topping up the participation tokens of
+/// `wallet` in `round`, whose mint is `round_mint`. `payer` signs and pays for the
+/// participation mint and the wallet's token account.
+pub fn mint_participation(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    round_mint: &Pubkey,
+    wallet: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (registry, _) = Pubkey::find_program_address(
+        &[b"registry", &round.to_bytes(), &wallet.to_bytes()],
+        program_id,
+    );
+    let (mint, _) =
+        Pubkey::find_program_address(&[b"participation", &round.to_bytes()], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*round, false),
+            AccountMeta::new_readonly(registry, false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*round_mint, false),
+            AccountMeta::new(mint, false),
+            AccountMeta::new(token_2022::get_associated_token_address(wallet, &mint), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(token_2022::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: QFInstruction::MintParticipation.pack(),
+    }
+}
+
+/// Creates an `EpochVote` instruction for `epoch`, the current epoch of the round. The
+/// project is in `category`, the accounts are those of `vote` and the epoch tallies of
+/// the round, the project and the voter, created by `from_auth` on the first vote.
+#[allow(clippy::too_many_arguments)]
+pub fn epoch_vote(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    project: &Pubkey,
+    category: u8,
+    epoch: u64,
+    from: &Pubkey,
+    mint: &Pubkey,
+    vault: &Pubkey,
+    from_auth: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let (voter, _) =
+        Pubkey::find_program_address(&[&project.to_bytes(), &from_auth.to_bytes()], program_id);
+    let (registry, _) = Pubkey::find_program_address(
+        &[b"registry", &round.to_bytes(), &from_auth.to_bytes()],
+        program_id,
+    );
+    let tally = |parent: &Pubkey| {
+        Pubkey::find_program_address(
+            &[
+                b"epoch",
+                &parent.to_bytes(),
+                &epoch.to_le_bytes(),
+                &[category],
+            ],
+            program_id,
+        )
+        .0
+    };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new(*project, false),
+            AccountMeta::new(voter, false),
+            AccountMeta::new(tally(round), false),
+            AccountMeta::new(tally(project), false),
+            AccountMeta::new(tally(&voter), false),
+            AccountMeta::new(*from, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*from_auth, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(registry, false),
+        ],
+        data: QFInstruction::EpochVote { amount, decimals }.pack(),
+    }
+}
+
+/// Creates a `VoteWithSwap` instruction from `route`, the Jupiter route instruction
+/// swapping a token of `from_auth` into `from`, its token account of the round mint.
+/// The accounts are those of `vote`.
+#[allow(clippy::too_many_arguments)]
+pub fn vote_with_swap(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    project: &Pubkey,
+    voter: &Pubkey,
+    from: &Pubkey,
+    mint: &Pubkey,
+    vault: &Pubkey,
+    from_auth: &Pubkey,
+    registry: &Pubkey,
+    decimals: u8,
+    route: Instruction,
+) -> Instruction {
+    let mut instruction = vote(
+        program_id, round, project, voter, from, mint, vault, from_auth, registry, 0, decimals,
+    );
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(route.program_id, false));
+    instruction.accounts.extend(route.accounts);
+    instruction.data = QFInstruction::VoteWithSwap {
+        decimals,
+        route: route.data,
+    }
+    .pack();
+    instruction
+}
+
+/// Creates a `CrankEndRound` instruction, it needs no signer
+pub fn crank_end_round(program_id: &Pubkey, round: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*round, false)],
+        data: QFInstruction::CrankEndRound.pack(),
+    }
+}
+
+/// Creates a `CrankFinalize` instruction, it needs no signer
+pub fn crank_finalize(program_id: &Pubkey, round: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*round, false)],
+        data: QFInstruction::CrankFinalize.pack(),
+    }
+}
+
+/// Creates an `InitVoterTree` instruction for the voter tree of `round`, `payer` signs and
+/// pays for it
+pub fn init_voter_tree(program_id: &Pubkey, round: &Pubkey, payer: &Pubkey) -> Instruction {
+    let (voter_tree, _) =
+        Pubkey::find_program_address(&[b"voter_tree", &round.to_bytes()], program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*round, false),
+            AccountMeta::new(voter_tree, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: QFInstruction::InitVoterTree.pack(),
+    }
+}
+
+/// Creates a `CompressedVote` instruction. `votes` and `last_vote_slot` are those of the
+/// leaf of `from_auth` for the project, `proof` its siblings against `root`. `from_auth`
+/// signs and pays for its round registry.
+#[allow(clippy::too_many_arguments)]
+pub fn compressed_vote(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    project: &Pubkey,
+    from: &Pubkey,
+    mint: &Pubkey,
+    vault: &Pubkey,
+    from_auth: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    root: [u8; 32],
+    votes: u64,
+    last_vote_slot: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (voter_tree, _) =
+        Pubkey::find_program_address(&[b"voter_tree", &round.to_bytes()], program_id);
+    let (registry, _) = Pubkey::find_program_address(
+        &[b"registry", &round.to_bytes(), &from_auth.to_bytes()],
+        program_id,
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*round, false),
+            AccountMeta::new(*project, false),
+            AccountMeta::new(voter_tree, false),
+            AccountMeta::new(*from, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*from_auth, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(registry, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: QFInstruction::CompressedVote {
+            amount,
+            decimals,
+            root,
+            votes,
+            last_vote_slot,
+            proof,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CompressedRefund` instruction giving the votes of `wallet`'s leaf for the
+/// project back to its token account `to`, the wallet signs
+#[allow(clippy::too_many_arguments)]
+pub fn compressed_refund(
+    program_id: &Pubkey,
+    round: &Pubkey,
+    round_owner: &Pubkey,
+    vault: &Pubkey,
+    project: &Pubkey,
+    to: &Pubkey,
+    wallet: &Pubkey,
+    root: [u8; 32],
+    votes: u64,
+    last_vote_slot: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (vault_owner, _) = Pubkey::find_program_address(&[&round_owner.to_bytes()], program_id);
+    let (voter_tree, _) =
+        Pubkey::find_program_address(&[b"voter_tree", &round.to_bytes()], program_id);
+    let (registry, _) = Pubkey::find_program_address(
+        &[b"registry", &round.to_bytes(), &wallet.to_bytes()],
+        program_id,
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*round, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(vault_owner, false),
+            AccountMeta::new_readonly(*project, false),
+            AccountMeta::new(voter_tree, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*wallet, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(registry, false),
+        ],
+        data: QFInstruction::CompressedRefund {
+            root,
+            votes,
+            last_vote_slot,
+            proof,
+        }
+        .pack(),
+    }
+}
@@ -0,0 +1,273 @@
+//! The program config and timelocked actions
+
+use crate::{
+    error::QFError,
+    processor::Processor,
+    state::{ActionKind, Config, PendingAction},
+    validation::{self, RoundAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+impl Processor {
+    /// Proposes a timelocked owner action, it can be executed once the config's timelock passed
+    pub fn process_propose_action(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        kind: ActionKind,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let pending_action_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let config = Self::load_config(program_id, next_account_info(account_info_iter)?)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(rent_info)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let bump_seed = validation::check_pda(
+            pending_action_info,
+            &[b"action", &round_info.key.to_bytes(), &[kind as u8]],
+            program_id,
+            QFError::ActionMismatch,
+        )?;
+        if pending_action_info.owner != program_id {
+            let seeds: &[&[_]] = &[
+                b"action",
+                &round_info.key.to_bytes(),
+                &[kind as u8],
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                owner_info,
+                pending_action_info,
+                system_program_info,
+                rent,
+                PendingAction::LEN,
+                seeds,
+            )?;
+        }
+        let mut pending_action =
+            PendingAction::unpack_unchecked(&pending_action_info.data.borrow())?;
+        if pending_action.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        pending_action.round = *round_info.key;
+        pending_action.kind = kind;
+        pending_action.to = *to_info.key;
+        pending_action.executable_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(config.timelock)
+            .unwrap();
+        PendingAction::pack(pending_action, &mut pending_action_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Drops a proposed action before it is executed
+    pub fn process_cancel_action(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let pending_action_info = next_account_info(account_info_iter)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        validation::check_owner(pending_action_info, program_id)?;
+        let pending_action = PendingAction::unpack(&pending_action_info.data.borrow())?;
+        if pending_action.round != *round_info.key {
+            return Err(QFError::ActionMismatch.into());
+        }
+
+        PendingAction::pack(
+            PendingAction::default(),
+            &mut pending_action_info.data.borrow_mut(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Sets the delay between proposing and executing a timelocked owner action
+    pub fn process_set_timelock(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        timelock: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(config_info, program_id)?;
+        let mut config = Config::unpack(&config_info.data.borrow())?;
+
+        if admin_info.key != &config.admin {
+            return Err(QFError::AdminMismatch.into());
+        }
+        validation::check_signer(admin_info)?;
+        if timelock < 0 {
+            return Err(QFError::InvalidTimelock.into());
+        }
+
+        config.timelock = timelock;
+        Config::pack(config, &mut config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Sets how long after the start of voting ExtendRound can push a round's voting end
+    pub fn process_set_max_round_duration(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_round_duration: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(config_info, program_id)?;
+        let mut config = Config::unpack(&config_info.data.borrow())?;
+
+        if admin_info.key != &config.admin {
+            return Err(QFError::AdminMismatch.into());
+        }
+        validation::check_signer(admin_info)?;
+        if max_round_duration < 0 {
+            return Err(QFError::InvalidDeadline.into());
+        }
+
+        config.max_round_duration = max_round_duration;
+        Config::pack(config, &mut config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Approves a mint for the vaults of new rounds, or removes the approval
+    pub fn process_set_approved_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        mint: Pubkey,
+        approved: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(config_info, program_id)?;
+        let mut config = Config::unpack(&config_info.data.borrow())?;
+
+        if admin_info.key != &config.admin {
+            return Err(QFError::AdminMismatch.into());
+        }
+        validation::check_signer(admin_info)?;
+
+        // the default key is a free slot, approving it changes nothing
+        let slot = config.approved_mints.iter().position(|m| *m == mint);
+        match (approved, slot) {
+            (true, None) => {
+                let free = config
+                    .approved_mints
+                    .iter()
+                    .position(|m| *m == Pubkey::default())
+                    .ok_or(QFError::ApprovedMintsFull)?;
+                config.approved_mints[free] = mint;
+            }
+            (false, Some(i)) => config.approved_mints[i] = Pubkey::default(),
+            _ => {}
+        }
+        Config::pack(config, &mut config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Creates the program config. Only the program's upgrade authority can do it.
+    pub fn process_init_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        protocol_fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let program_data_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(rent_info)?;
+
+        validation::check_signer(admin_info)?;
+        if protocol_fee_bps > 10_000 {
+            return Err(QFError::InvalidFeeBps.into());
+        }
+        if !cfg!(feature = "fees") && protocol_fee_bps > 0 {
+            return Err(QFError::FeesDisabled.into());
+        }
+
+        // the program data account keeps the upgrade authority as an Option<Pubkey>
+        // after the u32 state tag and the u64 deploy slot
+        let (program_data_key, _) =
+            Pubkey::find_program_address(&[&program_id.to_bytes()], &bpf_loader_upgradeable::id());
+        if program_data_info.key != &program_data_key {
+            return Err(QFError::ProgramDataMismatch.into());
+        }
+        let program_data = program_data_info.data.borrow();
+        if program_data.len() < 45 || program_data[0..4] != 3u32.to_le_bytes() {
+            return Err(QFError::ProgramDataMismatch.into());
+        }
+        if program_data[12] != 1 || program_data[13..45] != admin_info.key.to_bytes() {
+            return Err(QFError::AdminMismatch.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            config_info,
+            &[b"config"],
+            program_id,
+            QFError::ConfigMismatch,
+        )?;
+        if config_info.owner == program_id {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        let seeds: &[&[_]] = &[b"config", &[bump_seed]];
+        Self::create_pda_account(
+            program_id,
+            admin_info,
+            config_info,
+            system_program_info,
+            rent,
+            Config::LEN,
+            seeds,
+        )?;
+
+        let config = Config {
+            admin: *admin_info.key,
+            protocol_fee_bps,
+            fee_recipient,
+            ..Config::default()
+        };
+        Config::pack(config, &mut config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
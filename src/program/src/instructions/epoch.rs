@@ -0,0 +1,319 @@
+//! Votes and withdrawals of an Epochs round
+
+use crate::{
+    error::QFError,
+    events::VoteKind,
+    processor::Processor,
+    state::{EpochTally, RoundMode, RoundStatus, Voter, VoterRegistry},
+    validation::{self, ProjectAccount, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_math::{
+    precise_number::{PreciseNumber, ONE},
+    uint::U256,
+};
+
+impl Processor {
+    /// Votes in the current epoch of an Epochs round, the tally starts over every epoch.
+    /// The voter account is checked like for Vote and keeps the tokens for a refund.
+    pub fn process_epoch_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let round_tally_info = next_account_info(account_info_iter)?;
+        let project_tally_info = next_account_info(account_info_iter)?;
+        let voter_tally_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if round.config.mode != RoundMode::Epochs {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+        let now = Clock::get()?.unix_timestamp;
+        let epoch = round.epoch_at(now);
+        if epoch >= round.config.epoch_count {
+            return Err(QFError::InvalidEpoch.into());
+        }
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_signer(from_auth_info)?;
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &from_auth_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+        if voter.blacklisted {
+            return Err(QFError::VoterBlacklisted.into());
+        }
+        Self::check_vote_cooldown(&round, &mut voter)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let mut round_tally = Self::epoch_tally(
+            program_id,
+            from_auth_info,
+            round_tally_info,
+            system_program_info,
+            rent,
+            round_info.key,
+            epoch,
+            project.category,
+        )?;
+        let mut project_tally = Self::epoch_tally(
+            program_id,
+            from_auth_info,
+            project_tally_info,
+            system_program_info,
+            rent,
+            project_info.key,
+            epoch,
+            project.category,
+        )?;
+        let mut voter_tally = Self::epoch_tally(
+            program_id,
+            from_auth_info,
+            voter_tally_info,
+            system_program_info,
+            rent,
+            voter_info.key,
+            epoch,
+            project.category,
+        )?;
+
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            to_info,
+            from_auth_info,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        // like Vote, the round area only holds the areas of the projects the round matches
+        let matched = round.matches(&project);
+        if matched {
+            round_tally.area = round_tally.area.checked_sub(project_tally.area).unwrap();
+        }
+
+        let new_votes_sqrt = PreciseNumber {
+            value: U256::from(voter_tally.votes.checked_add(amount).unwrap())
+                .checked_mul(U256::from(ONE))
+                .unwrap(),
+        }
+        .sqrt()
+        .unwrap();
+        let project_area_sqrt = PreciseNumber {
+            value: project_tally.sqrt,
+        }
+        .checked_sub(&PreciseNumber {
+            value: voter_tally.sqrt,
+        })
+        .unwrap()
+        .checked_add(&new_votes_sqrt)
+        .unwrap();
+        project_tally.area = project_area_sqrt.checked_pow(2).unwrap().value;
+        project_tally.sqrt = project_area_sqrt.value;
+
+        if matched {
+            round_tally.area = round_tally.area.checked_add(project_tally.area).unwrap();
+        }
+
+        voter_tally.votes = voter_tally.votes.checked_add(amount).unwrap();
+        voter_tally.sqrt = new_votes_sqrt.value;
+        project_tally.votes = project_tally.votes.checked_add(amount).unwrap();
+        round_tally.votes = round_tally.votes.checked_add(amount).unwrap();
+
+        // the tokens count for the quorum, a cancelled round refunds them to the voter
+        voter.votes = voter.votes.checked_add(amount).unwrap();
+        voter.weight = voter.weight.checked_add(amount).unwrap();
+        voter.last_vote_at = now;
+        project.votes = project.votes.checked_add(amount).unwrap();
+        round.votes = round.votes.checked_add(amount).unwrap();
+
+        EpochTally::pack(round_tally, &mut round_tally_info.data.borrow_mut())?;
+        EpochTally::pack(project_tally, &mut project_tally_info.data.borrow_mut())?;
+        EpochTally::pack(voter_tally, &mut voter_tally_info.data.borrow_mut())?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        project.save()?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            from_auth_info.key,
+            amount,
+            VoteKind::Vote,
+        )?;
+
+        let mut registry = Self::voter_registry(
+            program_id,
+            from_auth_info,
+            registry_info,
+            system_program_info,
+            rent,
+            round_info.key,
+            from_auth_info.key,
+        )?;
+        Self::add_registry_vote(&mut round, &mut registry, amount, amount, now);
+        VoterRegistry::pack(registry, &mut registry_info.data.borrow_mut())?;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Pays a project its votes and match of an epoch once the round is finalized, out of
+    /// what the round can still pay and with the fees and holdback of Withdraw, until the
+    /// claim deadline. A banned project only gets the votes.
+    pub fn process_withdraw_epoch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        epoch: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let project_owner_info = next_account_info(account_info_iter)?;
+        let round_tally_info = next_account_info(account_info_iter)?;
+        let project_tally_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let config = Self::load_config(program_id, next_account_info(account_info_iter)?)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if round.config.mode != RoundMode::Epochs {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        // Finalize locked the payouts, a cancelled round refunds the votes instead
+        validation::check_status(&round, RoundStatus::Claim)?;
+        if epoch >= round.config.epoch_count {
+            return Err(QFError::InvalidEpoch.into());
+        }
+        // what is left after the claim deadline goes to SweepDust or RolloverFunds
+        let claim_deadline = round
+            .voting_ended_at
+            .checked_add(round.config.dispute_window)
+            .unwrap()
+            .checked_add(round.config.claim_period)
+            .unwrap();
+        if round.config.claim_period != 0 && Clock::get()?.unix_timestamp >= claim_deadline {
+            return Err(QFError::ClaimPeriodOver.into());
+        }
+        VaultAccount::load(vault_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        validation::check_signer(project_owner_info)?;
+        if project.owner != *project_owner_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+
+        let round_tally = Self::unpack_epoch_tally(
+            program_id,
+            round_tally_info,
+            round_info.key,
+            epoch,
+            project.category,
+        )?;
+        let mut project_tally = Self::unpack_epoch_tally(
+            program_id,
+            project_tally_info,
+            project_info.key,
+            epoch,
+            project.category,
+        )?;
+        if project_tally.withdrawn {
+            return Err(QFError::ProjectAlreadyWithdraw.into());
+        }
+
+        if to_info.key != &get_associated_token_address(&project.payout_wallet, &round.mint) {
+            return Err(QFError::RecipientMismatch.into());
+        }
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let mut amount = project_tally.votes;
+        if round.matches(&project) && !round_tally.area.is_zero() {
+            // every epoch gets the same part of the category's pool, shared by the area
+            // of the category in the epoch
+            let (fund, _) = round.matching_pool(project.category);
+            amount = amount
+                .checked_add(
+                    fund.checked_div(U256::from(round.config.epoch_count))
+                        .unwrap()
+                        .checked_mul(project_tally.area)
+                        .unwrap()
+                        .checked_div(round_tally.area)
+                        .unwrap()
+                        .as_u64(),
+                )
+                .unwrap();
+        }
+
+        let exempt = config.is_fee_exempt(round_info.key) || config.is_fee_exempt(&project.owner);
+        let amount = Self::settle_payout(&mut round, &config, &mut project, amount, exempt);
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                vault_info.key,
+                to_info.key,
+                vault_owner_info.key,
+                &[vault_owner_info.key],
+                amount,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        project_tally.withdrawn = true;
+        EpochTally::pack(project_tally, &mut project_tally_info.data.borrow_mut())?;
+
+        project.save()?;
+        round.save()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+//! Protocol and operator fees, left out of builds without the `fees` feature
+
+use crate::{
+    error::QFError,
+    processor::Processor,
+    state::{ActionKind, Config, RoundStatus},
+    validation::{self, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+impl Processor {
+    /// Pays the operator fee to the round owner's account and the protocol fee to a
+    /// token account of the config's fee recipient
+    pub fn process_withdraw_fee(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let config = Self::load_config(program_id, next_account_info(account_info_iter)?)?;
+        let protocol_to_info = next_account_info(account_info_iter)?;
+        let pending_action_info = next_account_info(account_info_iter).ok();
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(round.status, RoundStatus::Claim | RoundStatus::Closed) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        if round.fees() == 0 {
+            return Err(QFError::NoFeesDue.into());
+        }
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        VaultAccount::load(vault_info, &round)?;
+        let (vault_authority, bump_seed) =
+            Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id);
+        if vault_owner_info.key != &vault_authority {
+            return Err(QFError::VaultAuthorityMismatch.into());
+        }
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        Self::execute_timelocked(
+            program_id,
+            &config,
+            round_info.key,
+            ActionKind::WithdrawFee,
+            to_info.key,
+            pending_action_info,
+        )?;
+
+        let seeds: &[&[_]] = &[&round.owner.to_bytes(), &[bump_seed]];
+
+        if round.operator_fee > 0 {
+            invoke_signed(
+                &spl_token::instruction::transfer(
+                    &token_program_info.key,
+                    &vault_info.key,
+                    &to_info.key,
+                    &vault_owner_info.key,
+                    &[&vault_owner_info.key],
+                    round.operator_fee,
+                )?,
+                &[
+                    vault_info.clone(),
+                    to_info.clone(),
+                    vault_owner_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&seeds],
+            )?;
+            round.operator_fee = 0;
+        }
+
+        if round.protocol_fee > 0 {
+            Self::check_fee_recipient(&config, protocol_to_info)?;
+            invoke_signed(
+                &spl_token::instruction::transfer(
+                    &token_program_info.key,
+                    &vault_info.key,
+                    &protocol_to_info.key,
+                    &vault_owner_info.key,
+                    &[&vault_owner_info.key],
+                    round.protocol_fee,
+                )?,
+                &[
+                    vault_info.clone(),
+                    protocol_to_info.clone(),
+                    vault_owner_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&seeds],
+            )?;
+            round.protocol_fee = 0;
+        }
+
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Changes the protocol fee, applies to payouts made from then on
+    pub fn process_set_protocol_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        protocol_fee_bps: u16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(config_info, program_id)?;
+        let mut config = Config::unpack(&config_info.data.borrow())?;
+
+        if admin_info.key != &config.admin {
+            return Err(QFError::AdminMismatch.into());
+        }
+        validation::check_signer(admin_info)?;
+        if protocol_fee_bps > 10_000 {
+            return Err(QFError::InvalidFeeBps.into());
+        }
+
+        config.protocol_fee_bps = protocol_fee_bps;
+        Config::pack(config, &mut config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Adds a round or a project owner to the fee exemptions of the config, or removes it
+    pub fn process_set_fee_exempt(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        key: Pubkey,
+        exempt: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(config_info, program_id)?;
+        let mut config = Config::unpack(&config_info.data.borrow())?;
+
+        if admin_info.key != &config.admin {
+            return Err(QFError::AdminMismatch.into());
+        }
+        validation::check_signer(admin_info)?;
+        if key == Pubkey::default() {
+            return Err(QFError::InvalidFeeExemptKey.into());
+        }
+
+        let slot = config.fee_exempt.iter().position(|k| *k == key);
+        match (exempt, slot) {
+            (true, None) => {
+                let free = config
+                    .fee_exempt
+                    .iter()
+                    .position(|k| *k == Pubkey::default())
+                    .ok_or(QFError::FeeExemptFull)?;
+                config.fee_exempt[free] = key;
+            }
+            (false, Some(i)) => config.fee_exempt[i] = Pubkey::default(),
+            _ => {}
+        }
+        Config::pack(config, &mut config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Proposes a new fee recipient, it has to accept with AcceptFeeRecipient
+    pub fn process_set_fee_recipient(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fee_recipient: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(config_info, program_id)?;
+        let mut config = Config::unpack(&config_info.data.borrow())?;
+
+        if admin_info.key != &config.admin {
+            return Err(QFError::AdminMismatch.into());
+        }
+        validation::check_signer(admin_info)?;
+
+        // a default key cancels the proposal
+        config.pending_fee_recipient = fee_recipient;
+        Config::pack(config, &mut config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// The proposed fee recipient takes over the protocol fee
+    pub fn process_accept_fee_recipient(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let fee_recipient_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(config_info, program_id)?;
+        let mut config = Config::unpack(&config_info.data.borrow())?;
+
+        if config.pending_fee_recipient == Pubkey::default()
+            || fee_recipient_info.key != &config.pending_fee_recipient
+        {
+            return Err(QFError::FeeRecipientMismatch.into());
+        }
+        validation::check_signer(fee_recipient_info)?;
+
+        config.fee_recipient = config.pending_fee_recipient;
+        config.pending_fee_recipient = Pubkey::default();
+        Config::pack(config, &mut config_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+//! Instruction handlers, each module adds the `process_*` functions of a group of
+//! instructions to the [`Processor`](crate::processor::Processor)
+
+mod config;
+mod epoch;
+#[cfg(feature = "fees")]
+mod fees;
+#[cfg(feature = "moderation")]
+mod moderation;
+mod project;
+mod recurring;
+mod rewards;
+mod round;
+mod sponsor;
+mod vote;
+mod voter;
+mod voter_tree;
+mod withdraw;
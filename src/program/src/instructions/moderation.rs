@@ -0,0 +1,473 @@
+//! Project bans, appeals, flags and voter blacklists, left out of builds without the
+//! `moderation` feature
+
+use crate::{
+    error::QFError,
+    events::{self, Event},
+    processor::Processor,
+    state::{AppealStatus, Flag, Project, Round, RoundStatus, Voter},
+    validation::{self, ProjectAccount, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_math::{precise_number::PreciseNumber, uint::U256};
+
+impl Processor {
+    pub fn process_ban_project(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting | RoundStatus::Tallying
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        if project.banned {
+            return Err(QFError::ProjectBanned.into());
+        }
+
+        let deposit = Self::ban(&mut round, &mut project);
+        events::emit(
+            round_info.key,
+            &Event::ProjectBanned {
+                project: *project_info.key,
+                deposit,
+            },
+        );
+
+        round.save()?;
+        project.save()?;
+
+        Ok(())
+    }
+
+    /// Takes a project out of the matching and forfeits its registration deposit into
+    /// the fund. Returns the forfeited deposit.
+    pub fn ban(round: &mut Round, project: &mut Project) -> u64 {
+        // the whole area leaves the round, the votes stay on the project so they can
+        // still be revoked and the area comes back if an appeal overturns the ban
+        if round.matches(project) {
+            round.sub_area(project.category, project.area);
+        }
+        project.banned = true;
+        project.banned_area = project.area;
+
+        // the registration deposit is forfeited into the matching pool
+        let deposit = project.deposit;
+        round.fund = round.fund.checked_add(deposit).unwrap();
+        round.deposits = round.deposits.checked_sub(deposit).unwrap();
+        project.banned_deposit = project.banned_deposit.checked_add(deposit).unwrap();
+        project.deposit = 0;
+        deposit
+    }
+
+    /// Appeals a ban during Tallying, the project owner bonds `appeal_bond` tokens
+    pub fn process_appeal_ban(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let project_owner_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Tallying)?;
+        VaultAccount::load(to_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        validation::check_signer(project_owner_info)?;
+        if project.owner != *project_owner_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        // a ban is appealed once
+        if !project.banned || project.appeal != AppealStatus::None {
+            return Err(QFError::InvalidAppeal.into());
+        }
+
+        let bond = round.config.appeal_bond;
+        if bond > 0 {
+            if token_program_info.key != &spl_token::ID {
+                return Err(QFError::UnexpectedTokenProgramID.into());
+            }
+            let decimals = spl_token::state::Mint::unpack(&mint_info.data.borrow())?.decimals;
+            Self::transfer_checked_with_signers(
+                token_program_info,
+                from_info,
+                mint_info,
+                to_info,
+                project_owner_info,
+                &[],
+                bond,
+                decimals,
+            )?;
+            round.deposits = round.deposits.checked_add(bond).unwrap();
+        }
+
+        project.appeal = AppealStatus::Pending;
+        project.appeal_bond = bond;
+        round.pending_appeals = round.pending_appeals.checked_add(1).unwrap();
+
+        round.save()?;
+        project.save()?;
+
+        Ok(())
+    }
+
+    /// Resolves an appeal, the round owner or the moderator can do it. An overturned ban
+    /// gives the project its area and deposit back and the bond is paid with them on
+    /// Withdraw, an upheld ban sends the bond to the fund.
+    pub fn process_resolve_appeal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        overturn: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Tallying)?;
+
+        if authority_info.key != &round.owner
+            && (round.config.moderator == Pubkey::default()
+                || authority_info.key != &round.config.moderator)
+        {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(authority_info)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        if project.appeal != AppealStatus::Pending {
+            return Err(QFError::InvalidAppeal.into());
+        }
+
+        if overturn {
+            project.banned = false;
+            project.banned_area = U256::zero();
+            if round.matches(&project) {
+                round.add_area(project.category, project.area);
+            }
+
+            // the bond stays in the deposits and is paid back with the deposit
+            round.fund = round.fund.checked_sub(project.banned_deposit).unwrap();
+            round.deposits = round.deposits.checked_add(project.banned_deposit).unwrap();
+            project.deposit = project
+                .banned_deposit
+                .checked_add(project.appeal_bond)
+                .unwrap();
+            project.banned_deposit = 0;
+            project.appeal = AppealStatus::Overturned;
+        } else {
+            round.fund = round.fund.checked_add(project.appeal_bond).unwrap();
+            round.deposits = round.deposits.checked_sub(project.appeal_bond).unwrap();
+            project.appeal = AppealStatus::Upheld;
+        }
+        project.appeal_bond = 0;
+        round.pending_appeals = round.pending_appeals.checked_sub(1).unwrap();
+
+        round.save()?;
+        project.save()?;
+
+        Ok(())
+    }
+
+    /// Flags a project for review, the flagger stakes `flag_stake` tokens in the vault.
+    /// A wallet flags a project once, the stake is held until the flag is resolved.
+    pub fn process_flag_project(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let flag_info = next_account_info(account_info_iter)?;
+        let flagger_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting | RoundStatus::Tallying
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        let stake = round.config.flag_stake;
+        if stake == 0 {
+            return Err(QFError::FlaggingDisabled.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        let project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        if project.banned {
+            return Err(QFError::ProjectBanned.into());
+        }
+
+        validation::check_signer(flagger_info)?;
+        let project_key = project_info.key.to_bytes();
+        let flagger_key = flagger_info.key.to_bytes();
+        let bump_seed = validation::check_pda(
+            flag_info,
+            &[b"flag", &project_key, &flagger_key],
+            program_id,
+            QFError::FlagMismatch,
+        )?;
+        if flag_info.owner == program_id {
+            return Err(QFError::InvalidFlag.into());
+        }
+        Self::create_pda_account(
+            program_id,
+            flagger_info,
+            flag_info,
+            system_program_info,
+            rent,
+            Flag::LEN,
+            &[b"flag", &project_key, &flagger_key, &[bump_seed]],
+        )?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+        let decimals = spl_token::state::Mint::unpack(&mint_info.data.borrow())?.decimals;
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            to_info,
+            flagger_info,
+            &[],
+            stake,
+            decimals,
+        )?;
+        round.deposits = round.deposits.checked_add(stake).unwrap();
+        round.pending_flags = round.pending_flags.checked_add(1).unwrap();
+
+        let mut flag = Flag::unpack_unchecked(&flag_info.data.borrow())?;
+        flag.round = *round_info.key;
+        flag.project = *project_info.key;
+        flag.flagger = *flagger_info.key;
+        flag.stake = stake;
+
+        round.save()?;
+        Flag::pack(flag, &mut flag_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Resolves a flag, the round owner or the moderator can do it. Upholding needs the
+    /// project to be banned without a pending appeal, the flagger gets the stake back and
+    /// `flag_bounty_bps` of the banned deposit, and the ban can not be appealed anymore.
+    /// A rejected flag sends the stake to the fund. In a cancelled round the flagger can
+    /// resolve it too and the stake is paid back. The flag is closed to the flagger.
+    pub fn process_resolve_flag(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        uphold: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let flag_info = next_account_info(account_info_iter)?;
+        let flagger_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        let cancelled = round.status == RoundStatus::Cancelled;
+        if !cancelled
+            && !matches!(
+                round.status,
+                RoundStatus::Registration | RoundStatus::Voting | RoundStatus::Tallying
+            )
+        {
+            return Err(QFError::RoundStatusError.into());
+        }
+        VaultAccount::load(vault_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_owner(flag_info, program_id)?;
+        let flag = Flag::unpack(&flag_info.data.borrow())?;
+        if flag.project != *project_info.key || flag.flagger != *flagger_info.key {
+            return Err(QFError::FlagMismatch.into());
+        }
+
+        if authority_info.key != &round.owner
+            && (round.config.moderator == Pubkey::default()
+                || authority_info.key != &round.config.moderator)
+            && !(cancelled && authority_info.key == flagger_info.key)
+        {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(authority_info)?;
+
+        round.deposits = round.deposits.checked_sub(flag.stake).unwrap();
+        let paid = if cancelled {
+            flag.stake
+        } else if uphold {
+            if !project.banned || project.appeal == AppealStatus::Pending {
+                return Err(QFError::InvalidFlag.into());
+            }
+            let bounty = Self::bps_of(project.banned_deposit, round.config.flag_bounty_bps);
+            project.banned_deposit = project.banned_deposit.checked_sub(bounty).unwrap();
+            round.fund = round.fund.checked_sub(bounty).unwrap();
+            // the flag reviewed the ban, an appeal could give back a deposit already paid
+            if project.appeal == AppealStatus::None {
+                project.appeal = AppealStatus::Upheld;
+            }
+            flag.stake.checked_add(bounty).unwrap()
+        } else {
+            round.fund = round.fund.checked_add(flag.stake).unwrap();
+            0
+        };
+        round.pending_flags = round.pending_flags.checked_sub(1).unwrap();
+
+        if paid > 0 {
+            let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+            if to.owner != flag.flagger {
+                return Err(QFError::OwnerMismatch.into());
+            }
+            let bump_seed = validation::check_pda(
+                vault_owner_info,
+                &[&round.owner.to_bytes()],
+                program_id,
+                QFError::VaultAuthorityMismatch,
+            )?;
+            if token_program_info.key != &spl_token::ID {
+                return Err(QFError::UnexpectedTokenProgramID.into());
+            }
+            invoke_signed(
+                &spl_token::instruction::transfer(
+                    token_program_info.key,
+                    vault_info.key,
+                    to_info.key,
+                    vault_owner_info.key,
+                    &[vault_owner_info.key],
+                    paid,
+                )?,
+                &[
+                    vault_info.clone(),
+                    to_info.clone(),
+                    vault_owner_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&[&round.owner.to_bytes(), &[bump_seed]]],
+            )?;
+        }
+
+        round.save()?;
+        project.save()?;
+        Self::close_account(flag_info, flagger_info)?;
+
+        Ok(())
+    }
+
+    /// Blacklists voters so they can not vote anymore, the round owner or the moderator
+    /// can do it until the round is finalized. With `reverse` the area the voters added to
+    /// their projects is taken out, their tokens stay with the projects.
+    pub fn process_blacklist_voter(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        reverse: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting | RoundStatus::Tallying
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        if authority_info.key != &round.owner
+            && (round.config.moderator == Pubkey::default()
+                || authority_info.key != &round.config.moderator)
+        {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(authority_info)?;
+
+        // the rest of the accounts are (project, voter, wallet of the voter)
+        while let Ok(project_info) = next_account_info(account_info_iter) {
+            let voter_info = next_account_info(account_info_iter)?;
+            let wallet_info = next_account_info(account_info_iter)?;
+
+            let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+            validation::check_owner(voter_info, program_id)?;
+            validation::check_pda(
+                voter_info,
+                &[&project_info.key.to_bytes(), &wallet_info.key.to_bytes()],
+                program_id,
+                QFError::VoterMismatch,
+            )?;
+            let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+            if voter.blacklisted {
+                continue;
+            }
+            voter.blacklisted = true;
+
+            if reverse && (!voter.votes_sqrt.is_zero() || voter.downvotes > 0) {
+                let matched = round.matches(&project);
+                if matched {
+                    round.sub_area(project.category, project.area);
+                }
+                let project_area_sqrt = PreciseNumber {
+                    value: project.area_sqrt,
+                }
+                .checked_sub(&PreciseNumber {
+                    value: voter.votes_sqrt,
+                })
+                .unwrap();
+                project.area_sqrt = project_area_sqrt.value;
+                project.down_sqrt = project
+                    .down_sqrt
+                    .checked_sub(Self::votes_sqrt(voter.downvotes))
+                    .unwrap();
+                project.area = Self::project_area(
+                    project.area_sqrt,
+                    project.down_sqrt,
+                    round.config.endorsement_bonus_bps(project.endorsements),
+                );
+                if matched {
+                    round.add_area(project.category, project.area);
+                }
+                voter.votes_sqrt = U256::zero();
+                voter.downvotes = 0;
+                project.save()?;
+            }
+            Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        }
+
+        round.save()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,400 @@
+//! Projects: registration and curation
+
+use crate::{
+    allowlist,
+    error::QFError,
+    metaplex,
+    processor::Processor,
+    state::{
+        Curator, CuratorAttestation, Project, ProjectUpdate, RoundStatus, Tier,
+        MAX_PROJECTS_PER_OWNER, MAX_TIERS,
+    },
+    validation::{self, ProjectAccount, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_math::uint::U256;
+
+impl Processor {
+    pub fn process_register_project(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        category: u8,
+        index: u8,
+        proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let new_project_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let project_owner_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let payout_wallet_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Registration)?;
+        if category >= round.category_count.max(1) {
+            return Err(QFError::InvalidCategory.into());
+        }
+        if round.config.max_projects > 0 && round.project_count >= round.config.max_projects {
+            return Err(QFError::ProjectLimitReached.into());
+        }
+
+        // an owner has a few project addresses per round, so it can not flood the round
+        if index >= MAX_PROJECTS_PER_OWNER {
+            return Err(QFError::InvalidProjectIndex.into());
+        }
+        let bump_seed = validation::check_pda(
+            new_project_info,
+            &[
+                &round_info.key.to_bytes(),
+                &project_owner_info.key.to_bytes(),
+                &[index],
+            ],
+            program_id,
+            QFError::ProjectMismatch,
+        )?;
+        validation::check_signer(project_owner_info)?;
+        if round.registration_root != [0; 32]
+            && !allowlist::verify(&round.registration_root, project_owner_info.key, &proof)
+        {
+            return Err(QFError::NotAllowlisted.into());
+        }
+        if new_project_info.owner != program_id {
+            let seeds: &[&[_]] = &[
+                &round_info.key.to_bytes(),
+                &project_owner_info.key.to_bytes(),
+                &[index],
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                project_owner_info,
+                new_project_info,
+                system_program_info,
+                rent,
+                Project::LEN,
+                seeds,
+            )?;
+        }
+        let mut project = Project::unpack_unchecked(&new_project_info.data.borrow())?;
+        if project.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        project.round = *round_info.key;
+        project.owner = *project_owner_info.key;
+        project.withdraw = false;
+        project.votes = 0;
+        project.area = U256::zero();
+        project.payout_wallet = *payout_wallet_info.key;
+        project.deposit = 0;
+        project.banned = false;
+        project.category = category;
+        project.index = round.project_count;
+
+        let deposit = round.config.registration_deposit;
+        if deposit > 0 {
+            let from_info = next_account_info(account_info_iter)?;
+            let mint_info = next_account_info(account_info_iter)?;
+            let to_info = next_account_info(account_info_iter)?;
+            let from_auth_info = next_account_info(account_info_iter)?;
+            let token_program_info = next_account_info(account_info_iter)?;
+
+            VaultAccount::load(to_info, &round)?;
+            if token_program_info.key != &spl_token::ID {
+                return Err(QFError::UnexpectedTokenProgramID.into());
+            }
+
+            msg!("Deposit {} tokens for the registration", deposit);
+            let decimals = spl_token::state::Mint::unpack(&mint_info.data.borrow())?.decimals;
+            Self::transfer_checked_with_signers(
+                token_program_info,
+                from_info,
+                mint_info,
+                to_info,
+                from_auth_info,
+                &[],
+                deposit,
+                decimals,
+            )?;
+
+            project.deposit = deposit;
+            round.deposits = round.deposits.checked_add(deposit).unwrap();
+        }
+
+        round.project_count = round.project_count.checked_add(1).unwrap();
+        round.save()?;
+
+        // an optional Metaplex metadata account gives the project its name and image
+        if let Ok(metadata_info) = next_account_info(account_info_iter) {
+            let metadata = metaplex::unpack(metadata_info)?;
+            if metadata.update_authority != *project_owner_info.key {
+                return Err(QFError::OwnerMismatch.into());
+            }
+            match metadata.verified_collection {
+                Some(collection)
+                    if round.config.collection == Pubkey::default()
+                        || round.config.collection == collection => {}
+                _ => return Err(QFError::CollectionMismatch.into()),
+            }
+            project.metadata = *metadata_info.key;
+        }
+
+        Project::pack(project, &mut new_project_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Adds a curator key to the round, only the round owner can do it
+    pub fn process_add_curator(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let curator_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let curator_key_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let bump_seed = validation::check_pda(
+            curator_info,
+            &[
+                b"curator",
+                &round_info.key.to_bytes(),
+                &curator_key_info.key.to_bytes(),
+            ],
+            program_id,
+            QFError::CuratorMismatch,
+        )?;
+        if curator_info.owner == program_id {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let seeds: &[&[_]] = &[
+            b"curator",
+            &round_info.key.to_bytes(),
+            &curator_key_info.key.to_bytes(),
+            &[bump_seed],
+        ];
+        Self::create_pda_account(
+            program_id,
+            owner_info,
+            curator_info,
+            system_program_info,
+            rent,
+            Curator::LEN,
+            seeds,
+        )?;
+
+        let curator = Curator {
+            round: *round_info.key,
+            curator: *curator_key_info.key,
+        };
+        Curator::pack(curator, &mut curator_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Marks a project verified, only a curator of the round can do it
+    pub fn process_attest_project(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let attestation_info = next_account_info(account_info_iter)?;
+        let curator_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let curator_key_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting | RoundStatus::Tallying
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        if project.verified {
+            return Err(QFError::ProjectAlreadyVerified.into());
+        }
+
+        validation::check_owner(curator_info, program_id)?;
+        let curator = Curator::unpack(&curator_info.data.borrow())?;
+        if curator.round != *round_info.key || curator.curator != *curator_key_info.key {
+            return Err(QFError::CuratorMismatch.into());
+        }
+        validation::check_signer(curator_key_info)?;
+
+        let bump_seed = validation::check_pda(
+            attestation_info,
+            &[b"attestation", &project_info.key.to_bytes()],
+            program_id,
+            QFError::CuratorMismatch,
+        )?;
+        let seeds: &[&[_]] = &[b"attestation", &project_info.key.to_bytes(), &[bump_seed]];
+        Self::create_pda_account(
+            program_id,
+            curator_key_info,
+            attestation_info,
+            system_program_info,
+            rent,
+            CuratorAttestation::LEN,
+            seeds,
+        )?;
+
+        let attestation = CuratorAttestation {
+            project: *project_info.key,
+            curator: *curator_key_info.key,
+            attested_at: Clock::get()?.unix_timestamp,
+        };
+        CuratorAttestation::pack(attestation, &mut attestation_info.data.borrow_mut())?;
+
+        // the votes the project already got start counting toward the round
+        let matched = round.matches(&project);
+        project.verified = true;
+        if !matched && round.matches(&project) {
+            round.add_area(project.category, project.area);
+            round.save()?;
+        }
+        project.save()?;
+
+        Ok(())
+    }
+
+    pub fn process_set_payout_wallet(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let project_info = next_account_info(account_info_iter)?;
+        let project_owner_info = next_account_info(account_info_iter)?;
+        let payout_wallet_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(project_info, program_id)?;
+        let mut project = Project::unpack(&project_info.data.borrow())?;
+        if project.withdraw {
+            return Err(QFError::ProjectAlreadyWithdraw.into());
+        }
+        validation::check_signer(project_owner_info)?;
+        if project.owner != *project_owner_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+
+        project.payout_wallet = *payout_wallet_info.key;
+        Project::pack(project, &mut project_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Sets the donation tiers of a project, each voter account then records the tier
+    /// its votes reach. They are fixed once voting starts so a voter knows what its
+    /// votes get.
+    pub fn process_set_project_tiers(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        tiers: Vec<Tier>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let project_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let project_owner_info = next_account_info(account_info_iter)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Registration)?;
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        validation::check_signer(project_owner_info)?;
+        if project.owner != *project_owner_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+
+        // thresholds increase, a zero one would end the list early
+        if tiers.len() > MAX_TIERS
+            || tiers.iter().any(|tier| tier.threshold == 0)
+            || tiers
+                .windows(2)
+                .any(|pair| pair[1].threshold <= pair[0].threshold)
+        {
+            return Err(QFError::InvalidTiers.into());
+        }
+        project.tiers = [Tier::default(); MAX_TIERS];
+        project.tiers[..tiers.len()].copy_from_slice(&tiers);
+        project.save()?;
+
+        Ok(())
+    }
+
+    /// Posts an update of a project, like a progress report or the evidence of a
+    /// milestone. Updates are numbered and never change, so others can point to one by
+    /// its address.
+    pub fn process_post_project_update(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        body: Vec<u8>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let project_info = next_account_info(account_info_iter)?;
+        let update_info = next_account_info(account_info_iter)?;
+        let project_owner_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        validation::check_owner(project_info, program_id)?;
+        let mut project = Project::unpack(&project_info.data.borrow())?;
+        validation::check_signer(project_owner_info)?;
+        if project.owner != *project_owner_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+
+        let project_key = project_info.key.to_bytes();
+        let index = project.updates.to_le_bytes();
+        let bump_seed = validation::check_pda(
+            update_info,
+            &[b"update", &project_key, &index],
+            program_id,
+            QFError::UpdateMismatch,
+        )?;
+        Self::create_pda_account(
+            program_id,
+            project_owner_info,
+            update_info,
+            system_program_info,
+            rent,
+            ProjectUpdate::LEN,
+            &[b"update", &project_key, &index, &[bump_seed]],
+        )?;
+
+        let mut update = ProjectUpdate {
+            project: *project_info.key,
+            author: *project_owner_info.key,
+            index: project.updates,
+            posted_at: Clock::get()?.unix_timestamp,
+            hash,
+            body_len: body.len() as u16,
+            ..ProjectUpdate::default()
+        };
+        update.body[..body.len()].copy_from_slice(&body);
+        project.updates = project.updates.checked_add(1).unwrap();
+
+        ProjectUpdate::pack(update, &mut update_info.data.borrow_mut())?;
+        Project::pack(project, &mut project_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
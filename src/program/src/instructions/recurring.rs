@@ -0,0 +1,321 @@
+//! Recurring votes executed by a cranker
+
+use crate::{
+    error::QFError,
+    events::VoteKind,
+    processor::Processor,
+    state::{RoundStatus, Subscription, Voter},
+    validation::{self, ProjectAccount, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+impl Processor {
+    /// Locks tokens in escrow that a cranker turns into a vote every interval
+    pub fn process_create_recurring_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        interval: i64,
+        total: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let subscription_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let cranker_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        if mint_info.key != &round.mint {
+            return Err(QFError::MintMismatch.into());
+        }
+
+        ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_signer(from_auth_info)?;
+        let from = spl_token::state::Account::unpack(&from_info.data.borrow())?;
+        if from.owner != *from_auth_info.key {
+            return Err(QFError::InvalidTokenAuthority.into());
+        }
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+        if amount == 0 || interval <= 0 {
+            return Err(QFError::InvalidSubscription.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            subscription_info,
+            &[
+                b"subscription",
+                &project_info.key.to_bytes(),
+                &from_info.key.to_bytes(),
+            ],
+            program_id,
+            QFError::SubscriptionMismatch,
+        )?;
+
+        let escrow = spl_token::state::Account::unpack(&escrow_info.data.borrow())?;
+        if escrow.owner != *subscription_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        if escrow.mint != round.mint {
+            return Err(QFError::MintMismatch.into());
+        }
+
+        if subscription_info.owner != program_id {
+            let seeds: &[&[_]] = &[
+                b"subscription",
+                &project_info.key.to_bytes(),
+                &from_info.key.to_bytes(),
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                from_auth_info,
+                subscription_info,
+                system_program_info,
+                rent,
+                Subscription::LEN,
+                seeds,
+            )?;
+        }
+        let mut subscription = Subscription::unpack_unchecked(&subscription_info.data.borrow())?;
+        if subscription.is_initialized() && subscription.remaining > 0 {
+            return Err(QFError::SubscriptionActive.into());
+        }
+
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            escrow_info,
+            from_auth_info,
+            &[],
+            total,
+            decimals,
+        )?;
+
+        subscription.project = *project_info.key;
+        subscription.source = *from_info.key;
+        subscription.escrow = *escrow_info.key;
+        subscription.authority = *from_auth_info.key;
+        subscription.cranker = *cranker_info.key;
+        subscription.amount = amount;
+        subscription.interval = interval;
+        subscription.next_vote_at = Clock::get()?.unix_timestamp;
+        subscription.remaining = total;
+        Subscription::pack(subscription, &mut subscription_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Votes the next amount of a subscription, only the cranker of the subscription can do it
+    pub fn process_execute_recurring_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let subscription_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let cranker_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(vault_info, &round)?;
+
+        validation::check_owner(subscription_info, program_id)?;
+        let mut subscription = Subscription::unpack(&subscription_info.data.borrow())?;
+        if subscription.project != *project_info.key || subscription.escrow != *escrow_info.key {
+            return Err(QFError::SubscriptionMismatch.into());
+        }
+        validation::check_signer(cranker_info)?;
+        if subscription.cranker != *cranker_info.key {
+            return Err(QFError::SubscriptionMismatch.into());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if subscription.remaining == 0 || now < subscription.next_vote_at {
+            return Err(QFError::SubscriptionNotDue.into());
+        }
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[
+                &project_info.key.to_bytes(),
+                &subscription.authority.to_bytes(),
+            ],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let amount = subscription.amount.min(subscription.remaining);
+
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[
+                b"subscription",
+                &project_info.key.to_bytes(),
+                &subscription.source.to_bytes(),
+            ],
+            &program_id,
+        );
+        let seeds: &[&[_]] = &[
+            b"subscription",
+            &project_info.key.to_bytes(),
+            &subscription.source.to_bytes(),
+            &[bump_seed],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &escrow_info.key,
+                &vault_info.key,
+                &subscription_info.key,
+                &[],
+                amount,
+            )?,
+            &[
+                escrow_info.clone(),
+                vault_info.clone(),
+                subscription_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        Self::apply_vote(&mut round, &mut project, &mut voter, amount, now)?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            &subscription.authority,
+            amount,
+            VoteKind::Vote,
+        )?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        Self::record_vote(
+            program_id,
+            registry_info,
+            round_info.key,
+            &mut round,
+            &subscription.authority,
+            amount,
+            amount,
+            voter.last_vote_at,
+        )?;
+        round.save()?;
+
+        // a late crank does not catch up on the missed intervals
+        subscription.remaining = subscription.remaining.checked_sub(amount).unwrap();
+        subscription.next_vote_at = now.checked_add(subscription.interval).unwrap();
+        Subscription::pack(subscription, &mut subscription_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Stops a subscription and sends what is left in escrow back to the source
+    pub fn process_cancel_recurring_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let subscription_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(subscription_info, program_id)?;
+        let mut subscription = Subscription::unpack(&subscription_info.data.borrow())?;
+        if subscription.escrow != *escrow_info.key || subscription.source != *to_info.key {
+            return Err(QFError::SubscriptionMismatch.into());
+        }
+        validation::check_signer(authority_info)?;
+        if subscription.authority != *authority_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[
+                b"subscription",
+                &subscription.project.to_bytes(),
+                &subscription.source.to_bytes(),
+            ],
+            &program_id,
+        );
+        let seeds: &[&[_]] = &[
+            b"subscription",
+            &subscription.project.to_bytes(),
+            &subscription.source.to_bytes(),
+            &[bump_seed],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &escrow_info.key,
+                &to_info.key,
+                &subscription_info.key,
+                &[],
+                subscription.remaining,
+            )?,
+            &[
+                escrow_info.clone(),
+                to_info.clone(),
+                subscription_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        subscription.remaining = 0;
+        Subscription::pack(subscription, &mut subscription_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,525 @@
+//! Voter rewards: receipt NFTs, participation tokens and airdrops
+
+use crate::{
+    error::QFError,
+    metaplex,
+    processor::Processor,
+    state::{Airdrop, RoundStatus, Voter},
+    token_2022,
+    validation::{self, ProjectAccount, RoundAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
+use spl_math::uint::U256;
+
+/// Name, symbol and address prefix of the metadata of a receipt, the uri points at the
+/// voter the receipt was minted for
+const RECEIPT_NAME: &str = "QF Receipt";
+const RECEIPT_SYMBOL: &str = "QFR";
+const RECEIPT_URI: &str = "https://explorer.solana.com/address/";
+
+/// Accounts of a receipt, as MintReceipt and Vote take them
+pub struct Receipt<'b, 'a> {
+    pub mint: &'b AccountInfo<'a>,
+    pub metadata: &'b AccountInfo<'a>,
+    pub to: &'b AccountInfo<'a>,
+    pub payer: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+    pub metadata_program: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub rent: &'b AccountInfo<'a>,
+}
+
+impl<'b, 'a> Receipt<'b, 'a> {
+    /// Takes the receipt mint, its metadata, the wallet's token account of the receipt,
+    /// the payer, the token, associated token and token metadata programs, the system
+    /// program and the rent sysvar from `infos`
+    pub fn new(infos: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let infos = &mut infos.iter();
+        let receipt = Receipt {
+            mint: next_account_info(infos)?,
+            metadata: next_account_info(infos)?,
+            to: next_account_info(infos)?,
+            payer: next_account_info(infos)?,
+            token_program: next_account_info(infos)?,
+            associated_token_program: next_account_info(infos)?,
+            metadata_program: next_account_info(infos)?,
+            system_program: next_account_info(infos)?,
+            rent: next_account_info(infos)?,
+        };
+        if receipt.token_program.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+        if receipt.associated_token_program.key != &spl_associated_token_account::id() {
+            return Err(QFError::UnexpectedAssociatedTokenProgramID.into());
+        }
+        if receipt.metadata_program.key != &metaplex::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(receipt)
+    }
+
+    /// Whether the receipt of the voter was minted, `mint` has to be its receipt mint
+    pub fn is_minted(&self) -> bool {
+        self.mint.owner == &spl_token::ID
+    }
+
+    /// Mints the receipt of `voter_info` to the associated token account of `wallet_info`,
+    /// the wallet the voter is derived from, with metadata pointing at the voter
+    pub fn mint(
+        &self,
+        program_id: &Pubkey,
+        voter_info: &AccountInfo<'a>,
+        wallet_info: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let rent = &Rent::from_account_info(self.rent)?;
+        validation::check_signer(self.payer)?;
+
+        // the receipt goes to the wallet the voter is derived from
+        if self.to.key != &get_associated_token_address(wallet_info.key, self.mint.key) {
+            return Err(QFError::RecipientMismatch.into());
+        }
+        let bump_seed = validation::check_pda(
+            self.mint,
+            &[b"receipt", &voter_info.key.to_bytes()],
+            program_id,
+            QFError::ReceiptMismatch,
+        )?;
+        if self.is_minted() {
+            return Err(QFError::ReceiptAlreadyMinted.into());
+        }
+        if self.metadata.key != &metaplex::find_metadata_address(self.mint.key) {
+            return Err(QFError::ReceiptMismatch.into());
+        }
+        let seeds: &[&[_]] = &[b"receipt", &voter_info.key.to_bytes(), &[bump_seed]];
+
+        // the mint is its own authority until the single token is minted
+        Processor::create_pda_account(
+            &spl_token::ID,
+            self.payer,
+            self.mint,
+            self.system_program,
+            rent,
+            spl_token::state::Mint::LEN,
+            seeds,
+        )?;
+        invoke(
+            &spl_token::instruction::initialize_mint(
+                self.token_program.key,
+                self.mint.key,
+                self.mint.key,
+                None,
+                0,
+            )?,
+            &[
+                self.mint.clone(),
+                self.rent.clone(),
+                self.token_program.clone(),
+            ],
+        )?;
+
+        // the metadata can not be changed, the mint is its update authority
+        invoke_signed(
+            &metaplex::create_metadata_account(
+                self.metadata.key,
+                self.mint.key,
+                self.mint.key,
+                self.payer.key,
+                self.mint.key,
+                RECEIPT_NAME,
+                RECEIPT_SYMBOL,
+                &format!("{}{}", RECEIPT_URI, voter_info.key),
+            ),
+            &[
+                self.metadata.clone(),
+                self.mint.clone(),
+                self.payer.clone(),
+                self.system_program.clone(),
+                self.rent.clone(),
+                self.metadata_program.clone(),
+            ],
+            &[seeds],
+        )?;
+
+        if self.to.lamports() == 0 {
+            msg!("Create the wallet's receipt token account");
+            invoke(
+                &create_associated_token_account(self.payer.key, wallet_info.key, self.mint.key),
+                &[
+                    self.payer.clone(),
+                    self.to.clone(),
+                    wallet_info.clone(),
+                    self.mint.clone(),
+                    self.system_program.clone(),
+                    self.token_program.clone(),
+                    self.rent.clone(),
+                    self.associated_token_program.clone(),
+                ],
+            )?;
+        }
+
+        invoke_signed(
+            &spl_token::instruction::mint_to(
+                self.token_program.key,
+                self.mint.key,
+                self.to.key,
+                self.mint.key,
+                &[],
+                1,
+            )?,
+            &[
+                self.mint.clone(),
+                self.to.clone(),
+                self.token_program.clone(),
+            ],
+            &[seeds],
+        )?;
+
+        // no more can be minted, the supply stays at one
+        invoke_signed(
+            &spl_token::instruction::set_authority(
+                self.token_program.key,
+                self.mint.key,
+                None,
+                spl_token::instruction::AuthorityType::MintTokens,
+                self.mint.key,
+                &[],
+            )?,
+            &[self.mint.clone(), self.token_program.clone()],
+            &[seeds],
+        )
+    }
+}
+
+impl Processor {
+    /// Mints the receipt NFT of a voter that voted without minting it, once per voter
+    pub fn process_mint_receipt(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let wallet_info = next_account_info(account_info_iter)?;
+        let receipt = Receipt::new(account_info_iter.as_slice())?;
+
+        validation::check_owner(round_info, program_id)?;
+        ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &wallet_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let voter = Voter::unpack(&voter_info.data.borrow())?;
+        if voter.votes == 0 {
+            return Err(QFError::NoVotes.into());
+        }
+
+        receipt.mint(program_id, voter_info, wallet_info)
+    }
+
+    /// Tops the non-transferable participation tokens of a wallet in a round up to the
+    /// tokens its registry says it voted. The mint is derived from the round and has the
+    /// decimals of the round mint, the payer creates it and the wallet's token account
+    /// when needed.
+    pub fn process_mint_participation(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let wallet_info = next_account_info(account_info_iter)?;
+        let round_mint_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let associated_token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        let registry = Self::unpack_voter_registry(
+            program_id,
+            registry_info,
+            round_info.key,
+            wallet_info.key,
+        )?;
+        if registry.votes == 0 {
+            return Err(QFError::NoVotes.into());
+        }
+
+        if round_mint_info.key != &round.mint {
+            return Err(QFError::MintMismatch.into());
+        }
+        let decimals = spl_token::state::Mint::unpack(&round_mint_info.data.borrow())?.decimals;
+
+        validation::check_signer(payer_info)?;
+        if token_program_info.key != &token_2022::id() {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+        if associated_token_program_info.key != &spl_associated_token_account::id() {
+            return Err(QFError::UnexpectedAssociatedTokenProgramID.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            mint_info,
+            &[b"participation", &round_info.key.to_bytes()],
+            program_id,
+            QFError::ParticipationMintMismatch,
+        )?;
+        let seeds: &[&[_]] = &[b"participation", &round_info.key.to_bytes(), &[bump_seed]];
+
+        // the mint is its own authority, its tokens can not be moved
+        if mint_info.owner != &token_2022::id() {
+            msg!("Create the round's participation mint");
+            Self::create_pda_account(
+                &token_2022::id(),
+                payer_info,
+                mint_info,
+                system_program_info,
+                rent,
+                token_2022::NON_TRANSFERABLE_MINT_LEN,
+                seeds,
+            )?;
+            invoke(
+                &token_2022::initialize_non_transferable_mint(mint_info.key),
+                &[mint_info.clone(), token_program_info.clone()],
+            )?;
+            invoke(
+                &token_2022::initialize_mint2(mint_info.key, mint_info.key, decimals),
+                &[mint_info.clone(), token_program_info.clone()],
+            )?;
+        }
+
+        if to_info.key != &token_2022::get_associated_token_address(wallet_info.key, mint_info.key)
+        {
+            return Err(QFError::RecipientMismatch.into());
+        }
+        if to_info.lamports() == 0 {
+            msg!("Create the wallet's participation token account");
+            invoke(
+                &token_2022::create_associated_token_account(
+                    payer_info.key,
+                    wallet_info.key,
+                    mint_info.key,
+                ),
+                &[
+                    payer_info.clone(),
+                    to_info.clone(),
+                    wallet_info.clone(),
+                    mint_info.clone(),
+                    system_program_info.clone(),
+                    token_program_info.clone(),
+                    associated_token_program_info.clone(),
+                ],
+            )?;
+        }
+
+        // revoked and refunded votes are not taken back, the tokens can not be burned
+        let held = token_2022::token_amount(to_info, mint_info.key, wallet_info.key)?;
+        let amount = registry.votes.saturating_sub(held);
+        if amount > 0 {
+            invoke_signed(
+                &token_2022::mint_to(mint_info.key, to_info.key, mint_info.key, amount),
+                &[
+                    mint_info.clone(),
+                    to_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[seeds],
+            )?;
+        }
+        msg!(
+            "Participation of the wallet: {} tokens",
+            registry.votes.max(held)
+        );
+
+        Ok(())
+    }
+
+    /// Funds the reward vault of a round airdrop, only the round owner can do it
+    pub fn process_create_airdrop(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let airdrop_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let reward_mint_info = next_account_info(account_info_iter)?;
+        let reward_vault_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        if round.status != RoundStatus::Claim && round.status != RoundStatus::Closed {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            airdrop_info,
+            &[b"airdrop", &round_info.key.to_bytes()],
+            program_id,
+            QFError::AirdropMismatch,
+        )?;
+
+        let reward_vault = spl_token::state::Account::unpack(&reward_vault_info.data.borrow())?;
+        if reward_vault.owner != *airdrop_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        if reward_vault.mint != *reward_mint_info.key {
+            return Err(QFError::MintMismatch.into());
+        }
+
+        if airdrop_info.owner != program_id {
+            let seeds: &[&[_]] = &[b"airdrop", &round_info.key.to_bytes(), &[bump_seed]];
+            Self::create_pda_account(
+                program_id,
+                owner_info,
+                airdrop_info,
+                system_program_info,
+                rent,
+                Airdrop::LEN,
+                seeds,
+            )?;
+        }
+        let mut airdrop = Airdrop::unpack_unchecked(&airdrop_info.data.borrow())?;
+        if !airdrop.is_initialized() {
+            airdrop.round = *round_info.key;
+            airdrop.reward_mint = *reward_mint_info.key;
+            airdrop.reward_vault = *reward_vault_info.key;
+        }
+        // claims already paid are shares of the old amount, so it can not grow after them
+        if airdrop.reward_vault != *reward_vault_info.key || airdrop.claimed != 0 {
+            return Err(QFError::AirdropMismatch.into());
+        }
+
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            reward_mint_info,
+            reward_vault_info,
+            owner_info,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        airdrop.amount = airdrop.amount.checked_add(amount).unwrap();
+        Airdrop::pack(airdrop, &mut airdrop_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Pays a voter its share of the round airdrop, the votes of the voter over the round votes
+    pub fn process_claim_airdrop(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let airdrop_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let wallet_info = next_account_info(account_info_iter)?;
+        let reward_vault_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+
+        validation::check_owner(airdrop_info, program_id)?;
+        let mut airdrop = Airdrop::unpack(&airdrop_info.data.borrow())?;
+        if airdrop.round != *round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        if airdrop.reward_vault != *reward_vault_info.key {
+            return Err(QFError::AirdropMismatch.into());
+        }
+
+        ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &wallet_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+        if voter.airdrop_claimed {
+            return Err(QFError::AirdropAlreadyClaimed.into());
+        }
+        if voter.votes == 0 {
+            return Err(QFError::NoVotes.into());
+        }
+
+        // anyone can claim, the reward goes to a token account of the voter's wallet
+        let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+        if to.owner != *wallet_info.key || to.mint != airdrop.reward_mint {
+            return Err(QFError::RecipientMismatch.into());
+        }
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let amount = U256::from(airdrop.amount)
+            .checked_mul(U256::from(voter.votes))
+            .unwrap()
+            .checked_div(U256::from(round.votes))
+            .unwrap()
+            .as_u64();
+
+        let (_, bump_seed) =
+            Pubkey::find_program_address(&[b"airdrop", &round_info.key.to_bytes()], &program_id);
+        let seeds: &[&[_]] = &[b"airdrop", &round_info.key.to_bytes(), &[bump_seed]];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &reward_vault_info.key,
+                &to_info.key,
+                &airdrop_info.key,
+                &[],
+                amount,
+            )?,
+            &[
+                reward_vault_info.clone(),
+                to_info.clone(),
+                airdrop_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        voter.airdrop_claimed = true;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+
+        airdrop.claimed = airdrop.claimed.checked_add(amount).unwrap();
+        Airdrop::pack(airdrop, &mut airdrop_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
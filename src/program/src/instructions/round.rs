@@ -0,0 +1,1029 @@
+//! Rounds: starting them, their templates and categories, and the phase transitions
+
+use crate::{
+    error::QFError,
+    events,
+    events::Event,
+    lending::Reserve,
+    processor::Processor,
+    state::{
+        Category, Round, RoundArchive, RoundConfig, RoundMode, RoundStatus, RoundTemplate,
+        MAX_CATEGORIES,
+    },
+    validation::{self, ProjectAccount, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    hash::{hashv, Hash},
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_math::uint::U256;
+
+impl Processor {
+    pub fn process_start_round(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        config: RoundConfig,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let new_round_info = next_account_info(account_info_iter)?;
+        let round_owner_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let program_config = Self::load_config(program_id, next_account_info(account_info_iter)?)?;
+
+        validation::check_owner(new_round_info, program_id)?;
+        let mut round = Round::unpack_unchecked(&new_round_info.data.borrow())?;
+        if round.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        if new_round_info.data_len() != Round::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !rent.is_exempt(new_round_info.lamports(), Round::LEN) {
+            return Err(ProgramError::AccountNotRentExempt);
+        }
+
+        if config.committee_share_bps > 10_000 {
+            return Err(QFError::CommitteeShareExceeded.into());
+        }
+        if config.operator_fee_bps > 10_000 {
+            return Err(QFError::InvalidFeeBps.into());
+        }
+        if config.holdback_bps > 10_000 {
+            return Err(QFError::InvalidHoldback.into());
+        }
+        // without the `fees` feature the fee could never be withdrawn
+        if !cfg!(feature = "fees") && config.operator_fee_bps > 0 {
+            return Err(QFError::FeesDisabled.into());
+        }
+        // conviction and epochs are matched without the round area
+        if config.verified_only && matches!(config.mode, RoundMode::Conviction | RoundMode::Epochs)
+        {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        if [
+            config.voting_end,
+            config.tally_period,
+            config.dispute_window,
+            config.claim_period,
+            config.recovery_grace,
+        ]
+        .iter()
+        .any(|seconds| *seconds < 0)
+        {
+            return Err(QFError::InvalidDeadline.into());
+        }
+        if config.mode == RoundMode::Epochs && (config.epoch_length <= 0 || config.epoch_count == 0)
+        {
+            return Err(QFError::InvalidEpoch.into());
+        }
+
+        let (pda, _) =
+            Pubkey::find_program_address(&[&round_owner_info.key.to_bytes()], &program_id);
+        let vault = spl_token::state::Account::unpack(&vault_info.data.borrow())?;
+        if vault.owner != pda {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        if !program_config.is_mint_approved(&vault.mint) {
+            return Err(QFError::MintNotApproved.into());
+        }
+
+        round.status = RoundStatus::Registration;
+        round.fund = vault.amount;
+        round.owner = *round_owner_info.key;
+        round.vault = *vault_info.key;
+        round.area = U256::zero();
+        round.batch_nonce = 0;
+        round.config = config;
+        round.mint = vault.mint;
+        round.deposits = 0;
+        round.voters = 0;
+        round.votes = 0;
+        round.pledged = 0;
+        round.unallocated = 0;
+        round.previous_round = Pubkey::default();
+        round.next_round = Pubkey::default();
+        round.category_count = 0;
+        round.categories = [Category::default(); MAX_CATEGORIES];
+        round.discretionary = 0;
+        round.conviction = U256::zero();
+        round.conviction_at = 0;
+        round.voting_started_at = 0;
+        round.voting_ended_at = 0;
+        round.project_count = 0;
+        round.fund_remaining = 0;
+        round.swept = 0;
+        round.holdbacks = 0;
+        round.withdrawals = 0;
+        round.reclaimed = 0;
+        round.lent = 0;
+        round.collateral = 0;
+        round.collateral_account = Pubkey::default();
+
+        Round::pack(round, &mut new_round_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    pub fn process_create_round_template(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        id: u64,
+        config: RoundConfig,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let new_template_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        validation::check_signer(owner_info)?;
+
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[b"template", &owner_info.key.to_bytes(), &id.to_le_bytes()],
+            &program_id,
+        );
+        let seeds: &[&[_]] = &[
+            b"template",
+            &owner_info.key.to_bytes(),
+            &id.to_le_bytes(),
+            &[bump_seed],
+        ];
+
+        Self::create_pda_account(
+            program_id,
+            owner_info,
+            new_template_info,
+            system_program_info,
+            rent,
+            RoundTemplate::LEN,
+            seeds,
+        )?;
+
+        let mut template = RoundTemplate::unpack_unchecked(&new_template_info.data.borrow())?;
+        if template.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        template.owner = *owner_info.key;
+        template.id = id;
+        template.config = config;
+
+        RoundTemplate::pack(template, &mut new_template_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Starts a round with the config of one of the owner's templates
+    pub fn process_start_round_from_template(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let _new_round_info = next_account_info(account_info_iter)?;
+        let round_owner_info = next_account_info(account_info_iter)?;
+        let _vault_info = next_account_info(account_info_iter)?;
+        let _rent_info = next_account_info(account_info_iter)?;
+        let template_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        validation::check_owner(template_info, program_id)?;
+        let template = RoundTemplate::unpack(&template_info.data.borrow())?;
+        if template.owner != *round_owner_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+
+        let mut start_accounts = accounts[..4].to_vec();
+        start_accounts.push(config_info.clone());
+        Self::process_start_round(program_id, &start_accounts, template.config)
+    }
+
+    /// Splits the fund into categories, each share is in basis points of the fund
+    pub fn process_set_categories(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        shares: Vec<u16>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Registration)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        // conviction is only tracked for the whole round, epochs have a tally per category
+        if round.config.mode == RoundMode::Conviction {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        // projects may already be registered in category 0
+        if round.category_count != 0 {
+            return Err(QFError::InvalidCategory.into());
+        }
+        if shares.is_empty() || shares.len() > MAX_CATEGORIES {
+            return Err(QFError::InvalidCategory.into());
+        }
+        if shares.iter().map(|&share| share as u64).sum::<u64>() > 10_000 {
+            return Err(QFError::InvalidCategory.into());
+        }
+
+        for (category, &share_bps) in round.categories.iter_mut().zip(shares.iter()) {
+            category.share_bps = share_bps;
+            category.area = U256::zero();
+        }
+        round.category_count = shares.len() as u8;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Sets the Merkle root of the wallets that may register projects, all zeros opens
+    /// registration to anyone again. It can be changed until voting starts.
+    pub fn process_set_registration_allowlist(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        root: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Registration)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        round.registration_root = root;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Moves a project to another category before voting starts
+    pub fn process_set_project_category(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        category: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Registration)?;
+
+        if authority_info.key != &round.owner
+            && (round.config.moderator == Pubkey::default()
+                || authority_info.key != &round.config.moderator)
+        {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(authority_info)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        if category >= round.category_count {
+            return Err(QFError::InvalidCategory.into());
+        }
+
+        round.sub_area(project.category, project.area);
+        project.category = category;
+        project.discretionary = 0;
+        project.conviction = U256::zero();
+        project.conviction_at = 0;
+        round.add_area(project.category, project.area);
+
+        round.save()?;
+        project.save()?;
+
+        Ok(())
+    }
+
+    /// Opens the voting, or cancels the round if the fund or the projects are below the
+    /// minimum
+    pub fn process_start_voting(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Registration)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        // pledges count toward the minimum, they are executed once voting starts
+        if round.fund.checked_add(round.pledged).unwrap() < round.config.min_fund {
+            msg!("Fund below the minimum, the round is cancelled");
+            round.status = RoundStatus::Cancelled;
+        } else if round.project_count < round.config.min_projects {
+            // matching one or two projects only splits the fund between them
+            msg!("Projects below the minimum, the round is cancelled");
+            round.status = RoundStatus::Cancelled;
+        } else {
+            round.status = RoundStatus::Voting;
+            round.conviction_at = Clock::get()?.unix_timestamp;
+            round.voting_started_at = round.conviction_at;
+        }
+        round.save()?;
+
+        Ok(())
+    }
+
+    pub fn process_end_round(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        Self::process_transition(
+            program_id,
+            accounts,
+            RoundStatus::Voting,
+            RoundStatus::Tallying,
+        )?;
+
+        let mut round = RoundAccount::load(&accounts[0], program_id)?;
+        Self::recall_fund(program_id, &mut round, &accounts[2..])?;
+        let now = Clock::get()?.unix_timestamp;
+        round.voting_ended_at = now;
+        // conviction stops accruing when voting ends
+        if round.config.mode == RoundMode::Conviction {
+            Self::accrue_round_conviction(&mut round, now);
+        }
+        Self::emit_round_ended(&accounts[0], &round, now);
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Opens the claims, or cancels the round if the quorum was not reached. Writes the
+    /// claims of the projects passed after the owner.
+    pub fn process_finalize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Tallying)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        Self::finalize(&mut round, Clock::get()?.unix_timestamp)?;
+        // the system program, rent and pairs of project and claim are optional
+        if round.status == RoundStatus::Claim && accounts.len() > 2 {
+            let system_program_info = next_account_info(account_info_iter)?;
+            let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+            Self::write_claims(
+                program_id,
+                round_info.key,
+                &round,
+                owner_info,
+                system_program_info,
+                rent,
+                &accounts[4..],
+            )?;
+        }
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Ends the voting once `voting_end` passed, anyone can do it and it does nothing
+    /// if the voting already ended. The caller can be paid the round's crank reward, its
+    /// accounts come after those of a lent out fund.
+    pub fn process_crank_end_round(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        match round.status {
+            RoundStatus::Voting => {}
+            RoundStatus::Registration => return Err(QFError::RoundStatusError.into()),
+            _ => return Ok(()),
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if round.config.voting_end == 0 || now < round.config.voting_end {
+            return Err(QFError::CrankNotDue.into());
+        }
+
+        let recalled = Self::recall_fund(program_id, &mut round, &accounts[1..])?;
+        Self::pay_crank_reward(program_id, &mut round, &accounts[1 + recalled..])?;
+        round.status = RoundStatus::Tallying;
+        round.voting_ended_at = now;
+        if round.config.mode == RoundMode::Conviction {
+            Self::accrue_round_conviction(&mut round, now);
+        }
+        Self::emit_round_ended(round_info, &round, now);
+        round.save()?;
+
+        Ok(())
+    }
+
+    fn emit_round_ended(round_info: &AccountInfo, round: &Round, now: i64) {
+        events::emit(
+            round_info.key,
+            &Event::RoundEnded {
+                votes: round.votes,
+                voters: round.voters,
+                at: now,
+            },
+        );
+    }
+
+    /// Pushes the voting end of a live round later, when an outage or an exploit cut into
+    /// the voting. It can never be brought earlier, only before it passed, and not past
+    /// the config's `max_round_duration` from the start of voting.
+    pub fn process_extend_round(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        voting_end: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let config = Self::load_config(program_id, config_info)?;
+        let from = round.config.voting_end;
+        // a round without a voting end is ended by its owner, there is nothing to extend,
+        // and a passed voting end is final even before the crank ends the round
+        if from == 0
+            || Clock::get()?.unix_timestamp >= from
+            || voting_end <= from
+            || config.max_round_duration == 0
+            || voting_end.saturating_sub(round.voting_started_at) > config.max_round_duration
+        {
+            return Err(QFError::InvalidExtension.into());
+        }
+
+        round.config.voting_end = voting_end;
+        round.save()?;
+        events::emit(
+            round_info.key,
+            &Event::RoundExtended {
+                from,
+                to: voting_end,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Finalizes the round once `tally_period` passed after `voting_end`, anyone can do it
+    /// and it does nothing if the round was already finalized. The caller can be paid the
+    /// round's crank reward, see `pay_crank_reward`.
+    pub fn process_crank_finalize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        match round.status {
+            RoundStatus::Tallying => {}
+            RoundStatus::Registration | RoundStatus::Voting => {
+                return Err(QFError::RoundStatusError.into())
+            }
+            _ => return Ok(()),
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if round.config.voting_end == 0
+            || now
+                < round
+                    .config
+                    .voting_end
+                    .checked_add(round.config.tally_period)
+                    .unwrap()
+        {
+            return Err(QFError::CrankNotDue.into());
+        }
+
+        let reward_accounts = Self::pay_crank_reward(program_id, &mut round, &accounts[1..])?;
+        Self::finalize(&mut round, now)?;
+        // a payer, the system program, rent and pairs of project and claim are optional
+        let claim_accounts = &accounts[1 + reward_accounts..];
+        if round.status == RoundStatus::Claim && !claim_accounts.is_empty() {
+            let account_info_iter = &mut claim_accounts.iter();
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+            Self::write_claims(
+                program_id,
+                round_info.key,
+                &round,
+                payer_info,
+                system_program_info,
+                rent,
+                &claim_accounts[3..],
+            )?;
+        }
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Moves a tallied round to Claim, or cancels it if the quorum was not reached. Bans,
+    /// appeals and flags can still change the allocation until the dispute window after
+    /// the end of voting passed, the payouts are locked after it.
+    pub fn finalize(round: &mut Round, now: i64) -> ProgramResult {
+        if now
+            < round
+                .voting_ended_at
+                .checked_add(round.config.dispute_window)
+                .unwrap()
+        {
+            return Err(QFError::DisputeWindowOpen.into());
+        }
+        if round.pending_appeals > 0 {
+            return Err(QFError::AppealsPending.into());
+        }
+        if round.pending_flags > 0 {
+            return Err(QFError::FlagsPending.into());
+        }
+        if round.voters < round.config.min_unique_voters
+            || round.votes < round.config.min_total_votes
+        {
+            msg!("Quorum not reached, the round is cancelled");
+            round.status = RoundStatus::Cancelled;
+        } else {
+            // the fund has to be final before projects withdraw
+            if round.pledged != 0 {
+                return Err(QFError::PledgesNotExecuted.into());
+            }
+            round.lock_payouts();
+            round.status = RoundStatus::Claim;
+        }
+        Ok(())
+    }
+
+    /// Closes a finalized round once every project withdrew or the claim deadline passed,
+    /// only the round owner can do it
+    pub fn process_close_round(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Claim)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        // projects can only withdraw from a round in Claim
+        let claim_deadline = round
+            .voting_ended_at
+            .checked_add(round.config.dispute_window)
+            .unwrap()
+            .checked_add(round.config.claim_period)
+            .unwrap();
+        let claim_over =
+            round.config.claim_period != 0 && Clock::get()?.unix_timestamp >= claim_deadline;
+        if !claim_over && round.withdrawals < round.project_count {
+            return Err(QFError::ClaimPeriodOpen.into());
+        }
+
+        round.status = RoundStatus::Closed;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Moves the round from one phase to the next, only the round owner can do it.
+    fn process_transition(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        from: RoundStatus,
+        to: RoundStatus,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if round.status != from {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        round.status = to;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Moves what projects did not withdraw from a closed round's vault to the next round
+    pub fn process_rollover_funds(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let next_round_info = next_account_info(account_info_iter)?;
+        let next_vault_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Closed)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let vault = VaultAccount::load(vault_info, &round)?;
+
+        if next_round_info.key == round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        if round.next_round != Pubkey::default() && round.next_round != *next_round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        let mut next_round = RoundAccount::load(next_round_info, program_id)?;
+        if !matches!(
+            next_round.status,
+            RoundStatus::Registration | RoundStatus::Voting
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        if next_round.owner != round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        if next_round.mint != round.mint {
+            return Err(QFError::MintMismatch.into());
+        }
+        VaultAccount::load(next_vault_info, &next_round)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        // the fees, holdbacks and deposits still owed stay in the vault
+        let amount = round.residual(vault.amount);
+        if amount == 0 {
+            return Err(QFError::InsufficientVaultBalance.into());
+        }
+        msg!("Roll over {} tokens", amount);
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &next_vault_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                amount,
+            )?,
+            &[
+                vault_info.clone(),
+                next_vault_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        round.fund_remaining = 0;
+        round.save()?;
+
+        next_round.fund = next_round.fund.checked_add(amount).unwrap();
+        next_round.save()?;
+
+        Ok(())
+    }
+
+    /// Closes the projects of a closed round and folds them into the round archive, a
+    /// hash chained over their final votes, area and payout state. Only the round owner
+    /// can do it, the rent goes back to the project owners. Once every project is
+    /// archived and the vault is empty, the round account is closed too.
+    pub fn process_archive_round(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let archive_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Closed)?;
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+        let vault = VaultAccount::load(vault_info, &round)?;
+
+        let round_key = round_info.key.to_bytes();
+        let bump_seed = validation::check_pda(
+            archive_info,
+            &[b"archive", &round_key],
+            program_id,
+            QFError::ArchiveMismatch,
+        )?;
+        if archive_info.owner != program_id {
+            Self::create_pda_account(
+                program_id,
+                owner_info,
+                archive_info,
+                system_program_info,
+                rent,
+                RoundArchive::LEN,
+                &[b"archive", &round_key, &[bump_seed]],
+            )?;
+        }
+        let mut archive = RoundArchive::unpack_unchecked(&archive_info.data.borrow())?;
+        if !archive.is_initialized() {
+            archive.round = *round_info.key;
+            archive.fund = round.fund;
+            archive.votes = round.votes;
+        }
+
+        let claim_deadline = round
+            .voting_ended_at
+            .checked_add(round.config.dispute_window)
+            .unwrap()
+            .checked_add(round.config.claim_period)
+            .unwrap();
+        let claim_over =
+            round.config.claim_period != 0 && Clock::get()?.unix_timestamp >= claim_deadline;
+
+        while let Ok(project_info) = next_account_info(account_info_iter) {
+            let project_owner_info = next_account_info(account_info_iter)?;
+            let project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+            if project_owner_info.key != &project.owner {
+                return Err(QFError::OwnerMismatch.into());
+            }
+            // a project that can still withdraw keeps its account
+            if !project.withdraw && !project.banned && !claim_over {
+                return Err(QFError::ClaimPeriodOpen.into());
+            }
+
+            let mut area = [0u8; 32];
+            project.area.to_little_endian(&mut area);
+            archive.hash = hashv(&[
+                &archive.hash,
+                &project_info.key.to_bytes(),
+                &project.votes.to_le_bytes(),
+                &area,
+                &project.discretionary.to_le_bytes(),
+                &[project.banned as u8, project.withdraw as u8],
+            ])
+            .to_bytes();
+            archive.projects = archive.projects.checked_add(1).unwrap();
+            events::emit(
+                round_info.key,
+                &Event::ProjectArchived {
+                    project: *project_info.key,
+                    votes: project.votes,
+                    area: project.area,
+                    discretionary: project.discretionary,
+                    banned: project.banned,
+                    withdraw: project.withdraw,
+                },
+            );
+            Self::close_account(project_info, project_owner_info)?;
+        }
+
+        if archive.projects >= round.project_count {
+            if vault.amount > 0 {
+                return Err(QFError::VaultNotEmpty.into());
+            }
+            archive.complete = true;
+            events::emit(
+                round_info.key,
+                &Event::RoundArchived {
+                    projects: archive.projects,
+                    hash: Hash::new_from_array(archive.hash),
+                },
+            );
+            Self::close_account(round_info, owner_info)?;
+        }
+
+        RoundArchive::pack(archive, &mut archive_info.data.borrow_mut())
+    }
+
+    /// Links a round to the round that follows it, both rounds need the same owner
+    pub fn process_link_rounds(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let next_round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        if round_info.key == next_round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        let mut next_round = RoundAccount::load(next_round_info, program_id)?;
+
+        if owner_info.key != &round.owner || owner_info.key != &next_round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        if round.next_round != Pubkey::default() || next_round.previous_round != Pubkey::default() {
+            return Err(QFError::RoundAlreadyLinked.into());
+        }
+
+        round.next_round = *next_round_info.key;
+        next_round.previous_round = *round_info.key;
+        round.save()?;
+        next_round.save()?;
+
+        Ok(())
+    }
+
+    /// Gives a project part of the committee share, once voting ended
+    pub fn process_allocate_discretionary(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Tallying)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        round.discretionary = round.discretionary.checked_add(amount).unwrap();
+        if round.discretionary > round.committee_pool() {
+            return Err(QFError::CommitteeShareExceeded.into());
+        }
+        project.discretionary = project.discretionary.checked_add(amount).unwrap();
+
+        round.save()?;
+        project.save()?;
+
+        Ok(())
+    }
+
+    /// Lends part of the fund to a Solend reserve while the round is in Voting, the
+    /// owner opts in. The vault authority gets the collateral in a token account it owns
+    /// and EndRound redeems it. The votes, deposits and fees stay in the vault.
+    pub fn process_lend_fund(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let collateral_info = next_account_info(account_info_iter)?;
+        let reserve = Reserve::new(account_info_iter.as_slice())?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+        VaultAccount::load(vault_info, &round)?;
+
+        if round.lent > 0 {
+            return Err(QFError::FundLent.into());
+        }
+        if amount > round.fund {
+            return Err(QFError::LoanExceedsFund.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            vault_owner_info,
+            &[&round.owner.to_bytes()],
+            program_id,
+            QFError::VaultAuthorityMismatch,
+        )?;
+        let collateral = spl_token::state::Account::unpack(&collateral_info.data.borrow())?;
+        if collateral.owner != *vault_owner_info.key {
+            return Err(QFError::CollateralMismatch.into());
+        }
+
+        let minted = reserve.deposit(
+            vault_info,
+            collateral_info,
+            vault_owner_info,
+            amount,
+            &[&round.owner.to_bytes(), &[bump_seed]],
+        )?;
+        msg!(
+            "Lent {} tokens of the fund for {} collateral",
+            amount,
+            minted
+        );
+        round.lent = amount;
+        round.collateral = minted;
+        round.collateral_account = *collateral_info.key;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Redeems the collateral of a fund lent out with LendFund back into the vault and
+    /// settles the loan, when `accounts` starts with the vault, its authority, the
+    /// collateral account and the reserve accounts. Returns how many of the accounts it
+    /// took, none if the fund is not lent out.
+    pub(crate) fn recall_fund(
+        program_id: &Pubkey,
+        round: &mut Round,
+        accounts: &[AccountInfo],
+    ) -> Result<usize, ProgramError> {
+        if round.lent == 0 {
+            return Ok(0);
+        }
+        let (vault_info, vault_owner_info, collateral_info, reserve_infos) = match accounts {
+            [vault_info, vault_owner_info, collateral_info, reserve_infos @ ..] => {
+                (vault_info, vault_owner_info, collateral_info, reserve_infos)
+            }
+            _ => return Err(ProgramError::NotEnoughAccountKeys),
+        };
+        if vault_info.key != &round.vault {
+            return Err(QFError::VaultMismatch.into());
+        }
+        if collateral_info.key != &round.collateral_account {
+            return Err(QFError::CollateralMismatch.into());
+        }
+        let bump_seed = validation::check_pda(
+            vault_owner_info,
+            &[&round.owner.to_bytes()],
+            program_id,
+            QFError::VaultAuthorityMismatch,
+        )?;
+        let reserve = Reserve::new(reserve_infos)?;
+
+        let returned = reserve.redeem(
+            collateral_info,
+            vault_info,
+            vault_owner_info,
+            round.collateral,
+            &[&round.owner.to_bytes(), &[bump_seed]],
+        )?;
+        let earned = Self::settle_loan(round, returned);
+        msg!(
+            "Redeemed {} tokens of the fund, {} earned",
+            returned,
+            earned
+        );
+        Ok(3 + Reserve::ACCOUNTS)
+    }
+
+    /// Settles the loan of the fund once redeeming it paid `returned` into the vault.
+    /// What the reserve paid on top of the loan is added to the fund, a shortfall is
+    /// taken out of it. Returns the yield.
+    pub fn settle_loan(round: &mut Round, returned: u64) -> u64 {
+        let earned = returned.saturating_sub(round.lent);
+        round.fund = round
+            .fund
+            .checked_add(returned)
+            .unwrap()
+            .saturating_sub(round.lent);
+        round.lent = 0;
+        round.collateral = 0;
+        round.collateral_account = Pubkey::default();
+        earned
+    }
+}
@@ -0,0 +1,540 @@
+//! Sponsors: donations, pledges and what they get back
+
+use crate::{
+    error::QFError,
+    processor::Processor,
+    state::{Pledge, Round, RoundStatus, Sponsor},
+    validation::{self, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_math::uint::U256;
+
+impl Processor {
+    pub fn process_donate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let sponsor_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let signer_infos = account_info_iter.as_slice();
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        VaultAccount::load(to_info, &round)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            sponsor_info,
+            &[
+                b"sponsor",
+                &round_info.key.to_bytes(),
+                &from_info.key.to_bytes(),
+            ],
+            program_id,
+            QFError::SponsorMismatch,
+        )?;
+        if sponsor_info.owner != program_id {
+            validation::check_signer(payer_info)?;
+            let seeds: &[&[_]] = &[
+                b"sponsor",
+                &round_info.key.to_bytes(),
+                &from_info.key.to_bytes(),
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                payer_info,
+                sponsor_info,
+                system_program_info,
+                rent,
+                Sponsor::LEN,
+                seeds,
+            )?;
+        }
+        let now = Clock::get()?.unix_timestamp;
+        let mut sponsor = Sponsor::unpack_unchecked(&sponsor_info.data.borrow())?;
+        if !sponsor.is_initialized() {
+            sponsor.round = *round_info.key;
+            sponsor.source = *from_info.key;
+            sponsor.authority = *from_auth_info.key;
+            sponsor.first_donated_at = now;
+        }
+
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            to_info,
+            from_auth_info,
+            signer_infos,
+            amount,
+            decimals,
+        )?;
+
+        sponsor.contributed = sponsor.contributed.checked_add(amount).unwrap();
+        sponsor.last_donated_at = now;
+        sponsor.tier = round.config.sponsor_tier(sponsor.contributed);
+        Sponsor::pack(sponsor, &mut sponsor_info.data.borrow_mut())?;
+
+        round.fund = round.fund.checked_add(amount).unwrap();
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Gives a sponsor's donations back after the round was cancelled
+    pub fn process_refund_donation(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let sponsor_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let to_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Cancelled)?;
+        VaultAccount::load(vault_info, &round)?;
+
+        validation::check_owner(sponsor_info, program_id)?;
+        let mut sponsor = Sponsor::unpack(&sponsor_info.data.borrow())?;
+        if sponsor.round != *round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        if sponsor.source != *to_info.key {
+            return Err(QFError::RecipientMismatch.into());
+        }
+
+        let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+        if to.owner != *to_auth_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(to_auth_info)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &to_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                sponsor.contributed,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        round.fund = round.fund.checked_sub(sponsor.contributed).unwrap();
+        round.save()?;
+
+        sponsor.contributed = 0;
+        Sponsor::pack(sponsor, &mut sponsor_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Gives a sponsor their share of the fund no project was matched with
+    pub fn process_reclaim_matching(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let sponsor_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let to_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Claim)?;
+        VaultAccount::load(vault_info, &round)?;
+
+        validation::check_owner(sponsor_info, program_id)?;
+        let mut sponsor = Sponsor::unpack(&sponsor_info.data.borrow())?;
+        if sponsor.round != *round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        if sponsor.source != *to_info.key {
+            return Err(QFError::RecipientMismatch.into());
+        }
+        if sponsor.reclaimed {
+            return Err(QFError::MatchingAlreadyReclaimed.into());
+        }
+
+        let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+        if to.owner != *to_auth_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(to_auth_info)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let amount = Self::reclaim_share(&mut round, sponsor.contributed);
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &to_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                amount,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        sponsor.reclaimed = true;
+        Sponsor::pack(sponsor, &mut sponsor_info.data.borrow_mut())?;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// A sponsor's share of the fund applied to the unallocated part, capped at what the
+    /// other sponsors left of it and added to the round's `reclaimed`
+    pub fn reclaim_share(round: &mut Round, contributed: u64) -> u64 {
+        let share = if round.fund == 0 {
+            0
+        } else {
+            U256::from(round.unallocated)
+                .checked_mul(U256::from(contributed))
+                .unwrap()
+                .checked_div(U256::from(round.fund))
+                .unwrap()
+                .as_u64()
+        };
+        let amount = share.min(round.unallocated.saturating_sub(round.reclaimed));
+        round.reclaimed = round.reclaimed.checked_add(amount).unwrap();
+        amount
+    }
+
+    pub fn process_pledge(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pledge_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Registration)?;
+
+        validation::check_signer(from_auth_info)?;
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+        if mint_info.key != &round.mint {
+            return Err(QFError::MintMismatch.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            pledge_info,
+            &[
+                b"pledge",
+                &round_info.key.to_bytes(),
+                &from_info.key.to_bytes(),
+            ],
+            program_id,
+            QFError::PledgeMismatch,
+        )?;
+
+        let escrow = spl_token::state::Account::unpack(&escrow_info.data.borrow())?;
+        if escrow.owner != *pledge_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        if escrow.mint != round.mint {
+            return Err(QFError::MintMismatch.into());
+        }
+
+        if pledge_info.owner != program_id {
+            let seeds: &[&[_]] = &[
+                b"pledge",
+                &round_info.key.to_bytes(),
+                &from_info.key.to_bytes(),
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                from_auth_info,
+                pledge_info,
+                system_program_info,
+                rent,
+                Pledge::LEN,
+                seeds,
+            )?;
+        }
+        let mut pledge = Pledge::unpack_unchecked(&pledge_info.data.borrow())?;
+        if !pledge.is_initialized() {
+            pledge.round = *round_info.key;
+            pledge.source = *from_info.key;
+            pledge.escrow = *escrow_info.key;
+        }
+        if pledge.escrow != *escrow_info.key {
+            return Err(QFError::PledgeMismatch.into());
+        }
+
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            escrow_info,
+            from_auth_info,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        pledge.amount = pledge.amount.checked_add(amount).unwrap();
+        Pledge::pack(pledge, &mut pledge_info.data.borrow_mut())?;
+
+        round.pledged = round.pledged.checked_add(amount).unwrap();
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Moves a pledge into the vault, anyone can do it once voting started
+    pub fn process_execute_pledge(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pledge_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(round.status, RoundStatus::Voting | RoundStatus::Tallying) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        VaultAccount::load(vault_info, &round)?;
+
+        validation::check_owner(pledge_info, program_id)?;
+        let mut pledge = Pledge::unpack(&pledge_info.data.borrow())?;
+        if pledge.round != *round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        if pledge.escrow != *escrow_info.key {
+            return Err(QFError::PledgeMismatch.into());
+        }
+        if pledge.executed {
+            return Err(QFError::PledgeAlreadyExecuted.into());
+        }
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[
+                b"pledge",
+                &round_info.key.to_bytes(),
+                &pledge.source.to_bytes(),
+            ],
+            &program_id,
+        );
+        let seeds: &[&[_]] = &[
+            b"pledge",
+            &round_info.key.to_bytes(),
+            &pledge.source.to_bytes(),
+            &[bump_seed],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &escrow_info.key,
+                &vault_info.key,
+                &pledge_info.key,
+                &[],
+                pledge.amount,
+            )?,
+            &[
+                escrow_info.clone(),
+                vault_info.clone(),
+                pledge_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        round.pledged = round.pledged.checked_sub(pledge.amount).unwrap();
+        round.fund = round.fund.checked_add(pledge.amount).unwrap();
+        round.save()?;
+
+        pledge.executed = true;
+        Pledge::pack(pledge, &mut pledge_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Gives a pledge back after the round was cancelled
+    pub fn process_reclaim_pledge(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pledge_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let to_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Cancelled)?;
+        VaultAccount::load(vault_info, &round)?;
+
+        validation::check_owner(pledge_info, program_id)?;
+        let mut pledge = Pledge::unpack(&pledge_info.data.borrow())?;
+        if pledge.round != *round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        if pledge.escrow != *escrow_info.key {
+            return Err(QFError::PledgeMismatch.into());
+        }
+        if pledge.source != *to_info.key {
+            return Err(QFError::RecipientMismatch.into());
+        }
+
+        let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+        if to.owner != *to_auth_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(to_auth_info)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        if pledge.executed {
+            // the pledge is in the vault already
+            let seeds: &[&[_]] = &[
+                &round.owner.to_bytes(),
+                &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
+            ];
+            invoke_signed(
+                &spl_token::instruction::transfer(
+                    &token_program_info.key,
+                    &vault_info.key,
+                    &to_info.key,
+                    &vault_owner_info.key,
+                    &[&vault_owner_info.key],
+                    pledge.amount,
+                )?,
+                &[
+                    vault_info.clone(),
+                    to_info.clone(),
+                    vault_owner_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&seeds],
+            )?;
+            round.fund = round.fund.checked_sub(pledge.amount).unwrap();
+        } else {
+            let (_, bump_seed) = Pubkey::find_program_address(
+                &[
+                    b"pledge",
+                    &round_info.key.to_bytes(),
+                    &pledge.source.to_bytes(),
+                ],
+                &program_id,
+            );
+            let seeds: &[&[_]] = &[
+                b"pledge",
+                &round_info.key.to_bytes(),
+                &pledge.source.to_bytes(),
+                &[bump_seed],
+            ];
+            invoke_signed(
+                &spl_token::instruction::transfer(
+                    &token_program_info.key,
+                    &escrow_info.key,
+                    &to_info.key,
+                    &pledge_info.key,
+                    &[],
+                    pledge.amount,
+                )?,
+                &[
+                    escrow_info.clone(),
+                    to_info.clone(),
+                    pledge_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&seeds],
+            )?;
+            round.pledged = round.pledged.checked_sub(pledge.amount).unwrap();
+        }
+        round.save()?;
+
+        pledge.amount = 0;
+        Pledge::pack(pledge, &mut pledge_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
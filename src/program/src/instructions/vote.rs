@@ -0,0 +1,1328 @@
+//! Votes, signed directly or through a batch, a permit, a session or a delegation
+
+use super::rewards::Receipt;
+use crate::{
+    ed25519,
+    error::QFError,
+    events::VoteKind,
+    instruction::{permit_message, vote_batch_message, VoteBatchEntry},
+    jupiter,
+    processor::Processor,
+    stake_pool,
+    state::{Credits, Delegation, RoundMode, RoundStatus, Session, Voter, VoterRegistry},
+    validation::{self, ProjectAccount, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_math::uint::U256;
+
+/// Accounts Vote takes, VoteWithSwap passes the route's after them
+const VOTE_ACCOUNTS: usize = 9;
+
+impl Processor {
+    pub fn process_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let receipt_infos = account_info_iter.as_slice();
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_signer(from_auth_info)?;
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &from_auth_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+
+        // the authority is either the owner or a delegate approved for enough tokens
+        let from = spl_token::state::Account::unpack(&from_info.data.borrow())?;
+        if from.owner != *from_auth_info.key {
+            match from.delegate {
+                COption::Some(delegate) if delegate == *from_auth_info.key => {
+                    if from.delegated_amount < amount {
+                        return Err(QFError::InsufficientDelegatedAmount.into());
+                    }
+                }
+                _ => return Err(QFError::InvalidTokenAuthority.into()),
+            }
+        }
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            to_info,
+            from_auth_info,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        Self::apply_vote(
+            &mut round,
+            &mut project,
+            &mut voter,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            from_auth_info.key,
+            amount,
+            VoteKind::Vote,
+        )?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        Self::record_vote(
+            program_id,
+            registry_info,
+            round_info.key,
+            &mut round,
+            from_auth_info.key,
+            amount,
+            amount,
+            voter.last_vote_at,
+        )?;
+        round.save()?;
+
+        // the first vote passing the receipt accounts mints the voter's receipt
+        if !receipt_infos.is_empty() {
+            let receipt = Receipt::new(receipt_infos)?;
+            if !receipt.is_minted() {
+                receipt.mint(program_id, voter_info, from_auth_info)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Swaps any token into the round mint through Jupiter and votes with exactly what the
+    /// swap paid into the wallet's token account of the round mint. The accounts are
+    /// those of Vote, then the Jupiter program and the accounts of the route.
+    pub fn process_vote_with_swap(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        decimals: u8,
+        route: Vec<u8>,
+    ) -> ProgramResult {
+        if accounts.len() < VOTE_ACCOUNTS + 1 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let (vote_infos, swap_infos) = accounts.split_at(VOTE_ACCOUNTS);
+        let from_info = &vote_infos[3];
+
+        let amount = jupiter::swap(&swap_infos[0], &swap_infos[1..], route, from_info)?;
+        msg!("Swapped into {} tokens of the round mint", amount);
+        Self::process_vote(program_id, vote_infos, amount, decimals)
+    }
+
+    /// Spends tokens against a project in a Standard round whose config allows downvotes.
+    /// The square root of a voter's downvotes is taken out of the project's, the area is
+    /// floored at zero. A wallet can downvote up to `downvote_share_bps` of its votes in
+    /// the round, the tokens go to the fund.
+    pub fn process_downvote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if round.config.mode != RoundMode::Standard || !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_signer(from_auth_info)?;
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &from_auth_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+        if voter.blacklisted {
+            return Err(QFError::VoterBlacklisted.into());
+        }
+        Self::check_vote_cooldown(&round, &mut voter)?;
+
+        let mut registry = Self::unpack_voter_registry(
+            program_id,
+            registry_info,
+            round_info.key,
+            from_auth_info.key,
+        )?;
+        registry.downvotes = registry.downvotes.checked_add(amount).unwrap();
+        if registry.downvotes > Self::bps_of(registry.votes, round.config.downvote_share_bps) {
+            return Err(QFError::DownvoteBudgetExceeded.into());
+        }
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            to_info,
+            from_auth_info,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        let matched = round.matches(&project);
+        if matched {
+            round.sub_area(project.category, project.area);
+        }
+        let downvotes = voter.downvotes.checked_add(amount).unwrap();
+        project.down_sqrt = project
+            .down_sqrt
+            .checked_sub(Self::votes_sqrt(voter.downvotes))
+            .unwrap()
+            .checked_add(Self::votes_sqrt(downvotes))
+            .unwrap();
+        project.downvotes = project.downvotes.checked_add(amount).unwrap();
+        project.area = Self::project_area(
+            project.area_sqrt,
+            project.down_sqrt,
+            round.config.endorsement_bonus_bps(project.endorsements),
+        );
+        if matched {
+            round.add_area(project.category, project.area);
+        }
+        voter.downvotes = downvotes;
+        round.fund = round.fund.checked_add(amount).unwrap();
+
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            from_auth_info.key,
+            amount,
+            VoteKind::Downvote,
+        )?;
+        round.save()?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        VoterRegistry::pack(registry, &mut registry_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Votes with stake pool tokens, normalized to lamports by the pool exchange rate
+    pub fn process_stake_pool_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let stake_pool_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !matches!(
+            round.config.mode,
+            RoundMode::Standard | RoundMode::Conviction
+        ) {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        if round.config.stake_pool == Pubkey::default()
+            || round.config.stake_pool != *stake_pool_info.key
+        {
+            return Err(QFError::StakePoolMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_signer(from_auth_info)?;
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &from_auth_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+
+        // the authority is either the owner or a delegate approved for enough tokens
+        let from = spl_token::state::Account::unpack(&from_info.data.borrow())?;
+        if from.owner != *from_auth_info.key {
+            match from.delegate {
+                COption::Some(delegate) if delegate == *from_auth_info.key => {
+                    if from.delegated_amount < amount {
+                        return Err(QFError::InsufficientDelegatedAmount.into());
+                    }
+                }
+                _ => return Err(QFError::InvalidTokenAuthority.into()),
+            }
+        }
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            to_info,
+            from_auth_info,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        // the pool tokens are counted at what they are worth in lamports right now
+        let weight = stake_pool::to_lamports(stake_pool_info, &round.mint, amount)?;
+        Self::apply_weighted_vote(
+            &mut round,
+            &mut project,
+            &mut voter,
+            amount,
+            weight,
+            Clock::get()?.unix_timestamp,
+        )?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            from_auth_info.key,
+            amount,
+            VoteKind::Vote,
+        )?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        Self::record_vote(
+            program_id,
+            registry_info,
+            round_info.key,
+            &mut round,
+            from_auth_info.key,
+            amount,
+            weight,
+            voter.last_vote_at,
+        )?;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Spends credits from the voter's budget on a project, in a Retro round
+    pub fn process_cast_credits(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let credits_info = next_account_info(account_info_iter)?;
+        let wallet_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if round.config.mode != RoundMode::Retro {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_signer(wallet_info)?;
+
+        // the voter is created with InitVoter for the wallet, like for token votes
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &wallet_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+
+        let bump_seed = validation::check_pda(
+            credits_info,
+            &[
+                b"credits",
+                &round_info.key.to_bytes(),
+                &wallet_info.key.to_bytes(),
+            ],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        if credits_info.owner != program_id {
+            let seeds: &[&[_]] = &[
+                b"credits",
+                &round_info.key.to_bytes(),
+                &wallet_info.key.to_bytes(),
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                wallet_info,
+                credits_info,
+                system_program_info,
+                rent,
+                Credits::LEN,
+                seeds,
+            )?;
+        }
+        let mut credits = Credits::unpack_unchecked(&credits_info.data.borrow())?;
+        if !credits.is_initialized() {
+            credits.round = *round_info.key;
+            credits.voter = *wallet_info.key;
+        }
+
+        credits.spent = credits.spent.checked_add(amount).unwrap();
+        if credits.spent > round.config.voter_credits {
+            return Err(QFError::CreditBudgetExceeded.into());
+        }
+
+        Self::apply_vote(
+            &mut round,
+            &mut project,
+            &mut voter,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            wallet_info.key,
+            amount,
+            VoteKind::Vote,
+        )?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        Self::record_vote(
+            program_id,
+            registry_info,
+            round_info.key,
+            &mut round,
+            wallet_info.key,
+            amount,
+            amount,
+            voter.last_vote_at,
+        )?;
+        round.save()?;
+        Credits::pack(credits, &mut credits_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub fn process_submit_vote_batch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        nonce: u64,
+        entries: Vec<VoteBatchEntry>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let instructions_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        // a round started without an aggregator takes no batches
+        if round.config.aggregator == Pubkey::default() {
+            return Err(QFError::AggregatorNotSet.into());
+        }
+        if nonce != round.batch_nonce {
+            return Err(QFError::InvalidBatchNonce.into());
+        }
+        ed25519::verify_previous_instruction(
+            instructions_info,
+            &round.config.aggregator,
+            &vote_batch_message(round_info.key, nonce, &entries),
+        )?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let total = entries
+            .iter()
+            .try_fold(0u64, |total, entry| total.checked_add(entry.amount))
+            .ok_or(QFError::BatchTotalOverflow)?;
+        let decimals = spl_token::state::Mint::unpack(&mint_info.data.borrow())?.decimals;
+
+        invoke(
+            &spl_token::instruction::transfer_checked(
+                &token_program_info.key,
+                &from_info.key,
+                &mint_info.key,
+                &to_info.key,
+                &from_auth_info.key,
+                &[&from_auth_info.key],
+                total,
+                decimals,
+            )?,
+            &[
+                from_info.clone(),
+                mint_info.clone(),
+                to_info.clone(),
+                from_auth_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        for entry in entries.iter() {
+            let project_info = next_account_info(account_info_iter)?;
+            let voter_info = next_account_info(account_info_iter)?;
+            let registry_info = next_account_info(account_info_iter)?;
+
+            if project_info.key != &entry.project {
+                return Err(QFError::ProjectMismatch.into());
+            }
+            let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+            validation::check_owner(voter_info, program_id)?;
+            validation::check_pda(
+                voter_info,
+                &[&project_info.key.to_bytes(), &entry.voter.to_bytes()],
+                program_id,
+                QFError::VoterMismatch,
+            )?;
+            let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+
+            // the custodian paid for the entry, a refund gives the tokens back to it
+            Self::apply_custodial_vote(
+                &mut round,
+                &mut project,
+                &mut voter,
+                from_auth_info.key,
+                entry.amount,
+                Clock::get()?.unix_timestamp,
+            )?;
+            Self::log_vote(
+                round_info.key,
+                &mut round,
+                project_info.key,
+                &entry.voter,
+                entry.amount,
+                VoteKind::Vote,
+            )?;
+            project.save()?;
+            Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+            Self::record_vote(
+                program_id,
+                registry_info,
+                round_info.key,
+                &mut round,
+                &entry.voter,
+                entry.amount,
+                entry.amount,
+                voter.last_vote_at,
+            )?;
+        }
+
+        round.batch_nonce = round.batch_nonce.checked_add(1).unwrap();
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Undoes the voter's last vote within the grace period and gives the tokens back
+    pub fn process_revoke_vote(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let to_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(vault_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &to_auth_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        validation::check_owner(voter_info, program_id)?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+        // only the wallet's own votes can be revoked, a custodian's are refunded to it
+        if voter.custodian != Pubkey::default() {
+            return Err(QFError::CustodianMismatch.into());
+        }
+        if round.config.vote_grace_period == 0
+            || voter.last_vote_amount == 0
+            || clock.unix_timestamp > voter.last_vote_at + round.config.vote_grace_period
+        {
+            return Err(QFError::GracePeriodOver.into());
+        }
+
+        let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+        if to.owner != *to_auth_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(to_auth_info)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &to_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                voter.last_vote_amount,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        Self::unrecord_vote(
+            program_id,
+            registry_info,
+            round_info.key,
+            &mut round,
+            to_auth_info.key,
+            voter.last_vote_amount,
+            voter.last_vote_weight,
+        )?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            to_auth_info.key,
+            voter.last_vote_amount,
+            VoteKind::Revoke,
+        )?;
+        Self::revert_vote(&mut round, &mut project, &mut voter, clock.unix_timestamp)?;
+        round.save()?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Gives a voter's tokens back after the round was cancelled
+    pub fn process_refund(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let to_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Cancelled)?;
+        if !round.config.mode.votes_are_tokens() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(vault_info, &round)?;
+
+        ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_owner(voter_info, program_id)?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+        // the voter account is derived from the wallet that voted. Votes a custodian paid
+        // in a batch go back to the custodian, which passes the wallet after the token
+        // program.
+        let wallet = if voter.custodian == Pubkey::default() {
+            *to_auth_info.key
+        } else if voter.custodian == *to_auth_info.key {
+            *next_account_info(account_info_iter)?.key
+        } else {
+            return Err(QFError::CustodianMismatch.into());
+        };
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &wallet.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+
+        let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+        if to.owner != *to_auth_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(to_auth_info)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &to_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                voter.votes,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        voter.votes = 0;
+        voter.weight = 0;
+        voter.votes_sqrt = U256::zero();
+        voter.tier = 0;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub fn process_vote_with_permit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+        expiry: i64,
+        nonce: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let permit_authority_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let instructions_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        // the voter is the wallet owning the token account, it signs the permit
+        let from = spl_token::state::Account::unpack(&from_info.data.borrow())?;
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &from.owner.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+
+        if clock.unix_timestamp > expiry {
+            return Err(QFError::PermitExpired.into());
+        }
+        if nonce != voter.permit_nonce {
+            return Err(QFError::InvalidPermitNonce.into());
+        }
+        ed25519::verify_previous_instruction(
+            instructions_info,
+            &from.owner,
+            &permit_message(project_info.key, amount, expiry, nonce),
+        )?;
+
+        let (permit_authority, bump_seed) = Pubkey::find_program_address(&[b"permit"], &program_id);
+        if permit_authority_info.key != &permit_authority {
+            return Err(QFError::OwnerMismatch.into());
+        }
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        invoke_signed(
+            &spl_token::instruction::transfer_checked(
+                &token_program_info.key,
+                &from_info.key,
+                &mint_info.key,
+                &to_info.key,
+                &permit_authority_info.key,
+                &[],
+                amount,
+                decimals,
+            )?,
+            &[
+                from_info.clone(),
+                mint_info.clone(),
+                to_info.clone(),
+                permit_authority_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&[b"permit", &[bump_seed]]],
+        )?;
+
+        Self::apply_vote(
+            &mut round,
+            &mut project,
+            &mut voter,
+            amount,
+            clock.unix_timestamp,
+        )?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            &from.owner,
+            amount,
+            VoteKind::Vote,
+        )?;
+        voter.permit_nonce = voter.permit_nonce.checked_add(1).unwrap();
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        Self::record_vote(
+            program_id,
+            registry_info,
+            round_info.key,
+            &mut round,
+            &from.owner,
+            amount,
+            amount,
+            voter.last_vote_at,
+        )?;
+        round.save()?;
+
+        Ok(())
+    }
+
+    pub fn process_create_session(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_amount: u64,
+        expiry: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let new_session_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let session_key_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        validation::check_signer(authority_info)?;
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[
+                b"session",
+                &authority_info.key.to_bytes(),
+                &session_key_info.key.to_bytes(),
+            ],
+            &program_id,
+        );
+        let seeds: &[&[_]] = &[
+            b"session",
+            &authority_info.key.to_bytes(),
+            &session_key_info.key.to_bytes(),
+            &[bump_seed],
+        ];
+
+        Self::create_pda_account(
+            program_id,
+            authority_info,
+            new_session_info,
+            system_program_info,
+            rent,
+            Session::LEN,
+            seeds,
+        )?;
+
+        let mut session = Session::unpack_unchecked(&new_session_info.data.borrow())?;
+        if session.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Approve the session to spend {} tokens", max_amount);
+        invoke(
+            &spl_token::instruction::approve(
+                &token_program_info.key,
+                &source_info.key,
+                &new_session_info.key,
+                &authority_info.key,
+                &[],
+                max_amount,
+            )?,
+            &[
+                source_info.clone(),
+                new_session_info.clone(),
+                authority_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        session.is_initialized = true;
+        session.authority = *authority_info.key;
+        session.session_key = *session_key_info.key;
+        session.source = *source_info.key;
+        session.max_amount = max_amount;
+        session.spent = 0;
+        session.expiry = expiry;
+
+        Session::pack(session, &mut new_session_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub fn process_session_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let session_info = next_account_info(account_info_iter)?;
+        let session_key_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_owner(session_info, program_id)?;
+        let mut session = Session::unpack(&session_info.data.borrow())?;
+        validation::check_signer(session_key_info)?;
+        if session.session_key != *session_key_info.key {
+            return Err(QFError::SessionMismatch.into());
+        }
+        if session.source != *from_info.key {
+            return Err(QFError::SessionMismatch.into());
+        }
+        if clock.unix_timestamp > session.expiry {
+            return Err(QFError::SessionExpired.into());
+        }
+        session.spent = session.spent.checked_add(amount).unwrap();
+        if session.spent > session.max_amount {
+            return Err(QFError::SessionLimitExceeded.into());
+        }
+
+        // the votes are counted for the wallet that created the session
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &session.authority.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[
+                b"session",
+                &session.authority.to_bytes(),
+                &session.session_key.to_bytes(),
+            ],
+            &program_id,
+        );
+        let seeds: &[&[_]] = &[
+            b"session",
+            &session.authority.to_bytes(),
+            &session.session_key.to_bytes(),
+            &[bump_seed],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer_checked(
+                &token_program_info.key,
+                &from_info.key,
+                &mint_info.key,
+                &to_info.key,
+                &session_info.key,
+                &[],
+                amount,
+                decimals,
+            )?,
+            &[
+                from_info.clone(),
+                mint_info.clone(),
+                to_info.clone(),
+                session_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        Self::apply_vote(
+            &mut round,
+            &mut project,
+            &mut voter,
+            amount,
+            clock.unix_timestamp,
+        )?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            &session.authority,
+            amount,
+            VoteKind::Vote,
+        )?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        Self::record_vote(
+            program_id,
+            registry_info,
+            round_info.key,
+            &mut round,
+            &session.authority,
+            amount,
+            amount,
+            voter.last_vote_at,
+        )?;
+        round.save()?;
+        Session::pack(session, &mut session_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub fn process_delegate_votes(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        budget: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let new_delegation_info = next_account_info(account_info_iter)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let delegator_info = next_account_info(account_info_iter)?;
+        let delegate_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        validation::check_signer(delegator_info)?;
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            new_delegation_info,
+            &[
+                b"delegation",
+                &round_info.key.to_bytes(),
+                &delegator_info.key.to_bytes(),
+            ],
+            program_id,
+            QFError::DelegationMismatch,
+        )?;
+        let seeds: &[&[_]] = &[
+            b"delegation",
+            &round_info.key.to_bytes(),
+            &delegator_info.key.to_bytes(),
+            &[bump_seed],
+        ];
+
+        Self::create_pda_account(
+            program_id,
+            delegator_info,
+            new_delegation_info,
+            system_program_info,
+            rent,
+            Delegation::LEN,
+            seeds,
+        )?;
+
+        let mut delegation = Delegation::unpack_unchecked(&new_delegation_info.data.borrow())?;
+        if delegation.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        msg!("Approve the delegation to spend {} tokens", budget);
+        invoke(
+            &spl_token::instruction::approve(
+                &token_program_info.key,
+                &source_info.key,
+                &new_delegation_info.key,
+                &delegator_info.key,
+                &[],
+                budget,
+            )?,
+            &[
+                source_info.clone(),
+                new_delegation_info.clone(),
+                delegator_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        delegation.round = *round_info.key;
+        delegation.delegator = *delegator_info.key;
+        delegation.delegate = *delegate_info.key;
+        delegation.source = *source_info.key;
+        delegation.budget = budget;
+        delegation.spent = 0;
+
+        Delegation::pack(delegation, &mut new_delegation_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub fn process_vote_as_delegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let delegation_info = next_account_info(account_info_iter)?;
+        let delegate_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_owner(delegation_info, program_id)?;
+        let mut delegation = Delegation::unpack(&delegation_info.data.borrow())?;
+        if delegation.round != *round_info.key {
+            return Err(QFError::RoundMismatch.into());
+        }
+        let bump_seed = validation::check_pda(
+            delegation_info,
+            &[
+                b"delegation",
+                &delegation.round.to_bytes(),
+                &delegation.delegator.to_bytes(),
+            ],
+            program_id,
+            QFError::DelegationMismatch,
+        )?;
+        validation::check_signer(delegate_info)?;
+        if delegation.delegate != *delegate_info.key || delegation.source != *from_info.key {
+            return Err(QFError::DelegationMismatch.into());
+        }
+        delegation.spent = delegation.spent.checked_add(amount).unwrap();
+        if delegation.spent > delegation.budget {
+            return Err(QFError::DelegationBudgetExceeded.into());
+        }
+
+        // the votes are counted for the delegator
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[
+                &project_info.key.to_bytes(),
+                &delegation.delegator.to_bytes(),
+            ],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let seeds: &[&[_]] = &[
+            b"delegation",
+            &delegation.round.to_bytes(),
+            &delegation.delegator.to_bytes(),
+            &[bump_seed],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer_checked(
+                &token_program_info.key,
+                &from_info.key,
+                &mint_info.key,
+                &to_info.key,
+                &delegation_info.key,
+                &[],
+                amount,
+                decimals,
+            )?,
+            &[
+                from_info.clone(),
+                mint_info.clone(),
+                to_info.clone(),
+                delegation_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        Self::apply_vote(
+            &mut round,
+            &mut project,
+            &mut voter,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            &delegation.delegator,
+            amount,
+            VoteKind::Vote,
+        )?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        Self::record_vote(
+            program_id,
+            registry_info,
+            round_info.key,
+            &mut round,
+            &delegation.delegator,
+            amount,
+            amount,
+            voter.last_vote_at,
+        )?;
+        round.save()?;
+        Delegation::pack(delegation, &mut delegation_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+//! Voter accounts: creating and migrating them, and endorsing with them
+
+use crate::{
+    error::QFError,
+    processor::Processor,
+    state::{RoundStatus, Voter, VoterRegistry},
+    validation::{self, ProjectAccount, RoundAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_math::{
+    precise_number::{PreciseNumber, ONE},
+    uint::U256,
+};
+
+impl Processor {
+    pub fn process_init_voter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let new_voter_info = next_account_info(account_info_iter)?;
+        let wallet_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let round_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        // a wallet has one voter per project, whatever token accounts it votes from
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[&project_info.key.to_bytes(), &wallet_info.key.to_bytes()],
+            &program_id,
+        );
+        let seeds: &[&[_]] = &[
+            &project_info.key.to_bytes(),
+            &wallet_info.key.to_bytes(),
+            &[bump_seed],
+        ];
+
+        Self::create_pda_account(
+            program_id,
+            from_info,
+            new_voter_info,
+            system_program_info,
+            rent,
+            Voter::LEN,
+            seeds,
+        )?;
+
+        let mut voter = Voter::unpack_unchecked(&new_voter_info.data.borrow())?;
+        if voter.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        voter.is_initialized = true;
+        voter.votes = 0;
+        voter.votes_sqrt = U256::from(0);
+        voter.permit_nonce = 0;
+        voter.last_vote_amount = 0;
+        voter.last_vote_weight = 0;
+        voter.last_vote_at = 0;
+        voter.airdrop_claimed = false;
+        voter.weight = 0;
+        voter.blacklisted = false;
+
+        Voter::pack(voter, &mut new_voter_info.data.borrow_mut())?;
+
+        let mut registry = Self::voter_registry(
+            program_id,
+            from_info,
+            registry_info,
+            system_program_info,
+            rent,
+            round_info.key,
+            wallet_info.key,
+        )?;
+        // a wallet in the voter tree keeps voting through it
+        if registry.tree_slot != 0 {
+            return Err(QFError::VoterKindMismatch.into());
+        }
+        registry.projects = registry.projects.checked_add(1).unwrap();
+        VoterRegistry::pack(registry, &mut registry_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Moves a voter derived from a token account to the voter derived from the wallet
+    /// owning it, merging the two if the wallet already has one
+    pub fn process_migrate_voter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let old_voter_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let wallet_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        // only the owner of the token account can move its votes
+        let from = spl_token::state::Account::unpack(&from_info.data.borrow())?;
+        if from.owner != *wallet_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(wallet_info)?;
+
+        validation::check_owner(old_voter_info, program_id)?;
+        validation::check_pda(
+            old_voter_info,
+            &[&project_info.key.to_bytes(), &from_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let old_voter = Voter::unpack(&old_voter_info.data.borrow())?;
+
+        let bump_seed = validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &wallet_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        if voter_info.owner != program_id {
+            let seeds: &[&[_]] = &[
+                &project_info.key.to_bytes(),
+                &wallet_info.key.to_bytes(),
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                payer_info,
+                voter_info,
+                system_program_info,
+                rent,
+                Voter::LEN,
+                seeds,
+            )?;
+        }
+        let voter = Voter::unpack_unchecked(&voter_info.data.borrow())?;
+        // a custodian's votes are refunded to it, the wallet's own can not join them
+        if voter.custodian != Pubkey::default() {
+            return Err(QFError::CustodianMismatch.into());
+        }
+
+        // the old voter was never counted in the wallet's registry
+        let mut registry = Self::voter_registry(
+            program_id,
+            payer_info,
+            registry_info,
+            system_program_info,
+            rent,
+            round_info.key,
+            wallet_info.key,
+        )?;
+        if !voter.is_initialized() {
+            registry.projects = registry.projects.checked_add(1).unwrap();
+        }
+        registry.votes = registry.votes.checked_add(old_voter.votes).unwrap();
+        registry.weight = registry.weight.checked_add(old_voter.weight).unwrap();
+        registry.last_vote_at = registry.last_vote_at.max(old_voter.last_vote_at);
+        VoterRegistry::pack(registry, &mut registry_info.data.borrow_mut())?;
+
+        let voter = if !voter.is_initialized() {
+            old_voter
+        } else if old_voter.weight == 0 || voter.weight == 0 {
+            Voter {
+                votes: voter.votes.checked_add(old_voter.votes).unwrap(),
+                weight: voter.weight.checked_add(old_voter.weight).unwrap(),
+                votes_sqrt: voter.votes_sqrt.checked_add(old_voter.votes_sqrt).unwrap(),
+                ..Self::merge_voter_flags(&voter, &old_voter)
+            }
+        } else {
+            // both voted, the square root of the sum replaces the two square roots
+            validation::check_status(&round, RoundStatus::Voting)?;
+            if voter.blacklisted || old_voter.blacklisted {
+                return Err(QFError::VoterBlacklisted.into());
+            }
+            let weight = voter.weight.checked_add(old_voter.weight).unwrap();
+            let votes_sqrt = PreciseNumber {
+                value: U256::from(weight).checked_mul(U256::from(ONE)).unwrap(),
+            }
+            .sqrt()
+            .unwrap();
+
+            let matched = round.matches(&project);
+            if matched {
+                round.sub_area(project.category, project.area);
+            }
+            let project_area_sqrt = PreciseNumber {
+                value: project.area_sqrt,
+            }
+            .checked_sub(&PreciseNumber {
+                value: voter.votes_sqrt,
+            })
+            .unwrap()
+            .checked_sub(&PreciseNumber {
+                value: old_voter.votes_sqrt,
+            })
+            .unwrap()
+            .checked_add(&votes_sqrt)
+            .unwrap();
+            project.area = Self::project_area(
+                project_area_sqrt.value,
+                project.down_sqrt,
+                round.config.endorsement_bonus_bps(project.endorsements),
+            );
+            if matched {
+                round.add_area(project.category, project.area);
+            }
+            project.area_sqrt = project_area_sqrt.value;
+
+            Voter {
+                votes: voter.votes.checked_add(old_voter.votes).unwrap(),
+                weight,
+                votes_sqrt: votes_sqrt.value,
+                ..Self::merge_voter_flags(&voter, &old_voter)
+            }
+        };
+
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        Voter::pack(Voter::default(), &mut old_voter_info.data.borrow_mut())?;
+        project.save()?;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Everything but the votes of two voters merged into one, the last vote is the later one
+    fn merge_voter_flags(voter: &Voter, other: &Voter) -> Voter {
+        let last = if other.last_vote_at > voter.last_vote_at {
+            other
+        } else {
+            voter
+        };
+        Voter {
+            is_initialized: true,
+            permit_nonce: voter.permit_nonce.max(other.permit_nonce),
+            last_vote_amount: last.last_vote_amount,
+            last_vote_weight: last.last_vote_weight,
+            last_vote_at: last.last_vote_at,
+            last_vote_slot: voter.last_vote_slot.max(other.last_vote_slot),
+            airdrop_claimed: voter.airdrop_claimed || other.airdrop_claimed,
+            blacklisted: voter.blacklisted || other.blacklisted,
+            // only voters derived from a wallet can downvote
+            downvotes: voter.downvotes,
+            ..Voter::default()
+        }
+    }
+
+    /// Endorses the project of a voter, once per voter and without tokens. The count is
+    /// kept on the project, and rounds with an `endorsement_bonus_bps` add to its area.
+    pub fn process_endorse(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let voter_info = next_account_info(account_info_iter)?;
+        let wallet_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        validation::check_signer(wallet_info)?;
+        validation::check_owner(voter_info, program_id)?;
+        validation::check_pda(
+            voter_info,
+            &[&project_info.key.to_bytes(), &wallet_info.key.to_bytes()],
+            program_id,
+            QFError::VoterMismatch,
+        )?;
+        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
+        if voter.blacklisted {
+            return Err(QFError::VoterBlacklisted.into());
+        }
+        if voter.endorsed {
+            return Err(QFError::AlreadyEndorsed.into());
+        }
+
+        let matched = round.matches(&project);
+        if matched {
+            round.sub_area(project.category, project.area);
+        }
+        project.endorsements = project.endorsements.checked_add(1).unwrap();
+        project.area = Self::project_area(
+            project.area_sqrt,
+            project.down_sqrt,
+            round.config.endorsement_bonus_bps(project.endorsements),
+        );
+        if matched {
+            round.add_area(project.category, project.area);
+        }
+        voter.endorsed = true;
+
+        round.save()?;
+        project.save()?;
+        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
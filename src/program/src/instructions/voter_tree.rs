@@ -0,0 +1,403 @@
+//! Compressed voters, kept as the leaves of a round's voter tree instead of a voter
+//! account per project
+
+use crate::{
+    error::QFError,
+    events::VoteKind,
+    merkle,
+    processor::Processor,
+    state::{
+        ChangeLog, RoundStatus, Voter, VoterRegistry, VoterTree, VOTER_TREE_BUFFER,
+        VOTER_TREE_DEPTH, VOTER_TREE_PROJECT_BITS,
+    },
+    validation::{self, ProjectAccount, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    hash::hashv,
+    program::invoke_signed,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+impl Processor {
+    /// Creates the voter tree of a round, anyone can pay for it
+    pub fn process_init_voter_tree(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let tree_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(
+            round.status,
+            RoundStatus::Registration | RoundStatus::Voting
+        ) {
+            return Err(QFError::RoundStatusError.into());
+        }
+
+        let bump_seed = validation::check_pda(
+            tree_info,
+            &[b"voter_tree", &round_info.key.to_bytes()],
+            program_id,
+            QFError::VoterTreeMismatch,
+        )?;
+        let seeds: &[&[_]] = &[b"voter_tree", &round_info.key.to_bytes(), &[bump_seed]];
+        Self::create_pda_account(
+            program_id,
+            payer_info,
+            tree_info,
+            system_program_info,
+            rent,
+            VoterTree::ACCOUNT_LEN,
+            seeds,
+        )?;
+
+        let mut data = tree_info.data.borrow_mut();
+        let (tree_data, change_logs) = data.split_at_mut(VoterTree::LEN);
+        if VoterTree::unpack_unchecked(tree_data).is_ok() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        let tree = VoterTree::init(*round_info.key, change_logs);
+        VoterTree::pack(tree, tree_data)
+    }
+
+    /// Votes like Vote, but the wallet's votes for the project are a leaf of the round's
+    /// voter tree. `votes` and `last_vote_slot` are those of the leaf and `proof` its
+    /// siblings against `root`, a root of the tree's change logs. The wallet's round
+    /// registry is created with its first compressed vote, which also gives it a slot of
+    /// leaves.
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_compressed_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+        root: [u8; 32],
+        votes: u64,
+        last_vote_slot: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let tree_info = next_account_info(account_info_iter)?;
+        let from_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let from_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Voting)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(to_info, &round)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        if project.index >> VOTER_TREE_PROJECT_BITS != 0 {
+            return Err(QFError::VoterTreeFull.into());
+        }
+
+        validation::check_signer(from_auth_info)?;
+        let mut registry = Self::voter_registry(
+            program_id,
+            from_auth_info,
+            registry_info,
+            system_program_info,
+            &Rent::get()?,
+            round_info.key,
+            from_auth_info.key,
+        )?;
+
+        let mut data = tree_info.data.borrow_mut();
+        let (tree_data, change_logs) = data.split_at_mut(VoterTree::LEN);
+        let mut tree = Self::unpack_voter_tree(program_id, tree_info, tree_data, round_info.key)?;
+        if registry.tree_slot == 0 {
+            // a wallet with voter accounts keeps voting through them
+            if registry.projects > 0 {
+                return Err(QFError::VoterKindMismatch.into());
+            }
+            if tree.wallets >> (VOTER_TREE_DEPTH - VOTER_TREE_PROJECT_BITS) != 0 {
+                return Err(QFError::VoterTreeFull.into());
+            }
+            tree.wallets = tree.wallets.checked_add(1).unwrap();
+            registry.tree_slot = tree.wallets;
+        }
+
+        // the authority is either the owner or a delegate approved for enough tokens
+        let from = spl_token::state::Account::unpack(&from_info.data.borrow())?;
+        if from.owner != *from_auth_info.key {
+            match from.delegate {
+                COption::Some(delegate) if delegate == *from_auth_info.key => {
+                    if from.delegated_amount < amount {
+                        return Err(QFError::InsufficientDelegatedAmount.into());
+                    }
+                }
+                _ => return Err(QFError::InvalidTokenAuthority.into()),
+            }
+        }
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        Self::transfer_checked_with_signers(
+            token_program_info,
+            from_info,
+            mint_info,
+            to_info,
+            from_auth_info,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        // the voter lives only for the vote, the leaf keeps what the next one needs. An
+        // empty leaf has no last vote, whatever slot the client passed.
+        let mut voter = Voter {
+            is_initialized: true,
+            votes,
+            votes_sqrt: Self::votes_sqrt(votes),
+            weight: votes,
+            last_vote_slot: if votes == 0 { 0 } else { last_vote_slot },
+            ..Voter::default()
+        };
+        Self::apply_vote(
+            &mut round,
+            &mut project,
+            &mut voter,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let index = Self::voter_leaf_index(registry.tree_slot, project.index);
+        Self::set_voter_leaf(
+            &mut tree,
+            change_logs,
+            &root,
+            &Self::voter_leaf(project_info.key, from_auth_info.key, votes, last_vote_slot),
+            &Self::voter_leaf(
+                project_info.key,
+                from_auth_info.key,
+                voter.votes,
+                voter.last_vote_slot,
+            ),
+            index,
+            proof,
+        )?;
+        VoterTree::pack(tree, tree_data)?;
+
+        Self::log_vote(
+            round_info.key,
+            &mut round,
+            project_info.key,
+            from_auth_info.key,
+            amount,
+            VoteKind::Vote,
+        )?;
+        project.save()?;
+        if votes == 0 && voter.votes > 0 {
+            registry.projects = registry.projects.checked_add(1).unwrap();
+        }
+        Self::add_registry_vote(
+            &mut round,
+            &mut registry,
+            amount,
+            amount,
+            voter.last_vote_at,
+        );
+        VoterRegistry::pack(registry, &mut registry_info.data.borrow_mut())?;
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Gives a compressed voter's tokens back after the round was cancelled, the leaf
+    /// proven against `root` is emptied
+    pub fn process_compressed_refund(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        root: [u8; 32],
+        votes: u64,
+        last_vote_slot: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let tree_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let to_auth_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Cancelled)?;
+        if !round.config.raw_token_votes() {
+            return Err(QFError::RoundModeMismatch.into());
+        }
+        VaultAccount::load(vault_info, &round)?;
+
+        let project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+        if to.owner != *to_auth_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(to_auth_info)?;
+        let registry = Self::unpack_voter_registry(
+            program_id,
+            registry_info,
+            round_info.key,
+            to_auth_info.key,
+        )?;
+        if registry.tree_slot == 0 {
+            return Err(QFError::VoterKindMismatch.into());
+        }
+
+        let mut data = tree_info.data.borrow_mut();
+        let (tree_data, change_logs) = data.split_at_mut(VoterTree::LEN);
+        let mut tree = Self::unpack_voter_tree(program_id, tree_info, tree_data, round_info.key)?;
+        Self::set_voter_leaf(
+            &mut tree,
+            change_logs,
+            &root,
+            &Self::voter_leaf(project_info.key, to_auth_info.key, votes, last_vote_slot),
+            &merkle::EMPTY,
+            Self::voter_leaf_index(registry.tree_slot, project.index),
+            proof,
+        )?;
+        VoterTree::pack(tree, tree_data)?;
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                vault_info.key,
+                to_info.key,
+                vault_owner_info.key,
+                &[vault_owner_info.key],
+                votes,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[seeds],
+        )?;
+
+        Ok(())
+    }
+
+    /// Loads the voter tree of `round` from `tree_data`, the head of its account
+    fn unpack_voter_tree(
+        program_id: &Pubkey,
+        tree_info: &AccountInfo,
+        tree_data: &[u8],
+        round: &Pubkey,
+    ) -> Result<VoterTree, ProgramError> {
+        validation::check_owner(tree_info, program_id)?;
+        let tree = VoterTree::unpack(tree_data)?;
+        if tree.round != *round {
+            return Err(QFError::VoterTreeMismatch.into());
+        }
+        Ok(tree)
+    }
+
+    /// The position of a wallet's leaf for a project, from the wallet's slot of leaves
+    /// and the project's index
+    pub fn voter_leaf_index(tree_slot: u64, project_index: u64) -> u64 {
+        (tree_slot - 1) << VOTER_TREE_PROJECT_BITS | project_index
+    }
+
+    /// The leaf of a wallet's votes for a project, empty while it has none
+    pub fn voter_leaf(
+        project: &Pubkey,
+        wallet: &Pubkey,
+        votes: u64,
+        last_vote_slot: u64,
+    ) -> [u8; 32] {
+        if votes == 0 {
+            return merkle::EMPTY;
+        }
+        hashv(&[
+            &project.to_bytes(),
+            &wallet.to_bytes(),
+            &votes.to_le_bytes(),
+            &last_vote_slot.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+
+    /// Replaces `previous` with `leaf` at `index` of a voter tree whose change logs are
+    /// `change_logs`. `proof` is against `root`, the root after one of the changes the
+    /// tree still keeps, and is moved past the changes after it. A change of the same
+    /// leaf since `root` makes `previous` stale and the proof invalid.
+    pub fn set_voter_leaf(
+        tree: &mut VoterTree,
+        change_logs: &mut [u8],
+        root: &[u8; 32],
+        previous: &[u8; 32],
+        leaf: &[u8; 32],
+        index: u64,
+        mut proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        if proof.len() != VOTER_TREE_DEPTH {
+            return Err(QFError::InvalidVoterProof.into());
+        }
+
+        // positions are counted back from the latest change
+        let latest = tree.active_index + VOTER_TREE_BUFFER as u64;
+        let mut age = None;
+        for back in 0..tree.buffer_size {
+            if VoterTree::change_log(change_logs, latest - back)?.root == *root {
+                age = Some(back);
+                break;
+            }
+        }
+        let age = age.ok_or(QFError::StaleVoterTreeRoot)?;
+        for back in (0..age).rev() {
+            let change_log = VoterTree::change_log(change_logs, latest - back)?;
+            if !merkle::fast_forward(&mut proof, index, change_log.index, &change_log.path) {
+                return Err(QFError::InvalidVoterProof.into());
+            }
+        }
+        if !merkle::verify(&tree.root(change_logs)?, previous, index, &proof) {
+            return Err(QFError::InvalidVoterProof.into());
+        }
+
+        let mut change_log = ChangeLog {
+            index,
+            ..ChangeLog::default()
+        };
+        change_log.root = merkle::write_path(&mut change_log.path, leaf, index, &proof);
+        tree.active_index = (tree.active_index + 1) % VOTER_TREE_BUFFER as u64;
+        tree.buffer_size = (tree.buffer_size + 1).min(VOTER_TREE_BUFFER as u64);
+        tree.sequence = tree.sequence.checked_add(1).unwrap();
+        let offset = tree.active_index as usize * ChangeLog::LEN;
+        change_log.pack_into_slice(&mut change_logs[offset..offset + ChangeLog::LEN]);
+        Ok(())
+    }
+}
@@ -0,0 +1,580 @@
+//! Payouts to projects and the round owner, and the vault checks
+
+use crate::{
+    error::QFError,
+    events,
+    events::Event,
+    processor::Processor,
+    state::{ActionKind, Claim, Config, Project, Round, RoundStatus, MAX_CATEGORIES},
+    validation::{self, ProjectAccount, RoundAccount, VaultAccount},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
+use spl_math::uint::U256;
+
+impl Processor {
+    /// Checks the round bookkeeping against a batch of its projects, anyone can do it.
+    /// Violations are logged as events, the instruction does not fail on them.
+    pub fn process_verify_invariants(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let project_infos = account_info_iter.as_slice();
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        let vault = VaultAccount::load(vault_info, &round)?;
+
+        let mut keys = Vec::with_capacity(project_infos.len());
+        let mut area = U256::zero();
+        let mut category_areas = [U256::zero(); MAX_CATEGORIES];
+        let mut committed = round.fees().checked_add(round.holdbacks).unwrap();
+        for project_info in project_infos {
+            let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+            keys.push(*project_info.key);
+
+            if round.matches(&project) {
+                area = area.checked_add(project.area).unwrap();
+                let category = &mut category_areas[project.category as usize];
+                *category = category.checked_add(project.area).unwrap();
+            }
+
+            // payouts are only known once the round is finalized
+            if round.status == RoundStatus::Claim && !project.withdraw {
+                let deposit = if project.banned { 0 } else { project.deposit };
+                let amount = Self::payout(&round, &mut project);
+                committed = committed
+                    .checked_add(amount)
+                    .unwrap()
+                    .checked_add(deposit)
+                    .unwrap();
+            }
+        }
+        keys.sort();
+        keys.dedup();
+        if keys.len() != project_infos.len() {
+            return Err(QFError::DuplicateProject.into());
+        }
+
+        let mut violations = 0;
+        // the areas only add up when every project of the round is passed
+        if keys.len() as u64 == round.project_count {
+            if area != round.area {
+                events::emit(
+                    round_info.key,
+                    &Event::AreaMismatch {
+                        expected: round.area,
+                        actual: area,
+                    },
+                );
+                violations += 1;
+            }
+            for category in 0..round.category_count {
+                let expected = round.categories[category as usize].area;
+                let actual = category_areas[category as usize];
+                if expected != actual {
+                    events::emit(
+                        round_info.key,
+                        &Event::CategoryAreaMismatch {
+                            category,
+                            expected,
+                            actual,
+                        },
+                    );
+                    violations += 1;
+                }
+            }
+        }
+        // a fund lent out comes back to the vault at EndRound
+        let vault_amount = vault.amount.checked_add(round.lent).unwrap();
+        if committed > vault_amount {
+            events::emit(
+                round_info.key,
+                &Event::PayoutsExceedVault {
+                    committed,
+                    vault: vault_amount,
+                },
+            );
+            violations += 1;
+        }
+
+        msg!(
+            "Checked {} of {} projects, {} violations",
+            keys.len(),
+            round.project_count,
+            violations
+        );
+
+        Ok(())
+    }
+
+    pub fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let project_owner_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let associated_token_program_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let payout_wallet_info = next_account_info(account_info_iter)?;
+        let config = Self::load_config(program_id, next_account_info(account_info_iter)?)?;
+        let claim_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(round.status, RoundStatus::Claim | RoundStatus::Cancelled) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        // the dispute window passed before Finalize moved the round to Claim
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+        if project.withdraw {
+            return Err(QFError::ProjectAlreadyWithdraw.into());
+        }
+        validation::check_signer(project_owner_info)?;
+        if project.owner != *project_owner_info.key {
+            return Err(QFError::OwnerMismatch.into());
+        }
+
+        VaultAccount::load(vault_info, &round)?;
+        let (vault_authority, bump_seed) =
+            Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id);
+        if vault_owner_info.key != &vault_authority {
+            return Err(QFError::VaultAuthorityMismatch.into());
+        }
+
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        if mint_info.key != &round.mint {
+            return Err(QFError::MintMismatch.into());
+        }
+        if associated_token_program_info.key != &spl_associated_token_account::id() {
+            return Err(QFError::UnexpectedAssociatedTokenProgramID.into());
+        }
+        if payout_wallet_info.key != &project.payout_wallet {
+            return Err(QFError::RecipientMismatch.into());
+        }
+        if to_info.key != &get_associated_token_address(&project.payout_wallet, &round.mint) {
+            return Err(QFError::RecipientMismatch.into());
+        }
+        if to_info.lamports() == 0 {
+            msg!("Create the payout wallet's associated token account");
+            invoke(
+                &create_associated_token_account(
+                    project_owner_info.key,
+                    payout_wallet_info.key,
+                    mint_info.key,
+                ),
+                &[
+                    project_owner_info.clone(),
+                    to_info.clone(),
+                    payout_wallet_info.clone(),
+                    mint_info.clone(),
+                    system_program_info.clone(),
+                    token_program_info.clone(),
+                    rent_info.clone(),
+                    associated_token_program_info.clone(),
+                ],
+            )?;
+        }
+
+        let seeds: &[&[_]] = &[&round.owner.to_bytes(), &[bump_seed]];
+
+        // a finalized round pays what the project's claim holds
+        let amount = if round.status == RoundStatus::Claim {
+            validation::check_pda(
+                claim_info,
+                &[b"claim", &project_info.key.to_bytes()],
+                program_id,
+                QFError::ClaimMismatch,
+            )?;
+            validation::check_owner(claim_info, program_id)?;
+            let mut claim = Claim::unpack(&claim_info.data.borrow())?;
+            if claim.claimed {
+                return Err(QFError::ClaimAlreadyPaid.into());
+            }
+            claim.claimed = true;
+            Claim::pack(claim, &mut claim_info.data.borrow_mut())?;
+            claim.amount.min(round.fund_remaining)
+        } else {
+            Self::payout(&round, &mut project)
+        };
+        let exempt = config.is_fee_exempt(round_info.key) || config.is_fee_exempt(&project.owner);
+        let mut amount = Self::settle_payout(&mut round, &config, &mut project, amount, exempt);
+
+        // refund the registration deposit unless the project was banned
+        if !project.banned && project.deposit > 0 {
+            amount = amount.checked_add(project.deposit).unwrap();
+            round.deposits = round.deposits.checked_sub(project.deposit).unwrap();
+        }
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &to_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                amount,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+        events::emit(
+            round_info.key,
+            &Event::PayoutClaimed {
+                project: *project_info.key,
+                to: *to_info.key,
+                amount,
+            },
+        );
+
+        project.withdraw = true;
+        project.save()?;
+
+        round.withdrawals = round.withdrawals.checked_add(1).unwrap();
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Writes the claims of a finalized round's projects that Finalize did not write,
+    /// anyone can do it
+    pub fn process_write_claims(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let round = RoundAccount::load(round_info, program_id)?;
+        validation::check_status(&round, RoundStatus::Claim)?;
+
+        Self::write_claims(
+            program_id,
+            round_info.key,
+            &round,
+            payer_info,
+            system_program_info,
+            rent,
+            &accounts[4..],
+        )
+    }
+
+    /// Sends what projects did not withdraw by the claim deadline, the rounding dust
+    /// included, to the fee recipient. Only the round owner can do it.
+    pub fn process_sweep_dust(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let config = Self::load_config(program_id, next_account_info(account_info_iter)?)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(round.status, RoundStatus::Claim | RoundStatus::Closed) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        let deadline = round
+            .voting_ended_at
+            .checked_add(round.config.dispute_window)
+            .unwrap()
+            .checked_add(round.config.claim_period)
+            .unwrap();
+        if round.config.claim_period == 0 || Clock::get()?.unix_timestamp < deadline {
+            return Err(QFError::ClaimPeriodOpen.into());
+        }
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let vault = VaultAccount::load(vault_info, &round)?;
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+        Self::check_fee_recipient(&config, to_info)?;
+
+        // the fees stay for WithdrawFee and the holdbacks for ReleaseHoldback
+        let amount = round.residual(vault.amount);
+        if amount == 0 {
+            return Err(QFError::InsufficientVaultBalance.into());
+        }
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &to_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                amount,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        round.fund_remaining = 0;
+        round.swept = round.swept.checked_add(amount).unwrap();
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Sends everything left in the vault except the fees, the deposits that were not
+    /// refunded and the holdbacks that were not released included, to the owner once the
+    /// recovery grace after the claim deadline passed
+    pub fn process_recover_abandoned_funds(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let config = Self::load_config(program_id, next_account_info(account_info_iter)?)?;
+        let pending_action_info = next_account_info(account_info_iter).ok();
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(round.status, RoundStatus::Claim | RoundStatus::Closed) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        if round.config.claim_period == 0 || round.config.recovery_grace == 0 {
+            return Err(QFError::ClaimPeriodOpen.into());
+        }
+        let recoverable_at = round
+            .voting_ended_at
+            .checked_add(round.config.dispute_window)
+            .unwrap()
+            .checked_add(round.config.claim_period)
+            .unwrap()
+            .checked_add(round.config.recovery_grace)
+            .unwrap();
+        if Clock::get()?.unix_timestamp < recoverable_at {
+            return Err(QFError::ClaimPeriodOpen.into());
+        }
+
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let vault = VaultAccount::load(vault_info, &round)?;
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        Self::execute_timelocked(
+            program_id,
+            &config,
+            round_info.key,
+            ActionKind::RecoverAbandonedFunds,
+            to_info.key,
+            pending_action_info,
+        )?;
+
+        // the fees stay for WithdrawFee
+        let amount = vault.amount.saturating_sub(round.fees());
+        if amount == 0 {
+            return Err(QFError::InsufficientVaultBalance.into());
+        }
+
+        let seeds: &[&[_]] = &[
+            &round.owner.to_bytes(),
+            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
+        ];
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &to_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                amount,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+
+        events::emit(
+            round_info.key,
+            &Event::FundsRecovered {
+                amount,
+                to: *to_info.key,
+            },
+        );
+
+        // holdbacks not released by now are abandoned with the rest
+        round.fund_remaining = 0;
+        round.holdbacks = 0;
+        round.swept = round.swept.checked_add(amount).unwrap();
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Pays a project the holdback Withdraw kept from its payout, the round owner does
+    /// it once the project delivered
+    pub fn process_release_holdback(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let round_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let vault_owner_info = next_account_info(account_info_iter)?;
+        let project_info = next_account_info(account_info_iter)?;
+        let to_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut round = RoundAccount::load(round_info, program_id)?;
+        if !matches!(round.status, RoundStatus::Claim | RoundStatus::Closed) {
+            return Err(QFError::RoundStatusError.into());
+        }
+        if owner_info.key != &round.owner {
+            return Err(QFError::OwnerMismatch.into());
+        }
+        validation::check_signer(owner_info)?;
+
+        let mut project = ProjectAccount::load(project_info, program_id, round_info.key)?;
+
+        VaultAccount::load(vault_info, &round)?;
+        let (vault_authority, bump_seed) =
+            Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id);
+        if vault_owner_info.key != &vault_authority {
+            return Err(QFError::VaultAuthorityMismatch.into());
+        }
+        if to_info.key != &get_associated_token_address(&project.payout_wallet, &round.mint) {
+            return Err(QFError::RecipientMismatch.into());
+        }
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+
+        let amount = Self::release_holdback(&mut round, &mut project)?;
+
+        let seeds: &[&[_]] = &[&round.owner.to_bytes(), &[bump_seed]];
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &token_program_info.key,
+                &vault_info.key,
+                &to_info.key,
+                &vault_owner_info.key,
+                &[&vault_owner_info.key],
+                amount,
+            )?,
+            &[
+                vault_info.clone(),
+                to_info.clone(),
+                vault_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&seeds],
+        )?;
+        events::emit(
+            round_info.key,
+            &Event::HoldbackReleased {
+                project: *project_info.key,
+                to: *to_info.key,
+                amount,
+            },
+        );
+
+        project.save()?;
+
+        round.save()?;
+
+        Ok(())
+    }
+
+    /// Takes a payout of `amount` before fees out of what the round can still pay, then
+    /// charges the fees and keeps the holdback, as Withdraw and WithdrawEpoch pay.
+    /// Returns what the project is sent.
+    pub fn settle_payout(
+        round: &mut Round,
+        config: &Config,
+        project: &mut Project,
+        amount: u64,
+        exempt: bool,
+    ) -> u64 {
+        // nothing is left once SweepDust or RolloverFunds took what was not withdrawn
+        let amount = amount.min(round.fund_remaining);
+        round.fund_remaining = round.fund_remaining.checked_sub(amount).unwrap();
+        let amount = Self::charge_fees(round, config, amount, exempt);
+        Self::hold_back(round, project, amount)
+    }
+
+    /// Keeps the round's holdback of a payout after fees in the vault for the project,
+    /// on top of what it kept of earlier payouts. Returns what is paid now.
+    pub fn hold_back(round: &mut Round, project: &mut Project, amount: u64) -> u64 {
+        let holdback = (amount as u128)
+            .checked_mul(round.config.holdback_bps as u128)
+            .unwrap()
+            .checked_div(10_000)
+            .unwrap() as u64;
+        project.holdback = project.holdback.checked_add(holdback).unwrap();
+        round.holdbacks = round.holdbacks.checked_add(holdback).unwrap();
+        amount.checked_sub(holdback).unwrap()
+    }
+
+    /// Releases the holdback of a project. Returns what is paid.
+    pub fn release_holdback(round: &mut Round, project: &mut Project) -> Result<u64, ProgramError> {
+        if project.holdback == 0 {
+            return Err(QFError::NoHoldback.into());
+        }
+        // RecoverAbandonedFunds took the holdbacks that were still kept
+        let amount = project.holdback;
+        round.holdbacks = round
+            .holdbacks
+            .checked_sub(amount)
+            .ok_or(QFError::InsufficientVaultBalance)?;
+        project.holdback = 0;
+        Ok(amount)
+    }
+}
@@ -0,0 +1,56 @@
+//! Swaps through the Jupiter aggregator without depending on its crate
+//!
+//! Routes are found off-chain: the client passes the data of Jupiter's route
+//! instruction and the accounts it lists, and the program runs it as it is.
+
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_error::ProgramError,
+    program_pack::Pack,
+};
+
+solana_program::declare_id!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+/// Runs the route in `data` with `route_infos` and returns what `destination`, a token
+/// account, received from it. The signers of the transaction sign the swap.
+pub fn swap<'a>(
+    program_info: &AccountInfo<'a>,
+    route_infos: &[AccountInfo<'a>],
+    data: Vec<u8>,
+    destination: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    if program_info.key != &id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let before = token_amount(destination)?;
+
+    let accounts = route_infos
+        .iter()
+        .map(|info| AccountMeta {
+            pubkey: *info.key,
+            is_signer: info.is_signer,
+            is_writable: info.is_writable,
+        })
+        .collect();
+    let mut account_infos = route_infos.to_vec();
+    account_infos.push(program_info.clone());
+    invoke(
+        &Instruction {
+            program_id: id(),
+            accounts,
+            data,
+        },
+        &account_infos,
+    )?;
+
+    // a route that takes from the destination is not a swap into it
+    token_amount(destination)?
+        .checked_sub(before)
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+fn token_amount(info: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(spl_token::state::Account::unpack(&info.data.borrow())?.amount)
+}
@@ -0,0 +1,174 @@
+//! Lends through Solend without depending on its crate
+//!
+//! Solend is a fork of spl-token-lending and keeps its instructions: DepositReserveLiquidity
+//! is tag 4 and RedeemReserveCollateral tag 5, each followed by the amount, and both take
+//! the accounts of the reserve in the order below. The lending program checks that the
+//! reserve accounts belong together, the reserve has to be refreshed in the same slot, so
+//! the client sends RefreshReserve before the instruction.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    program_error::ProgramError,
+    program_pack::Pack,
+    sysvar,
+};
+
+solana_program::declare_id!("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo");
+
+const DEPOSIT_RESERVE_LIQUIDITY: u8 = 4;
+const REDEEM_RESERVE_COLLATERAL: u8 = 5;
+
+/// Accounts of a reserve, as LendFund, EndRound and CrankEndRound take them
+pub struct Reserve<'b, 'a> {
+    pub reserve: &'b AccountInfo<'a>,
+    pub liquidity_supply: &'b AccountInfo<'a>,
+    pub collateral_mint: &'b AccountInfo<'a>,
+    pub lending_market: &'b AccountInfo<'a>,
+    pub lending_market_authority: &'b AccountInfo<'a>,
+    pub clock: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub program: &'b AccountInfo<'a>,
+}
+
+impl<'b, 'a> Reserve<'b, 'a> {
+    /// Accounts `Reserve::new` takes
+    pub const ACCOUNTS: usize = 8;
+
+    /// Takes the reserve, its liquidity supply, its collateral mint, the lending market and
+    /// its authority, the clock, the token program and the lending program from `infos`
+    pub fn new(infos: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let infos = &mut infos.iter();
+        let reserve = Reserve {
+            reserve: next_account_info(infos)?,
+            liquidity_supply: next_account_info(infos)?,
+            collateral_mint: next_account_info(infos)?,
+            lending_market: next_account_info(infos)?,
+            lending_market_authority: next_account_info(infos)?,
+            clock: next_account_info(infos)?,
+            token_program: next_account_info(infos)?,
+            program: next_account_info(infos)?,
+        };
+        if reserve.program.key != &id()
+            || reserve.token_program.key != &spl_token::ID
+            || reserve.clock.key != &sysvar::clock::ID
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(reserve)
+    }
+
+    /// Deposits `amount` of `source` into the reserve and returns the collateral minted
+    /// to `collateral`. `authority` owns the source and signs with `seeds`.
+    pub fn deposit(
+        &self,
+        source: &AccountInfo<'a>,
+        collateral: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        amount: u64,
+        seeds: &[&[u8]],
+    ) -> Result<u64, ProgramError> {
+        let source_before = token_amount(source)?;
+        let before = token_amount(collateral)?;
+        self.invoke(
+            DEPOSIT_RESERVE_LIQUIDITY,
+            amount,
+            &[
+                source,
+                collateral,
+                self.reserve,
+                self.liquidity_supply,
+                self.collateral_mint,
+            ],
+            authority,
+            seeds,
+        )?;
+
+        // the reserve takes exactly the amount, anything else is not a deposit
+        if source_before.checked_sub(token_amount(source)?) != Some(amount) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        token_amount(collateral)?
+            .checked_sub(before)
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Redeems `amount` of `collateral` and returns the liquidity `destination` received.
+    /// `authority` owns the collateral and signs with `seeds`.
+    pub fn redeem(
+        &self,
+        collateral: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        amount: u64,
+        seeds: &[&[u8]],
+    ) -> Result<u64, ProgramError> {
+        let before = token_amount(destination)?;
+        self.invoke(
+            REDEEM_RESERVE_COLLATERAL,
+            amount,
+            &[
+                collateral,
+                destination,
+                self.reserve,
+                self.collateral_mint,
+                self.liquidity_supply,
+            ],
+            authority,
+            seeds,
+        )?;
+        token_amount(destination)?
+            .checked_sub(before)
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Runs the instruction `tag` with the writable accounts `writable`, then the lending
+    /// market and its authority, `authority`, the clock and the token program
+    fn invoke(
+        &self,
+        tag: u8,
+        amount: u64,
+        writable: &[&AccountInfo<'a>],
+        authority: &AccountInfo<'a>,
+        seeds: &[&[u8]],
+    ) -> Result<(), ProgramError> {
+        let mut accounts: Vec<AccountMeta> = writable
+            .iter()
+            .map(|info| AccountMeta::new(*info.key, false))
+            .collect();
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(*self.lending_market.key, false),
+            AccountMeta::new_readonly(*self.lending_market_authority.key, false),
+            AccountMeta::new_readonly(*authority.key, true),
+            AccountMeta::new_readonly(*self.clock.key, false),
+            AccountMeta::new_readonly(*self.token_program.key, false),
+        ]);
+        let mut data = vec![tag];
+        data.extend_from_slice(&amount.to_le_bytes());
+
+        let mut account_infos: Vec<AccountInfo<'a>> =
+            writable.iter().map(|info| (*info).clone()).collect();
+        account_infos.extend_from_slice(&[
+            self.lending_market.clone(),
+            self.lending_market_authority.clone(),
+            authority.clone(),
+            self.clock.clone(),
+            self.token_program.clone(),
+            self.program.clone(),
+        ]);
+        invoke_signed(
+            &Instruction {
+                program_id: id(),
+                accounts,
+                data,
+            },
+            &account_infos,
+            &[seeds],
+        )
+    }
+}
+
+fn token_amount(info: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(spl_token::state::Account::unpack(&info.data.borrow())?.amount)
+}
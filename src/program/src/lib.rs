@@ -1,7 +1,20 @@
+pub mod allowlist;
+pub mod automation;
+pub mod cpi;
+pub mod ed25519;
 pub mod error;
+pub mod events;
 pub mod instruction;
+mod instructions;
+pub mod jupiter;
+pub mod lending;
+pub mod merkle;
+pub mod metaplex;
 pub mod processor;
+pub mod stake_pool;
 pub mod state;
+pub mod token_2022;
+pub mod validation;
 
 #[cfg(not(feature = "no-entrypoint"))]
 mod entrypoint;
@@ -0,0 +1,145 @@
+//! Merkle trees of a fixed depth that the program keeps by their root
+//!
+//! Leaves are numbered from the left and a node is the hash of its left and its right
+//! child, so a proof is the list of siblings from the leaf up and the index of the leaf
+//! tells on which side each one is. A leaf that was never written is all zeros.
+
+use solana_program::hash::hashv;
+
+/// A leaf or a node of a tree
+pub type Node = [u8; 32];
+
+/// The leaf of a position nothing was written to
+pub const EMPTY: Node = [0; 32];
+
+/// The parent of `left` and `right`
+pub fn node(left: &Node, right: &Node) -> Node {
+    hashv(&[left, right]).to_bytes()
+}
+
+/// The root of a subtree of `height` with only empty leaves
+pub fn empty_root(height: usize) -> Node {
+    (0..height).fold(EMPTY, |hash, _| node(&hash, &hash))
+}
+
+/// The root `proof` leads to from `leaf` at `index`
+pub fn root_of(leaf: &Node, index: u64, proof: &[Node]) -> Node {
+    proof
+        .iter()
+        .enumerate()
+        .fold(*leaf, |hash, (height, sibling)| {
+            if index >> height & 1 == 0 {
+                node(&hash, sibling)
+            } else {
+                node(sibling, &hash)
+            }
+        })
+}
+
+/// Whether `proof` leads from `leaf` at `index` to `root`
+pub fn verify(root: &Node, leaf: &Node, index: u64, proof: &[Node]) -> bool {
+    root_of(leaf, index, proof) == *root
+}
+
+/// Writes in `path` the nodes from `leaf` at `index` up to the root, the leaf first,
+/// and returns the root
+pub fn write_path(path: &mut [Node], leaf: &Node, index: u64, proof: &[Node]) -> Node {
+    let mut hash = *leaf;
+    for (height, (node_on_path, sibling)) in path.iter_mut().zip(proof).enumerate() {
+        *node_on_path = hash;
+        hash = if index >> height & 1 == 0 {
+            node(&hash, sibling)
+        } else {
+            node(sibling, &hash)
+        };
+    }
+    hash
+}
+
+/// Moves the `proof` of the leaf at `index` past a change that wrote `path` from the
+/// leaf at `changed` up. The paths of the two leaves meet at the height of the highest
+/// bit their indexes differ in, there the changed path is the sibling the proof needs.
+/// Returns false if the change wrote the leaf at `index` itself.
+pub fn fast_forward(proof: &mut [Node], index: u64, changed: u64, path: &[Node]) -> bool {
+    if index == changed {
+        return false;
+    }
+    let height = 63 - (index ^ changed).leading_zeros() as usize;
+    proof[height] = path[height];
+    true
+}
+
+/// Appends `leaf` at `index`, the number of leaves so far, to an append-only tree as
+/// deep as `frontier` is long. The frontier keeps, for each height, the last left node
+/// that is still waiting for its right sibling. Returns the new root, None once the
+/// tree is full.
+pub fn append(frontier: &mut [Node], index: u64, leaf: &Node) -> Option<Node> {
+    let depth = frontier.len();
+    if index >> depth != 0 {
+        return None;
+    }
+
+    let size = index + 1;
+    let mut hash = *leaf;
+    for (height, left) in frontier.iter_mut().enumerate() {
+        if size >> height & 1 == 1 {
+            *left = hash;
+            break;
+        }
+        hash = node(left, &hash);
+    }
+
+    let mut root = EMPTY;
+    let mut empty = EMPTY;
+    for (height, left) in frontier.iter().enumerate() {
+        root = if size >> height & 1 == 1 {
+            node(left, &root)
+        } else {
+            node(&root, &empty)
+        };
+        empty = node(&empty, &empty);
+    }
+    Some(root)
+}
+
+/// The levels of a tree of `depth` over `leaves`, leaves first and the root last, each
+/// with the root of an empty subtree of its height
+fn levels(leaves: &[Node], depth: usize) -> Vec<(Vec<Node>, Node)> {
+    let mut levels = vec![(leaves.to_vec(), EMPTY)];
+    for height in 0..depth {
+        let (level, empty) = &levels[height];
+        let next = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node(left, right),
+                [left] => node(left, empty),
+                _ => unreachable!(),
+            })
+            .collect();
+        let empty = node(empty, empty);
+        levels.push((next, empty));
+    }
+    levels
+}
+
+/// The root of a tree of `depth` with `leaves` at its first positions, for clients that
+/// replay a tree
+pub fn root(leaves: &[Node], depth: usize) -> Node {
+    let (level, empty) = &levels(leaves, depth)[depth];
+    level.first().copied().unwrap_or(*empty)
+}
+
+/// The proof of the leaf at `index` in a tree of `depth` with `leaves` at its first
+/// positions
+pub fn proof(leaves: &[Node], index: u64, depth: usize) -> Vec<Node> {
+    levels(leaves, depth)[..depth]
+        .iter()
+        .enumerate()
+        .map(|(height, (level, empty))| {
+            level
+                .get((index >> height ^ 1) as usize)
+                .copied()
+                .unwrap_or(*empty)
+        })
+        .collect()
+}
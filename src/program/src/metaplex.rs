@@ -0,0 +1,167 @@
+//! Reads and creates Metaplex token metadata accounts without depending on their crate
+//!
+//! The token metadata program only accepts CreateMetadataAccountV3 (tag 33) to create
+//! metadata, the older versions are disabled. Its arguments are Borsh encoded: the data
+//! (name, symbol and uri as length prefixed strings, the seller fee basis points, then
+//! the optional creators, collection and uses), whether the metadata is mutable and the
+//! optional collection details.
+
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use std::convert::TryInto;
+
+solana_program::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+const KEY_METADATA_V1: u8 = 4;
+const CREATOR_LEN: usize = 34;
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+/// Returns the metadata account of `mint`
+pub fn find_metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"metadata", &id().to_bytes(), &mint.to_bytes()], &id()).0
+}
+
+/// Creates immutable metadata without creators, collection or uses for `mint`. The mint
+/// authority signs, the payer pays for the metadata account.
+#[allow(clippy::too_many_arguments)]
+pub fn create_metadata_account(
+    metadata: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    payer: &Pubkey,
+    update_authority: &Pubkey,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Instruction {
+    let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+    for field in &[name, symbol, uri] {
+        data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        data.extend_from_slice(field.as_bytes());
+    }
+    // no seller fee, no creators, collection or uses, immutable, no collection details
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&[0, 0, 0, 0, 0]);
+
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*update_authority, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data,
+    }
+}
+
+/// The fields of a metadata account a project is checked against
+pub struct Metadata {
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    /// collection key, if it was verified by the collection authority
+    pub verified_collection: Option<Pubkey>,
+}
+
+/// Parses a metadata account owned by the token metadata program
+pub fn unpack(metadata_info: &AccountInfo) -> Result<Metadata, ProgramError> {
+    if metadata_info.owner != &id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = metadata_info.data.borrow();
+    let mut reader = Reader {
+        data: &data,
+        offset: 0,
+    };
+
+    if reader.u8()? != KEY_METADATA_V1 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let update_authority = reader.pubkey()?;
+    let mint = reader.pubkey()?;
+    // name, symbol and uri
+    for _ in 0..3 {
+        let len = reader.u32()? as usize;
+        reader.skip(len)?;
+    }
+    // seller fee basis points
+    reader.skip(2)?;
+    if reader.u8()? == 1 {
+        let creators = reader.u32()? as usize;
+        reader.skip(
+            creators
+                .checked_mul(CREATOR_LEN)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
+    }
+    // primary sale happened and is mutable
+    reader.skip(2)?;
+    // edition nonce and token standard
+    for _ in 0..2 {
+        if reader.u8()? == 1 {
+            reader.skip(1)?;
+        }
+    }
+
+    // metadata written before collections existed ends here
+    let verified_collection = match reader.u8() {
+        Ok(1) => {
+            let verified = reader.u8()? == 1;
+            let key = reader.pubkey()?;
+            if verified {
+                Some(key)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+
+    Ok(Metadata {
+        update_authority,
+        mint,
+        verified_collection,
+    })
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProgramError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), ProgramError> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, ProgramError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, ProgramError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, ProgramError> {
+        Ok(Pubkey::new_from_array(self.take(32)?.try_into().unwrap()))
+    }
+}
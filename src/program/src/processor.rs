@@ -1,13 +1,22 @@
 use crate::{
     error::QFError,
+    events,
+    events::{Event, VoteKind},
     instruction::QFInstruction,
-    state::{Project, Round, RoundStatus, Voter},
+    merkle,
+    state::{
+        ActionKind, Claim, Config, EpochTally, PendingAction, Project, Round, RoundMode,
+        RoundStatus, Voter, VoterRegistry,
+    },
+    validation::{self, ProjectAccount},
 };
 use num_traits::FromPrimitive;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
+    account_info::AccountInfo,
+    clock::Clock,
     decode_error::DecodeError,
     entrypoint::ProgramResult,
+    hash::hashv,
     msg,
     program::{invoke, invoke_signed},
     program_error::{PrintProgramError, ProgramError},
@@ -25,75 +34,32 @@ use spl_token;
 
 pub struct Processor {}
 impl Processor {
-    pub fn process_start_round(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let new_round_info = next_account_info(account_info_iter)?;
-        let round_owner_info = next_account_info(account_info_iter)?;
-        let vault_info = next_account_info(account_info_iter)?;
-        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
-
-        if new_round_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-        let mut round = Round::unpack_unchecked(&new_round_info.data.borrow())?;
-        if round.is_initialized() {
-            return Err(ProgramError::AccountAlreadyInitialized);
-        }
-
-        if new_round_info.data_len() != Round::LEN {
-            return Err(ProgramError::InvalidAccountData);
-        }
-
-        if !rent.is_exempt(new_round_info.lamports(), Round::LEN) {
-            return Err(ProgramError::AccountNotRentExempt);
-        }
-
-        let (pda, _) =
-            Pubkey::find_program_address(&[&round_owner_info.key.to_bytes()], &program_id);
-        let vault = spl_token::state::Account::unpack(&vault_info.data.borrow())?;
-        if vault.owner != pda {
-            return Err(QFError::OwnerMismatch.into());
-        }
-
-        round.status = RoundStatus::Ongoing;
-        round.fund = vault.amount;
-        round.owner = *round_owner_info.key;
-        round.vault = *vault_info.key;
-        round.area = U256::zero();
-
-        Round::pack(round, &mut new_round_info.data.borrow_mut())?;
-        Ok(())
-    }
-
-    pub fn process_donate(
-        program_id: &Pubkey,
-        accounts: &[AccountInfo],
+    /// Transfers tokens with `transfer_checked`. When `signer_infos` is not
+    /// empty `from_auth_info` is a multisig and those are its signers.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn transfer_checked_with_signers<'a>(
+        token_program_info: &AccountInfo<'a>,
+        from_info: &AccountInfo<'a>,
+        mint_info: &AccountInfo<'a>,
+        to_info: &AccountInfo<'a>,
+        from_auth_info: &AccountInfo<'a>,
+        signer_infos: &[AccountInfo<'a>],
         amount: u64,
         decimals: u8,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let round_info = next_account_info(account_info_iter)?;
-        let from_info = next_account_info(account_info_iter)?;
-        let mint_info = next_account_info(account_info_iter)?;
-        let to_info = next_account_info(account_info_iter)?;
-        let from_auth_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
-
-        if round_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-        let mut round = Round::unpack(&round_info.data.borrow())?;
-        if round.status != RoundStatus::Ongoing {
-            return Err(QFError::RoundStatusError.into());
-        }
-
-        if to_info.key != &round.vault {
-            return Err(QFError::VaultMismatch.into());
-        }
-
-        if token_program_info.key != &spl_token::ID {
-            return Err(QFError::UnexpectedTokenProgramID.into());
-        }
+        let signer_pubkeys = signer_infos
+            .iter()
+            .map(|signer_info| signer_info.key)
+            .collect::<Vec<_>>();
+
+        let mut transfer_infos = vec![
+            from_info.clone(),
+            mint_info.clone(),
+            to_info.clone(),
+            from_auth_info.clone(),
+            token_program_info.clone(),
+        ];
+        transfer_infos.extend_from_slice(signer_infos);
 
         invoke(
             &spl_token::instruction::transfer_checked(
@@ -102,224 +68,388 @@ impl Processor {
                 &mint_info.key,
                 &to_info.key,
                 &from_auth_info.key,
-                &[&from_auth_info.key],
+                &signer_pubkeys,
                 amount,
                 decimals,
             )?,
+            &transfer_infos,
+        )
+    }
+
+    /// Loads the epoch tally of `parent` in `category`, creating it on the first vote of
+    /// the epoch
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn epoch_tally<'a>(
+        program_id: &Pubkey,
+        payer_info: &AccountInfo<'a>,
+        tally_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        rent: &Rent,
+        parent: &Pubkey,
+        epoch: u64,
+        category: u8,
+    ) -> Result<EpochTally, ProgramError> {
+        let bump_seed = validation::check_pda(
+            tally_info,
             &[
-                from_info.clone(),
-                mint_info.clone(),
-                to_info.clone(),
-                from_auth_info.clone(),
-                token_program_info.clone(),
+                b"epoch",
+                &parent.to_bytes(),
+                &epoch.to_le_bytes(),
+                &[category],
             ],
+            program_id,
+            QFError::InvalidEpoch,
         )?;
-
-        round.fund = round.fund.checked_add(amount).unwrap();
-        Round::pack(round, &mut round_info.data.borrow_mut())?;
-
-        Ok(())
+        if tally_info.owner != program_id {
+            let seeds: &[&[_]] = &[
+                b"epoch",
+                &parent.to_bytes(),
+                &epoch.to_le_bytes(),
+                &[category],
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                payer_info,
+                tally_info,
+                system_program_info,
+                rent,
+                EpochTally::LEN,
+                seeds,
+            )?;
+        }
+        let mut tally = EpochTally::unpack_unchecked(&tally_info.data.borrow())?;
+        if !tally.is_initialized() {
+            tally.parent = *parent;
+            tally.epoch = epoch;
+            tally.category = category;
+        }
+        Ok(tally)
     }
 
-    pub fn process_register_project(
+    /// Loads an existing epoch tally of `parent` in `category`
+    pub(crate) fn unpack_epoch_tally(
         program_id: &Pubkey,
-        accounts: &[AccountInfo],
-    ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let new_project_info = next_account_info(account_info_iter)?;
-        let round_info = next_account_info(account_info_iter)?;
-        let project_owner_info = next_account_info(account_info_iter)?;
-        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
-
-        if round_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-        let round = Round::unpack(&round_info.data.borrow())?;
-        if round.status != RoundStatus::Ongoing {
-            return Err(QFError::RoundStatusError.into());
+        tally_info: &AccountInfo,
+        parent: &Pubkey,
+        epoch: u64,
+        category: u8,
+    ) -> Result<EpochTally, ProgramError> {
+        validation::check_owner(tally_info, program_id)?;
+        let tally = EpochTally::unpack(&tally_info.data.borrow())?;
+        if tally.parent != *parent || tally.epoch != epoch || tally.category != category {
+            return Err(QFError::InvalidEpoch.into());
         }
+        Ok(tally)
+    }
 
-        if new_project_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+    /// Loads the round registry of `wallet`, creating it with the wallet's first voter
+    pub(crate) fn voter_registry<'a>(
+        program_id: &Pubkey,
+        payer_info: &AccountInfo<'a>,
+        registry_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        rent: &Rent,
+        round: &Pubkey,
+        wallet: &Pubkey,
+    ) -> Result<VoterRegistry, ProgramError> {
+        let bump_seed = validation::check_pda(
+            registry_info,
+            &[b"registry", &round.to_bytes(), &wallet.to_bytes()],
+            program_id,
+            QFError::RegistryMismatch,
+        )?;
+        if registry_info.owner != program_id {
+            let seeds: &[&[_]] = &[
+                b"registry",
+                &round.to_bytes(),
+                &wallet.to_bytes(),
+                &[bump_seed],
+            ];
+            Self::create_pda_account(
+                program_id,
+                payer_info,
+                registry_info,
+                system_program_info,
+                rent,
+                VoterRegistry::LEN,
+                seeds,
+            )?;
         }
-        let mut project = Project::unpack_unchecked(&new_project_info.data.borrow())?;
-        if project.is_initialized() {
-            return Err(ProgramError::AccountAlreadyInitialized);
+        let mut registry = VoterRegistry::unpack_unchecked(&registry_info.data.borrow())?;
+        if !registry.is_initialized() {
+            registry.round = *round;
+            registry.wallet = *wallet;
         }
+        Ok(registry)
+    }
 
-        if new_project_info.data_len() != Project::LEN {
-            return Err(ProgramError::InvalidAccountData);
+    /// Loads the existing round registry of `wallet`
+    pub(crate) fn unpack_voter_registry(
+        program_id: &Pubkey,
+        registry_info: &AccountInfo,
+        round: &Pubkey,
+        wallet: &Pubkey,
+    ) -> Result<VoterRegistry, ProgramError> {
+        validation::check_owner(registry_info, program_id)?;
+        let registry = VoterRegistry::unpack(&registry_info.data.borrow())?;
+        if registry.round != *round || registry.wallet != *wallet {
+            return Err(QFError::RegistryMismatch.into());
         }
+        Ok(registry)
+    }
 
-        if !rent.is_exempt(new_project_info.lamports(), Project::LEN) {
-            return Err(ProgramError::AccountNotRentExempt);
-        }
+    /// Adds a vote to the round registry of `wallet`
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn record_vote(
+        program_id: &Pubkey,
+        registry_info: &AccountInfo,
+        round_key: &Pubkey,
+        round: &mut Round,
+        wallet: &Pubkey,
+        amount: u64,
+        weight: u64,
+        now: i64,
+    ) -> ProgramResult {
+        let mut registry =
+            Self::unpack_voter_registry(program_id, registry_info, round_key, wallet)?;
+        Self::add_registry_vote(round, &mut registry, amount, weight, now);
+        VoterRegistry::pack(registry, &mut registry_info.data.borrow_mut())
+    }
 
-        project.round = *round_info.key;
-        project.owner = *project_owner_info.key;
-        project.withdraw = false;
-        project.votes = 0;
-        project.area = U256::zero();
+    /// Adds a vote to a wallet's registry, the round counts the wallet as a voter from
+    /// its first vote on, whatever the projects it votes for
+    pub fn add_registry_vote(
+        round: &mut Round,
+        registry: &mut VoterRegistry,
+        amount: u64,
+        weight: u64,
+        now: i64,
+    ) {
+        if registry.votes == 0 && amount > 0 {
+            round.voters = round.voters.checked_add(1).unwrap();
+        }
+        registry.votes = registry.votes.checked_add(amount).unwrap();
+        registry.weight = registry.weight.checked_add(weight).unwrap();
+        registry.last_vote_at = now;
+    }
 
-        Project::pack(project, &mut new_project_info.data.borrow_mut())?;
+    /// Takes a revoked vote of `amount` credited with `weight` out of a wallet's registry,
+    /// the wallet stops counting as a voter once it has no votes left in the round
+    pub fn remove_registry_vote(
+        round: &mut Round,
+        registry: &mut VoterRegistry,
+        amount: u64,
+        weight: u64,
+    ) {
+        registry.votes = registry.votes.checked_sub(amount).unwrap();
+        registry.weight = registry.weight.checked_sub(weight).unwrap();
+        if registry.votes == 0 && amount > 0 {
+            round.voters = round.voters.checked_sub(1).unwrap();
+        }
+    }
 
+    /// Appends a vote, a revoke or a downvote of `amount` to the round's vote log. The log
+    /// is an append-only Merkle tree of the entries, anyone can replay the `vote_logged`
+    /// events to check its root and prove a single entry with the siblings of its leaf.
+    pub(crate) fn log_vote(
+        round_key: &Pubkey,
+        round: &mut Round,
+        project: &Pubkey,
+        wallet: &Pubkey,
+        amount: u64,
+        kind: VoteKind,
+    ) -> ProgramResult {
+        let slot = Clock::get()?.slot;
+        let leaf = Self::vote_log_leaf(wallet, project, amount, kind, slot);
+        round.vote_log = merkle::append(&mut round.vote_log_frontier, round.vote_log_len, &leaf)
+            .ok_or(QFError::VoteLogFull)?;
+        events::emit(
+            round_key,
+            &Event::VoteLogged {
+                index: round.vote_log_len,
+                wallet: *wallet,
+                project: *project,
+                amount,
+                kind,
+                slot,
+            },
+        );
+        round.vote_log_len = round.vote_log_len.checked_add(1).unwrap();
         Ok(())
     }
 
-    pub fn process_init_voter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let new_voter_info = next_account_info(account_info_iter)?;
-        let voter_token_holder_info = next_account_info(account_info_iter)?;
-        let project_info = next_account_info(account_info_iter)?;
-        let from_info = next_account_info(account_info_iter)?;
-        let system_program_info = next_account_info(account_info_iter)?;
-        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
-
-        if project_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-        Project::unpack(&project_info.data.borrow())?;
+    /// The leaf of a vote log entry, the hash of the fields of its `vote_logged` event
+    pub fn vote_log_leaf(
+        wallet: &Pubkey,
+        project: &Pubkey,
+        amount: u64,
+        kind: VoteKind,
+        slot: u64,
+    ) -> [u8; 32] {
+        hashv(&[
+            &wallet.to_bytes(),
+            &project.to_bytes(),
+            &amount.to_le_bytes(),
+            &[kind as u8],
+            &slot.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
 
-        let (_, bump_seed) = Pubkey::find_program_address(
-            &[
-                &project_info.key.to_bytes(),
-                &voter_token_holder_info.key.to_bytes(),
-            ],
-            &program_id,
-        );
-        let seeds: &[&[_]] = &[
-            &project_info.key.to_bytes(),
-            &voter_token_holder_info.key.to_bytes(),
-            &[bump_seed],
-        ];
+    /// Takes a revoked vote out of the round registry of `wallet`
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn unrecord_vote(
+        program_id: &Pubkey,
+        registry_info: &AccountInfo,
+        round_key: &Pubkey,
+        round: &mut Round,
+        wallet: &Pubkey,
+        amount: u64,
+        weight: u64,
+    ) -> ProgramResult {
+        let mut registry =
+            Self::unpack_voter_registry(program_id, registry_info, round_key, wallet)?;
+        Self::remove_registry_vote(round, &mut registry, amount, weight);
+        VoterRegistry::pack(registry, &mut registry_info.data.borrow_mut())
+    }
 
+    /// Funds, allocates and assigns a program derived account to this program.
+    pub(crate) fn create_pda_account<'a>(
+        program_id: &Pubkey,
+        payer_info: &AccountInfo<'a>,
+        new_account_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        rent: &Rent,
+        space: usize,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
         let required_lamports = rent
-            .minimum_balance(Voter::LEN)
+            .minimum_balance(space)
             .max(1)
-            .saturating_sub(new_voter_info.lamports());
+            .saturating_sub(new_account_info.lamports());
 
         if required_lamports > 0 {
-            msg!("Transfer {} lamports to the voter", required_lamports);
+            msg!("Transfer {} lamports to the new account", required_lamports);
             invoke(
                 &system_instruction::transfer(
-                    &from_info.key,
-                    &new_voter_info.key,
+                    &payer_info.key,
+                    &new_account_info.key,
                     required_lamports,
                 ),
                 &[
-                    from_info.clone(),
-                    new_voter_info.clone(),
+                    payer_info.clone(),
+                    new_account_info.clone(),
                     system_program_info.clone(),
                 ],
             )?;
         }
 
-        msg!("Allocate space for the voter");
+        msg!("Allocate space for the new account");
         invoke_signed(
-            &system_instruction::allocate(new_voter_info.key, Voter::LEN as u64),
-            &[new_voter_info.clone(), system_program_info.clone()],
-            &[&seeds],
+            &system_instruction::allocate(new_account_info.key, space as u64),
+            &[new_account_info.clone(), system_program_info.clone()],
+            &[seeds],
         )?;
 
-        msg!("Assign voter to QF Program");
+        msg!("Assign the new account to QF Program");
         invoke_signed(
-            &system_instruction::assign(new_voter_info.key, &program_id),
-            &[new_voter_info.clone(), system_program_info.clone()],
-            &[&seeds],
+            &system_instruction::assign(new_account_info.key, &program_id),
+            &[new_account_info.clone(), system_program_info.clone()],
+            &[seeds],
         )?;
 
-        let mut voter = Voter::unpack_unchecked(&new_voter_info.data.borrow())?;
-        if voter.is_initialized() {
-            return Err(ProgramError::AccountAlreadyInitialized);
-        }
-
-        voter.is_initialized = true;
-        voter.votes = 0;
-        voter.votes_sqrt = U256::from(0);
-
-        Voter::pack(voter, &mut new_voter_info.data.borrow_mut())?;
+        Ok(())
+    }
 
+    /// Closes a program account, its lamports go to `to_info` and its data is zeroed
+    pub(crate) fn close_account(
+        account_info: &AccountInfo,
+        to_info: &AccountInfo,
+    ) -> ProgramResult {
+        let lamports = account_info.lamports();
+        **to_info.lamports.borrow_mut() = to_info.lamports().checked_add(lamports).unwrap();
+        **account_info.lamports.borrow_mut() = 0;
+        for byte in account_info.data.borrow_mut().iter_mut() {
+            *byte = 0;
+        }
         Ok(())
     }
 
-    pub fn process_vote(
-        program_id: &Pubkey,
-        accounts: &[AccountInfo],
+    /// Adds `amount` to the voter's votes and moves the project and round
+    /// areas to match the new square root of the voter's votes.
+    pub fn apply_vote(
+        round: &mut Round,
+        project: &mut Project,
+        voter: &mut Voter,
         amount: u64,
-        decimals: u8,
+        now: i64,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let round_info = next_account_info(account_info_iter)?;
-        let project_info = next_account_info(account_info_iter)?;
-        let voter_info = next_account_info(account_info_iter)?;
-        let from_info = next_account_info(account_info_iter)?;
-        let mint_info = next_account_info(account_info_iter)?;
-        let to_info = next_account_info(account_info_iter)?;
-        let from_auth_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+        Self::apply_weighted_vote(round, project, voter, amount, amount, now)
+    }
 
-        if round_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-        let mut round = Round::unpack(&round_info.data.borrow())?;
-        if round.status != RoundStatus::Ongoing {
-            return Err(QFError::RoundStatusError.into());
-        }
-        if to_info.key != &round.vault {
-            return Err(QFError::VaultMismatch.into());
+    /// Same as `apply_vote`, but the sqrt math uses `weight` instead of the tokens voted
+    pub fn apply_weighted_vote(
+        round: &mut Round,
+        project: &mut Project,
+        voter: &mut Voter,
+        amount: u64,
+        weight: u64,
+        now: i64,
+    ) -> ProgramResult {
+        // votes a custodian paid go back to it on a refund, the wallet can not add its own
+        if voter.custodian != Pubkey::default() {
+            return Err(QFError::CustodianMismatch.into());
         }
+        Self::credit_vote(round, project, voter, amount, weight, now)
+    }
 
-        if project_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-        let mut project = Project::unpack(&project_info.data.borrow())?;
-        if project.round != *round_info.key {
-            return Err(QFError::RoundMismatch.into());
+    /// Same as `apply_vote` for the votes a custodian paid in a batch, the voter's
+    /// refund goes to `custodian`. A voter takes the votes of a single payer.
+    pub fn apply_custodial_vote(
+        round: &mut Round,
+        project: &mut Project,
+        voter: &mut Voter,
+        custodian: &Pubkey,
+        amount: u64,
+        now: i64,
+    ) -> ProgramResult {
+        if voter.votes > 0 && voter.custodian != *custodian {
+            return Err(QFError::CustodianMismatch.into());
         }
+        Self::credit_vote(round, project, voter, amount, amount, now)?;
+        voter.custodian = *custodian;
+        Ok(())
+    }
 
-        if voter_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+    fn credit_vote(
+        round: &mut Round,
+        project: &mut Project,
+        voter: &mut Voter,
+        amount: u64,
+        weight: u64,
+        now: i64,
+    ) -> ProgramResult {
+        if voter.blacklisted {
+            return Err(QFError::VoterBlacklisted.into());
         }
-        let (expected_key, _) = Pubkey::find_program_address(
-            &[&project_info.key.to_bytes(), &from_info.key.to_bytes()],
-            &program_id,
-        );
-        if voter_info.key != &expected_key {
-            return Err(QFError::VoterMismatch.into());
+        Self::check_vote_cooldown(round, voter)?;
+        if round.config.mode == RoundMode::Conviction {
+            Self::accrue_round_conviction(round, now);
+            Self::accrue_project_conviction(project, now);
         }
-        let mut voter = Voter::unpack(&voter_info.data.borrow())?;
-
-        if token_program_info.key != &spl_token::ID {
-            return Err(QFError::UnexpectedTokenProgramID.into());
+        round.votes = round.votes.checked_add(amount).unwrap();
+        let matched = round.matches(project);
+        if matched {
+            round.sub_area(project.category, project.area);
         }
 
-        invoke(
-            &spl_token::instruction::transfer_checked(
-                &token_program_info.key,
-                &from_info.key,
-                &mint_info.key,
-                &to_info.key,
-                &from_auth_info.key,
-                &[&from_auth_info.key],
-                amount,
-                decimals,
-            )?,
-            &[
-                from_info.clone(),
-                mint_info.clone(),
-                to_info.clone(),
-                from_auth_info.clone(),
-                token_program_info.clone(),
-            ],
-        )?;
-        round.area = round.area.checked_sub(project.area).unwrap();
-
         let mut project_area_sqrt = PreciseNumber {
             value: project.area_sqrt,
         };
 
         let new_votes_sqrt = PreciseNumber {
-            value: U256::from(voter.votes.checked_add(amount).unwrap())
+            value: U256::from(voter.weight.checked_add(weight).unwrap())
                 .checked_mul(U256::from(ONE))
                 .unwrap(),
         }
@@ -333,243 +463,403 @@ impl Processor {
             .unwrap()
             .checked_add(&new_votes_sqrt)
             .unwrap();
-        project.area = project_area_sqrt.checked_pow(2).unwrap().value;
+        project.area = Self::project_area(
+            project_area_sqrt.value,
+            project.down_sqrt,
+            round.config.endorsement_bonus_bps(project.endorsements),
+        );
 
-        round.area = round.area.checked_add(project.area).unwrap();
-        Round::pack(round, &mut round_info.data.borrow_mut())?;
+        if matched {
+            round.add_area(project.category, project.area);
+        }
 
         project.area_sqrt = project_area_sqrt.value;
         project.votes = project.votes.checked_add(amount).unwrap();
-        Project::pack(project, &mut project_info.data.borrow_mut())?;
 
         voter.votes = voter.votes.checked_add(amount).unwrap();
+        voter.weight = voter.weight.checked_add(weight).unwrap();
         voter.votes_sqrt = new_votes_sqrt.value;
-        Voter::pack(voter, &mut voter_info.data.borrow_mut())?;
+        voter.last_vote_amount = amount;
+        voter.last_vote_weight = weight;
+        voter.last_vote_at = now;
+        // the project fulfills the perks of the tier from the voter account
+        voter.tier = project.tier_of(voter.votes);
 
         Ok(())
     }
 
-    pub fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let round_info = next_account_info(account_info_iter)?;
-        let vault_info = next_account_info(account_info_iter)?;
-        let vault_owner_info = next_account_info(account_info_iter)?;
-        let project_info = next_account_info(account_info_iter)?;
-        let project_owner_info = next_account_info(account_info_iter)?;
-        let to_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
-
-        if round_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+    /// Fails if the voter voted less than `vote_cooldown` slots ago, then records the
+    /// slot of this vote. Rounds without a cooldown do not read the clock.
+    pub(crate) fn check_vote_cooldown(round: &Round, voter: &mut Voter) -> ProgramResult {
+        if round.config.vote_cooldown == 0 {
+            return Ok(());
         }
-        let mut round = Round::unpack(&round_info.data.borrow())?;
-        if round.status != RoundStatus::Finished {
-            return Err(QFError::RoundStatusError.into());
+        let slot = Clock::get()?.slot;
+        if voter.last_vote_slot != 0
+            && slot
+                < voter
+                    .last_vote_slot
+                    .saturating_add(round.config.vote_cooldown)
+        {
+            return Err(QFError::VoteCooldown.into());
         }
+        voter.last_vote_slot = slot;
+        Ok(())
+    }
 
-        if project_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+    /// Square root of `votes` in the scale of the other square roots
+    pub(crate) fn votes_sqrt(votes: u64) -> U256 {
+        if votes == 0 {
+            return U256::zero();
         }
-        let mut project = Project::unpack(&project_info.data.borrow())?;
-        if project.round != *round_info.key {
-            return Err(QFError::RoundMismatch.into());
+        PreciseNumber {
+            value: U256::from(votes).checked_mul(U256::from(ONE)).unwrap(),
         }
-        if project.withdraw {
-            return Err(QFError::ProjectAlreadyWithdraw.into());
-        }
-        if !project_owner_info.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        .sqrt()
+        .unwrap()
+        .value
+    }
+
+    /// Area of a project, the square of its vote square roots less its downvote square
+    /// roots, zero once the downvotes outweigh the votes. Endorsements add `bonus_bps`
+    /// of it.
+    pub(crate) fn project_area(area_sqrt: U256, down_sqrt: U256, bonus_bps: u64) -> U256 {
+        if down_sqrt >= area_sqrt {
+            return U256::zero();
         }
-        if project.owner != *project_owner_info.key {
-            return Err(QFError::OwnerMismatch.into());
+        PreciseNumber {
+            value: area_sqrt - down_sqrt,
         }
+        .checked_pow(2)
+        .unwrap()
+        .value
+        .checked_mul(U256::from(10_000 + bonus_bps))
+        .unwrap()
+            / U256::from(10_000)
+    }
 
-        if token_program_info.key != &spl_token::ID {
-            return Err(QFError::UnexpectedTokenProgramID.into());
+    /// What Withdraw pays a project before the fees, without its deposit
+    pub fn payout(round: &Round, project: &mut Project) -> u64 {
+        if round.status == RoundStatus::Cancelled {
+            // votes are refunded to the voters, only the deposit is paid back
+            return 0;
         }
 
-        let seeds: &[&[_]] = &[
-            &round.owner.to_bytes(),
-            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
-        ];
+        let (fund, area) = round.matching_pool(project.category);
+        // Retro votes are credits, not tokens
+        // epoch votes and matches are paid with WithdrawEpoch
+        let votes = match round.config.mode {
+            RoundMode::Standard | RoundMode::Conviction => project.votes,
+            RoundMode::Retro | RoundMode::Epochs => 0,
+        };
+        let mut amount = votes.checked_add(project.discretionary).unwrap();
+
+        if round.config.mode == RoundMode::Conviction {
+            // accrue up to the end of voting
+            Self::accrue_project_conviction(project, round.conviction_at);
+            if !round.conviction.is_zero() && !project.banned {
+                amount = amount
+                    .checked_add(
+                        fund.checked_mul(project.conviction)
+                            .unwrap()
+                            .checked_div(round.conviction)
+                            .unwrap()
+                            .as_u64(),
+                    )
+                    .unwrap();
+            }
+        } else if round.config.mode != RoundMode::Epochs
+            && !area.is_zero()
+            && round.matches(project)
+        {
+            amount = amount
+                .checked_add(
+                    fund.checked_mul(project.area)
+                        .unwrap()
+                        .checked_div(area)
+                        .unwrap()
+                        .as_u64(),
+                )
+                .unwrap();
+        }
 
-        let fund = U256::from(round.fund);
-        let mut amount = project.votes;
-
-        amount = amount
-            .checked_add(
-                fund.checked_mul(project.area)
-                    .unwrap()
-                    .checked_div(round.area)
-                    .unwrap()
-                    .as_u64(),
-            )
-            .unwrap();
+        // rounding can not make the projects take more than the round holds for them
+        amount.min(round.fund_remaining)
+    }
 
-        // charge 5% fee
-        let fee = amount.checked_mul(5).unwrap().checked_div(100).unwrap();
-        let amount = amount.checked_sub(fee).unwrap();
+    /// Takes the crank reward out of the fees the round holds, the operator fee first,
+    /// and returns it. It is capped at the fees not withdrawn yet.
+    pub fn take_crank_reward(round: &mut Round) -> u64 {
+        let amount = round.config.crank_reward.min(round.fees());
+        let from_operator = amount.min(round.operator_fee);
+        round.operator_fee -= from_operator;
+        round.protocol_fee -= amount - from_operator;
+        amount
+    }
 
-        invoke_signed(
-            &spl_token::instruction::transfer(
-                &token_program_info.key,
-                &vault_info.key,
-                &to_info.key,
-                &vault_owner_info.key,
-                &[&vault_owner_info.key],
-                amount,
-            )?,
-            &[
-                vault_info.clone(),
-                to_info.clone(),
-                vault_owner_info.clone(),
-                token_program_info.clone(),
-            ],
-            &[&seeds],
+    /// Pays the round's crank reward to the caller out of its fees, when `accounts`
+    /// starts with the vault, its authority, the caller's token account and the token
+    /// program. Returns how many of the accounts it took.
+    pub(crate) fn pay_crank_reward(
+        program_id: &Pubkey,
+        round: &mut Round,
+        accounts: &[AccountInfo],
+    ) -> Result<usize, ProgramError> {
+        let (vault_info, vault_owner_info, to_info, token_program_info) = match accounts {
+            [vault_info, vault_owner_info, to_info, token_program_info, ..]
+                if vault_info.key == &round.vault =>
+            {
+                (vault_info, vault_owner_info, to_info, token_program_info)
+            }
+            _ => return Ok(0),
+        };
+        let bump_seed = validation::check_pda(
+            vault_owner_info,
+            &[&round.owner.to_bytes()],
+            program_id,
+            QFError::VaultAuthorityMismatch,
         )?;
+        if token_program_info.key != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
 
-        project.withdraw = true;
-        Project::pack(project, &mut project_info.data.borrow_mut())?;
-
-        round.fee = round.fee.checked_add(fee).unwrap();
-        Round::pack(round, &mut round_info.data.borrow_mut())?;
-
-        Ok(())
+        let amount = Self::take_crank_reward(round);
+        if amount > 0 {
+            msg!("Pay a crank reward of {} tokens", amount);
+            invoke_signed(
+                &spl_token::instruction::transfer(
+                    token_program_info.key,
+                    vault_info.key,
+                    to_info.key,
+                    vault_owner_info.key,
+                    &[vault_owner_info.key],
+                    amount,
+                )?,
+                &[
+                    vault_info.clone(),
+                    to_info.clone(),
+                    vault_owner_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&[&round.owner.to_bytes(), &[bump_seed]]],
+            )?;
+        }
+        Ok(4)
     }
 
-    pub fn process_end_round(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let round_info = next_account_info(account_info_iter)?;
-        let owner_info = next_account_info(account_info_iter)?;
-
-        if round_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-        let mut round = Round::unpack(&round_info.data.borrow())?;
-        if round.status != RoundStatus::Ongoing {
-            return Err(QFError::RoundStatusError.into());
+    /// Writes the claims of a finalized round's projects, `pairs` holds each project
+    /// followed by its claim. A project that already has a claim is skipped.
+    pub(crate) fn write_claims<'a>(
+        program_id: &Pubkey,
+        round_key: &Pubkey,
+        round: &Round,
+        payer_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        rent: &Rent,
+        pairs: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        validation::check_signer(payer_info)?;
+        for pair in pairs.chunks(2) {
+            let (project_info, claim_info) = match pair {
+                [project_info, claim_info] => (project_info, claim_info),
+                _ => return Err(ProgramError::NotEnoughAccountKeys),
+            };
+            let mut project = ProjectAccount::load(project_info, program_id, round_key)?;
+
+            let project_key = project_info.key.to_bytes();
+            let bump_seed = validation::check_pda(
+                claim_info,
+                &[b"claim", &project_key],
+                program_id,
+                QFError::ClaimMismatch,
+            )?;
+            if claim_info.owner == program_id {
+                continue;
+            }
+            Self::create_pda_account(
+                program_id,
+                payer_info,
+                claim_info,
+                system_program_info,
+                rent,
+                Claim::LEN,
+                &[b"claim", &project_key, &[bump_seed]],
+            )?;
+            let claim = Claim {
+                project: *project_info.key,
+                amount: Self::payout(round, &mut project),
+                claimed: false,
+            };
+            Claim::pack(claim, &mut claim_info.data.borrow_mut())?;
         }
+        Ok(())
+    }
 
-        if owner_info.key != &round.owner {
-            return Err(QFError::OwnerMismatch.into());
-        }
-        if !owner_info.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+    /// Takes the operator fee and then the protocol fee out of a payout, returns what is
+    /// left for the project. An exempt payout is not charged.
+    pub fn charge_fees(round: &mut Round, config: &Config, amount: u64, exempt: bool) -> u64 {
+        if exempt {
+            return amount;
         }
+        let operator_fee = Self::bps_of(amount, round.config.operator_fee_bps);
+        let amount = amount.checked_sub(operator_fee).unwrap();
+        let protocol_fee = Self::bps_of(amount, config.protocol_fee_bps);
 
-        round.status = RoundStatus::Finished;
-        Round::pack(round, &mut round_info.data.borrow_mut())?;
-
-        Ok(())
+        round.operator_fee = round.operator_fee.checked_add(operator_fee).unwrap();
+        round.protocol_fee = round.protocol_fee.checked_add(protocol_fee).unwrap();
+        amount.checked_sub(protocol_fee).unwrap()
     }
 
-    pub fn process_withdraw_fee(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let round_info = next_account_info(account_info_iter)?;
-        let owner_info = next_account_info(account_info_iter)?;
-        let vault_info = next_account_info(account_info_iter)?;
-        let vault_owner_info = next_account_info(account_info_iter)?;
-        let to_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+    pub(crate) fn bps_of(amount: u64, bps: u16) -> u64 {
+        (amount as u128 * bps.min(10_000) as u128 / 10_000) as u64
+    }
 
-        if round_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+    /// Checks that a timelocked action was proposed for `to` and its timelock passed, then
+    /// clears the proposal. Nothing is checked while the config sets no timelock.
+    pub(crate) fn execute_timelocked(
+        program_id: &Pubkey,
+        config: &Config,
+        round: &Pubkey,
+        kind: ActionKind,
+        to: &Pubkey,
+        pending_action_info: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        if config.timelock == 0 {
+            return Ok(());
         }
-        let mut round = Round::unpack(&round_info.data.borrow())?;
-        if round.status != RoundStatus::Finished {
-            return Err(QFError::RoundStatusError.into());
+        let pending_action_info = pending_action_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        validation::check_owner(pending_action_info, program_id)?;
+        let pending_action = PendingAction::unpack(&pending_action_info.data.borrow())?;
+        if pending_action.round != *round || pending_action.kind != kind || pending_action.to != *to
+        {
+            return Err(QFError::ActionMismatch.into());
         }
-        if round.fee == 0 {
-            return Err(ProgramError::InsufficientFunds);
+        if Clock::get()?.unix_timestamp < pending_action.executable_at {
+            return Err(QFError::TimelockNotElapsed.into());
         }
 
-        if owner_info.key != &round.owner {
-            return Err(QFError::OwnerMismatch.into());
-        }
-        if !owner_info.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
+        PendingAction::pack(
+            PendingAction::default(),
+            &mut pending_action_info.data.borrow_mut(),
+        )
+    }
 
-        if vault_info.key != &round.vault {
-            return Err(QFError::VaultMismatch.into());
+    /// Loads the program config, a program without one takes no protocol fee
+    pub(crate) fn load_config(
+        program_id: &Pubkey,
+        config_info: &AccountInfo,
+    ) -> Result<Config, ProgramError> {
+        validation::check_pda(
+            config_info,
+            &[b"config"],
+            program_id,
+            QFError::ConfigMismatch,
+        )?;
+        if config_info.owner != program_id {
+            return Ok(Config::default());
         }
+        Config::unpack(&config_info.data.borrow())
+    }
 
-        if token_program_info.key != &spl_token::ID {
-            return Err(QFError::UnexpectedTokenProgramID.into());
+    /// Checks that `to_info` is a token account owned by the config's fee recipient
+    pub fn check_fee_recipient(config: &Config, to_info: &AccountInfo) -> ProgramResult {
+        let to = spl_token::state::Account::unpack(&to_info.data.borrow())?;
+        if to.owner != config.fee_recipient {
+            return Err(QFError::FeeRecipientMismatch.into());
         }
-
-        let seeds: &[&[_]] = &[
-            &round.owner.to_bytes(),
-            &[Pubkey::find_program_address(&[&round.owner.to_bytes()], &program_id).1],
-        ];
-
-        invoke_signed(
-            &spl_token::instruction::transfer(
-                &token_program_info.key,
-                &vault_info.key,
-                &to_info.key,
-                &vault_owner_info.key,
-                &[&vault_owner_info.key],
-                round.fee,
-            )?,
-            &[
-                vault_info.clone(),
-                to_info.clone(),
-                vault_owner_info.clone(),
-                token_program_info.clone(),
-            ],
-            &[&seeds],
-        )?;
-
-        round.fee = 0;
-        Round::pack(round, &mut round_info.data.borrow_mut())?;
-
         Ok(())
     }
 
-    pub fn process_ban_project(
-        program_id: &Pubkey,
-        accounts: &[AccountInfo], 
-        ban_amount: U256,
-    ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let round_info = next_account_info(account_info_iter)?;
-        let owner_info = next_account_info(account_info_iter)?;
-        let project_info = next_account_info(account_info_iter)?;
-
-        if round_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+    /// Adds the round's stake times the seconds since the last accrual
+    pub(crate) fn accrue_round_conviction(round: &mut Round, now: i64) {
+        if now > round.conviction_at {
+            let elapsed = U256::from((now - round.conviction_at) as u64);
+            round.conviction = round
+                .conviction
+                .checked_add(U256::from(round.votes).checked_mul(elapsed).unwrap())
+                .unwrap();
         }
-        let mut round = Round::unpack(&round_info.data.borrow())?;
-        if round.status != RoundStatus::Ongoing {
-            return Err(QFError::RoundStatusError.into());
+        round.conviction_at = now;
+    }
+
+    /// Adds the project's stake times the seconds since the last accrual
+    pub(crate) fn accrue_project_conviction(project: &mut Project, now: i64) {
+        if now > project.conviction_at {
+            let elapsed = U256::from((now - project.conviction_at) as u64);
+            project.conviction = project
+                .conviction
+                .checked_add(U256::from(project.votes).checked_mul(elapsed).unwrap())
+                .unwrap();
         }
+        project.conviction_at = now;
+    }
 
-        if owner_info.key != &round.owner {
-            return Err(QFError::OwnerMismatch.into());
+    /// Takes the voter's last vote out of the round, the reverse of `apply_vote`
+    pub fn revert_vote(
+        round: &mut Round,
+        project: &mut Project,
+        voter: &mut Voter,
+        now: i64,
+    ) -> ProgramResult {
+        if voter.blacklisted {
+            return Err(QFError::VoterBlacklisted.into());
         }
-        if !owner_info.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        if round.config.mode == RoundMode::Conviction {
+            Self::accrue_round_conviction(round, now);
+            Self::accrue_project_conviction(project, now);
         }
-
-        if project_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+        let amount = voter.last_vote_amount;
+        let votes = voter.votes.checked_sub(amount).unwrap();
+        // the vote may have been credited with another weight than its tokens
+        let weight = voter.weight.checked_sub(voter.last_vote_weight).unwrap();
+
+        let matched = round.matches(project);
+        if matched {
+            round.sub_area(project.category, project.area);
         }
-        let mut project = Project::unpack(&project_info.data.borrow())?;
 
-        project.area = project.area.checked_sub(ban_amount).unwrap();
-        project.area_sqrt = PreciseNumber {
-            value: project.area.checked_div(U256::from(ONE)).unwrap(),
+        let new_votes_sqrt = if weight == 0 {
+            PreciseNumber {
+                value: U256::zero(),
+            }
+        } else {
+            PreciseNumber {
+                value: U256::from(weight).checked_mul(U256::from(ONE)).unwrap(),
+            }
+            .sqrt()
+            .unwrap()
+        };
+
+        let project_area_sqrt = PreciseNumber {
+            value: project.area_sqrt,
         }
-        .sqrt()
+        .checked_sub(&PreciseNumber {
+            value: voter.votes_sqrt,
+        })
         .unwrap()
-        .value
-        .checked_mul(U256::from(1000000))
+        .checked_add(&new_votes_sqrt)
         .unwrap();
-        round.area = round.area.checked_sub(ban_amount).unwrap();
+        project.area = Self::project_area(
+            project_area_sqrt.value,
+            project.down_sqrt,
+            round.config.endorsement_bonus_bps(project.endorsements),
+        );
+
+        if matched {
+            round.add_area(project.category, project.area);
+        }
+
+        project.area_sqrt = project_area_sqrt.value;
+        project.votes = project.votes.checked_sub(amount).unwrap();
+
+        round.votes = round.votes.checked_sub(amount).unwrap();
 
-        Round::pack(round, &mut round_info.data.borrow_mut())?;
-        Project::pack(project, &mut project_info.data.borrow_mut())?;
+        voter.votes = votes;
+        voter.weight = weight;
+        voter.votes_sqrt = new_votes_sqrt.value;
+        voter.last_vote_amount = 0;
+        voter.last_vote_weight = 0;
+        voter.tier = project.tier_of(votes);
 
         Ok(())
     }
@@ -578,17 +868,21 @@ impl Processor {
     pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
         let instruction = QFInstruction::unpack(input)?;
         match instruction {
-            QFInstruction::StartRound => {
+            QFInstruction::StartRound { config } => {
                 msg!("Instruction: StartRound");
-                Self::process_start_round(program_id, accounts)
+                Self::process_start_round(program_id, accounts, config)
             }
             QFInstruction::Donate { amount, decimals } => {
                 msg!("Instruction: Donate");
                 Self::process_donate(program_id, accounts, amount, decimals)
             }
-            QFInstruction::RegisterProject => {
+            QFInstruction::RegisterProject {
+                category,
+                index,
+                proof,
+            } => {
                 msg!("Instruction: RegisterProject");
-                Self::process_register_project(program_id, accounts)
+                Self::process_register_project(program_id, accounts, category, index, proof)
             }
             QFInstruction::InitVoter => {
                 msg!("Instruction: InitVoter");
@@ -606,14 +900,360 @@ impl Processor {
                 msg!("Instruction: EndRound");
                 Self::process_end_round(program_id, accounts)
             }
+            #[cfg(feature = "fees")]
             QFInstruction::WithdrawFee => {
                 msg!("Instruction: WithdrawFee");
                 Self::process_withdraw_fee(program_id, accounts)
             }
-            QFInstruction::BanProject { ban_amount } => {
+            #[cfg(feature = "moderation")]
+            QFInstruction::BanProject => {
                 msg!("Instruction: BanProject");
-                Self::process_ban_project(program_id, accounts, ban_amount)
+                Self::process_ban_project(program_id, accounts)
+            }
+            QFInstruction::SubmitVoteBatch { nonce, entries } => {
+                msg!("Instruction: SubmitVoteBatch");
+                Self::process_submit_vote_batch(program_id, accounts, nonce, entries)
+            }
+            QFInstruction::VoteWithPermit {
+                amount,
+                decimals,
+                expiry,
+                nonce,
+            } => {
+                msg!("Instruction: VoteWithPermit");
+                Self::process_vote_with_permit(
+                    program_id, accounts, amount, decimals, expiry, nonce,
+                )
+            }
+            QFInstruction::CreateSession { max_amount, expiry } => {
+                msg!("Instruction: CreateSession");
+                Self::process_create_session(program_id, accounts, max_amount, expiry)
+            }
+            QFInstruction::SessionVote { amount, decimals } => {
+                msg!("Instruction: SessionVote");
+                Self::process_session_vote(program_id, accounts, amount, decimals)
             }
+            QFInstruction::DelegateVotes { budget } => {
+                msg!("Instruction: DelegateVotes");
+                Self::process_delegate_votes(program_id, accounts, budget)
+            }
+            QFInstruction::VoteAsDelegate { amount, decimals } => {
+                msg!("Instruction: VoteAsDelegate");
+                Self::process_vote_as_delegate(program_id, accounts, amount, decimals)
+            }
+            QFInstruction::SetPayoutWallet => {
+                msg!("Instruction: SetPayoutWallet");
+                Self::process_set_payout_wallet(program_id, accounts)
+            }
+            QFInstruction::StartVoting => {
+                msg!("Instruction: StartVoting");
+                Self::process_start_voting(program_id, accounts)
+            }
+            QFInstruction::Finalize => {
+                msg!("Instruction: Finalize");
+                Self::process_finalize(program_id, accounts)
+            }
+            QFInstruction::CloseRound => {
+                msg!("Instruction: CloseRound");
+                Self::process_close_round(program_id, accounts)
+            }
+            QFInstruction::Refund => {
+                msg!("Instruction: Refund");
+                Self::process_refund(program_id, accounts)
+            }
+            QFInstruction::RefundDonation => {
+                msg!("Instruction: RefundDonation");
+                Self::process_refund_donation(program_id, accounts)
+            }
+            QFInstruction::Pledge { amount, decimals } => {
+                msg!("Instruction: Pledge");
+                Self::process_pledge(program_id, accounts, amount, decimals)
+            }
+            QFInstruction::ExecutePledge => {
+                msg!("Instruction: ExecutePledge");
+                Self::process_execute_pledge(program_id, accounts)
+            }
+            QFInstruction::ReclaimPledge => {
+                msg!("Instruction: ReclaimPledge");
+                Self::process_reclaim_pledge(program_id, accounts)
+            }
+            QFInstruction::RevokeVote => {
+                msg!("Instruction: RevokeVote");
+                Self::process_revoke_vote(program_id, accounts)
+            }
+            QFInstruction::ReclaimMatching => {
+                msg!("Instruction: ReclaimMatching");
+                Self::process_reclaim_matching(program_id, accounts)
+            }
+            QFInstruction::RolloverFunds => {
+                msg!("Instruction: RolloverFunds");
+                Self::process_rollover_funds(program_id, accounts)
+            }
+            QFInstruction::LinkRounds => {
+                msg!("Instruction: LinkRounds");
+                Self::process_link_rounds(program_id, accounts)
+            }
+            QFInstruction::CreateRoundTemplate { id, config } => {
+                msg!("Instruction: CreateRoundTemplate");
+                Self::process_create_round_template(program_id, accounts, id, config)
+            }
+            QFInstruction::StartRoundFromTemplate => {
+                msg!("Instruction: StartRoundFromTemplate");
+                Self::process_start_round_from_template(program_id, accounts)
+            }
+            QFInstruction::SetCategories { shares } => {
+                msg!("Instruction: SetCategories");
+                Self::process_set_categories(program_id, accounts, shares)
+            }
+            QFInstruction::SetProjectCategory { category } => {
+                msg!("Instruction: SetProjectCategory");
+                Self::process_set_project_category(program_id, accounts, category)
+            }
+            QFInstruction::AllocateDiscretionary { amount } => {
+                msg!("Instruction: AllocateDiscretionary");
+                Self::process_allocate_discretionary(program_id, accounts, amount)
+            }
+            QFInstruction::CastCredits { amount } => {
+                msg!("Instruction: CastCredits");
+                Self::process_cast_credits(program_id, accounts, amount)
+            }
+            QFInstruction::EpochVote { amount, decimals } => {
+                msg!("Instruction: EpochVote");
+                Self::process_epoch_vote(program_id, accounts, amount, decimals)
+            }
+            QFInstruction::WithdrawEpoch { epoch } => {
+                msg!("Instruction: WithdrawEpoch");
+                Self::process_withdraw_epoch(program_id, accounts, epoch)
+            }
+            QFInstruction::CreateRecurringVote {
+                amount,
+                interval,
+                total,
+                decimals,
+            } => {
+                msg!("Instruction: CreateRecurringVote");
+                Self::process_create_recurring_vote(
+                    program_id, accounts, amount, interval, total, decimals,
+                )
+            }
+            QFInstruction::ExecuteRecurringVote => {
+                msg!("Instruction: ExecuteRecurringVote");
+                Self::process_execute_recurring_vote(program_id, accounts)
+            }
+            QFInstruction::CancelRecurringVote => {
+                msg!("Instruction: CancelRecurringVote");
+                Self::process_cancel_recurring_vote(program_id, accounts)
+            }
+            QFInstruction::CrankEndRound => {
+                msg!("Instruction: CrankEndRound");
+                Self::process_crank_end_round(program_id, accounts)
+            }
+            QFInstruction::CrankFinalize => {
+                msg!("Instruction: CrankFinalize");
+                Self::process_crank_finalize(program_id, accounts)
+            }
+            QFInstruction::MintReceipt => {
+                msg!("Instruction: MintReceipt");
+                Self::process_mint_receipt(program_id, accounts)
+            }
+            QFInstruction::CreateAirdrop { amount, decimals } => {
+                msg!("Instruction: CreateAirdrop");
+                Self::process_create_airdrop(program_id, accounts, amount, decimals)
+            }
+            QFInstruction::ClaimAirdrop => {
+                msg!("Instruction: ClaimAirdrop");
+                Self::process_claim_airdrop(program_id, accounts)
+            }
+            QFInstruction::StakePoolVote { amount, decimals } => {
+                msg!("Instruction: StakePoolVote");
+                Self::process_stake_pool_vote(program_id, accounts, amount, decimals)
+            }
+            QFInstruction::AddCurator => {
+                msg!("Instruction: AddCurator");
+                Self::process_add_curator(program_id, accounts)
+            }
+            QFInstruction::AttestProject => {
+                msg!("Instruction: AttestProject");
+                Self::process_attest_project(program_id, accounts)
+            }
+            #[cfg(feature = "moderation")]
+            QFInstruction::BlacklistVoter { reverse } => {
+                msg!("Instruction: BlacklistVoter");
+                Self::process_blacklist_voter(program_id, accounts, reverse)
+            }
+            #[cfg(feature = "moderation")]
+            QFInstruction::AppealBan => {
+                msg!("Instruction: AppealBan");
+                Self::process_appeal_ban(program_id, accounts)
+            }
+            #[cfg(feature = "moderation")]
+            QFInstruction::ResolveAppeal { overturn } => {
+                msg!("Instruction: ResolveAppeal");
+                Self::process_resolve_appeal(program_id, accounts, overturn)
+            }
+            QFInstruction::VerifyInvariants => {
+                msg!("Instruction: VerifyInvariants");
+                Self::process_verify_invariants(program_id, accounts)
+            }
+            QFInstruction::SweepDust => {
+                msg!("Instruction: SweepDust");
+                Self::process_sweep_dust(program_id, accounts)
+            }
+            QFInstruction::InitConfig {
+                protocol_fee_bps,
+                fee_recipient,
+            } => {
+                msg!("Instruction: InitConfig");
+                Self::process_init_config(program_id, accounts, protocol_fee_bps, fee_recipient)
+            }
+            #[cfg(feature = "fees")]
+            QFInstruction::SetProtocolFee { protocol_fee_bps } => {
+                msg!("Instruction: SetProtocolFee");
+                Self::process_set_protocol_fee(program_id, accounts, protocol_fee_bps)
+            }
+            #[cfg(feature = "fees")]
+            QFInstruction::SetFeeExempt { key, exempt } => {
+                msg!("Instruction: SetFeeExempt");
+                Self::process_set_fee_exempt(program_id, accounts, key, exempt)
+            }
+            #[cfg(feature = "fees")]
+            QFInstruction::SetFeeRecipient { fee_recipient } => {
+                msg!("Instruction: SetFeeRecipient");
+                Self::process_set_fee_recipient(program_id, accounts, fee_recipient)
+            }
+            #[cfg(feature = "fees")]
+            QFInstruction::AcceptFeeRecipient => {
+                msg!("Instruction: AcceptFeeRecipient");
+                Self::process_accept_fee_recipient(program_id, accounts)
+            }
+            QFInstruction::RecoverAbandonedFunds => {
+                msg!("Instruction: RecoverAbandonedFunds");
+                Self::process_recover_abandoned_funds(program_id, accounts)
+            }
+            QFInstruction::ProposeAction { kind } => {
+                msg!("Instruction: ProposeAction");
+                Self::process_propose_action(program_id, accounts, kind)
+            }
+            QFInstruction::CancelAction => {
+                msg!("Instruction: CancelAction");
+                Self::process_cancel_action(program_id, accounts)
+            }
+            QFInstruction::SetTimelock { timelock } => {
+                msg!("Instruction: SetTimelock");
+                Self::process_set_timelock(program_id, accounts, timelock)
+            }
+            QFInstruction::MigrateVoter => {
+                msg!("Instruction: MigrateVoter");
+                Self::process_migrate_voter(program_id, accounts)
+            }
+            QFInstruction::ArchiveRound => {
+                msg!("Instruction: ArchiveRound");
+                Self::process_archive_round(program_id, accounts)
+            }
+            QFInstruction::WriteClaims => {
+                msg!("Instruction: WriteClaims");
+                Self::process_write_claims(program_id, accounts)
+            }
+            QFInstruction::Downvote { amount, decimals } => {
+                msg!("Instruction: Downvote");
+                Self::process_downvote(program_id, accounts, amount, decimals)
+            }
+            #[cfg(feature = "moderation")]
+            QFInstruction::FlagProject => {
+                msg!("Instruction: FlagProject");
+                Self::process_flag_project(program_id, accounts)
+            }
+            #[cfg(feature = "moderation")]
+            QFInstruction::ResolveFlag { uphold } => {
+                msg!("Instruction: ResolveFlag");
+                Self::process_resolve_flag(program_id, accounts, uphold)
+            }
+            QFInstruction::SetRegistrationAllowlist { root } => {
+                msg!("Instruction: SetRegistrationAllowlist");
+                Self::process_set_registration_allowlist(program_id, accounts, root)
+            }
+            QFInstruction::SetProjectTiers { tiers } => {
+                msg!("Instruction: SetProjectTiers");
+                Self::process_set_project_tiers(program_id, accounts, tiers)
+            }
+            QFInstruction::PostProjectUpdate { hash, body } => {
+                msg!("Instruction: PostProjectUpdate");
+                Self::process_post_project_update(program_id, accounts, hash, body)
+            }
+            QFInstruction::Endorse => {
+                msg!("Instruction: Endorse");
+                Self::process_endorse(program_id, accounts)
+            }
+            QFInstruction::SetMaxRoundDuration { max_round_duration } => {
+                msg!("Instruction: SetMaxRoundDuration");
+                Self::process_set_max_round_duration(program_id, accounts, max_round_duration)
+            }
+            QFInstruction::ExtendRound { voting_end } => {
+                msg!("Instruction: ExtendRound");
+                Self::process_extend_round(program_id, accounts, voting_end)
+            }
+            QFInstruction::VoteWithSwap { decimals, route } => {
+                msg!("Instruction: VoteWithSwap");
+                Self::process_vote_with_swap(program_id, accounts, decimals, route)
+            }
+            QFInstruction::SetApprovedMint { mint, approved } => {
+                msg!("Instruction: SetApprovedMint");
+                Self::process_set_approved_mint(program_id, accounts, mint, approved)
+            }
+            QFInstruction::ReleaseHoldback => {
+                msg!("Instruction: ReleaseHoldback");
+                Self::process_release_holdback(program_id, accounts)
+            }
+            QFInstruction::InitVoterTree => {
+                msg!("Instruction: InitVoterTree");
+                Self::process_init_voter_tree(program_id, accounts)
+            }
+            QFInstruction::CompressedVote {
+                amount,
+                decimals,
+                root,
+                votes,
+                last_vote_slot,
+                proof,
+            } => {
+                msg!("Instruction: CompressedVote");
+                Self::process_compressed_vote(
+                    program_id,
+                    accounts,
+                    amount,
+                    decimals,
+                    root,
+                    votes,
+                    last_vote_slot,
+                    proof,
+                )
+            }
+            QFInstruction::CompressedRefund {
+                root,
+                votes,
+                last_vote_slot,
+                proof,
+            } => {
+                msg!("Instruction: CompressedRefund");
+                Self::process_compressed_refund(
+                    program_id,
+                    accounts,
+                    root,
+                    votes,
+                    last_vote_slot,
+                    proof,
+                )
+            }
+            QFInstruction::LendFund { amount } => {
+                msg!("Instruction: LendFund");
+                Self::process_lend_fund(program_id, accounts, amount)
+            }
+            QFInstruction::MintParticipation => {
+                msg!("Instruction: MintParticipation");
+                Self::process_mint_participation(program_id, accounts)
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(QFError::InstructionDisabled.into()),
         }
     }
 }
@@ -631,6 +1271,110 @@ impl PrintProgramError for QFError {
             QFError::ProjectAlreadyWithdraw => msg!("project has already withdraw"),
             QFError::UnexpectedTokenProgramID => msg!("unexpected token program id"),
             QFError::VoterMismatch => msg!("voter mismatch"),
+            QFError::ProjectMismatch => msg!("project does not match"),
+            QFError::InvalidBatchNonce => msg!("invalid batch nonce"),
+            QFError::SignatureVerificationFailed => msg!("signature verification failed"),
+            QFError::PermitExpired => msg!("permit has expired"),
+            QFError::InvalidPermitNonce => msg!("invalid permit nonce"),
+            QFError::SessionMismatch => msg!("session does not match"),
+            QFError::SessionExpired => msg!("session has expired"),
+            QFError::SessionLimitExceeded => msg!("session limit exceeded"),
+            QFError::DelegationMismatch => msg!("delegation does not match"),
+            QFError::DelegationBudgetExceeded => msg!("delegation budget exceeded"),
+            QFError::InvalidTokenAuthority => msg!("invalid token authority"),
+            QFError::InsufficientDelegatedAmount => msg!("insufficient delegated amount"),
+            QFError::MintMismatch => msg!("mint does not match"),
+            QFError::RecipientMismatch => msg!("recipient does not match"),
+            QFError::UnexpectedAssociatedTokenProgramID => {
+                msg!("unexpected associated token program id")
+            }
+            QFError::SponsorMismatch => msg!("sponsor does not match"),
+            QFError::PledgeMismatch => msg!("pledge does not match"),
+            QFError::PledgeAlreadyExecuted => msg!("pledge already executed"),
+            QFError::PledgesNotExecuted => msg!("pledges are not executed"),
+            QFError::GracePeriodOver => msg!("grace period is over"),
+            QFError::MatchingAlreadyReclaimed => msg!("matching already reclaimed"),
+            QFError::RoundAlreadyLinked => msg!("round already linked"),
+            QFError::InvalidCategory => msg!("invalid category"),
+            QFError::CommitteeShareExceeded => msg!("committee share exceeded"),
+            QFError::RoundModeMismatch => msg!("not allowed in this round mode"),
+            QFError::CreditBudgetExceeded => msg!("credit budget exceeded"),
+            QFError::InvalidEpoch => msg!("invalid epoch"),
+            QFError::ProjectBanned => msg!("project is banned"),
+            QFError::SubscriptionMismatch => msg!("subscription mismatch"),
+            QFError::SubscriptionActive => msg!("subscription is still active"),
+            QFError::SubscriptionNotDue => msg!("subscription vote is not due"),
+            QFError::CrankNotDue => msg!("crank is not due"),
+            QFError::NoVotes => msg!("voter has no votes"),
+            QFError::ReceiptMismatch => msg!("receipt mismatch"),
+            QFError::ReceiptAlreadyMinted => msg!("receipt already minted"),
+            QFError::AirdropMismatch => msg!("airdrop mismatch"),
+            QFError::AirdropAlreadyClaimed => msg!("airdrop already claimed"),
+            QFError::StakePoolMismatch => msg!("stake pool mismatch"),
+            QFError::CollectionMismatch => msg!("collection mismatch"),
+            QFError::CuratorMismatch => msg!("curator mismatch"),
+            QFError::ProjectAlreadyVerified => msg!("project already verified"),
+            QFError::VoterBlacklisted => msg!("voter is blacklisted"),
+            QFError::InvalidAppeal => msg!("invalid appeal"),
+            QFError::AppealsPending => msg!("appeals are pending"),
+            QFError::DisputeWindowOpen => msg!("dispute window is still open"),
+            QFError::DuplicateProject => msg!("duplicate project"),
+            QFError::ClaimPeriodOpen => msg!("claim period is still open"),
+            QFError::ConfigMismatch => msg!("config mismatch"),
+            QFError::AdminMismatch => msg!("admin mismatch"),
+            QFError::FeeRecipientMismatch => msg!("fee recipient mismatch"),
+            QFError::FeeExemptFull => msg!("fee exemption list is full"),
+            QFError::ActionMismatch => msg!("pending action mismatch"),
+            QFError::TimelockNotElapsed => msg!("timelock has not elapsed"),
+            QFError::VaultAuthorityMismatch => msg!("vault authority does not match"),
+            QFError::InvalidProjectIndex => msg!("invalid project index"),
+            QFError::ProjectLimitReached => msg!("round has reached its project limit"),
+            QFError::RegistryMismatch => msg!("voter registry does not match"),
+            QFError::InstructionDisabled => msg!("instruction is not enabled in this build"),
+            QFError::FeesDisabled => msg!("fees are not enabled in this build"),
+            QFError::ProgramDataMismatch => msg!("program data account does not match"),
+            QFError::InvalidDeadline => msg!("invalid deadline"),
+            QFError::InvalidSubscription => msg!("invalid subscription"),
+            QFError::InsufficientVaultBalance => msg!("insufficient vault balance"),
+            QFError::NoFeesDue => msg!("no fees due"),
+            QFError::InvalidFeeBps => msg!("fee exceeds 10000 basis points"),
+            QFError::InvalidFeeExemptKey => msg!("invalid fee exempt key"),
+            QFError::InvalidTimelock => msg!("invalid timelock"),
+            QFError::ArchiveMismatch => msg!("archive does not match"),
+            QFError::VaultNotEmpty => msg!("vault is not empty"),
+            QFError::ClaimMismatch => msg!("claim does not match"),
+            QFError::ClaimAlreadyPaid => msg!("claim already paid"),
+            QFError::DownvoteBudgetExceeded => msg!("downvote budget exceeded"),
+            QFError::VoteCooldown => msg!("vote cooldown has not passed"),
+            QFError::FlagMismatch => msg!("flag does not match"),
+            QFError::FlagsPending => msg!("flags are pending"),
+            QFError::FlaggingDisabled => msg!("flagging is disabled in this round"),
+            QFError::InvalidFlag => msg!("invalid flag"),
+            QFError::NotAllowlisted => msg!("wallet is not on the registration allowlist"),
+            QFError::InvalidTiers => msg!("invalid donation tiers"),
+            QFError::UpdateMismatch => msg!("project update does not match"),
+            QFError::AlreadyEndorsed => msg!("voter already endorsed the project"),
+            QFError::InvalidExtension => msg!("round can not be extended to this voting end"),
+            QFError::MintNotApproved => msg!("mint is not approved by the config"),
+            QFError::ApprovedMintsFull => msg!("approved mint list is full"),
+            QFError::InvalidHoldback => msg!("holdback exceeds 10000 basis points"),
+            QFError::NoHoldback => msg!("no holdback to release"),
+            QFError::AggregatorNotSet => msg!("round has no aggregator"),
+            QFError::BatchTotalOverflow => msg!("vote batch total overflows"),
+            QFError::VoteLogFull => msg!("vote log is full"),
+            QFError::VoterTreeFull => msg!("voter tree is full"),
+            QFError::StaleVoterTreeRoot => msg!("voter tree root is not in its change logs"),
+            QFError::InvalidVoterProof => msg!("invalid voter tree proof"),
+            QFError::VoterTreeMismatch => msg!("voter tree does not match"),
+            QFError::VoterKindMismatch => {
+                msg!("wallet already votes through voter accounts or the voter tree")
+            }
+            QFError::CollateralMismatch => msg!("collateral account does not match"),
+            QFError::FundLent => msg!("round fund is already lent out"),
+            QFError::LoanExceedsFund => msg!("loan exceeds the round fund"),
+            QFError::CustodianMismatch => msg!("votes of the voter were paid by another wallet"),
+            QFError::ClaimPeriodOver => msg!("claim period is over"),
+            QFError::ParticipationMintMismatch => msg!("participation mint mismatch"),
         }
     }
 }
@@ -0,0 +1,48 @@
+//! Reads the exchange rate of an SPL stake pool without depending on its crate
+
+use crate::error::QFError;
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use std::convert::TryInto;
+
+solana_program::declare_id!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+
+const POOL_MINT_OFFSET: usize = 162;
+const TOTAL_LAMPORTS_OFFSET: usize = 258;
+const POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+
+/// Converts `amount` pool tokens of `pool_mint` to the lamports they are worth
+/// at the current exchange rate of the stake pool.
+///
+/// An empty pool is worth one lamport per token, like the stake pool program
+/// does on its first deposit.
+pub fn to_lamports(
+    stake_pool_info: &AccountInfo,
+    pool_mint: &Pubkey,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    if stake_pool_info.owner != &id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = stake_pool_info.data.borrow();
+    if data.len() < POOL_TOKEN_SUPPLY_OFFSET + 8 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if &data[POOL_MINT_OFFSET..POOL_MINT_OFFSET + 32] != pool_mint.as_ref() {
+        return Err(QFError::MintMismatch.into());
+    }
+    let total_lamports = read_u64(&data, TOTAL_LAMPORTS_OFFSET);
+    let pool_token_supply = read_u64(&data, POOL_TOKEN_SUPPLY_OFFSET);
+    if pool_token_supply == 0 {
+        return Ok(amount);
+    }
+
+    (amount as u128)
+        .checked_mul(total_lamports as u128)
+        .and_then(|lamports| lamports.checked_div(pool_token_supply as u128))
+        .and_then(|lamports| lamports.try_into().ok())
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
@@ -1,3 +1,4 @@
+use crate::merkle;
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use num_enum::TryFromPrimitive;
 use solana_program::{
@@ -7,31 +8,624 @@ use solana_program::{
 };
 use spl_math::uint::U256;
 
+/// Bytes every account of the program starts with
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// Type and layout version of an account, written at offset 0 so indexers can tell
+/// accounts apart with a memcmp filter. The first 7 bytes are the start of
+/// sha256("account:<type name>"), the last one is the version of the layout, it is
+/// bumped when the layout changes so a migration can tell old accounts from new ones.
+pub trait Discriminator {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN];
+}
+
+/// Strips the discriminator off an account, an account that was just created and is
+/// still zeroed has none yet
+fn strip_discriminator<T: Discriminator>(src: &[u8]) -> Result<&[u8], ProgramError> {
+    let (discriminator, rest) = src.split_at(DISCRIMINATOR_LEN);
+    if discriminator != T::DISCRIMINATOR && discriminator != [0; DISCRIMINATOR_LEN] {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(rest)
+}
+
+/// Writes the discriminator of `T`, returns the rest of the account
+fn write_discriminator<T: Discriminator>(dst: &mut [u8]) -> &mut [u8] {
+    let (discriminator, rest) = dst.split_at_mut(DISCRIMINATOR_LEN);
+    discriminator.copy_from_slice(&T::DISCRIMINATOR);
+    rest
+}
+
 /// Round status
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
 pub enum RoundStatus {
     Uninitialized,
-    Ongoing,
-    Finished,
+    /// projects can register and the pool can be funded
+    Registration,
+    /// votes are accepted
+    Voting,
+    /// votes are closed, bans can still change the result
+    Tallying,
+    /// projects can withdraw
+    Claim,
+    Closed,
+    /// quorum was not reached, voters and projects get refunds
+    Cancelled,
 }
 
+// the enums implement Default by hand, `#[default]` on a variant needs a newer
+// compiler than the program builds with
+#[allow(clippy::derivable_impls)]
 impl Default for RoundStatus {
     fn default() -> Self {
         RoundStatus::Uninitialized
     }
 }
 
+/// Round mode
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+pub enum RoundMode {
+    /// votes are token contributions, projects get them plus the match
+    Standard,
+    /// votes are credits from a per voter budget, projects only get the match
+    Retro,
+    /// votes are stakes that accrue conviction over time, the match follows conviction
+    Conviction,
+    /// voting is split into epochs, each with its own part of the fund and its own tally
+    Epochs,
+}
+
+impl RoundMode {
+    /// Whether votes are paid in tokens that go to the vault, and are refunded when the
+    /// round is cancelled
+    pub fn votes_are_tokens(&self) -> bool {
+        matches!(
+            self,
+            RoundMode::Standard | RoundMode::Conviction | RoundMode::Epochs
+        )
+    }
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for RoundMode {
+    fn default() -> Self {
+        RoundMode::Standard
+    }
+}
+
+/// Round config, chosen by the owner when the round starts
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoundConfig {
+    /// key allowed to sign vote batches, default means batches are disabled
+    pub aggregator: Pubkey,
+    /// refundable deposit required to register a project, in the round mint
+    pub registration_deposit: u64,
+    /// wallets that voted needed to finalize the round, 0 means no minimum
+    pub min_unique_voters: u64,
+    /// total votes needed to finalize the round, 0 means no minimum
+    pub min_total_votes: u64,
+    /// fund needed to start voting, 0 means no minimum
+    pub min_fund: u64,
+    /// seconds a voter can revoke their last vote, 0 disables RevokeVote
+    pub vote_grace_period: i64,
+    /// contribution that makes a sponsor bronze, 0 disables the tier
+    pub bronze_threshold: u64,
+    /// contribution that makes a sponsor silver, 0 disables the tier
+    pub silver_threshold: u64,
+    /// contribution that makes a sponsor gold, 0 disables the tier
+    pub gold_threshold: u64,
+    /// fund the round aims for, only used to report progress
+    pub fund_target: u64,
+    /// key that can also set project categories, default means only the owner
+    pub moderator: Pubkey,
+    /// share of the fund in basis points the owner allocates with AllocateDiscretionary
+    pub committee_share_bps: u16,
+    /// how votes are counted and paid
+    pub mode: RoundMode,
+    /// credits each voter can spend in a Retro round
+    pub voter_credits: u64,
+    /// seconds in an epoch of an Epochs round
+    pub epoch_length: i64,
+    /// epochs in an Epochs round, the fund is split evenly between them
+    pub epoch_count: u64,
+    /// time anyone can end the voting with CrankEndRound, zero disables it
+    pub voting_end: i64,
+    /// seconds after voting_end before anyone can finalize with CrankFinalize
+    pub tally_period: i64,
+    /// SPL stake pool whose pool tokens are voted with StakePoolVote, default for none
+    pub stake_pool: Pubkey,
+    /// collection the metadata of projects has to be verified in, default for any
+    pub collection: Pubkey,
+    /// only projects a curator attested are matched
+    pub verified_only: bool,
+    /// tokens a banned project bonds to appeal the ban
+    pub appeal_bond: u64,
+    /// seconds after the end of voting before the first Withdraw
+    pub dispute_window: i64,
+    /// seconds projects have to withdraw after the dispute window, after it the owner can sweep what is left, zero for no deadline
+    pub claim_period: i64,
+    /// fee the round owner takes from every payout, in basis points
+    pub operator_fee_bps: u16,
+    /// seconds after the claim deadline from which the owner can recover everything left in the vault, 0 disables it
+    pub recovery_grace: i64,
+    /// most projects the round accepts, 0 for no limit
+    pub max_projects: u64,
+    /// tokens of the round's fees paid to whoever runs CrankEndRound or CrankFinalize, 0 for none
+    pub crank_reward: u64,
+    /// share of its votes in basis points a wallet can spend on downvotes, 0 disables them
+    pub downvote_share_bps: u16,
+    /// tokens a wallet stakes to flag a project, zero disables flagging
+    pub flag_stake: u64,
+    /// share of a banned project's deposit paid to each upheld flag
+    pub flag_bounty_bps: u16,
+    /// slots a voter waits between two votes, zero means no cooldown
+    pub vote_cooldown: u64,
+    /// area bonus in basis points each endorsement gives a project, 0 disables it
+    pub endorsement_bonus_bps: u16,
+    /// projects the round needs when voting starts, 0 for no minimum
+    pub min_projects: u64,
+    /// share of every payout after fees, in basis points, Withdraw keeps in the vault until ReleaseHoldback
+    pub holdback_bps: u16,
+}
+impl Sealed for RoundConfig {}
+impl Pack for RoundConfig {
+    const LEN: usize = 326;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 326];
+        let (
+            aggregator,
+            registration_deposit,
+            min_unique_voters,
+            min_total_votes,
+            min_fund,
+            vote_grace_period,
+            bronze_threshold,
+            silver_threshold,
+            gold_threshold,
+            fund_target,
+            moderator,
+            committee_share_bps,
+            mode,
+            voter_credits,
+            epoch_length,
+            epoch_count,
+            voting_end,
+            tally_period,
+            stake_pool,
+            collection,
+            verified_only,
+            appeal_bond,
+            dispute_window,
+            claim_period,
+            operator_fee_bps,
+            recovery_grace,
+            max_projects,
+            crank_reward,
+            downvote_share_bps,
+            flag_stake,
+            flag_bounty_bps,
+            vote_cooldown,
+            endorsement_bonus_bps,
+            min_projects,
+            holdback_bps,
+        ) = array_refs![
+            src, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 32, 2, 1, 8, 8, 8, 8, 8, 32, 32, 1, 8, 8, 8, 2, 8,
+            8, 8, 2, 8, 2, 8, 2, 8, 2
+        ];
+        Ok(RoundConfig {
+            aggregator: Pubkey::new_from_array(*aggregator),
+            registration_deposit: u64::from_le_bytes(*registration_deposit),
+            min_unique_voters: u64::from_le_bytes(*min_unique_voters),
+            min_total_votes: u64::from_le_bytes(*min_total_votes),
+            min_fund: u64::from_le_bytes(*min_fund),
+            vote_grace_period: i64::from_le_bytes(*vote_grace_period),
+            bronze_threshold: u64::from_le_bytes(*bronze_threshold),
+            silver_threshold: u64::from_le_bytes(*silver_threshold),
+            gold_threshold: u64::from_le_bytes(*gold_threshold),
+            fund_target: u64::from_le_bytes(*fund_target),
+            moderator: Pubkey::new_from_array(*moderator),
+            committee_share_bps: u16::from_le_bytes(*committee_share_bps),
+            mode: RoundMode::try_from_primitive(mode[0])
+                .or(Err(ProgramError::InvalidAccountData))?,
+            voter_credits: u64::from_le_bytes(*voter_credits),
+            epoch_length: i64::from_le_bytes(*epoch_length),
+            epoch_count: u64::from_le_bytes(*epoch_count),
+            voting_end: i64::from_le_bytes(*voting_end),
+            tally_period: i64::from_le_bytes(*tally_period),
+            stake_pool: Pubkey::new_from_array(*stake_pool),
+            collection: Pubkey::new_from_array(*collection),
+            verified_only: match verified_only {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            appeal_bond: u64::from_le_bytes(*appeal_bond),
+            dispute_window: i64::from_le_bytes(*dispute_window),
+            claim_period: i64::from_le_bytes(*claim_period),
+            operator_fee_bps: u16::from_le_bytes(*operator_fee_bps),
+            recovery_grace: i64::from_le_bytes(*recovery_grace),
+            max_projects: u64::from_le_bytes(*max_projects),
+            crank_reward: u64::from_le_bytes(*crank_reward),
+            downvote_share_bps: u16::from_le_bytes(*downvote_share_bps),
+            flag_stake: u64::from_le_bytes(*flag_stake),
+            flag_bounty_bps: u16::from_le_bytes(*flag_bounty_bps),
+            vote_cooldown: u64::from_le_bytes(*vote_cooldown),
+            endorsement_bonus_bps: u16::from_le_bytes(*endorsement_bonus_bps),
+            min_projects: u64::from_le_bytes(*min_projects),
+            holdback_bps: u16::from_le_bytes(*holdback_bps),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 326];
+        let (
+            aggregator_dst,
+            registration_deposit_dst,
+            min_unique_voters_dst,
+            min_total_votes_dst,
+            min_fund_dst,
+            vote_grace_period_dst,
+            bronze_threshold_dst,
+            silver_threshold_dst,
+            gold_threshold_dst,
+            fund_target_dst,
+            moderator_dst,
+            committee_share_bps_dst,
+            mode_dst,
+            voter_credits_dst,
+            epoch_length_dst,
+            epoch_count_dst,
+            voting_end_dst,
+            tally_period_dst,
+            stake_pool_dst,
+            collection_dst,
+            verified_only_dst,
+            appeal_bond_dst,
+            dispute_window_dst,
+            claim_period_dst,
+            operator_fee_bps_dst,
+            recovery_grace_dst,
+            max_projects_dst,
+            crank_reward_dst,
+            downvote_share_bps_dst,
+            flag_stake_dst,
+            flag_bounty_bps_dst,
+            vote_cooldown_dst,
+            endorsement_bonus_bps_dst,
+            min_projects_dst,
+            holdback_bps_dst,
+        ) = mut_array_refs![
+            dst, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 32, 2, 1, 8, 8, 8, 8, 8, 32, 32, 1, 8, 8, 8, 2, 8,
+            8, 8, 2, 8, 2, 8, 2, 8, 2
+        ];
+        let &RoundConfig {
+            ref aggregator,
+            registration_deposit,
+            min_unique_voters,
+            min_total_votes,
+            min_fund,
+            vote_grace_period,
+            bronze_threshold,
+            silver_threshold,
+            gold_threshold,
+            fund_target,
+            ref moderator,
+            committee_share_bps,
+            mode,
+            voter_credits,
+            epoch_length,
+            epoch_count,
+            voting_end,
+            tally_period,
+            ref stake_pool,
+            ref collection,
+            verified_only,
+            appeal_bond,
+            dispute_window,
+            claim_period,
+            operator_fee_bps,
+            recovery_grace,
+            max_projects,
+            crank_reward,
+            downvote_share_bps,
+            flag_stake,
+            flag_bounty_bps,
+            vote_cooldown,
+            endorsement_bonus_bps,
+            min_projects,
+            holdback_bps,
+        } = self;
+        aggregator_dst.copy_from_slice(aggregator.as_ref());
+        *registration_deposit_dst = registration_deposit.to_le_bytes();
+        *min_unique_voters_dst = min_unique_voters.to_le_bytes();
+        *min_total_votes_dst = min_total_votes.to_le_bytes();
+        *min_fund_dst = min_fund.to_le_bytes();
+        *vote_grace_period_dst = vote_grace_period.to_le_bytes();
+        *bronze_threshold_dst = bronze_threshold.to_le_bytes();
+        *silver_threshold_dst = silver_threshold.to_le_bytes();
+        *gold_threshold_dst = gold_threshold.to_le_bytes();
+        *fund_target_dst = fund_target.to_le_bytes();
+        moderator_dst.copy_from_slice(moderator.as_ref());
+        *committee_share_bps_dst = committee_share_bps.to_le_bytes();
+        mode_dst[0] = mode as u8;
+        *voter_credits_dst = voter_credits.to_le_bytes();
+        *epoch_length_dst = epoch_length.to_le_bytes();
+        *epoch_count_dst = epoch_count.to_le_bytes();
+        *voting_end_dst = voting_end.to_le_bytes();
+        *tally_period_dst = tally_period.to_le_bytes();
+        stake_pool_dst.copy_from_slice(stake_pool.as_ref());
+        collection_dst.copy_from_slice(collection.as_ref());
+        verified_only_dst[0] = verified_only as u8;
+        *appeal_bond_dst = appeal_bond.to_le_bytes();
+        *dispute_window_dst = dispute_window.to_le_bytes();
+        *claim_period_dst = claim_period.to_le_bytes();
+        *operator_fee_bps_dst = operator_fee_bps.to_le_bytes();
+        *recovery_grace_dst = recovery_grace.to_le_bytes();
+        *max_projects_dst = max_projects.to_le_bytes();
+        *crank_reward_dst = crank_reward.to_le_bytes();
+        *downvote_share_bps_dst = downvote_share_bps.to_le_bytes();
+        *flag_stake_dst = flag_stake.to_le_bytes();
+        *flag_bounty_bps_dst = flag_bounty_bps.to_le_bytes();
+        *vote_cooldown_dst = vote_cooldown.to_le_bytes();
+        *endorsement_bonus_bps_dst = endorsement_bonus_bps.to_le_bytes();
+        *min_projects_dst = min_projects.to_le_bytes();
+        *holdback_bps_dst = holdback_bps.to_le_bytes();
+    }
+}
+
+/// Most area bonus in basis points endorsements can give a project, so they stay a
+/// small signal next to the votes
+pub const MAX_ENDORSEMENT_BONUS_BPS: u64 = 1_000;
+
+/// Most categories a round can have
+pub const MAX_CATEGORIES: usize = 8;
+
+/// Depth of the vote log tree, it takes up to 2^24 entries
+pub const VOTE_LOG_DEPTH: usize = 24;
+
+/// Most projects one owner can register in a round, the index in the project address
+/// is below it
+pub const MAX_PROJECTS_PER_OWNER: u8 = 4;
+
+/// Category, a part of the round fund matched on its own
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Category {
+    /// share of the fund in basis points
+    pub share_bps: u16,
+    /// sum of the area of the category's projects
+    pub area: U256,
+}
+impl Category {
+    /// The category's share of `fund`
+    pub fn share_of(&self, fund: U256) -> U256 {
+        fund.checked_mul(U256::from(self.share_bps))
+            .unwrap()
+            .checked_div(U256::from(10_000))
+            .unwrap()
+    }
+}
+impl Sealed for Category {}
+impl Pack for Category {
+    const LEN: usize = 34;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 34];
+        let (share_bps, area) = array_refs![src, 2, 32];
+        Ok(Category {
+            share_bps: u16::from_le_bytes(*share_bps),
+            area: U256::from_little_endian(area),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 34];
+        let (share_bps_dst, area_dst) = mut_array_refs![dst, 2, 32];
+        let &Category { share_bps, area } = self;
+        *share_bps_dst = share_bps.to_le_bytes();
+        area.to_little_endian(area_dst);
+    }
+}
+
 /// Round
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Round {
     pub status: RoundStatus,
     pub fund: u64,
-    pub fee: u64,
+    pub operator_fee: u64,
     pub vault: Pubkey,
     pub owner: Pubkey,
     pub area: U256,
+    pub batch_nonce: u64,
+    pub config: RoundConfig,
+    pub mint: Pubkey,
+    pub deposits: u64,
+    /// wallets with votes in the round
+    pub voters: u64,
+    /// sum of all votes
+    pub votes: u64,
+    /// pledges waiting in escrow
+    pub pledged: u64,
+    /// part of the fund no project is matched with, sponsors reclaim it pro rata
+    pub unallocated: u64,
+    /// round this one follows, default if none
+    pub previous_round: Pubkey,
+    /// round that follows this one, default if none
+    pub next_round: Pubkey,
+    /// categories set by the owner, 0 means the whole fund is one pool
+    pub category_count: u8,
+    pub categories: [Category; MAX_CATEGORIES],
+    /// committee share allocated so far
+    pub discretionary: u64,
+    /// stake times seconds summed over all projects, in Conviction rounds
+    pub conviction: U256,
+    /// when conviction was last accrued, frozen when voting ends
+    pub conviction_at: i64,
+    /// unix timestamp of StartVoting
+    pub voting_started_at: i64,
+    /// appeals waiting for ResolveAppeal, the round can not be finalized until they are resolved
+    pub pending_appeals: u64,
+    /// unix timestamp of EndRound
+    pub voting_ended_at: i64,
+    /// projects registered in the round
+    pub project_count: u64,
+    /// what projects can still withdraw, set at Finalize
+    pub fund_remaining: u64,
+    /// tokens swept by SweepDust
+    pub swept: u64,
+    /// fee owed to the protocol fee recipient
+    pub protocol_fee: u64,
+    /// Merkle root of every vote, revoke and downvote of the round, see log_vote
+    pub vote_log: [u8; 32],
+    /// entries in the vote log
+    pub vote_log_len: u64,
+    /// flags not resolved yet, the round can not be finalized while there are some
+    pub pending_flags: u64,
+    /// Merkle root of the wallets that may register projects, all zeros lets anyone register
+    pub registration_root: [u8; 32],
+    /// payouts kept in the vault until ReleaseHoldback
+    pub holdbacks: u64,
+    /// projects that withdrew, CloseRound waits for all of them until the claim deadline
+    pub withdrawals: u64,
+    /// part of the unallocated fund sponsors reclaimed, never more than it
+    pub reclaimed: u64,
+    /// left nodes of the vote log waiting for their right sibling, one per height
+    pub vote_log_frontier: [[u8; 32]; VOTE_LOG_DEPTH],
+    /// fund lent out with LendFund, the vault gets it back at EndRound
+    pub lent: u64,
+    /// collateral the lending reserve minted for the fund lent out
+    pub collateral: u64,
+    /// token account of the vault authority holding the collateral
+    pub collateral_account: Pubkey,
+}
+impl Round {
+    /// Epoch of an Epochs round at `now`, counted from the start of voting
+    pub fn epoch_at(&self, now: i64) -> u64 {
+        if self.config.epoch_length <= 0 || now < self.voting_started_at {
+            return 0;
+        }
+        ((now - self.voting_started_at) / self.config.epoch_length) as u64
+    }
+
+    /// Fund and pending pledges in basis points of the fund target, 0 if there is no target
+    pub fn fund_progress_bps(&self) -> u64 {
+        if self.config.fund_target == 0 {
+            return 0;
+        }
+        let raised = self.fund as u128 + self.pledged as u128;
+        (raised * 10_000 / self.config.fund_target as u128) as u64
+    }
+
+    /// Whether the fund and pending pledges reach the fund target
+    pub fn fund_target_reached(&self) -> bool {
+        self.config.fund_target > 0
+            && self.fund as u128 + self.pledged as u128 >= self.config.fund_target as u128
+    }
+
+    /// Adds area to the round and to the category
+    pub fn add_area(&mut self, category: u8, area: U256) {
+        self.area = self.area.checked_add(area).unwrap();
+        if self.category_count > 0 {
+            let category = &mut self.categories[category as usize];
+            category.area = category.area.checked_add(area).unwrap();
+        }
+    }
+
+    /// Takes area out of the round and out of the category
+    pub fn sub_area(&mut self, category: u8, area: U256) {
+        self.area = self.area.checked_sub(area).unwrap();
+        if self.category_count > 0 {
+            let category = &mut self.categories[category as usize];
+            category.area = category.area.checked_sub(area).unwrap();
+        }
+    }
+
+    /// Whether the area of the project counts toward the round, banned projects and
+    /// unattested projects of a `verified_only` round are left out
+    pub fn matches(&self, project: &Project) -> bool {
+        !project.banned && (!self.config.verified_only || project.verified)
+    }
+
+    /// Fees in the vault that are not withdrawn yet
+    pub fn fees(&self) -> u64 {
+        self.operator_fee.checked_add(self.protocol_fee).unwrap()
+    }
+
+    /// What SweepDust and RolloverFunds can take out of a vault holding `vault`: what
+    /// projects did not withdraw, without the fees, the holdbacks and the deposits still owed
+    pub fn residual(&self, vault: u64) -> u64 {
+        self.fund_remaining.min(
+            vault
+                .saturating_sub(self.fees())
+                .saturating_sub(self.holdbacks)
+                .saturating_sub(self.deposits),
+        )
+    }
+
+    /// Part of the fund the owner allocates with AllocateDiscretionary
+    pub fn committee_pool(&self) -> u64 {
+        U256::from(self.fund)
+            .checked_mul(U256::from(self.config.committee_share_bps))
+            .unwrap()
+            .checked_div(U256::from(10_000))
+            .unwrap()
+            .as_u64()
+    }
+
+    /// Fund and area the projects of a category are matched with
+    pub fn matching_pool(&self, category: u8) -> (U256, U256) {
+        let fund = U256::from(self.fund.checked_sub(self.committee_pool()).unwrap());
+        if self.category_count == 0 {
+            return (fund, self.area);
+        }
+        let category = &self.categories[category as usize];
+        (category.share_of(fund), category.area)
+    }
+
+    /// Part of the fund no project can be matched with
+    pub fn unmatched_fund(&self) -> u64 {
+        let committee_pool = self.committee_pool();
+        let unallocated = committee_pool.checked_sub(self.discretionary).unwrap();
+        let fund = U256::from(self.fund.checked_sub(committee_pool).unwrap());
+        let unmatched = if self.category_count == 0 {
+            if self.area.is_zero() {
+                fund
+            } else {
+                U256::zero()
+            }
+        } else {
+            let mut matched = U256::zero();
+            for category in self.categories[..self.category_count as usize].iter() {
+                if !category.area.is_zero() {
+                    matched = matched.checked_add(category.share_of(fund)).unwrap();
+                }
+            }
+            fund.checked_sub(matched).unwrap()
+        };
+        unmatched.as_u64().checked_add(unallocated).unwrap()
+    }
+
+    /// Sets what is left unallocated and what the projects can be paid, as Finalize does
+    /// once the quorum is reached
+    pub fn lock_payouts(&mut self) {
+        // categories nobody voted in cannot be matched, epochs are matched on their own
+        if self.config.mode != RoundMode::Epochs {
+            self.unallocated = self.unmatched_fund();
+        }
+        // payouts are capped at the fund and the votes still in the vault
+        let votes = if self.config.mode.votes_are_tokens() {
+            self.votes
+        } else {
+            0
+        };
+        self.fund_remaining = self
+            .fund
+            .checked_sub(self.unallocated)
+            .unwrap()
+            .checked_add(votes)
+            .unwrap();
+    }
 }
 impl Sealed for Round {}
 impl IsInitialized for Round {
@@ -39,39 +633,360 @@ impl IsInitialized for Round {
         self.status != RoundStatus::Uninitialized
     }
 }
+impl Discriminator for Round {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [87, 127, 165, 51, 73, 78, 116, 1];
+}
 impl Pack for Round {
-    const LEN: usize = 113;
+    const LEN: usize = 1888;
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let src = array_ref![src, 0, 113];
-        let (status, fund, fee, vault, owner, area) = array_refs![src, 1, 8, 8, 32, 32, 32];
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 1880];
+        let (
+            status,
+            fund,
+            operator_fee,
+            vault,
+            owner,
+            area,
+            batch_nonce,
+            config,
+            mint,
+            deposits,
+            voters,
+            votes,
+            pledged,
+            unallocated,
+            previous_round,
+            next_round,
+            category_count,
+            categories_src,
+            discretionary,
+            conviction,
+            conviction_at,
+            voting_started_at,
+            pending_appeals,
+            voting_ended_at,
+            project_count,
+            fund_remaining,
+            swept,
+            protocol_fee,
+            vote_log,
+            vote_log_len,
+            pending_flags,
+            registration_root,
+            holdbacks,
+            withdrawals,
+            reclaimed,
+            vote_log_frontier_src,
+            lent,
+            collateral,
+            collateral_account,
+        ) = array_refs![
+            src,
+            1,
+            8,
+            8,
+            32,
+            32,
+            32,
+            8,
+            RoundConfig::LEN,
+            32,
+            8,
+            8,
+            8,
+            8,
+            8,
+            32,
+            32,
+            1,
+            Category::LEN * MAX_CATEGORIES,
+            8,
+            32,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            32,
+            8,
+            8,
+            32,
+            8,
+            8,
+            8,
+            32 * VOTE_LOG_DEPTH,
+            8,
+            8,
+            32
+        ];
+        let mut vote_log_frontier = [[0; 32]; VOTE_LOG_DEPTH];
+        for (node, src) in vote_log_frontier
+            .iter_mut()
+            .zip(vote_log_frontier_src.chunks(32))
+        {
+            node.copy_from_slice(src);
+        }
+        let mut categories = [Category::default(); MAX_CATEGORIES];
+        for (category, src) in categories
+            .iter_mut()
+            .zip(categories_src.chunks(Category::LEN))
+        {
+            *category = Category::unpack_from_slice(src)?;
+        }
         Ok(Round {
             status: RoundStatus::try_from_primitive(status[0])
                 .or(Err(ProgramError::InvalidAccountData))?,
             fund: u64::from_le_bytes(*fund),
-            fee: u64::from_le_bytes(*fee),
+            operator_fee: u64::from_le_bytes(*operator_fee),
             vault: Pubkey::new_from_array(*vault),
             owner: Pubkey::new_from_array(*owner),
             area: U256::from_little_endian(area),
+            batch_nonce: u64::from_le_bytes(*batch_nonce),
+            config: RoundConfig::unpack_from_slice(config)?,
+            mint: Pubkey::new_from_array(*mint),
+            deposits: u64::from_le_bytes(*deposits),
+            voters: u64::from_le_bytes(*voters),
+            votes: u64::from_le_bytes(*votes),
+            pledged: u64::from_le_bytes(*pledged),
+            unallocated: u64::from_le_bytes(*unallocated),
+            previous_round: Pubkey::new_from_array(*previous_round),
+            next_round: Pubkey::new_from_array(*next_round),
+            category_count: category_count[0],
+            categories,
+            discretionary: u64::from_le_bytes(*discretionary),
+            conviction: U256::from_little_endian(conviction),
+            conviction_at: i64::from_le_bytes(*conviction_at),
+            voting_started_at: i64::from_le_bytes(*voting_started_at),
+            pending_appeals: u64::from_le_bytes(*pending_appeals),
+            voting_ended_at: i64::from_le_bytes(*voting_ended_at),
+            project_count: u64::from_le_bytes(*project_count),
+            fund_remaining: u64::from_le_bytes(*fund_remaining),
+            swept: u64::from_le_bytes(*swept),
+            protocol_fee: u64::from_le_bytes(*protocol_fee),
+            vote_log: *vote_log,
+            vote_log_len: u64::from_le_bytes(*vote_log_len),
+            pending_flags: u64::from_le_bytes(*pending_flags),
+            registration_root: *registration_root,
+            holdbacks: u64::from_le_bytes(*holdbacks),
+            withdrawals: u64::from_le_bytes(*withdrawals),
+            reclaimed: u64::from_le_bytes(*reclaimed),
+            vote_log_frontier,
+            lent: u64::from_le_bytes(*lent),
+            collateral: u64::from_le_bytes(*collateral),
+            collateral_account: Pubkey::new_from_array(*collateral_account),
         })
     }
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let dst = array_mut_ref![dst, 0, 113];
-        let (status_dst, fund_dst, fee_dst, vault_dst, owner_dst, area_dst) =
-            mut_array_refs![dst, 1, 8, 8, 32, 32, 32];
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 1880];
+        let (
+            status_dst,
+            fund_dst,
+            operator_fee_dst,
+            vault_dst,
+            owner_dst,
+            area_dst,
+            batch_nonce_dst,
+            config_dst,
+            mint_dst,
+            deposits_dst,
+            voters_dst,
+            votes_dst,
+            pledged_dst,
+            unallocated_dst,
+            previous_round_dst,
+            next_round_dst,
+            category_count_dst,
+            categories_dst,
+            discretionary_dst,
+            conviction_dst,
+            conviction_at_dst,
+            voting_started_at_dst,
+            pending_appeals_dst,
+            voting_ended_at_dst,
+            project_count_dst,
+            fund_remaining_dst,
+            swept_dst,
+            protocol_fee_dst,
+            vote_log_dst,
+            vote_log_len_dst,
+            pending_flags_dst,
+            registration_root_dst,
+            holdbacks_dst,
+            withdrawals_dst,
+            reclaimed_dst,
+            vote_log_frontier_dst,
+            lent_dst,
+            collateral_dst,
+            collateral_account_dst,
+        ) = mut_array_refs![
+            dst,
+            1,
+            8,
+            8,
+            32,
+            32,
+            32,
+            8,
+            RoundConfig::LEN,
+            32,
+            8,
+            8,
+            8,
+            8,
+            8,
+            32,
+            32,
+            1,
+            Category::LEN * MAX_CATEGORIES,
+            8,
+            32,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            32,
+            8,
+            8,
+            32,
+            8,
+            8,
+            8,
+            32 * VOTE_LOG_DEPTH,
+            8,
+            8,
+            32
+        ];
         let &Round {
             status,
             fund,
-            fee,
+            operator_fee,
             ref owner,
             ref vault,
             area,
+            batch_nonce,
+            ref config,
+            ref mint,
+            deposits,
+            voters,
+            votes,
+            pledged,
+            unallocated,
+            ref previous_round,
+            ref next_round,
+            category_count,
+            ref categories,
+            discretionary,
+            conviction,
+            conviction_at,
+            voting_started_at,
+            pending_appeals,
+            voting_ended_at,
+            project_count,
+            fund_remaining,
+            swept,
+            protocol_fee,
+            ref vote_log,
+            vote_log_len,
+            pending_flags,
+            ref registration_root,
+            holdbacks,
+            withdrawals,
+            reclaimed,
+            ref vote_log_frontier,
+            lent,
+            collateral,
+            ref collateral_account,
         } = self;
         status_dst[0] = status as u8;
         *fund_dst = fund.to_le_bytes();
-        *fee_dst = fee.to_le_bytes();
+        *operator_fee_dst = operator_fee.to_le_bytes();
         owner_dst.copy_from_slice(owner.as_ref());
         vault_dst.copy_from_slice(vault.as_ref());
         area.to_little_endian(area_dst);
+        *batch_nonce_dst = batch_nonce.to_le_bytes();
+        config.pack_into_slice(config_dst);
+        mint_dst.copy_from_slice(mint.as_ref());
+        *deposits_dst = deposits.to_le_bytes();
+        *voters_dst = voters.to_le_bytes();
+        *votes_dst = votes.to_le_bytes();
+        *pledged_dst = pledged.to_le_bytes();
+        *unallocated_dst = unallocated.to_le_bytes();
+        previous_round_dst.copy_from_slice(previous_round.as_ref());
+        next_round_dst.copy_from_slice(next_round.as_ref());
+        category_count_dst[0] = category_count;
+        for (category, dst) in categories
+            .iter()
+            .zip(categories_dst.chunks_mut(Category::LEN))
+        {
+            category.pack_into_slice(dst);
+        }
+        *discretionary_dst = discretionary.to_le_bytes();
+        conviction.to_little_endian(conviction_dst);
+        *conviction_at_dst = conviction_at.to_le_bytes();
+        *voting_started_at_dst = voting_started_at.to_le_bytes();
+        *pending_appeals_dst = pending_appeals.to_le_bytes();
+        *voting_ended_at_dst = voting_ended_at.to_le_bytes();
+        *project_count_dst = project_count.to_le_bytes();
+        *fund_remaining_dst = fund_remaining.to_le_bytes();
+        *swept_dst = swept.to_le_bytes();
+        *protocol_fee_dst = protocol_fee.to_le_bytes();
+        vote_log_dst.copy_from_slice(vote_log);
+        *vote_log_len_dst = vote_log_len.to_le_bytes();
+        *pending_flags_dst = pending_flags.to_le_bytes();
+        registration_root_dst.copy_from_slice(registration_root);
+        *holdbacks_dst = holdbacks.to_le_bytes();
+        *withdrawals_dst = withdrawals.to_le_bytes();
+        *reclaimed_dst = reclaimed.to_le_bytes();
+        for (node, dst) in vote_log_frontier
+            .iter()
+            .zip(vote_log_frontier_dst.chunks_mut(32))
+        {
+            dst.copy_from_slice(node);
+        }
+        *lent_dst = lent.to_le_bytes();
+        *collateral_dst = collateral.to_le_bytes();
+        collateral_account_dst.copy_from_slice(collateral_account.as_ref());
+    }
+}
+
+/// Most donation tiers a project can define
+pub const MAX_TIERS: usize = 4;
+
+/// Donation tier of a project, the perks it promises the voters whose votes reach it
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tier {
+    /// votes of a voter that reach the tier
+    pub threshold: u64,
+    /// perks of the tier, their meaning is up to the project
+    pub perks: u32,
+}
+impl Sealed for Tier {}
+impl Pack for Tier {
+    const LEN: usize = 12;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 12];
+        let (threshold, perks) = array_refs![src, 8, 4];
+        Ok(Tier {
+            threshold: u64::from_le_bytes(*threshold),
+            perks: u32::from_le_bytes(*perks),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 12];
+        let (threshold_dst, perks_dst) = mut_array_refs![dst, 8, 4];
+        let &Tier { threshold, perks } = self;
+        *threshold_dst = threshold.to_le_bytes();
+        *perks_dst = perks.to_le_bytes();
     }
 }
 
@@ -85,6 +1000,42 @@ pub struct Project {
     pub votes: u64,
     pub area: U256,
     pub area_sqrt: U256,
+    pub payout_wallet: Pubkey,
+    pub deposit: u64,
+    pub banned: bool,
+    /// category the project is matched in
+    pub category: u8,
+    /// committee share allocated to the project
+    pub discretionary: u64,
+    /// stake times seconds, in Conviction rounds
+    pub conviction: U256,
+    /// when conviction was last accrued
+    pub conviction_at: i64,
+    /// Metaplex metadata account bound at registration, default for none
+    pub metadata: Pubkey,
+    /// a curator of the round attested the project
+    pub verified: bool,
+    /// area the project had when it was banned
+    pub banned_area: U256,
+    /// registration deposit forfeited by the ban
+    pub banned_deposit: u64,
+    pub appeal: AppealStatus,
+    /// bond of a pending appeal
+    pub appeal_bond: u64,
+    /// tokens spent downvoting the project
+    pub downvotes: u64,
+    /// sum of the square roots of the downvotes of each voter
+    pub down_sqrt: U256,
+    /// donation tiers set by the owner, in increasing thresholds, 0 ends the list
+    pub tiers: [Tier; MAX_TIERS],
+    /// updates the owner posted, the index of the next one
+    pub updates: u64,
+    /// voters that endorsed the project
+    pub endorsements: u64,
+    /// part of the payout kept in the vault until the round owner releases it
+    pub holdback: u64,
+    /// position of the project in the round, in the order projects registered
+    pub index: u64,
 }
 impl Sealed for Project {}
 impl IsInitialized for Project {
@@ -92,12 +1043,83 @@ impl IsInitialized for Project {
         self.round != Pubkey::default()
     }
 }
+impl Discriminator for Project {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [205, 168, 189, 202, 181, 247, 142, 1];
+}
+impl Project {
+    /// Highest tier whose threshold `votes` reach, counted from 1, 0 for none
+    pub fn tier_of(&self, votes: u64) -> u8 {
+        self.tiers
+            .iter()
+            .take_while(|tier| tier.threshold > 0 && votes >= tier.threshold)
+            .count() as u8
+    }
+}
 impl Pack for Project {
-    const LEN: usize = 137;
+    const LEN: usize = 437;
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let src = array_ref![src, 0, 137];
-        let (round, owner, withdraw, votes, area, area_sqrt) =
-            array_refs![src, 32, 32, 1, 8, 32, 32];
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 429];
+        let (
+            round,
+            owner,
+            withdraw,
+            votes,
+            area,
+            area_sqrt,
+            payout_wallet,
+            deposit,
+            banned,
+            category,
+            discretionary,
+            conviction,
+            conviction_at,
+            metadata,
+            verified,
+            banned_area,
+            banned_deposit,
+            appeal,
+            appeal_bond,
+            downvotes,
+            down_sqrt,
+            tiers_src,
+            updates,
+            endorsements,
+            holdback,
+            index,
+        ) = array_refs![
+            src,
+            32,
+            32,
+            1,
+            8,
+            32,
+            32,
+            32,
+            8,
+            1,
+            1,
+            8,
+            32,
+            8,
+            32,
+            1,
+            32,
+            8,
+            1,
+            8,
+            8,
+            32,
+            Tier::LEN * MAX_TIERS,
+            8,
+            8,
+            8,
+            8
+        ];
+        let mut tiers = [Tier::default(); MAX_TIERS];
+        for (tier, src) in tiers.iter_mut().zip(tiers_src.chunks(Tier::LEN)) {
+            *tier = Tier::unpack_from_slice(src)?;
+        }
         Ok(Project {
             round: Pubkey::new_from_array(*round),
             owner: Pubkey::new_from_array(*owner),
@@ -109,12 +1131,96 @@ impl Pack for Project {
             votes: u64::from_le_bytes(*votes),
             area: U256::from_little_endian(area),
             area_sqrt: U256::from_little_endian(area_sqrt),
+            payout_wallet: Pubkey::new_from_array(*payout_wallet),
+            deposit: u64::from_le_bytes(*deposit),
+            banned: match banned {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            category: category[0],
+            discretionary: u64::from_le_bytes(*discretionary),
+            conviction: U256::from_little_endian(conviction),
+            conviction_at: i64::from_le_bytes(*conviction_at),
+            metadata: Pubkey::new_from_array(*metadata),
+            verified: match verified {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            banned_area: U256::from_little_endian(banned_area),
+            banned_deposit: u64::from_le_bytes(*banned_deposit),
+            appeal: AppealStatus::try_from_primitive(appeal[0])
+                .or(Err(ProgramError::InvalidAccountData))?,
+            appeal_bond: u64::from_le_bytes(*appeal_bond),
+            downvotes: u64::from_le_bytes(*downvotes),
+            down_sqrt: U256::from_little_endian(down_sqrt),
+            tiers,
+            updates: u64::from_le_bytes(*updates),
+            endorsements: u64::from_le_bytes(*endorsements),
+            holdback: u64::from_le_bytes(*holdback),
+            index: u64::from_le_bytes(*index),
         })
     }
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let dst = array_mut_ref![dst, 0, 137];
-        let (round_dst, owner_dst, withdraw_dst, votes_dst, area_dst, area_sqrt_dst) =
-            mut_array_refs![dst, 32, 32, 1, 8, 32, 32];
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 429];
+        let (
+            round_dst,
+            owner_dst,
+            withdraw_dst,
+            votes_dst,
+            area_dst,
+            area_sqrt_dst,
+            payout_wallet_dst,
+            deposit_dst,
+            banned_dst,
+            category_dst,
+            discretionary_dst,
+            conviction_dst,
+            conviction_at_dst,
+            metadata_dst,
+            verified_dst,
+            banned_area_dst,
+            banned_deposit_dst,
+            appeal_dst,
+            appeal_bond_dst,
+            downvotes_dst,
+            down_sqrt_dst,
+            tiers_dst,
+            updates_dst,
+            endorsements_dst,
+            holdback_dst,
+            index_dst,
+        ) = mut_array_refs![
+            dst,
+            32,
+            32,
+            1,
+            8,
+            32,
+            32,
+            32,
+            8,
+            1,
+            1,
+            8,
+            32,
+            8,
+            32,
+            1,
+            32,
+            8,
+            1,
+            8,
+            8,
+            32,
+            Tier::LEN * MAX_TIERS,
+            8,
+            8,
+            8,
+            8
+        ];
         let &Project {
             ref round,
             ref owner,
@@ -122,6 +1228,26 @@ impl Pack for Project {
             votes,
             area,
             area_sqrt,
+            ref payout_wallet,
+            deposit,
+            banned,
+            category,
+            discretionary,
+            conviction,
+            conviction_at,
+            ref metadata,
+            verified,
+            banned_area,
+            banned_deposit,
+            appeal,
+            appeal_bond,
+            downvotes,
+            down_sqrt,
+            ref tiers,
+            updates,
+            endorsements,
+            holdback,
+            index,
         } = self;
         round_dst.copy_from_slice(round.as_ref());
         owner_dst.copy_from_slice(owner.as_ref());
@@ -129,6 +1255,28 @@ impl Pack for Project {
         *votes_dst = votes.to_le_bytes();
         area.to_little_endian(area_dst);
         area_sqrt.to_little_endian(area_sqrt_dst);
+        payout_wallet_dst.copy_from_slice(payout_wallet.as_ref());
+        *deposit_dst = deposit.to_le_bytes();
+        banned_dst[0] = banned as u8;
+        category_dst[0] = category;
+        *discretionary_dst = discretionary.to_le_bytes();
+        conviction.to_little_endian(conviction_dst);
+        *conviction_at_dst = conviction_at.to_le_bytes();
+        metadata_dst.copy_from_slice(metadata.as_ref());
+        verified_dst[0] = verified as u8;
+        banned_area.to_little_endian(banned_area_dst);
+        *banned_deposit_dst = banned_deposit.to_le_bytes();
+        appeal_dst[0] = appeal as u8;
+        *appeal_bond_dst = appeal_bond.to_le_bytes();
+        *downvotes_dst = downvotes.to_le_bytes();
+        down_sqrt.to_little_endian(down_sqrt_dst);
+        for (tier, dst) in tiers.iter().zip(tiers_dst.chunks_mut(Tier::LEN)) {
+            tier.pack_into_slice(dst);
+        }
+        *updates_dst = updates.to_le_bytes();
+        *endorsements_dst = endorsements.to_le_bytes();
+        *holdback_dst = holdback.to_le_bytes();
+        *index_dst = index.to_le_bytes();
     }
 }
 
@@ -139,6 +1287,29 @@ pub struct Voter {
     pub is_initialized: bool,
     pub votes: u64,
     pub votes_sqrt: U256,
+    pub permit_nonce: u64,
+    /// amount of the last vote, 0 once it was revoked
+    pub last_vote_amount: u64,
+    /// unix timestamp of the last vote
+    pub last_vote_at: i64,
+    /// the voter claimed its share of the round airdrop
+    pub airdrop_claimed: bool,
+    /// votes in the value the sqrt math uses, the lamports of stake pool tokens when they were voted
+    pub weight: u64,
+    /// the round owner or moderator blacklisted the voter, it can not vote anymore
+    pub blacklisted: bool,
+    /// tokens the voter spent downvoting the project
+    pub downvotes: u64,
+    /// slot of the last vote, for the round's vote cooldown
+    pub last_vote_slot: u64,
+    /// highest donation tier of the project the votes reached, 0 for none
+    pub tier: u8,
+    /// the voter endorsed the project
+    pub endorsed: bool,
+    /// custodian that paid the votes in a batch and gets them back on a refund
+    pub custodian: Pubkey,
+    /// weight the last vote was credited with, 0 once it was revoked
+    pub last_vote_weight: u64,
 }
 impl Sealed for Voter {}
 impl IsInitialized for Voter {
@@ -146,11 +1317,31 @@ impl IsInitialized for Voter {
         self.is_initialized
     }
 }
+impl Discriminator for Voter {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [241, 93, 35, 191, 254, 147, 17, 1];
+}
 impl Pack for Voter {
-    const LEN: usize = 41;
+    const LEN: usize = 141;
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let src = array_ref![src, 0, 41];
-        let (is_initialized, votes, votes_sqrt) = array_refs![src, 1, 8, 32];
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 133];
+        let (
+            is_initialized,
+            votes,
+            votes_sqrt,
+            permit_nonce,
+            last_vote_amount,
+            last_vote_at,
+            airdrop_claimed,
+            weight,
+            blacklisted,
+            downvotes,
+            last_vote_slot,
+            tier,
+            endorsed,
+            custodian,
+            last_vote_weight,
+        ) = array_refs![src, 1, 8, 32, 8, 8, 8, 1, 8, 1, 8, 8, 1, 1, 32, 8];
         Ok(Voter {
             is_initialized: match is_initialized {
                 [0] => false,
@@ -159,19 +1350,1531 @@ impl Pack for Voter {
             },
             votes: u64::from_le_bytes(*votes),
             votes_sqrt: U256::from_little_endian(votes_sqrt),
+            permit_nonce: u64::from_le_bytes(*permit_nonce),
+            last_vote_amount: u64::from_le_bytes(*last_vote_amount),
+            last_vote_at: i64::from_le_bytes(*last_vote_at),
+            airdrop_claimed: match airdrop_claimed {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            weight: u64::from_le_bytes(*weight),
+            blacklisted: match blacklisted {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            downvotes: u64::from_le_bytes(*downvotes),
+            last_vote_slot: u64::from_le_bytes(*last_vote_slot),
+            tier: tier[0],
+            endorsed: match endorsed {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            custodian: Pubkey::new_from_array(*custodian),
+            last_vote_weight: u64::from_le_bytes(*last_vote_weight),
         })
     }
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let dst = array_mut_ref![dst, 0, 41];
-        let (is_initialized_dst, votes_dst, votes_sqrt_dst) = mut_array_refs![dst, 1, 8, 32];
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 133];
+        let (
+            is_initialized_dst,
+            votes_dst,
+            votes_sqrt_dst,
+            permit_nonce_dst,
+            last_vote_amount_dst,
+            last_vote_at_dst,
+            airdrop_claimed_dst,
+            weight_dst,
+            blacklisted_dst,
+            downvotes_dst,
+            last_vote_slot_dst,
+            tier_dst,
+            endorsed_dst,
+            custodian_dst,
+            last_vote_weight_dst,
+        ) = mut_array_refs![dst, 1, 8, 32, 8, 8, 8, 1, 8, 1, 8, 8, 1, 1, 32, 8];
         let &Voter {
             is_initialized,
             votes,
             votes_sqrt,
+            permit_nonce,
+            last_vote_amount,
+            last_vote_at,
+            airdrop_claimed,
+            weight,
+            blacklisted,
+            downvotes,
+            last_vote_slot,
+            tier,
+            endorsed,
+            ref custodian,
+            last_vote_weight,
         } = self;
         is_initialized_dst[0] = is_initialized as u8;
         *votes_dst = votes.to_le_bytes();
         votes_sqrt.to_little_endian(votes_sqrt_dst);
+        *permit_nonce_dst = permit_nonce.to_le_bytes();
+        *last_vote_amount_dst = last_vote_amount.to_le_bytes();
+        *last_vote_at_dst = last_vote_at.to_le_bytes();
+        airdrop_claimed_dst[0] = airdrop_claimed as u8;
+        *weight_dst = weight.to_le_bytes();
+        blacklisted_dst[0] = blacklisted as u8;
+        *downvotes_dst = downvotes.to_le_bytes();
+        *last_vote_slot_dst = last_vote_slot.to_le_bytes();
+        tier_dst[0] = tier;
+        endorsed_dst[0] = endorsed as u8;
+        custodian_dst.copy_from_slice(custodian.as_ref());
+        *last_vote_weight_dst = last_vote_weight.to_le_bytes();
+    }
+}
+
+/// Session, lets an ephemeral key vote with a wallet's tokens
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Session {
+    pub is_initialized: bool,
+    pub authority: Pubkey,
+    pub session_key: Pubkey,
+    pub source: Pubkey,
+    pub max_amount: u64,
+    pub spent: u64,
+    pub expiry: i64,
+}
+impl Sealed for Session {}
+impl IsInitialized for Session {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+impl Discriminator for Session {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [243, 81, 72, 115, 214, 188, 72, 1];
+}
+impl Pack for Session {
+    const LEN: usize = 129;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 121];
+        let (is_initialized, authority, session_key, source, max_amount, spent, expiry) =
+            array_refs![src, 1, 32, 32, 32, 8, 8, 8];
+        Ok(Session {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            authority: Pubkey::new_from_array(*authority),
+            session_key: Pubkey::new_from_array(*session_key),
+            source: Pubkey::new_from_array(*source),
+            max_amount: u64::from_le_bytes(*max_amount),
+            spent: u64::from_le_bytes(*spent),
+            expiry: i64::from_le_bytes(*expiry),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 121];
+        let (
+            is_initialized_dst,
+            authority_dst,
+            session_key_dst,
+            source_dst,
+            max_amount_dst,
+            spent_dst,
+            expiry_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 32, 8, 8, 8];
+        let &Session {
+            is_initialized,
+            ref authority,
+            ref session_key,
+            ref source,
+            max_amount,
+            spent,
+            expiry,
+        } = self;
+        is_initialized_dst[0] = is_initialized as u8;
+        authority_dst.copy_from_slice(authority.as_ref());
+        session_key_dst.copy_from_slice(session_key.as_ref());
+        source_dst.copy_from_slice(source.as_ref());
+        *max_amount_dst = max_amount.to_le_bytes();
+        *spent_dst = spent.to_le_bytes();
+        *expiry_dst = expiry.to_le_bytes();
+    }
+}
+
+/// Delegation, lets a delegate vote with a delegator's budget in a round
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Delegation {
+    pub round: Pubkey,
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub source: Pubkey,
+    pub budget: u64,
+    pub spent: u64,
+}
+impl Sealed for Delegation {}
+impl IsInitialized for Delegation {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for Delegation {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [237, 90, 140, 159, 124, 255, 243, 1];
+}
+impl Pack for Delegation {
+    const LEN: usize = 152;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 144];
+        let (round, delegator, delegate, source, budget, spent) =
+            array_refs![src, 32, 32, 32, 32, 8, 8];
+        Ok(Delegation {
+            round: Pubkey::new_from_array(*round),
+            delegator: Pubkey::new_from_array(*delegator),
+            delegate: Pubkey::new_from_array(*delegate),
+            source: Pubkey::new_from_array(*source),
+            budget: u64::from_le_bytes(*budget),
+            spent: u64::from_le_bytes(*spent),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 144];
+        let (round_dst, delegator_dst, delegate_dst, source_dst, budget_dst, spent_dst) =
+            mut_array_refs![dst, 32, 32, 32, 32, 8, 8];
+        let &Delegation {
+            ref round,
+            ref delegator,
+            ref delegate,
+            ref source,
+            budget,
+            spent,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        delegator_dst.copy_from_slice(delegator.as_ref());
+        delegate_dst.copy_from_slice(delegate.as_ref());
+        source_dst.copy_from_slice(source.as_ref());
+        *budget_dst = budget.to_le_bytes();
+        *spent_dst = spent.to_le_bytes();
+    }
+}
+
+/// Sponsor tier, from the thresholds in the round config
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, TryFromPrimitive)]
+pub enum SponsorTier {
+    None,
+    Bronze,
+    Silver,
+    Gold,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for SponsorTier {
+    fn default() -> Self {
+        SponsorTier::None
+    }
+}
+
+/// Appeal of a banned project
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+pub enum AppealStatus {
+    None,
+    Pending,
+    /// the ban stands and the bond went to the fund
+    Upheld,
+    /// the ban was lifted and the bond is paid back on Withdraw
+    Overturned,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for AppealStatus {
+    fn default() -> Self {
+        AppealStatus::None
+    }
+}
+
+impl RoundConfig {
+    /// The token vote instructions count the voted tokens as they are, not in a
+    /// Retro or Epochs round and not when votes are normalized by a stake pool
+    pub fn raw_token_votes(&self) -> bool {
+        matches!(self.mode, RoundMode::Standard | RoundMode::Conviction)
+            && self.stake_pool == Pubkey::default()
+    }
+
+    /// Highest tier whose threshold the contribution reaches
+    pub fn sponsor_tier(&self, contributed: u64) -> SponsorTier {
+        if self.gold_threshold > 0 && contributed >= self.gold_threshold {
+            SponsorTier::Gold
+        } else if self.silver_threshold > 0 && contributed >= self.silver_threshold {
+            SponsorTier::Silver
+        } else if self.bronze_threshold > 0 && contributed >= self.bronze_threshold {
+            SponsorTier::Bronze
+        } else {
+            SponsorTier::None
+        }
+    }
+
+    /// Area bonus in basis points of a project with `endorsements`
+    pub fn endorsement_bonus_bps(&self, endorsements: u64) -> u64 {
+        (self.endorsement_bonus_bps as u64)
+            .saturating_mul(endorsements)
+            .min(MAX_ENDORSEMENT_BONUS_BPS)
+    }
+}
+
+/// Sponsor, what a token account donated to a round
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sponsor {
+    pub round: Pubkey,
+    pub source: Pubkey,
+    /// authority of the source token account at the first donation
+    pub authority: Pubkey,
+    pub contributed: u64,
+    /// unix timestamp of the first donation
+    pub first_donated_at: i64,
+    /// unix timestamp of the last donation
+    pub last_donated_at: i64,
+    /// the share of the unallocated fund was reclaimed
+    pub reclaimed: bool,
+    /// tier reached with the contribution
+    pub tier: SponsorTier,
+}
+impl Sealed for Sponsor {}
+impl IsInitialized for Sponsor {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for Sponsor {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [19, 128, 115, 109, 118, 109, 66, 1];
+}
+impl Pack for Sponsor {
+    const LEN: usize = 130;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 122];
+        let (
+            round,
+            source,
+            authority,
+            contributed,
+            first_donated_at,
+            last_donated_at,
+            reclaimed,
+            tier,
+        ) = array_refs![src, 32, 32, 32, 8, 8, 8, 1, 1];
+        Ok(Sponsor {
+            round: Pubkey::new_from_array(*round),
+            source: Pubkey::new_from_array(*source),
+            authority: Pubkey::new_from_array(*authority),
+            contributed: u64::from_le_bytes(*contributed),
+            first_donated_at: i64::from_le_bytes(*first_donated_at),
+            last_donated_at: i64::from_le_bytes(*last_donated_at),
+            reclaimed: match reclaimed {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            tier: SponsorTier::try_from_primitive(tier[0])
+                .or(Err(ProgramError::InvalidAccountData))?,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 122];
+        let (
+            round_dst,
+            source_dst,
+            authority_dst,
+            contributed_dst,
+            first_donated_at_dst,
+            last_donated_at_dst,
+            reclaimed_dst,
+            tier_dst,
+        ) = mut_array_refs![dst, 32, 32, 32, 8, 8, 8, 1, 1];
+        let &Sponsor {
+            ref round,
+            ref source,
+            ref authority,
+            contributed,
+            first_donated_at,
+            last_donated_at,
+            reclaimed,
+            tier,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        source_dst.copy_from_slice(source.as_ref());
+        authority_dst.copy_from_slice(authority.as_ref());
+        *contributed_dst = contributed.to_le_bytes();
+        *first_donated_at_dst = first_donated_at.to_le_bytes();
+        *last_donated_at_dst = last_donated_at.to_le_bytes();
+        reclaimed_dst[0] = reclaimed as u8;
+        tier_dst[0] = tier as u8;
+    }
+}
+
+/// Pledge, tokens held in escrow until the round reaches its minimum fund
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pledge {
+    pub round: Pubkey,
+    /// token account the pledge came from and is reclaimed to
+    pub source: Pubkey,
+    /// token account owned by the pledge account
+    pub escrow: Pubkey,
+    pub amount: u64,
+    /// the amount was moved into the round vault
+    pub executed: bool,
+}
+impl Sealed for Pledge {}
+impl IsInitialized for Pledge {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for Pledge {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [161, 197, 121, 46, 99, 75, 169, 1];
+}
+impl Pack for Pledge {
+    const LEN: usize = 113;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 105];
+        let (round, source, escrow, amount, executed) = array_refs![src, 32, 32, 32, 8, 1];
+        Ok(Pledge {
+            round: Pubkey::new_from_array(*round),
+            source: Pubkey::new_from_array(*source),
+            escrow: Pubkey::new_from_array(*escrow),
+            amount: u64::from_le_bytes(*amount),
+            executed: match executed {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 105];
+        let (round_dst, source_dst, escrow_dst, amount_dst, executed_dst) =
+            mut_array_refs![dst, 32, 32, 32, 8, 1];
+        let &Pledge {
+            ref round,
+            ref source,
+            ref escrow,
+            amount,
+            executed,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        source_dst.copy_from_slice(source.as_ref());
+        escrow_dst.copy_from_slice(escrow.as_ref());
+        *amount_dst = amount.to_le_bytes();
+        executed_dst[0] = executed as u8;
+    }
+}
+
+/// Subscription, tokens in escrow a cranker turns into periodic votes for a project
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Subscription {
+    pub project: Pubkey,
+    /// token account the votes come from and the rest is cancelled to
+    pub source: Pubkey,
+    /// token account owned by the subscription account
+    pub escrow: Pubkey,
+    /// owner of the source, the votes are counted for it and it can cancel the subscription
+    pub authority: Pubkey,
+    /// the only key allowed to execute the votes
+    pub cranker: Pubkey,
+    /// votes cast every interval
+    pub amount: u64,
+    /// seconds between two votes
+    pub interval: i64,
+    pub next_vote_at: i64,
+    /// tokens left in escrow
+    pub remaining: u64,
+}
+impl Sealed for Subscription {}
+impl IsInitialized for Subscription {
+    fn is_initialized(&self) -> bool {
+        self.project != Pubkey::default()
+    }
+}
+impl Discriminator for Subscription {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [64, 7, 26, 135, 102, 132, 98, 1];
+}
+impl Pack for Subscription {
+    const LEN: usize = 200;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 192];
+        let (
+            project,
+            source,
+            escrow,
+            authority,
+            cranker,
+            amount,
+            interval,
+            next_vote_at,
+            remaining,
+        ) = array_refs![src, 32, 32, 32, 32, 32, 8, 8, 8, 8];
+        Ok(Subscription {
+            project: Pubkey::new_from_array(*project),
+            source: Pubkey::new_from_array(*source),
+            escrow: Pubkey::new_from_array(*escrow),
+            authority: Pubkey::new_from_array(*authority),
+            cranker: Pubkey::new_from_array(*cranker),
+            amount: u64::from_le_bytes(*amount),
+            interval: i64::from_le_bytes(*interval),
+            next_vote_at: i64::from_le_bytes(*next_vote_at),
+            remaining: u64::from_le_bytes(*remaining),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 192];
+        let (
+            project_dst,
+            source_dst,
+            escrow_dst,
+            authority_dst,
+            cranker_dst,
+            amount_dst,
+            interval_dst,
+            next_vote_at_dst,
+            remaining_dst,
+        ) = mut_array_refs![dst, 32, 32, 32, 32, 32, 8, 8, 8, 8];
+        let &Subscription {
+            ref project,
+            ref source,
+            ref escrow,
+            ref authority,
+            ref cranker,
+            amount,
+            interval,
+            next_vote_at,
+            remaining,
+        } = self;
+        project_dst.copy_from_slice(project.as_ref());
+        source_dst.copy_from_slice(source.as_ref());
+        escrow_dst.copy_from_slice(escrow.as_ref());
+        authority_dst.copy_from_slice(authority.as_ref());
+        cranker_dst.copy_from_slice(cranker.as_ref());
+        *amount_dst = amount.to_le_bytes();
+        *interval_dst = interval.to_le_bytes();
+        *next_vote_at_dst = next_vote_at.to_le_bytes();
+        *remaining_dst = remaining.to_le_bytes();
+    }
+}
+
+/// Round template, a config an owner can start rounds from
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoundTemplate {
+    pub owner: Pubkey,
+    pub id: u64,
+    pub config: RoundConfig,
+}
+impl Sealed for RoundTemplate {}
+impl IsInitialized for RoundTemplate {
+    fn is_initialized(&self) -> bool {
+        self.owner != Pubkey::default()
+    }
+}
+impl Discriminator for RoundTemplate {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [195, 173, 75, 238, 3, 118, 66, 1];
+}
+impl Pack for RoundTemplate {
+    const LEN: usize = 374;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 366];
+        let (owner, id, config) = array_refs![src, 32, 8, RoundConfig::LEN];
+        Ok(RoundTemplate {
+            owner: Pubkey::new_from_array(*owner),
+            id: u64::from_le_bytes(*id),
+            config: RoundConfig::unpack_from_slice(config)?,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 366];
+        let (owner_dst, id_dst, config_dst) = mut_array_refs![dst, 32, 8, RoundConfig::LEN];
+        let &RoundTemplate {
+            ref owner,
+            id,
+            ref config,
+        } = self;
+        owner_dst.copy_from_slice(owner.as_ref());
+        *id_dst = id.to_le_bytes();
+        config.pack_into_slice(config_dst);
+    }
+}
+
+/// Credits, what a voter spent of the credit budget of a Retro round
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Credits {
+    pub round: Pubkey,
+    pub voter: Pubkey,
+    pub spent: u64,
+}
+impl Sealed for Credits {}
+impl IsInitialized for Credits {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for Credits {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [167, 92, 156, 89, 224, 222, 250, 1];
+}
+impl Pack for Credits {
+    const LEN: usize = 80;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 72];
+        let (round, voter, spent) = array_refs![src, 32, 32, 8];
+        Ok(Credits {
+            round: Pubkey::new_from_array(*round),
+            voter: Pubkey::new_from_array(*voter),
+            spent: u64::from_le_bytes(*spent),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 72];
+        let (round_dst, voter_dst, spent_dst) = mut_array_refs![dst, 32, 32, 8];
+        let &Credits {
+            ref round,
+            ref voter,
+            spent,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        voter_dst.copy_from_slice(voter.as_ref());
+        *spent_dst = spent.to_le_bytes();
+    }
+}
+
+/// Voter registry, what a wallet voted in a round across all its voters
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VoterRegistry {
+    pub round: Pubkey,
+    pub wallet: Pubkey,
+    /// voters the wallet has in the round, one per project
+    pub projects: u64,
+    /// tokens voted in the round
+    pub votes: u64,
+    /// votes in the value the sqrt math uses
+    pub weight: u64,
+    /// unix timestamp of the last vote
+    pub last_vote_at: i64,
+    /// tokens the wallet spent on downvotes in the round
+    pub downvotes: u64,
+    /// position of the wallet's leaves in the round's voter tree plus one, 0 before its first compressed vote
+    pub tree_slot: u64,
+}
+impl Sealed for VoterRegistry {}
+impl IsInitialized for VoterRegistry {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for VoterRegistry {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [146, 143, 24, 89, 70, 216, 173, 1];
+}
+impl Pack for VoterRegistry {
+    const LEN: usize = 120;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 112];
+        let (round, wallet, projects, votes, weight, last_vote_at, downvotes, tree_slot) =
+            array_refs![src, 32, 32, 8, 8, 8, 8, 8, 8];
+        Ok(VoterRegistry {
+            round: Pubkey::new_from_array(*round),
+            wallet: Pubkey::new_from_array(*wallet),
+            projects: u64::from_le_bytes(*projects),
+            votes: u64::from_le_bytes(*votes),
+            weight: u64::from_le_bytes(*weight),
+            last_vote_at: i64::from_le_bytes(*last_vote_at),
+            downvotes: u64::from_le_bytes(*downvotes),
+            tree_slot: u64::from_le_bytes(*tree_slot),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 112];
+        let (
+            round_dst,
+            wallet_dst,
+            projects_dst,
+            votes_dst,
+            weight_dst,
+            last_vote_at_dst,
+            downvotes_dst,
+            tree_slot_dst,
+        ) = mut_array_refs![dst, 32, 32, 8, 8, 8, 8, 8, 8];
+        let &VoterRegistry {
+            ref round,
+            ref wallet,
+            projects,
+            votes,
+            weight,
+            last_vote_at,
+            downvotes,
+            tree_slot,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        wallet_dst.copy_from_slice(wallet.as_ref());
+        *projects_dst = projects.to_le_bytes();
+        *votes_dst = votes.to_le_bytes();
+        *weight_dst = weight.to_le_bytes();
+        *last_vote_at_dst = last_vote_at.to_le_bytes();
+        *downvotes_dst = downvotes.to_le_bytes();
+        *tree_slot_dst = tree_slot.to_le_bytes();
+    }
+}
+
+/// Epoch tally, the votes of a round, a project or a voter in one epoch
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EpochTally {
+    /// round, project or voter the tally is for
+    pub parent: Pubkey,
+    pub epoch: u64,
+    pub votes: u64,
+    /// square root of the votes for a voter, of the area for a project
+    pub sqrt: U256,
+    /// area of a project, sum of the areas of the category's projects for a round
+    pub area: U256,
+    /// the project withdrew the epoch match
+    pub withdrawn: bool,
+    /// category of the project, a round has a tally per category
+    pub category: u8,
+}
+impl Sealed for EpochTally {}
+impl IsInitialized for EpochTally {
+    fn is_initialized(&self) -> bool {
+        self.parent != Pubkey::default()
+    }
+}
+impl Discriminator for EpochTally {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [148, 242, 137, 74, 10, 41, 198, 1];
+}
+impl Pack for EpochTally {
+    const LEN: usize = 122;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 114];
+        let (parent, epoch, votes, sqrt, area, withdrawn, category) =
+            array_refs![src, 32, 8, 8, 32, 32, 1, 1];
+        Ok(EpochTally {
+            parent: Pubkey::new_from_array(*parent),
+            epoch: u64::from_le_bytes(*epoch),
+            votes: u64::from_le_bytes(*votes),
+            sqrt: U256::from_little_endian(sqrt),
+            area: U256::from_little_endian(area),
+            withdrawn: match withdrawn {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            category: category[0],
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 114];
+        let (parent_dst, epoch_dst, votes_dst, sqrt_dst, area_dst, withdrawn_dst, category_dst) =
+            mut_array_refs![dst, 32, 8, 8, 32, 32, 1, 1];
+        let &EpochTally {
+            ref parent,
+            epoch,
+            votes,
+            sqrt,
+            area,
+            withdrawn,
+            category,
+        } = self;
+        parent_dst.copy_from_slice(parent.as_ref());
+        *epoch_dst = epoch.to_le_bytes();
+        *votes_dst = votes.to_le_bytes();
+        sqrt.to_little_endian(sqrt_dst);
+        area.to_little_endian(area_dst);
+        withdrawn_dst[0] = withdrawn as u8;
+        category_dst[0] = category;
+    }
+}
+
+/// Airdrop, reward tokens shared by the voters of a round in proportion to their votes
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Airdrop {
+    pub round: Pubkey,
+    pub reward_mint: Pubkey,
+    /// token account owned by the airdrop account
+    pub reward_vault: Pubkey,
+    /// reward tokens shared by all the votes of the round
+    pub amount: u64,
+    /// reward tokens claimed so far
+    pub claimed: u64,
+}
+impl Sealed for Airdrop {}
+impl IsInitialized for Airdrop {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for Airdrop {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [31, 112, 159, 158, 124, 237, 9, 1];
+}
+impl Pack for Airdrop {
+    const LEN: usize = 120;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 112];
+        let (round, reward_mint, reward_vault, amount, claimed) =
+            array_refs![src, 32, 32, 32, 8, 8];
+        Ok(Airdrop {
+            round: Pubkey::new_from_array(*round),
+            reward_mint: Pubkey::new_from_array(*reward_mint),
+            reward_vault: Pubkey::new_from_array(*reward_vault),
+            amount: u64::from_le_bytes(*amount),
+            claimed: u64::from_le_bytes(*claimed),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 112];
+        let (round_dst, reward_mint_dst, reward_vault_dst, amount_dst, claimed_dst) =
+            mut_array_refs![dst, 32, 32, 32, 8, 8];
+        let &Airdrop {
+            ref round,
+            ref reward_mint,
+            ref reward_vault,
+            amount,
+            claimed,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        reward_mint_dst.copy_from_slice(reward_mint.as_ref());
+        reward_vault_dst.copy_from_slice(reward_vault.as_ref());
+        *amount_dst = amount.to_le_bytes();
+        *claimed_dst = claimed.to_le_bytes();
+    }
+}
+
+/// Curator, a key the round owner allows to attest projects of the round
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Curator {
+    pub round: Pubkey,
+    pub curator: Pubkey,
+}
+impl Sealed for Curator {}
+impl IsInitialized for Curator {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for Curator {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [126, 235, 162, 20, 21, 83, 244, 1];
+}
+impl Pack for Curator {
+    const LEN: usize = 72;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 64];
+        let (round, curator) = array_refs![src, 32, 32];
+        Ok(Curator {
+            round: Pubkey::new_from_array(*round),
+            curator: Pubkey::new_from_array(*curator),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 64];
+        let (round_dst, curator_dst) = mut_array_refs![dst, 32, 32];
+        let Curator { round, curator } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        curator_dst.copy_from_slice(curator.as_ref());
+    }
+}
+
+/// Curator attestation, a curator verified a project
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CuratorAttestation {
+    pub project: Pubkey,
+    pub curator: Pubkey,
+    pub attested_at: i64,
+}
+impl Sealed for CuratorAttestation {}
+impl IsInitialized for CuratorAttestation {
+    fn is_initialized(&self) -> bool {
+        self.project != Pubkey::default()
+    }
+}
+impl Discriminator for CuratorAttestation {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [184, 22, 141, 144, 43, 144, 195, 1];
+}
+impl Pack for CuratorAttestation {
+    const LEN: usize = 80;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 72];
+        let (project, curator, attested_at) = array_refs![src, 32, 32, 8];
+        Ok(CuratorAttestation {
+            project: Pubkey::new_from_array(*project),
+            curator: Pubkey::new_from_array(*curator),
+            attested_at: i64::from_le_bytes(*attested_at),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 72];
+        let (project_dst, curator_dst, attested_at_dst) = mut_array_refs![dst, 32, 32, 8];
+        let &CuratorAttestation {
+            ref project,
+            ref curator,
+            attested_at,
+        } = self;
+        project_dst.copy_from_slice(project.as_ref());
+        curator_dst.copy_from_slice(curator.as_ref());
+        *attested_at_dst = attested_at.to_le_bytes();
+    }
+}
+
+/// Most rounds and project owners the config can exempt from fees
+pub const MAX_FEE_EXEMPT: usize = 16;
+
+/// Most mints the config can approve for round vaults
+pub const MAX_APPROVED_MINTS: usize = 8;
+
+/// Config, the program wide settings kept by the protocol admin
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Config {
+    pub admin: Pubkey,
+    /// fee the protocol takes from what is left of every payout after the operator fee
+    pub protocol_fee_bps: u16,
+    /// wallet whose associated token accounts receive the protocol fee
+    pub fee_recipient: Pubkey,
+    /// rounds and project owners whose payouts are not charged any fee, default keys are free slots
+    pub fee_exempt: [Pubkey; MAX_FEE_EXEMPT],
+    /// fee recipient proposed by the admin, it becomes the fee recipient once it accepts
+    pub pending_fee_recipient: Pubkey,
+    /// seconds between proposing a timelocked owner action and executing it, 0 means no timelock
+    pub timelock: i64,
+    /// seconds from the start of voting ExtendRound can push a round's voting end to, 0 disables it
+    pub max_round_duration: i64,
+    /// mints StartRound accepts for the vault, default keys are free slots and an empty
+    /// list accepts any mint
+    pub approved_mints: [Pubkey; MAX_APPROVED_MINTS],
+}
+impl Config {
+    pub fn is_fee_exempt(&self, key: &Pubkey) -> bool {
+        *key != Pubkey::default() && self.fee_exempt.contains(key)
+    }
+
+    pub fn is_mint_approved(&self, mint: &Pubkey) -> bool {
+        self.approved_mints.iter().all(|m| *m == Pubkey::default())
+            || (*mint != Pubkey::default() && self.approved_mints.contains(mint))
+    }
+}
+impl Sealed for Config {}
+impl IsInitialized for Config {
+    fn is_initialized(&self) -> bool {
+        self.admin != Pubkey::default()
+    }
+}
+impl Discriminator for Config {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [155, 12, 170, 224, 30, 250, 204, 1];
+}
+impl Pack for Config {
+    const LEN: usize = 890;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 882];
+        let (
+            admin,
+            protocol_fee_bps,
+            fee_recipient,
+            fee_exempt_src,
+            pending_fee_recipient,
+            timelock,
+            max_round_duration,
+            approved_mints_src,
+        ) = array_refs![
+            src,
+            32,
+            2,
+            32,
+            32 * MAX_FEE_EXEMPT,
+            32,
+            8,
+            8,
+            32 * MAX_APPROVED_MINTS
+        ];
+        let mut fee_exempt = [Pubkey::default(); MAX_FEE_EXEMPT];
+        for (key, src) in fee_exempt.iter_mut().zip(fee_exempt_src.chunks(32)) {
+            *key = Pubkey::new(src);
+        }
+        let mut approved_mints = [Pubkey::default(); MAX_APPROVED_MINTS];
+        for (mint, src) in approved_mints.iter_mut().zip(approved_mints_src.chunks(32)) {
+            *mint = Pubkey::new(src);
+        }
+        Ok(Config {
+            admin: Pubkey::new_from_array(*admin),
+            protocol_fee_bps: u16::from_le_bytes(*protocol_fee_bps),
+            fee_recipient: Pubkey::new_from_array(*fee_recipient),
+            fee_exempt,
+            pending_fee_recipient: Pubkey::new_from_array(*pending_fee_recipient),
+            timelock: i64::from_le_bytes(*timelock),
+            max_round_duration: i64::from_le_bytes(*max_round_duration),
+            approved_mints,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 882];
+        let (
+            admin_dst,
+            protocol_fee_bps_dst,
+            fee_recipient_dst,
+            fee_exempt_dst,
+            pending_fee_recipient_dst,
+            timelock_dst,
+            max_round_duration_dst,
+            approved_mints_dst,
+        ) = mut_array_refs![
+            dst,
+            32,
+            2,
+            32,
+            32 * MAX_FEE_EXEMPT,
+            32,
+            8,
+            8,
+            32 * MAX_APPROVED_MINTS
+        ];
+        let &Config {
+            ref admin,
+            protocol_fee_bps,
+            ref fee_recipient,
+            ref fee_exempt,
+            ref pending_fee_recipient,
+            timelock,
+            max_round_duration,
+            ref approved_mints,
+        } = self;
+        admin_dst.copy_from_slice(admin.as_ref());
+        *protocol_fee_bps_dst = protocol_fee_bps.to_le_bytes();
+        fee_recipient_dst.copy_from_slice(fee_recipient.as_ref());
+        for (key, dst) in fee_exempt.iter().zip(fee_exempt_dst.chunks_mut(32)) {
+            dst.copy_from_slice(key.as_ref());
+        }
+        pending_fee_recipient_dst.copy_from_slice(pending_fee_recipient.as_ref());
+        *timelock_dst = timelock.to_le_bytes();
+        *max_round_duration_dst = max_round_duration.to_le_bytes();
+        for (mint, dst) in approved_mints.iter().zip(approved_mints_dst.chunks_mut(32)) {
+            dst.copy_from_slice(mint.as_ref());
+        }
+    }
+}
+
+/// Owner action that has to be proposed before it is executed when the config sets a timelock
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+pub enum ActionKind {
+    WithdrawFee,
+    RecoverAbandonedFunds,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for ActionKind {
+    fn default() -> Self {
+        ActionKind::WithdrawFee
+    }
+}
+
+/// Pending action, an owner action proposed for a round, derived from the round and the kind
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PendingAction {
+    pub round: Pubkey,
+    pub kind: ActionKind,
+    /// token account the action sends the funds to
+    pub to: Pubkey,
+    pub executable_at: i64,
+}
+impl Sealed for PendingAction {}
+impl IsInitialized for PendingAction {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for PendingAction {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [10, 76, 29, 155, 104, 63, 34, 1];
+}
+impl Pack for PendingAction {
+    const LEN: usize = 81;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 73];
+        let (round, kind, to, executable_at) = array_refs![src, 32, 1, 32, 8];
+        Ok(PendingAction {
+            round: Pubkey::new_from_array(*round),
+            kind: ActionKind::try_from_primitive(kind[0])
+                .or(Err(ProgramError::InvalidAccountData))?,
+            to: Pubkey::new_from_array(*to),
+            executable_at: i64::from_le_bytes(*executable_at),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 73];
+        let (round_dst, kind_dst, to_dst, executable_at_dst) = mut_array_refs![dst, 32, 1, 32, 8];
+        let &PendingAction {
+            ref round,
+            kind,
+            ref to,
+            executable_at,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        kind_dst[0] = kind as u8;
+        to_dst.copy_from_slice(to.as_ref());
+        *executable_at_dst = executable_at.to_le_bytes();
+    }
+}
+
+/// Archive of a closed round, derived from the round. Holds a hash chained over the
+/// final state of its projects once their accounts are closed.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoundArchive {
+    pub round: Pubkey,
+    /// projects archived so far
+    pub projects: u64,
+    /// hash over the archived projects, in the order they were archived
+    pub hash: [u8; 32],
+    /// the round account is closed, no more projects can be archived
+    pub complete: bool,
+    /// fund of the round when it was archived
+    pub fund: u64,
+    /// votes of the round when it was archived
+    pub votes: u64,
+}
+impl Sealed for RoundArchive {}
+impl IsInitialized for RoundArchive {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for RoundArchive {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [151, 100, 1, 213, 222, 140, 144, 1];
+}
+impl Pack for RoundArchive {
+    const LEN: usize = 97;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 89];
+        let (round, projects, hash, complete, fund, votes) = array_refs![src, 32, 8, 32, 1, 8, 8];
+        Ok(RoundArchive {
+            round: Pubkey::new_from_array(*round),
+            projects: u64::from_le_bytes(*projects),
+            hash: *hash,
+            complete: match complete {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            fund: u64::from_le_bytes(*fund),
+            votes: u64::from_le_bytes(*votes),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 89];
+        let (round_dst, projects_dst, hash_dst, complete_dst, fund_dst, votes_dst) =
+            mut_array_refs![dst, 32, 8, 32, 1, 8, 8];
+        let &RoundArchive {
+            ref round,
+            projects,
+            ref hash,
+            complete,
+            fund,
+            votes,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        *projects_dst = projects.to_le_bytes();
+        hash_dst.copy_from_slice(hash);
+        complete_dst[0] = complete as u8;
+        *fund_dst = fund.to_le_bytes();
+        *votes_dst = votes.to_le_bytes();
+    }
+}
+
+/// What a project is paid in a finalized round, written when the round is finalized and
+/// derived from the project
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Claim {
+    pub project: Pubkey,
+    /// votes and match of the project, before fees and without the deposit
+    pub amount: u64,
+    pub claimed: bool,
+}
+impl Sealed for Claim {}
+impl IsInitialized for Claim {
+    fn is_initialized(&self) -> bool {
+        self.project != Pubkey::default()
+    }
+}
+impl Discriminator for Claim {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [155, 70, 22, 176, 123, 215, 246, 1];
+}
+impl Pack for Claim {
+    const LEN: usize = 49;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 41];
+        let (project, amount, claimed) = array_refs![src, 32, 8, 1];
+        Ok(Claim {
+            project: Pubkey::new_from_array(*project),
+            amount: u64::from_le_bytes(*amount),
+            claimed: match claimed {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 41];
+        let (project_dst, amount_dst, claimed_dst) = mut_array_refs![dst, 32, 8, 1];
+        let &Claim {
+            ref project,
+            amount,
+            claimed,
+        } = self;
+        project_dst.copy_from_slice(project.as_ref());
+        *amount_dst = amount.to_le_bytes();
+        claimed_dst[0] = claimed as u8;
+    }
+}
+
+/// Stake of a wallet flagging a project for review, derived from the project and the
+/// flagger and closed when the flag is resolved
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Flag {
+    pub round: Pubkey,
+    pub project: Pubkey,
+    pub flagger: Pubkey,
+    pub stake: u64,
+}
+impl Sealed for Flag {}
+impl IsInitialized for Flag {
+    fn is_initialized(&self) -> bool {
+        self.project != Pubkey::default()
+    }
+}
+impl Discriminator for Flag {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [156, 221, 153, 134, 84, 147, 165, 1];
+}
+impl Pack for Flag {
+    const LEN: usize = 112;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 104];
+        let (round, project, flagger, stake) = array_refs![src, 32, 32, 32, 8];
+        Ok(Flag {
+            round: Pubkey::new_from_array(*round),
+            project: Pubkey::new_from_array(*project),
+            flagger: Pubkey::new_from_array(*flagger),
+            stake: u64::from_le_bytes(*stake),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 104];
+        let (round_dst, project_dst, flagger_dst, stake_dst) = mut_array_refs![dst, 32, 32, 32, 8];
+        let &Flag {
+            ref round,
+            ref project,
+            ref flagger,
+            stake,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        project_dst.copy_from_slice(project.as_ref());
+        flagger_dst.copy_from_slice(flagger.as_ref());
+        *stake_dst = stake.to_le_bytes();
+    }
+}
+
+/// Most bytes of the body of a project update
+pub const MAX_UPDATE_LEN: usize = 256;
+
+/// Update a project owner posted, like a progress report or the evidence of a
+/// milestone, derived from `update`, the project and its index
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProjectUpdate {
+    pub project: Pubkey,
+    pub author: Pubkey,
+    /// the update is the `index`-th of the project, counted from 0
+    pub index: u64,
+    pub posted_at: i64,
+    /// hash of evidence kept off-chain, zeros for none
+    pub hash: [u8; 32],
+    pub body_len: u16,
+    /// text or link of the update, only the first `body_len` bytes are set
+    pub body: [u8; MAX_UPDATE_LEN],
+}
+impl ProjectUpdate {
+    /// The set part of the body
+    pub fn body(&self) -> &[u8] {
+        &self.body[..self.body_len as usize]
+    }
+}
+impl Default for ProjectUpdate {
+    fn default() -> Self {
+        ProjectUpdate {
+            project: Pubkey::default(),
+            author: Pubkey::default(),
+            index: 0,
+            posted_at: 0,
+            hash: [0; 32],
+            body_len: 0,
+            body: [0; MAX_UPDATE_LEN],
+        }
+    }
+}
+impl Sealed for ProjectUpdate {}
+impl IsInitialized for ProjectUpdate {
+    fn is_initialized(&self) -> bool {
+        self.project != Pubkey::default()
+    }
+}
+impl Discriminator for ProjectUpdate {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [69, 155, 36, 176, 150, 153, 134, 1];
+}
+impl Pack for ProjectUpdate {
+    const LEN: usize = 378;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 370];
+        let (project, author, index, posted_at, hash, body_len, body) =
+            array_refs![src, 32, 32, 8, 8, 32, 2, MAX_UPDATE_LEN];
+        let body_len = u16::from_le_bytes(*body_len);
+        if body_len as usize > MAX_UPDATE_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(ProjectUpdate {
+            project: Pubkey::new_from_array(*project),
+            author: Pubkey::new_from_array(*author),
+            index: u64::from_le_bytes(*index),
+            posted_at: i64::from_le_bytes(*posted_at),
+            hash: *hash,
+            body_len,
+            body: *body,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 370];
+        let (project_dst, author_dst, index_dst, posted_at_dst, hash_dst, body_len_dst, body_dst) =
+            mut_array_refs![dst, 32, 32, 8, 8, 32, 2, MAX_UPDATE_LEN];
+        let &ProjectUpdate {
+            ref project,
+            ref author,
+            index,
+            posted_at,
+            ref hash,
+            body_len,
+            ref body,
+        } = self;
+        project_dst.copy_from_slice(project.as_ref());
+        author_dst.copy_from_slice(author.as_ref());
+        *index_dst = index.to_le_bytes();
+        *posted_at_dst = posted_at.to_le_bytes();
+        hash_dst.copy_from_slice(hash);
+        *body_len_dst = body_len.to_le_bytes();
+        body_dst.copy_from_slice(body);
+    }
+}
+
+/// Depth of a round's voter tree
+pub const VOTER_TREE_DEPTH: usize = 20;
+
+/// Bits of a voter tree leaf index that pick the project, each wallet of the tree has
+/// a leaf for each of the first 64 projects of the round
+pub const VOTER_TREE_PROJECT_BITS: usize = 6;
+
+/// Changes a voter tree remembers, a proof against the root before any of them is
+/// still accepted
+pub const VOTER_TREE_BUFFER: usize = 8;
+
+/// A change of a voter tree, the root after it and the path of the leaf it wrote
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChangeLog {
+    pub root: [u8; 32],
+    /// nodes from the leaf up to the root, the leaf first
+    pub path: [[u8; 32]; VOTER_TREE_DEPTH],
+    pub index: u64,
+}
+impl Sealed for ChangeLog {}
+impl Pack for ChangeLog {
+    const LEN: usize = 680;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 680];
+        let (root, path_src, index) = array_refs![src, 32, 32 * VOTER_TREE_DEPTH, 8];
+        let mut path = [[0; 32]; VOTER_TREE_DEPTH];
+        for (node, src) in path.iter_mut().zip(path_src.chunks(32)) {
+            node.copy_from_slice(src);
+        }
+        Ok(ChangeLog {
+            root: *root,
+            path,
+            index: u64::from_le_bytes(*index),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 680];
+        let (root_dst, path_dst, index_dst) = mut_array_refs![dst, 32, 32 * VOTER_TREE_DEPTH, 8];
+        let &ChangeLog {
+            ref root,
+            ref path,
+            index,
+        } = self;
+        root_dst.copy_from_slice(root);
+        for (node, dst) in path.iter().zip(path_dst.chunks_mut(32)) {
+            dst.copy_from_slice(node);
+        }
+        *index_dst = index.to_le_bytes();
+    }
+}
+
+/// Voter tree, the compressed voters of a round. A leaf is the hash of the project, the
+/// wallet, its votes and the slot of its last vote, at the position of the wallet's
+/// slot and the project's index. It is a concurrent Merkle tree: a proof against the
+/// root of one of its change logs is moved past the changes after it. The change logs
+/// follow the tree in its account, they are read one at a time to keep them off the
+/// stack.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VoterTree {
+    pub round: Pubkey,
+    /// wallets given a slot of leaves
+    pub wallets: u64,
+    /// changes written since the tree was created
+    pub sequence: u64,
+    /// position of the latest change in the change logs
+    pub active_index: u64,
+    /// changes kept in the change logs
+    pub buffer_size: u64,
+}
+impl VoterTree {
+    /// Bytes of a voter tree account, the tree and then its change logs
+    pub const ACCOUNT_LEN: usize = Self::LEN + ChangeLog::LEN * VOTER_TREE_BUFFER;
+
+    /// An empty tree of `round`, its first change log is the empty tree
+    pub fn init(round: Pubkey, change_logs: &mut [u8]) -> Self {
+        let mut change_log = ChangeLog::default();
+        let mut empty = merkle::EMPTY;
+        for node in change_log.path.iter_mut() {
+            *node = empty;
+            empty = merkle::node(&empty, &empty);
+        }
+        change_log.root = empty;
+        change_log.pack_into_slice(&mut change_logs[..ChangeLog::LEN]);
+        VoterTree {
+            round,
+            buffer_size: 1,
+            ..VoterTree::default()
+        }
+    }
+
+    /// The change log at `position` of `change_logs`
+    pub fn change_log(change_logs: &[u8], position: u64) -> Result<ChangeLog, ProgramError> {
+        let offset = position as usize % VOTER_TREE_BUFFER * ChangeLog::LEN;
+        ChangeLog::unpack_from_slice(&change_logs[offset..offset + ChangeLog::LEN])
+    }
+
+    /// The current root
+    pub fn root(&self, change_logs: &[u8]) -> Result<[u8; 32], ProgramError> {
+        Ok(Self::change_log(change_logs, self.active_index)?.root)
+    }
+}
+impl Sealed for VoterTree {}
+impl IsInitialized for VoterTree {
+    fn is_initialized(&self) -> bool {
+        self.round != Pubkey::default()
+    }
+}
+impl Discriminator for VoterTree {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [104, 221, 74, 62, 188, 221, 210, 1];
+}
+impl Pack for VoterTree {
+    const LEN: usize = 72;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = strip_discriminator::<Self>(src)?;
+        let src = array_ref![src, 0, 64];
+        let (round, wallets, sequence, active_index, buffer_size) =
+            array_refs![src, 32, 8, 8, 8, 8];
+        Ok(VoterTree {
+            round: Pubkey::new_from_array(*round),
+            wallets: u64::from_le_bytes(*wallets),
+            sequence: u64::from_le_bytes(*sequence),
+            active_index: u64::from_le_bytes(*active_index),
+            buffer_size: u64::from_le_bytes(*buffer_size),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = write_discriminator::<Self>(dst);
+        let dst = array_mut_ref![dst, 0, 64];
+        let (round_dst, wallets_dst, sequence_dst, active_index_dst, buffer_size_dst) =
+            mut_array_refs![dst, 32, 8, 8, 8, 8];
+        let &VoterTree {
+            ref round,
+            wallets,
+            sequence,
+            active_index,
+            buffer_size,
+        } = self;
+        round_dst.copy_from_slice(round.as_ref());
+        *wallets_dst = wallets.to_le_bytes();
+        *sequence_dst = sequence.to_le_bytes();
+        *active_index_dst = active_index.to_le_bytes();
+        *buffer_size_dst = buffer_size.to_le_bytes();
     }
 }
@@ -0,0 +1,118 @@
+//! Mints non-transferable Token-2022 tokens without depending on spl-token-2022
+//!
+//! Token-2022 keeps the instruction tags of spl-token and adds its own after them:
+//! MintTo is tag 7 followed by the amount, InitializeMint2 tag 20 followed by the
+//! decimals, the mint authority and an optional freeze authority, and
+//! InitializeNonTransferableMint tag 32 without data. A mint with extensions is laid out
+//! like a token account (165 bytes), then the account type and each extension as its
+//! type, its length and its data. The non-transferable extension has no data.
+//!
+//! Token accounts of a non-transferable mint need the immutable owner extension, so they
+//! are created through the associated token account program, which sizes and initializes
+//! them for the mint. Their first 165 bytes are those of an spl-token account.
+
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program,
+};
+
+solana_program::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const MINT_TO: u8 = 7;
+const INITIALIZE_MINT_2: u8 = 20;
+const INITIALIZE_NON_TRANSFERABLE_MINT: u8 = 32;
+
+/// Size of a mint with the non-transferable extension: a token account, the account type
+/// and the extension's type and length
+pub const NON_TRANSFERABLE_MINT_LEN: usize = spl_token::state::Account::LEN + 1 + 4;
+
+/// Marks an uninitialized mint as non-transferable, before InitializeMint2
+pub fn initialize_non_transferable_mint(mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![AccountMeta::new(*mint, false)],
+        data: vec![INITIALIZE_NON_TRANSFERABLE_MINT],
+    }
+}
+
+/// Initializes `mint` with `decimals` and `authority`, without a freeze authority
+pub fn initialize_mint2(mint: &Pubkey, authority: &Pubkey, decimals: u8) -> Instruction {
+    let mut data = vec![INITIALIZE_MINT_2, decimals];
+    data.extend_from_slice(&authority.to_bytes());
+    data.push(0);
+    Instruction {
+        program_id: id(),
+        accounts: vec![AccountMeta::new(*mint, false)],
+        data,
+    }
+}
+
+/// Mints `amount` of `mint` to `account`, `authority` signs
+pub fn mint_to(mint: &Pubkey, account: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![MINT_TO];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+/// Returns the associated token account of `wallet` for the Token-2022 `mint`
+pub fn get_associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[&wallet.to_bytes(), &id().to_bytes(), &mint.to_bytes()],
+        &spl_associated_token_account::id(),
+    )
+    .0
+}
+
+/// Creates the associated token account of `wallet` for the Token-2022 `mint`, the
+/// payer signs and pays for it
+pub fn create_associated_token_account(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: spl_associated_token_account::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(get_associated_token_address(wallet, mint), false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(id(), false),
+        ],
+        data: vec![],
+    }
+}
+
+/// Reads the amount of a Token-2022 token account of `mint` held by `owner`
+pub fn token_amount(
+    account_info: &AccountInfo,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Result<u64, ProgramError> {
+    if account_info.owner != &id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = account_info.data.borrow();
+    if data.len() < spl_token::state::Account::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let account =
+        spl_token::state::Account::unpack_from_slice(&data[..spl_token::state::Account::LEN])?;
+    if &account.mint != mint || &account.owner != owner {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(account.amount)
+}
@@ -0,0 +1,171 @@
+//! Checks shared by the instruction handlers, and typed accounts that run them when
+//! they are loaded
+
+use crate::{
+    error::QFError,
+    state::{Project, Round, RoundStatus},
+};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_token::state::Account;
+use std::ops::{Deref, DerefMut};
+
+/// Fails unless the account is owned by the program
+pub fn check_owner(info: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    if info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Fails unless the account signed the transaction
+pub fn check_signer(info: &AccountInfo) -> ProgramResult {
+    if !info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Fails unless the round is in `status`
+pub fn check_status(round: &Round, status: RoundStatus) -> ProgramResult {
+    if round.status != status {
+        return Err(QFError::RoundStatusError.into());
+    }
+    Ok(())
+}
+
+/// Fails with `error` unless the account is the program address derived from
+/// `seeds`, returns its bump seed
+pub fn check_pda(
+    info: &AccountInfo,
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+    error: QFError,
+) -> Result<u8, ProgramError> {
+    let (expected_key, bump_seed) = Pubkey::find_program_address(seeds, program_id);
+    if info.key != &expected_key {
+        return Err(error.into());
+    }
+    Ok(bump_seed)
+}
+
+/// Fails unless the account holds enough lamports to be rent exempt
+pub fn check_rent_exempt(info: &AccountInfo) -> ProgramResult {
+    if !Rent::get()?.is_exempt(info.lamports(), info.data_len()) {
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    Ok(())
+}
+
+/// A round owned by the program, derefs to the unpacked [`Round`]
+pub struct RoundAccount<'a, 'b> {
+    pub info: &'a AccountInfo<'b>,
+    round: Round,
+}
+
+impl<'a, 'b> RoundAccount<'a, 'b> {
+    /// Checks the owner, the size and the rent of the account and unpacks the round
+    pub fn load(info: &'a AccountInfo<'b>, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        check_owner(info, program_id)?;
+        check_rent_exempt(info)?;
+        let round = Round::unpack(&info.data.borrow())?;
+        Ok(Self { info, round })
+    }
+
+    /// Packs the round back into the account
+    pub fn save(&self) -> ProgramResult {
+        Round::pack(self.round, &mut self.info.data.borrow_mut())
+    }
+}
+
+impl Deref for RoundAccount<'_, '_> {
+    type Target = Round;
+    fn deref(&self) -> &Round {
+        &self.round
+    }
+}
+
+impl DerefMut for RoundAccount<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Round {
+        &mut self.round
+    }
+}
+
+/// A project of a round, derefs to the unpacked [`Project`]
+pub struct ProjectAccount<'a, 'b> {
+    pub info: &'a AccountInfo<'b>,
+    project: Project,
+}
+
+impl<'a, 'b> ProjectAccount<'a, 'b> {
+    /// Checks the owner, the size and the rent of the account, unpacks the project and
+    /// fails unless it belongs to `round`
+    pub fn load(
+        info: &'a AccountInfo<'b>,
+        program_id: &Pubkey,
+        round: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        check_owner(info, program_id)?;
+        check_rent_exempt(info)?;
+        let project = Project::unpack(&info.data.borrow())?;
+        if project.round != *round {
+            return Err(QFError::RoundMismatch.into());
+        }
+        Ok(Self { info, project })
+    }
+
+    /// Packs the project back into the account
+    pub fn save(&self) -> ProgramResult {
+        Project::pack(self.project, &mut self.info.data.borrow_mut())
+    }
+}
+
+impl Deref for ProjectAccount<'_, '_> {
+    type Target = Project;
+    fn deref(&self) -> &Project {
+        &self.project
+    }
+}
+
+impl DerefMut for ProjectAccount<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Project {
+        &mut self.project
+    }
+}
+
+/// The vault of a round, derefs to the unpacked token account
+pub struct VaultAccount<'a, 'b> {
+    pub info: &'a AccountInfo<'b>,
+    account: Account,
+}
+
+impl<'a, 'b> VaultAccount<'a, 'b> {
+    /// Fails unless the account is the vault of `round`, a token account of the round
+    /// mint, and unpacks it
+    pub fn load(info: &'a AccountInfo<'b>, round: &Round) -> Result<Self, ProgramError> {
+        if info.key != &round.vault {
+            return Err(QFError::VaultMismatch.into());
+        }
+        if info.owner != &spl_token::ID {
+            return Err(QFError::UnexpectedTokenProgramID.into());
+        }
+        let account = Account::unpack(&info.data.borrow())?;
+        if account.mint != round.mint {
+            return Err(QFError::MintMismatch.into());
+        }
+        Ok(Self { info, account })
+    }
+}
+
+impl Deref for VaultAccount<'_, '_> {
+    type Target = Account;
+    fn deref(&self) -> &Account {
+        &self.account
+    }
+}
@@ -0,0 +1,33 @@
+//! Every wallet of an allowlist has a proof of its root within MAX_PROOF_LEN, and
+//! a wallet outside of it has none that RegisterProject would accept.
+
+use proptest::{collection::vec, prelude::*};
+use qf::allowlist::{self, MAX_PROOF_LEN};
+use solana_program::pubkey::Pubkey;
+
+fn wallets() -> impl Strategy<Value = Vec<Pubkey>> {
+    vec(any::<[u8; 32]>().prop_map(Pubkey::new_from_array), 1..64)
+}
+
+proptest! {
+    #[test]
+    fn members_verify(wallets in wallets()) {
+        let root = allowlist::root(&wallets);
+        for wallet in wallets.iter() {
+            let proof = allowlist::proof(&wallets, wallet).unwrap();
+            prop_assert!(proof.len() <= MAX_PROOF_LEN);
+            prop_assert!(allowlist::verify(&root, wallet, &proof));
+        }
+    }
+
+    #[test]
+    fn outsiders_do_not_verify(wallets in wallets(), outsider in any::<[u8; 32]>()) {
+        let outsider = Pubkey::new_from_array(outsider);
+        prop_assume!(!wallets.contains(&outsider));
+        let root = allowlist::root(&wallets);
+        prop_assert!(allowlist::proof(&wallets, &outsider).is_none());
+        // the proof of a member does not lead another wallet to the root
+        let proof = allowlist::proof(&wallets, &wallets[0]).unwrap();
+        prop_assert!(!allowlist::verify(&root, &outsider, &proof));
+    }
+}
@@ -0,0 +1,272 @@
+//! Runs instructions of the program against accounts held by the test. Calls to the token
+//! program are processed by spl-token itself and calls to the programs a test adds by
+//! their processor, the clock and rent sysvars come from the test.
+
+#![allow(dead_code)]
+
+use qf::processor::Processor;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::{self, Sysvar},
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use std::{cell::RefCell, sync::Once};
+
+/// Processes an instruction of a program
+pub type Process = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+thread_local! {
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+    static PROGRAMS: RefCell<Vec<(Pubkey, Process)>> = RefCell::new(vec![]);
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        // the program signs for its addresses, the metas say which accounts sign
+        let infos = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let mut info = account_infos
+                    .iter()
+                    .find(|info| info.key == &meta.pubkey)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?
+                    .clone();
+                info.is_signer = signs(instruction, &meta.pubkey);
+                Ok(info)
+            })
+            .collect::<Result<Vec<_>, ProgramError>>()?;
+        if instruction.program_id == spl_token::ID {
+            return spl_token::processor::Processor::process(
+                &instruction.program_id,
+                &infos,
+                &instruction.data,
+            );
+        }
+        let process = PROGRAMS.with(|programs| {
+            programs
+                .borrow()
+                .iter()
+                .find(|(id, _)| id == &instruction.program_id)
+                .map(|(_, process)| *process)
+        });
+        match process {
+            Some(process) => process(&instruction.program_id, &infos, &instruction.data),
+            None => Err(ProgramError::IncorrectProgramId),
+        }
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = CLOCK.with(|clock| clock.borrow().clone());
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+/// An account signs when any meta naming it does, the way a transaction merges them
+fn signs(instruction: &Instruction, key: &Pubkey) -> bool {
+    instruction
+        .accounts
+        .iter()
+        .any(|meta| meta.is_signer && &meta.pubkey == key)
+}
+
+/// Sets the unix timestamp of the clock the instructions see
+pub fn set_now(now: i64) {
+    CLOCK.with(|clock| clock.borrow_mut().unix_timestamp = now);
+}
+
+/// Lets the program call `process` as the program `id`
+pub fn add_program(id: Pubkey, process: Process) {
+    PROGRAMS.with(|programs| {
+        let mut programs = programs.borrow_mut();
+        programs.retain(|(program, _)| program != &id);
+        programs.push((id, process));
+    });
+}
+
+/// An account the instructions read and write
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+/// The accounts of a test, an instruction gets those its metas name
+#[derive(Default)]
+pub struct Accounts {
+    accounts: Vec<TestAccount>,
+}
+
+impl Accounts {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+        let mut accounts = Self::default();
+        let mut rent = vec![0; Rent::size_of()];
+        Rent::default()
+            .to_account_info(&mut AccountInfo::new(
+                &sysvar::rent::ID,
+                false,
+                false,
+                &mut 0,
+                &mut rent,
+                &sysvar::ID,
+                false,
+                0,
+            ))
+            .unwrap();
+        accounts.add(sysvar::rent::ID, sysvar::ID, rent);
+        // the instructions read the clock with `Clock::get`, the account is only passed
+        accounts.add(sysvar::clock::ID, sysvar::ID, vec![]);
+        accounts.add(spl_token::ID, Pubkey::default(), vec![]);
+        accounts.add(system_program::ID, Pubkey::default(), vec![]);
+        accounts
+    }
+
+    /// Adds a rent exempt account holding `data`
+    pub fn add(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        self.accounts.push(TestAccount {
+            key,
+            owner,
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+        });
+    }
+
+    /// Adds an account of `owner` holding the packed `state`
+    pub fn add_packed<T: Pack>(&mut self, key: Pubkey, owner: Pubkey, state: T) {
+        let mut data = vec![0; T::LEN];
+        state.pack_into_slice(&mut data);
+        self.add(key, owner, data);
+    }
+
+    /// Adds an initialized mint of `decimals` nobody can mint more of
+    pub fn add_mint(&mut self, key: Pubkey, decimals: u8) {
+        self.add_mint_with_authority(key, COption::None, decimals);
+    }
+
+    /// Adds an initialized mint of `decimals` whose tokens `authority` mints
+    pub fn add_mint_with_authority(
+        &mut self,
+        key: Pubkey,
+        authority: COption<Pubkey>,
+        decimals: u8,
+    ) {
+        let mint = Mint {
+            mint_authority: authority,
+            // room to mint and burn either way
+            supply: u64::MAX / 2,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        self.add_packed(key, spl_token::ID, mint);
+    }
+
+    /// Adds a token account of `mint` held by `owner`
+    pub fn add_token_account(&mut self, key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+        let account = TokenAccount {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        self.add_packed(key, spl_token::ID, account);
+    }
+
+    /// Replaces the state of the account `key` with the packed `state`
+    pub fn pack<T: Pack>(&mut self, key: &Pubkey, state: T) {
+        let account = self
+            .accounts
+            .iter_mut()
+            .find(|account| &account.key == key)
+            .unwrap();
+        state.pack_into_slice(&mut account.data);
+    }
+
+    pub fn unpack<T: Pack>(&self, key: &Pubkey) -> T {
+        T::unpack_from_slice(&self.get(key).data).unwrap()
+    }
+
+    pub fn token_amount(&self, key: &Pubkey) -> u64 {
+        self.unpack::<TokenAccount>(key).amount
+    }
+
+    fn get(&self, key: &Pubkey) -> &TestAccount {
+        self.accounts
+            .iter()
+            .find(|account| &account.key == key)
+            .unwrap()
+    }
+
+    /// Processes `instruction` with the program, the program id is the instruction's
+    pub fn process(&mut self, instruction: &Instruction) -> ProgramResult {
+        self.process_with(instruction, Processor::process)
+    }
+
+    /// Processes `instruction` with `process`, the accounts a meta names twice are shared
+    pub fn process_with(&mut self, instruction: &Instruction, process: Process) -> ProgramResult {
+        let infos: Vec<AccountInfo> = self
+            .accounts
+            .iter_mut()
+            .map(|account| {
+                AccountInfo::new(
+                    &account.key,
+                    false,
+                    true,
+                    &mut account.lamports,
+                    &mut account.data,
+                    &account.owner,
+                    false,
+                    0,
+                )
+            })
+            .collect();
+        let metas = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let mut info = infos
+                    .iter()
+                    .find(|info| info.key == &meta.pubkey)
+                    .unwrap()
+                    .clone();
+                info.is_signer = signs(instruction, &meta.pubkey);
+                info
+            })
+            .collect::<Vec<_>>();
+        process(&instruction.program_id, &metas, &instruction.data)
+    }
+}
@@ -0,0 +1,67 @@
+//! A ban inside the dispute window after the end of voting still takes the project out
+//! of the matching, Finalize only locks the payouts once the window passed.
+#![cfg(feature = "moderation")]
+
+use proptest::{collection::vec, prelude::*};
+use qf::{
+    error::QFError,
+    processor::Processor,
+    state::{Project, Round, RoundStatus, Voter},
+};
+
+/// A tallied round that ended voting at `ended_at`, with its projects
+fn tallied(fund: u64, votes: &[Vec<u64>], ended_at: i64, window: i64) -> (Round, Vec<Project>) {
+    let mut round = Round {
+        status: RoundStatus::Voting,
+        fund,
+        ..Round::default()
+    };
+    let mut projects = vec![Project::default(); votes.len()];
+    for (project, voters) in projects.iter_mut().zip(votes) {
+        for &amount in voters {
+            let mut voter = Voter::default();
+            Processor::apply_vote(&mut round, project, &mut voter, amount, 0).unwrap();
+        }
+    }
+    round.status = RoundStatus::Tallying;
+    round.votes = votes.iter().flatten().sum();
+    round.voting_ended_at = ended_at;
+    round.config.dispute_window = window;
+    (round, projects)
+}
+
+proptest! {
+    #[test]
+    fn a_project_banned_in_the_window_gets_no_match(
+        fund in 1..1_000_000_000_000_000u64,
+        votes in vec(vec(1..1_000_000_000_000u64, 1..12), 2..8),
+        ended_at in 0..1_000_000_000i64,
+        window in 1..1_000_000i64,
+        elapsed in 0..1_000_000i64,
+    ) {
+        let (mut round, mut projects) = tallied(fund, &votes, ended_at, window);
+        let now = ended_at + elapsed % window;
+        prop_assert_eq!(
+            Processor::finalize(&mut round, now),
+            Err(QFError::DisputeWindowOpen.into())
+        );
+        prop_assert_eq!(round.status, RoundStatus::Tallying);
+
+        Processor::ban(&mut round, &mut projects[0]);
+        Processor::finalize(&mut round, ended_at + window).unwrap();
+        prop_assert_eq!(round.status, RoundStatus::Claim);
+
+        // the banned project only gets its own votes, the others share the whole fund
+        let payouts: Vec<u64> = projects
+            .iter_mut()
+            .map(|project| Processor::payout(&round, project))
+            .collect();
+        prop_assert_eq!(payouts[0], votes[0].iter().sum::<u64>());
+        let matched: u64 = payouts[1..]
+            .iter()
+            .zip(&votes[1..])
+            .map(|(payout, voters)| payout - voters.iter().sum::<u64>())
+            .sum();
+        prop_assert!(matched <= fund && fund - matched < projects.len() as u64);
+    }
+}
@@ -0,0 +1,144 @@
+//! The tokens voted with EpochVote stay in the vault until the round is finalized, so a
+//! cancelled Epochs round refunds them to the voters like a Standard round.
+
+mod common;
+
+use common::Accounts;
+use qf::{
+    error::QFError,
+    instruction,
+    state::{
+        EpochTally, Project, Round, RoundConfig, RoundMode, RoundStatus, Voter, VoterRegistry,
+    },
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey, system_program};
+
+#[test]
+fn cancelled_epochs_round_refunds_epoch_votes() {
+    let program_id = Pubkey::new_unique();
+    let (round, owner, project, wallet) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let (vault, mint, from) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let (vault_owner, _) = Pubkey::find_program_address(&[&owner.to_bytes()], &program_id);
+    let (voter, _) =
+        Pubkey::find_program_address(&[&project.to_bytes(), &wallet.to_bytes()], &program_id);
+    let (registry, _) = Pubkey::find_program_address(
+        &[b"registry", &round.to_bytes(), &wallet.to_bytes()],
+        &program_id,
+    );
+    let fund = 1_000_000;
+
+    let mut accounts = Accounts::new();
+    for wallet in &[owner, vault_owner, wallet] {
+        accounts.add(*wallet, system_program::ID, vec![]);
+    }
+    accounts.add_packed(
+        round,
+        program_id,
+        Round {
+            status: RoundStatus::Voting,
+            owner,
+            vault,
+            mint,
+            fund,
+            config: RoundConfig {
+                mode: RoundMode::Epochs,
+                epoch_length: 1_000,
+                epoch_count: 2,
+                // one voter does not reach the quorum
+                min_unique_voters: 2,
+                ..RoundConfig::default()
+            },
+            ..Round::default()
+        },
+    );
+    accounts.add_packed(
+        project,
+        program_id,
+        Project {
+            round,
+            owner: Pubkey::new_unique(),
+            ..Project::default()
+        },
+    );
+    accounts.add_packed(
+        voter,
+        program_id,
+        Voter {
+            is_initialized: true,
+            ..Voter::default()
+        },
+    );
+    // the epoch tallies and the registry are created by the first vote, the system
+    // program is not run here
+    accounts.add(registry, program_id, vec![0; VoterRegistry::LEN]);
+    for parent in &[round, project, voter] {
+        let (tally, _) = Pubkey::find_program_address(
+            &[b"epoch", &parent.to_bytes(), &1u64.to_le_bytes(), &[0]],
+            &program_id,
+        );
+        accounts.add(tally, program_id, vec![0; EpochTally::LEN]);
+    }
+    accounts.add_mint(mint, 6);
+    accounts.add_token_account(vault, mint, vault_owner, fund);
+    accounts.add_token_account(from, mint, wallet, 500);
+
+    common::set_now(1_500);
+    accounts
+        .process(&instruction::epoch_vote(
+            &program_id,
+            &round,
+            &project,
+            0,
+            1,
+            &from,
+            &mint,
+            &vault,
+            &wallet,
+            300,
+            6,
+        ))
+        .unwrap();
+    assert_eq!(accounts.token_amount(&from), 200);
+    assert_eq!(accounts.token_amount(&vault), fund + 300);
+    assert_eq!(accounts.unpack::<Voter>(&voter).votes, 300);
+
+    let refund = instruction::refund(
+        &program_id,
+        &round,
+        &owner,
+        &vault,
+        &project,
+        &from,
+        &wallet,
+    );
+    // nothing is refunded before the round is cancelled
+    assert_eq!(
+        accounts.process(&refund),
+        Err(QFError::RoundStatusError.into())
+    );
+
+    accounts
+        .process(&instruction::end_round(&program_id, &round, &owner))
+        .unwrap();
+    accounts
+        .process(&instruction::finalize(&program_id, &round, &owner, &[]))
+        .unwrap();
+    assert_eq!(
+        accounts.unpack::<Round>(&round).status,
+        RoundStatus::Cancelled
+    );
+
+    accounts.process(&refund).unwrap();
+    assert_eq!(accounts.token_amount(&from), 500);
+    assert_eq!(accounts.token_amount(&vault), fund);
+    assert_eq!(accounts.unpack::<Voter>(&voter).votes, 0);
+}
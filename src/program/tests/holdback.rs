@@ -0,0 +1,62 @@
+//! The holdbacks Withdraw keeps stay in the vault through RolloverFunds and SweepDust,
+//! so every project can still be released its holdback afterwards.
+
+use proptest::{collection::vec, prelude::*};
+use qf::{
+    error::QFError,
+    processor::Processor,
+    state::{Project, Round, RoundStatus},
+};
+
+proptest! {
+    #[test]
+    fn holdbacks_are_released_after_a_rollover(
+        payouts in vec((0..1_000_000_000_000u64, any::<bool>()), 1..16),
+        dust in 0..1_000_000u64,
+        deposits in 0..1_000_000_000u64,
+        fees in 0..1_000_000_000u64,
+        holdback_bps in 0..=10_000u16,
+    ) {
+        let fund_remaining = payouts.iter().map(|(payout, _)| payout).sum::<u64>() + dust;
+        let mut round = Round {
+            status: RoundStatus::Closed,
+            fund_remaining,
+            deposits,
+            operator_fee: fees,
+            ..Round::default()
+        };
+        round.config.holdback_bps = holdback_bps;
+        let mut vault = fund_remaining + deposits + fees;
+
+        // the projects that withdrew before the round was closed
+        let mut projects = vec![];
+        for &(payout, withdrew) in payouts.iter() {
+            let mut project = Project::default();
+            if withdrew {
+                round.fund_remaining -= payout;
+                vault -= Processor::hold_back(&mut round, &mut project, payout);
+            }
+            projects.push(project);
+        }
+
+        let rolled_over = round.residual(vault);
+        prop_assert!(rolled_over <= round.fund_remaining);
+        vault -= rolled_over;
+        round.fund_remaining = 0;
+
+        for project in projects.iter_mut() {
+            if project.holdback == 0 {
+                prop_assert_eq!(
+                    Processor::release_holdback(&mut round, project),
+                    Err(QFError::NoHoldback.into())
+                );
+                continue;
+            }
+            let released = Processor::release_holdback(&mut round, project).unwrap();
+            prop_assert!(released <= vault);
+            vault -= released;
+        }
+        prop_assert_eq!(round.holdbacks, 0);
+        prop_assert!(vault >= deposits + fees);
+    }
+}
@@ -0,0 +1,217 @@
+//! A fund lent out with LendFund is redeemed into the vault at EndRound, with the yield
+//! added to the fund or a shortfall taken out, so the vault still holds the fund it
+//! accounts for.
+
+mod common;
+
+use common::Accounts;
+use proptest::prelude::*;
+use qf::{
+    instruction::{self, LendingReserve},
+    lending,
+    state::{Round, RoundStatus},
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::invoke, program_option::COption,
+    pubkey::Pubkey, system_program,
+};
+use std::convert::TryInto;
+
+/// A reserve that mints one collateral token per token lent and pays back the rate in
+/// basis points its account holds for each collateral token redeemed. Deposits and
+/// redeems take the accounts `lending::Reserve` passes them.
+fn process_reserve(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
+    let market_authority = &accounts[6];
+    let authority = &accounts[7];
+    match data[0] {
+        4 => {
+            let (source, collateral, supply, collateral_mint) =
+                (&accounts[0], &accounts[1], &accounts[3], &accounts[4]);
+            invoke(
+                &spl_token::instruction::transfer(
+                    &spl_token::ID,
+                    source.key,
+                    supply.key,
+                    authority.key,
+                    &[],
+                    amount,
+                )?,
+                accounts,
+            )?;
+            invoke(
+                &spl_token::instruction::mint_to(
+                    &spl_token::ID,
+                    collateral_mint.key,
+                    collateral.key,
+                    market_authority.key,
+                    &[],
+                    amount,
+                )?,
+                accounts,
+            )
+        }
+        5 => {
+            let (collateral, destination, reserve, collateral_mint, supply) = (
+                &accounts[0],
+                &accounts[1],
+                &accounts[2],
+                &accounts[3],
+                &accounts[4],
+            );
+            let rate_bps = u64::from_le_bytes(reserve.data.borrow()[..8].try_into().unwrap());
+            invoke(
+                &spl_token::instruction::burn(
+                    &spl_token::ID,
+                    collateral.key,
+                    collateral_mint.key,
+                    authority.key,
+                    &[],
+                    amount,
+                )?,
+                accounts,
+            )?;
+            invoke(
+                &spl_token::instruction::transfer(
+                    &spl_token::ID,
+                    supply.key,
+                    destination.key,
+                    market_authority.key,
+                    &[],
+                    amount * rate_bps / 10_000,
+                )?,
+                accounts,
+            )
+        }
+        _ => panic!("unexpected lending instruction"),
+    }
+}
+
+/// Lends `share_bps` of the fund to a reserve paying back `rate_bps` and ends the round,
+/// returns the round and the vault balance before and after
+fn lend_and_end_round(fund: u64, owed: u64, share_bps: u64, rate_bps: u64) -> (Round, u64, u64) {
+    let program_id = Pubkey::new_unique();
+    let (round, owner, vault, mint, collateral) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let reserve = LendingReserve {
+        reserve: Pubkey::new_unique(),
+        liquidity_supply: Pubkey::new_unique(),
+        collateral_mint: Pubkey::new_unique(),
+        lending_market: Pubkey::new_unique(),
+    };
+    let (vault_owner, _) = Pubkey::find_program_address(&[&owner.to_bytes()], &program_id);
+    let (market_authority, _) =
+        Pubkey::find_program_address(&[reserve.lending_market.as_ref()], &lending::id());
+
+    let mut accounts = Accounts::new();
+    common::add_program(lending::id(), process_reserve);
+    accounts.add(lending::id(), Pubkey::default(), vec![]);
+    for wallet in &[owner, vault_owner, market_authority, reserve.lending_market] {
+        accounts.add(*wallet, system_program::ID, vec![]);
+    }
+    accounts.add_packed(
+        round,
+        program_id,
+        Round {
+            status: RoundStatus::Voting,
+            owner,
+            vault,
+            mint,
+            fund,
+            // the votes, deposits and fees the vault holds besides the fund
+            votes: owed,
+            ..Round::default()
+        },
+    );
+    accounts.add(
+        reserve.reserve,
+        lending::id(),
+        rate_bps.to_le_bytes().to_vec(),
+    );
+    accounts.add_mint(mint, 6);
+    accounts.add_mint_with_authority(reserve.collateral_mint, COption::Some(market_authority), 6);
+    accounts.add_token_account(vault, mint, vault_owner, fund + owed);
+    accounts.add_token_account(collateral, reserve.collateral_mint, vault_owner, 0);
+    // the reserve holds enough to pay a yield
+    accounts.add_token_account(reserve.liquidity_supply, mint, market_authority, fund * 2);
+
+    let lent = fund * share_bps / 10_000;
+    accounts
+        .process(&instruction::lend_fund(
+            &program_id,
+            &round,
+            &owner,
+            &vault,
+            &collateral,
+            &reserve,
+            lent,
+        ))
+        .unwrap();
+    let lent_vault = accounts.token_amount(&vault);
+    assert_eq!(lent_vault, fund + owed - lent);
+    assert_eq!(accounts.token_amount(&collateral), lent);
+    assert_eq!(accounts.unpack::<Round>(&round).lent, lent);
+
+    let mut end_round = instruction::end_round(&program_id, &round, &owner);
+    end_round.accounts.extend(instruction::recall_fund_accounts(
+        &program_id,
+        &owner,
+        &vault,
+        &collateral,
+        &reserve,
+    ));
+    accounts.process(&end_round).unwrap();
+    assert_eq!(accounts.token_amount(&collateral), 0);
+
+    (
+        accounts.unpack::<Round>(&round),
+        lent_vault,
+        accounts.token_amount(&vault),
+    )
+}
+
+#[test]
+fn end_round_adds_the_yield_to_the_fund() {
+    // half of the fund is lent and earns 5%
+    let (round, lent_vault, vault) = lend_and_end_round(1_000_000, 300, 5_000, 10_500);
+    assert_eq!(vault, lent_vault + 525_000);
+    assert_eq!(round.fund, 1_025_000);
+    assert_eq!(vault, round.fund + 300);
+    assert_eq!(round.status, RoundStatus::Tallying);
+    assert_eq!((round.lent, round.collateral), (0, 0));
+    assert_eq!(round.collateral_account, Pubkey::default());
+}
+
+#[test]
+fn end_round_takes_a_shortfall_out_of_the_fund() {
+    // the reserve loses 20% of what was lent
+    let (round, lent_vault, vault) = lend_and_end_round(1_000_000, 300, 5_000, 8_000);
+    assert_eq!(vault, lent_vault + 400_000);
+    assert_eq!(round.fund, 900_000);
+    assert_eq!(vault, round.fund + 300);
+    assert_eq!((round.lent, round.collateral), (0, 0));
+}
+
+proptest! {
+    #[test]
+    fn the_vault_holds_the_fund_after_the_loan(
+        fund in 1..1_000_000_000_000u64,
+        owed in 0..1_000_000_000u64,
+        share_bps in 1..=10_000u64,
+        rate_bps in 0..20_000u64,
+    ) {
+        let lent = fund * share_bps / 10_000;
+        prop_assume!(lent > 0);
+        let (round, lent_vault, vault) = lend_and_end_round(fund, owed, share_bps, rate_bps);
+        let returned = lent * rate_bps / 10_000;
+        prop_assert_eq!(vault, lent_vault + returned);
+        prop_assert_eq!(round.fund, fund + returned - lent);
+        prop_assert_eq!(vault, owed + round.fund);
+        prop_assert_eq!(round.lent, 0);
+    }
+}
@@ -0,0 +1,849 @@
+//! Every account and instruction has to unpack to what was packed, so the
+//! hand-rolled arrayref layouts can not drift from the structs they encode.
+
+use proptest::{
+    array::uniform16, array::uniform24, array::uniform4, array::uniform8, collection::vec,
+    prelude::*,
+};
+use qf::{
+    allowlist::MAX_PROOF_LEN,
+    instruction::{QFInstruction, VoteBatchEntry},
+    state::*,
+};
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+use spl_math::uint::U256;
+use std::fmt::Debug;
+
+fn pubkey() -> impl Strategy<Value = Pubkey> {
+    prop_oneof![
+        Just(Pubkey::default()),
+        any::<[u8; 32]>().prop_map(Pubkey::new_from_array),
+    ]
+}
+
+fn u256() -> impl Strategy<Value = U256> {
+    prop_oneof![
+        Just(U256::zero()),
+        Just(U256::one()),
+        Just(U256::max_value()),
+        Just(U256::from(u64::MAX)),
+        Just(U256::from(u64::MAX) + U256::one()),
+        any::<[u64; 4]>().prop_map(U256),
+    ]
+}
+
+fn round_status() -> impl Strategy<Value = RoundStatus> {
+    prop_oneof![
+        Just(RoundStatus::Uninitialized),
+        Just(RoundStatus::Registration),
+        Just(RoundStatus::Voting),
+        Just(RoundStatus::Tallying),
+        Just(RoundStatus::Claim),
+        Just(RoundStatus::Closed),
+        Just(RoundStatus::Cancelled),
+    ]
+}
+
+fn round_mode() -> impl Strategy<Value = RoundMode> {
+    prop_oneof![
+        Just(RoundMode::Standard),
+        Just(RoundMode::Retro),
+        Just(RoundMode::Conviction),
+        Just(RoundMode::Epochs),
+    ]
+}
+
+fn sponsor_tier() -> impl Strategy<Value = SponsorTier> {
+    prop_oneof![
+        Just(SponsorTier::None),
+        Just(SponsorTier::Bronze),
+        Just(SponsorTier::Silver),
+        Just(SponsorTier::Gold),
+    ]
+}
+
+fn appeal_status() -> impl Strategy<Value = AppealStatus> {
+    prop_oneof![
+        Just(AppealStatus::None),
+        Just(AppealStatus::Pending),
+        Just(AppealStatus::Upheld),
+        Just(AppealStatus::Overturned),
+    ]
+}
+
+fn action_kind() -> impl Strategy<Value = ActionKind> {
+    prop_oneof![
+        Just(ActionKind::WithdrawFee),
+        Just(ActionKind::RecoverAbandonedFunds),
+    ]
+}
+
+prop_compose! {
+    fn round_config()(
+        (aggregator, registration_deposit, min_unique_voters, min_total_votes, min_fund,
+            vote_grace_period, bronze_threshold, silver_threshold, gold_threshold) in
+            (pubkey(), any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>(),
+                any::<i64>(), any::<u64>(), any::<u64>(), any::<u64>()),
+        (fund_target, moderator, committee_share_bps, mode, voter_credits, epoch_length,
+            epoch_count, voting_end, tally_period) in
+            (any::<u64>(), pubkey(), any::<u16>(), round_mode(), any::<u64>(), any::<i64>(),
+                any::<u64>(), any::<i64>(), any::<i64>()),
+        (stake_pool, collection, verified_only, appeal_bond, dispute_window, claim_period,
+            operator_fee_bps, recovery_grace, max_projects, crank_reward, downvote_share_bps) in
+            (pubkey(), pubkey(), any::<bool>(), any::<u64>(), any::<i64>(), any::<i64>(),
+                any::<u16>(), any::<i64>(), any::<u64>(), any::<u64>(), any::<u16>()),
+        (flag_stake, flag_bounty_bps, vote_cooldown, endorsement_bonus_bps, min_projects,
+            holdback_bps) in
+            (any::<u64>(), any::<u16>(), any::<u64>(), any::<u16>(), any::<u64>(), any::<u16>()),
+    ) -> RoundConfig {
+        RoundConfig {
+            aggregator,
+            registration_deposit,
+            min_unique_voters,
+            min_total_votes,
+            min_fund,
+            vote_grace_period,
+            bronze_threshold,
+            silver_threshold,
+            gold_threshold,
+            fund_target,
+            moderator,
+            committee_share_bps,
+            mode,
+            voter_credits,
+            epoch_length,
+            epoch_count,
+            voting_end,
+            tally_period,
+            stake_pool,
+            collection,
+            verified_only,
+            appeal_bond,
+            dispute_window,
+            claim_period,
+            operator_fee_bps,
+            recovery_grace,
+            max_projects,
+            crank_reward,
+            downvote_share_bps,
+            flag_stake,
+            flag_bounty_bps,
+            vote_cooldown,
+            endorsement_bonus_bps,
+            min_projects,
+            holdback_bps,
+        }
+    }
+}
+
+prop_compose! {
+    fn tier()(threshold in any::<u64>(), perks in any::<u32>()) -> Tier {
+        Tier { threshold, perks }
+    }
+}
+
+prop_compose! {
+    fn category()(share_bps in any::<u16>(), area in u256()) -> Category {
+        Category { share_bps, area }
+    }
+}
+
+prop_compose! {
+    fn round()(
+        (status, fund, operator_fee, vault, owner, area, batch_nonce, config, mint) in
+            (round_status(), any::<u64>(), any::<u64>(), pubkey(), pubkey(), u256(),
+                any::<u64>(), round_config(), pubkey()),
+        (deposits, voters, votes, pledged, unallocated, previous_round, next_round,
+            category_count, categories) in
+            (any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>(), pubkey(),
+                pubkey(), any::<u8>(), uniform8(category())),
+        (discretionary, conviction, conviction_at, voting_started_at, pending_appeals,
+            voting_ended_at, project_count, fund_remaining, swept, protocol_fee) in
+            (any::<u64>(), u256(), any::<i64>(), any::<i64>(), any::<u64>(), any::<i64>(),
+                any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>()),
+        (vote_log, vote_log_len, pending_flags, registration_root, holdbacks, withdrawals,
+            reclaimed, vote_log_frontier) in
+            (any::<[u8; 32]>(), any::<u64>(), any::<u64>(), any::<[u8; 32]>(), any::<u64>(),
+                any::<u64>(), any::<u64>(), uniform24(any::<[u8; 32]>())),
+        (lent, collateral, collateral_account) in (any::<u64>(), any::<u64>(), pubkey()),
+    ) -> Round {
+        Round {
+            status,
+            fund,
+            operator_fee,
+            vault,
+            owner,
+            area,
+            batch_nonce,
+            config,
+            mint,
+            deposits,
+            voters,
+            votes,
+            pledged,
+            unallocated,
+            previous_round,
+            next_round,
+            category_count,
+            categories,
+            discretionary,
+            conviction,
+            conviction_at,
+            voting_started_at,
+            pending_appeals,
+            voting_ended_at,
+            project_count,
+            fund_remaining,
+            swept,
+            protocol_fee,
+            vote_log,
+            vote_log_len,
+            pending_flags,
+            registration_root,
+            holdbacks,
+            withdrawals,
+            reclaimed,
+            vote_log_frontier,
+            lent,
+            collateral,
+            collateral_account,
+        }
+    }
+}
+
+prop_compose! {
+    fn project()(
+        (round, owner, withdraw, votes, area, area_sqrt, payout_wallet, deposit, banned,
+            category) in
+            (pubkey(), pubkey(), any::<bool>(), any::<u64>(), u256(), u256(), pubkey(),
+                any::<u64>(), any::<bool>(), any::<u8>()),
+        (discretionary, conviction, conviction_at, metadata, verified, banned_area,
+            banned_deposit, appeal, appeal_bond) in
+            (any::<u64>(), u256(), any::<i64>(), pubkey(), any::<bool>(), u256(),
+                any::<u64>(), appeal_status(), any::<u64>()),
+        (downvotes, down_sqrt, tiers, updates, endorsements, holdback, index) in
+            (any::<u64>(), u256(), uniform4(tier()), any::<u64>(), any::<u64>(), any::<u64>(),
+                any::<u64>()),
+    ) -> Project {
+        Project {
+            round,
+            owner,
+            withdraw,
+            votes,
+            area,
+            area_sqrt,
+            payout_wallet,
+            deposit,
+            banned,
+            category,
+            discretionary,
+            conviction,
+            conviction_at,
+            metadata,
+            verified,
+            banned_area,
+            banned_deposit,
+            appeal,
+            appeal_bond,
+            downvotes,
+            down_sqrt,
+            tiers,
+            updates,
+            endorsements,
+            holdback,
+            index,
+        }
+    }
+}
+
+prop_compose! {
+    fn voter()(
+        (is_initialized, votes, votes_sqrt, permit_nonce, last_vote_amount, last_vote_at,
+            airdrop_claimed, weight, blacklisted, downvotes, last_vote_slot, tier) in
+            (any::<bool>(), any::<u64>(), u256(), any::<u64>(), any::<u64>(), any::<i64>(),
+                any::<bool>(), any::<u64>(), any::<bool>(), any::<u64>(), any::<u64>(),
+                any::<u8>()),
+        (endorsed, custodian, last_vote_weight) in (any::<bool>(), pubkey(), any::<u64>()),
+    ) -> Voter {
+        Voter {
+            is_initialized,
+            votes,
+            votes_sqrt,
+            permit_nonce,
+            last_vote_amount,
+            last_vote_at,
+            airdrop_claimed,
+            weight,
+            blacklisted,
+            downvotes,
+            last_vote_slot,
+            tier,
+            endorsed,
+            custodian,
+            last_vote_weight,
+        }
+    }
+}
+
+prop_compose! {
+    fn session()(
+        (is_initialized, authority, session_key, source, max_amount, spent, expiry) in
+            (any::<bool>(), pubkey(), pubkey(), pubkey(), any::<u64>(), any::<u64>(),
+                any::<i64>()),
+    ) -> Session {
+        Session { is_initialized, authority, session_key, source, max_amount, spent, expiry }
+    }
+}
+
+prop_compose! {
+    fn delegation()(
+        (round, delegator, delegate, source, budget, spent) in
+            (pubkey(), pubkey(), pubkey(), pubkey(), any::<u64>(), any::<u64>()),
+    ) -> Delegation {
+        Delegation { round, delegator, delegate, source, budget, spent }
+    }
+}
+
+prop_compose! {
+    fn sponsor()(
+        (round, source, authority, contributed, first_donated_at, last_donated_at, reclaimed,
+            tier) in
+            (pubkey(), pubkey(), pubkey(), any::<u64>(), any::<i64>(), any::<i64>(),
+                any::<bool>(), sponsor_tier()),
+    ) -> Sponsor {
+        Sponsor {
+            round,
+            source,
+            authority,
+            contributed,
+            first_donated_at,
+            last_donated_at,
+            reclaimed,
+            tier,
+        }
+    }
+}
+
+prop_compose! {
+    fn pledge()(
+        (round, source, escrow, amount, executed) in
+            (pubkey(), pubkey(), pubkey(), any::<u64>(), any::<bool>()),
+    ) -> Pledge {
+        Pledge { round, source, escrow, amount, executed }
+    }
+}
+
+prop_compose! {
+    fn subscription()(
+        (project, source, escrow, authority, cranker, amount, interval, next_vote_at,
+            remaining) in
+            (pubkey(), pubkey(), pubkey(), pubkey(), pubkey(), any::<u64>(), any::<i64>(),
+                any::<i64>(), any::<u64>()),
+    ) -> Subscription {
+        Subscription {
+            project,
+            source,
+            escrow,
+            authority,
+            cranker,
+            amount,
+            interval,
+            next_vote_at,
+            remaining,
+        }
+    }
+}
+
+prop_compose! {
+    fn round_template()(owner in pubkey(), id in any::<u64>(), config in round_config())
+        -> RoundTemplate {
+        RoundTemplate { owner, id, config }
+    }
+}
+
+prop_compose! {
+    fn credits()(round in pubkey(), voter in pubkey(), spent in any::<u64>()) -> Credits {
+        Credits { round, voter, spent }
+    }
+}
+
+prop_compose! {
+    fn voter_registry()(
+        (round, wallet, projects, votes, weight, last_vote_at, downvotes, tree_slot) in
+            (pubkey(), pubkey(), any::<u64>(), any::<u64>(), any::<u64>(), any::<i64>(),
+                any::<u64>(), any::<u64>()),
+    ) -> VoterRegistry {
+        VoterRegistry {
+            round,
+            wallet,
+            projects,
+            votes,
+            weight,
+            last_vote_at,
+            downvotes,
+            tree_slot,
+        }
+    }
+}
+
+prop_compose! {
+    fn change_log()(
+        root in any::<[u8; 32]>(),
+        path in vec(any::<[u8; 32]>(), VOTER_TREE_DEPTH),
+        index in any::<u64>(),
+    ) -> ChangeLog {
+        let mut change_log = ChangeLog { root, index, ..ChangeLog::default() };
+        change_log.path.copy_from_slice(&path);
+        change_log
+    }
+}
+
+prop_compose! {
+    fn voter_tree()(
+        (round, wallets, sequence, active_index, buffer_size) in
+            (pubkey(), any::<u64>(), any::<u64>(), 0..VOTER_TREE_BUFFER as u64, any::<u64>()),
+    ) -> VoterTree {
+        VoterTree { round, wallets, sequence, active_index, buffer_size }
+    }
+}
+
+prop_compose! {
+    fn claim()((project, amount, claimed) in (pubkey(), any::<u64>(), any::<bool>())) -> Claim {
+        Claim { project, amount, claimed }
+    }
+}
+
+prop_compose! {
+    fn flag()(
+        (round, project, flagger, stake) in (pubkey(), pubkey(), pubkey(), any::<u64>()),
+    ) -> Flag {
+        Flag { round, project, flagger, stake }
+    }
+}
+
+prop_compose! {
+    fn project_update()(
+        (project, author, index, posted_at, hash) in
+            (pubkey(), pubkey(), any::<u64>(), any::<i64>(), any::<[u8; 32]>()),
+        body_len in 0..=MAX_UPDATE_LEN as u16,
+        body in vec(any::<u8>(), MAX_UPDATE_LEN),
+    ) -> ProjectUpdate {
+        let mut update = ProjectUpdate { project, author, index, posted_at, hash, body_len,
+            ..ProjectUpdate::default() };
+        update.body.copy_from_slice(&body);
+        update
+    }
+}
+
+prop_compose! {
+    fn round_archive()(
+        (round, projects, hash, complete, fund, votes) in
+            (pubkey(), any::<u64>(), any::<[u8; 32]>(), any::<bool>(), any::<u64>(), any::<u64>()),
+    ) -> RoundArchive {
+        RoundArchive { round, projects, hash, complete, fund, votes }
+    }
+}
+
+prop_compose! {
+    fn epoch_tally()(
+        (parent, epoch, votes, sqrt, area, withdrawn, category) in
+            (pubkey(), any::<u64>(), any::<u64>(), u256(), u256(), any::<bool>(), any::<u8>()),
+    ) -> EpochTally {
+        EpochTally { parent, epoch, votes, sqrt, area, withdrawn, category }
+    }
+}
+
+prop_compose! {
+    fn airdrop()(
+        (round, reward_mint, reward_vault, amount, claimed) in
+            (pubkey(), pubkey(), pubkey(), any::<u64>(), any::<u64>()),
+    ) -> Airdrop {
+        Airdrop { round, reward_mint, reward_vault, amount, claimed }
+    }
+}
+
+prop_compose! {
+    fn curator()(round in pubkey(), curator in pubkey()) -> Curator {
+        Curator { round, curator }
+    }
+}
+
+prop_compose! {
+    fn curator_attestation()(project in pubkey(), curator in pubkey(), attested_at in any::<i64>())
+        -> CuratorAttestation {
+        CuratorAttestation { project, curator, attested_at }
+    }
+}
+
+prop_compose! {
+    fn config()(
+        (admin, protocol_fee_bps, fee_recipient, fee_exempt, pending_fee_recipient, timelock,
+            max_round_duration, approved_mints) in
+            (pubkey(), any::<u16>(), pubkey(), uniform16(pubkey()), pubkey(), any::<i64>(),
+                any::<i64>(), uniform8(pubkey())),
+    ) -> Config {
+        Config {
+            admin,
+            protocol_fee_bps,
+            fee_recipient,
+            fee_exempt,
+            pending_fee_recipient,
+            timelock,
+            max_round_duration,
+            approved_mints,
+        }
+    }
+}
+
+prop_compose! {
+    fn pending_action()(
+        (round, kind, to, executable_at) in (pubkey(), action_kind(), pubkey(), any::<i64>()),
+    ) -> PendingAction {
+        PendingAction { round, kind, to, executable_at }
+    }
+}
+
+prop_compose! {
+    fn vote_batch_entry()(voter in pubkey(), project in pubkey(), amount in any::<u64>())
+        -> VoteBatchEntry {
+        VoteBatchEntry { voter, project, amount }
+    }
+}
+
+fn amount_decimals() -> impl Strategy<Value = (u64, u8)> {
+    (any::<u64>(), any::<u8>())
+}
+
+fn instruction() -> impl Strategy<Value = QFInstruction> {
+    use QFInstruction::*;
+    prop_oneof![
+        round_config().prop_map(|config| StartRound { config }),
+        amount_decimals().prop_map(|(amount, decimals)| Donate { amount, decimals }),
+        (
+            any::<u8>(),
+            any::<u8>(),
+            vec(any::<[u8; 32]>(), 0..=MAX_PROOF_LEN)
+        )
+            .prop_map(|(category, index, proof)| RegisterProject {
+                category,
+                index,
+                proof
+            }),
+        Just(InitVoter),
+        amount_decimals().prop_map(|(amount, decimals)| Vote { amount, decimals }),
+        Just(Withdraw),
+        Just(EndRound),
+        Just(WithdrawFee),
+        Just(BanProject),
+        (any::<u64>(), vec(vote_batch_entry(), 0..8))
+            .prop_map(|(nonce, entries)| SubmitVoteBatch { nonce, entries }),
+        (any::<u64>(), any::<u8>(), any::<i64>(), any::<u64>()).prop_map(
+            |(amount, decimals, expiry, nonce)| VoteWithPermit {
+                amount,
+                decimals,
+                expiry,
+                nonce,
+            }
+        ),
+        (any::<u64>(), any::<i64>())
+            .prop_map(|(max_amount, expiry)| CreateSession { max_amount, expiry }),
+        amount_decimals().prop_map(|(amount, decimals)| SessionVote { amount, decimals }),
+        any::<u64>().prop_map(|budget| DelegateVotes { budget }),
+        amount_decimals().prop_map(|(amount, decimals)| VoteAsDelegate { amount, decimals }),
+        Just(SetPayoutWallet),
+        Just(StartVoting),
+        Just(Finalize),
+        Just(CloseRound),
+        Just(Refund),
+        Just(RefundDonation),
+        amount_decimals().prop_map(|(amount, decimals)| Pledge { amount, decimals }),
+        Just(ExecutePledge),
+        Just(ReclaimPledge),
+        Just(RevokeVote),
+        Just(ReclaimMatching),
+        Just(RolloverFunds),
+        Just(LinkRounds),
+        (any::<u64>(), round_config()).prop_map(|(id, config)| CreateRoundTemplate { id, config }),
+        Just(StartRoundFromTemplate),
+        vec(any::<u16>(), 0..=MAX_CATEGORIES).prop_map(|shares| SetCategories { shares }),
+        any::<u8>().prop_map(|category| SetProjectCategory { category }),
+        any::<u64>().prop_map(|amount| AllocateDiscretionary { amount }),
+        any::<u64>().prop_map(|amount| CastCredits { amount }),
+        amount_decimals().prop_map(|(amount, decimals)| EpochVote { amount, decimals }),
+        any::<u64>().prop_map(|epoch| WithdrawEpoch { epoch }),
+        (any::<u64>(), any::<i64>(), any::<u64>(), any::<u8>()).prop_map(
+            |(amount, interval, total, decimals)| CreateRecurringVote {
+                amount,
+                interval,
+                total,
+                decimals,
+            }
+        ),
+        Just(ExecuteRecurringVote),
+        Just(CancelRecurringVote),
+        Just(CrankEndRound),
+        Just(CrankFinalize),
+        Just(MintReceipt),
+        amount_decimals().prop_map(|(amount, decimals)| CreateAirdrop { amount, decimals }),
+        Just(ClaimAirdrop),
+        amount_decimals().prop_map(|(amount, decimals)| StakePoolVote { amount, decimals }),
+        Just(AddCurator),
+        Just(AttestProject),
+        any::<bool>().prop_map(|reverse| BlacklistVoter { reverse }),
+        Just(AppealBan),
+        any::<bool>().prop_map(|overturn| ResolveAppeal { overturn }),
+        Just(VerifyInvariants),
+        Just(SweepDust),
+        (any::<u16>(), pubkey()).prop_map(|(protocol_fee_bps, fee_recipient)| InitConfig {
+            protocol_fee_bps,
+            fee_recipient,
+        }),
+        any::<u16>().prop_map(|protocol_fee_bps| SetProtocolFee { protocol_fee_bps }),
+        (pubkey(), any::<bool>()).prop_map(|(key, exempt)| SetFeeExempt { key, exempt }),
+        pubkey().prop_map(|fee_recipient| SetFeeRecipient { fee_recipient }),
+        Just(AcceptFeeRecipient),
+        Just(RecoverAbandonedFunds),
+        action_kind().prop_map(|kind| ProposeAction { kind }),
+        Just(CancelAction),
+        any::<i64>().prop_map(|timelock| SetTimelock { timelock }),
+        Just(MigrateVoter),
+        Just(ArchiveRound),
+        Just(WriteClaims),
+        amount_decimals().prop_map(|(amount, decimals)| Downvote { amount, decimals }),
+        Just(FlagProject),
+        any::<bool>().prop_map(|uphold| ResolveFlag { uphold }),
+        any::<[u8; 32]>().prop_map(|root| SetRegistrationAllowlist { root }),
+        vec(tier(), 0..=MAX_TIERS).prop_map(|tiers| SetProjectTiers { tiers }),
+        (any::<[u8; 32]>(), vec(any::<u8>(), 0..=MAX_UPDATE_LEN))
+            .prop_map(|(hash, body)| PostProjectUpdate { hash, body }),
+        Just(Endorse),
+        any::<i64>().prop_map(|max_round_duration| SetMaxRoundDuration { max_round_duration }),
+        any::<i64>().prop_map(|voting_end| ExtendRound { voting_end }),
+        (any::<u8>(), vec(any::<u8>(), 0..512))
+            .prop_map(|(decimals, route)| VoteWithSwap { decimals, route }),
+        (pubkey(), any::<bool>()).prop_map(|(mint, approved)| SetApprovedMint { mint, approved }),
+        Just(ReleaseHoldback),
+        Just(InitVoterTree),
+        (
+            amount_decimals(),
+            any::<[u8; 32]>(),
+            any::<u64>(),
+            any::<u64>(),
+            vec(any::<[u8; 32]>(), 0..=VOTER_TREE_DEPTH)
+        )
+            .prop_map(|((amount, decimals), root, votes, last_vote_slot, proof)| {
+                CompressedVote {
+                    amount,
+                    decimals,
+                    root,
+                    votes,
+                    last_vote_slot,
+                    proof,
+                }
+            }),
+        (
+            any::<[u8; 32]>(),
+            any::<u64>(),
+            any::<u64>(),
+            vec(any::<[u8; 32]>(), 0..=VOTER_TREE_DEPTH)
+        )
+            .prop_map(|(root, votes, last_vote_slot, proof)| CompressedRefund {
+                root,
+                votes,
+                last_vote_slot,
+                proof
+            }),
+        any::<u64>().prop_map(|amount| LendFund { amount }),
+        Just(MintParticipation),
+    ]
+}
+
+fn roundtrip<T: Pack + PartialEq + Debug>(state: T) -> Result<(), TestCaseError> {
+    let mut dst = vec![0; T::LEN];
+    state.pack_into_slice(&mut dst);
+    prop_assert_eq!(T::unpack_from_slice(&dst).unwrap(), state);
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn round_config_roundtrip(state in round_config()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn category_roundtrip(state in category()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn tier_roundtrip(state in tier()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn round_roundtrip(state in round()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn project_roundtrip(state in project()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn voter_roundtrip(state in voter()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn session_roundtrip(state in session()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn delegation_roundtrip(state in delegation()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn sponsor_roundtrip(state in sponsor()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn pledge_roundtrip(state in pledge()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn subscription_roundtrip(state in subscription()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn round_template_roundtrip(state in round_template()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn credits_roundtrip(state in credits()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn voter_registry_roundtrip(state in voter_registry()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn change_log_roundtrip(state in change_log()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn voter_tree_roundtrip(state in voter_tree()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn round_archive_roundtrip(state in round_archive()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn claim_roundtrip(state in claim()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn flag_roundtrip(state in flag()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn project_update_roundtrip(state in project_update()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn epoch_tally_roundtrip(state in epoch_tally()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn airdrop_roundtrip(state in airdrop()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn curator_roundtrip(state in curator()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn curator_attestation_roundtrip(state in curator_attestation()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn config_roundtrip(state in config()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn pending_action_roundtrip(state in pending_action()) {
+        roundtrip(state)?;
+    }
+
+    #[test]
+    fn instruction_roundtrip(instruction in instruction()) {
+        let packed = instruction.pack();
+        prop_assert_eq!(QFInstruction::unpack(&packed).unwrap(), instruction);
+    }
+}
+
+#[test]
+fn discriminators_are_unique() {
+    let discriminators = [
+        Round::DISCRIMINATOR,
+        Project::DISCRIMINATOR,
+        Voter::DISCRIMINATOR,
+        Session::DISCRIMINATOR,
+        Delegation::DISCRIMINATOR,
+        Sponsor::DISCRIMINATOR,
+        Pledge::DISCRIMINATOR,
+        Subscription::DISCRIMINATOR,
+        RoundTemplate::DISCRIMINATOR,
+        Credits::DISCRIMINATOR,
+        VoterRegistry::DISCRIMINATOR,
+        EpochTally::DISCRIMINATOR,
+        Airdrop::DISCRIMINATOR,
+        Curator::DISCRIMINATOR,
+        CuratorAttestation::DISCRIMINATOR,
+        Config::DISCRIMINATOR,
+        PendingAction::DISCRIMINATOR,
+        RoundArchive::DISCRIMINATOR,
+        Claim::DISCRIMINATOR,
+        Flag::DISCRIMINATOR,
+        ProjectUpdate::DISCRIMINATOR,
+        VoterTree::DISCRIMINATOR,
+    ];
+    for (i, a) in discriminators.iter().enumerate() {
+        for b in &discriminators[i + 1..] {
+            assert_ne!(a[..DISCRIMINATOR_LEN - 1], b[..DISCRIMINATOR_LEN - 1]);
+        }
+    }
+}
+
+#[test]
+fn discriminator_of_another_account_is_rejected() {
+    let mut dst = vec![0; Flag::LEN];
+    Flag::default().pack_into_slice(&mut dst);
+    assert_eq!(&dst[..DISCRIMINATOR_LEN], &Flag::DISCRIMINATOR);
+    // a voter registry has the size of a flag
+    assert_eq!(
+        VoterRegistry::unpack_unchecked(&dst),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
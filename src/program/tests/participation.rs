@@ -0,0 +1,171 @@
+//! MintParticipation tops the non-transferable tokens of a wallet up to what its registry
+//! says it voted in the round, and never mints more than that.
+
+mod common;
+
+use common::Accounts;
+use qf::{
+    error::QFError,
+    instruction,
+    state::{Round, RoundStatus, VoterRegistry},
+    token_2022,
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey, system_program,
+};
+use spl_token::state::{Account as TokenAccount, AccountState};
+use std::convert::TryInto;
+
+/// Token-2022 as far as MintTo goes, on accounts laid out like spl-token's
+fn process_token_2022(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    assert_eq!(data[0], 7, "unexpected Token-2022 instruction");
+    assert!(accounts[2].is_signer);
+    let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
+    let mut account = accounts[1].data.borrow_mut();
+    let mut state = TokenAccount::unpack_from_slice(&account[..TokenAccount::LEN])?;
+    assert_eq!(&state.mint, accounts[0].key);
+    state.amount += amount;
+    state.pack_into_slice(&mut account[..TokenAccount::LEN]);
+    Ok(())
+}
+
+struct Participation {
+    program_id: Pubkey,
+    accounts: Accounts,
+    round: Pubkey,
+    round_mint: Pubkey,
+    wallet: Pubkey,
+    registry: Pubkey,
+    to: Pubkey,
+}
+
+impl Participation {
+    /// A round whose participation mint and the wallet's token account exist, the wallet
+    /// voted `votes` tokens
+    fn new(votes: u64) -> Self {
+        let program_id = Pubkey::new_unique();
+        let (round, round_mint, wallet) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (registry, _) = Pubkey::find_program_address(
+            &[b"registry", &round.to_bytes(), &wallet.to_bytes()],
+            &program_id,
+        );
+        let (mint, _) =
+            Pubkey::find_program_address(&[b"participation", &round.to_bytes()], &program_id);
+        let to = token_2022::get_associated_token_address(&wallet, &mint);
+
+        let mut accounts = Accounts::new();
+        common::add_program(token_2022::id(), process_token_2022);
+        accounts.add(token_2022::id(), Pubkey::default(), vec![]);
+        accounts.add(
+            spl_associated_token_account::id(),
+            Pubkey::default(),
+            vec![],
+        );
+        accounts.add(wallet, system_program::ID, vec![]);
+        accounts.add_packed(
+            round,
+            program_id,
+            Round {
+                status: RoundStatus::Voting,
+                mint: round_mint,
+                ..Round::default()
+            },
+        );
+        accounts.add_mint(round_mint, 6);
+        accounts.add(
+            mint,
+            token_2022::id(),
+            vec![0; token_2022::NON_TRANSFERABLE_MINT_LEN],
+        );
+        let mut data = vec![0; TokenAccount::LEN + 1];
+        TokenAccount {
+            mint,
+            owner: wallet,
+            state: AccountState::Initialized,
+            delegate: COption::None,
+            is_native: COption::None,
+            close_authority: COption::None,
+            ..TokenAccount::default()
+        }
+        .pack_into_slice(&mut data[..TokenAccount::LEN]);
+        accounts.add(to, token_2022::id(), data);
+
+        accounts.add_packed(
+            registry,
+            program_id,
+            VoterRegistry {
+                round,
+                wallet,
+                votes,
+                ..VoterRegistry::default()
+            },
+        );
+
+        Participation {
+            program_id,
+            accounts,
+            round,
+            round_mint,
+            wallet,
+            registry,
+            to,
+        }
+    }
+
+    fn set_votes(&mut self, votes: u64) {
+        let mut registry = self.accounts.unpack::<VoterRegistry>(&self.registry);
+        registry.votes = votes;
+        self.accounts.pack(&self.registry, registry);
+    }
+
+    fn mint(&mut self) -> ProgramResult {
+        let payer = Pubkey::new_unique();
+        self.accounts.add(payer, system_program::ID, vec![]);
+        self.accounts.process(&instruction::mint_participation(
+            &self.program_id,
+            &self.round,
+            &self.round_mint,
+            &self.wallet,
+            &payer,
+        ))
+    }
+
+    fn held(&self) -> u64 {
+        self.accounts.unpack::<TokenAccount>(&self.to).amount
+    }
+}
+
+#[test]
+fn participation_follows_the_votes_of_the_wallet() {
+    let mut participation = Participation::new(300);
+    participation.mint().unwrap();
+    assert_eq!(participation.held(), 300);
+
+    // nothing more until the wallet votes again
+    participation.mint().unwrap();
+    assert_eq!(participation.held(), 300);
+
+    participation.set_votes(750);
+    participation.mint().unwrap();
+    assert_eq!(participation.held(), 750);
+
+    // revoked votes are not taken back
+    participation.set_votes(100);
+    participation.mint().unwrap();
+    assert_eq!(participation.held(), 750);
+}
+
+#[test]
+fn participation_needs_votes() {
+    let mut participation = Participation::new(0);
+    assert_eq!(participation.mint(), Err(QFError::NoVotes.into()));
+}
@@ -0,0 +1,146 @@
+//! Differential tests of the on-chain matching math against a plain f64 model
+//! of the QF/CLR formula: a project's match is the fund times the square of the
+//! sum of its voters' square roots, over the same for every project.
+
+use proptest::{collection::vec, prelude::*};
+use qf::{
+    processor::Processor,
+    state::{Category, Project, Round, RoundStatus, Voter},
+};
+
+/// Payouts the formula gives, votes plus match, per project
+fn model(fund: u64, votes: &[Vec<u64>], categories: &[(u16, Vec<usize>)]) -> Vec<f64> {
+    let areas: Vec<f64> = votes
+        .iter()
+        .map(|voters| {
+            let sqrt_sum: f64 = voters.iter().map(|&v| (v as f64).sqrt()).sum();
+            sqrt_sum * sqrt_sum
+        })
+        .collect();
+    let mut payouts: Vec<f64> = votes
+        .iter()
+        .map(|voters| voters.iter().sum::<u64>() as f64)
+        .collect();
+
+    let pools: Vec<(f64, Vec<usize>)> = if categories.is_empty() {
+        vec![(fund as f64, (0..votes.len()).collect())]
+    } else {
+        categories
+            .iter()
+            .map(|(share_bps, projects)| {
+                (fund as f64 * *share_bps as f64 / 10_000.0, projects.clone())
+            })
+            .collect()
+    };
+    for (pool, projects) in pools {
+        let total: f64 = projects.iter().map(|&p| areas[p]).sum();
+        if total > 0.0 {
+            for &p in projects.iter() {
+                payouts[p] += pool * areas[p] / total;
+            }
+        }
+    }
+    payouts
+}
+
+/// Casts the votes one by one the way Vote does and returns what Withdraw would pay
+fn on_chain(fund: u64, votes: &[Vec<u64>], categories: &[(u16, Vec<usize>)]) -> Vec<u64> {
+    let mut round = Round {
+        status: RoundStatus::Voting,
+        fund,
+        ..Round::default()
+    };
+    let mut category_of = vec![0u8; votes.len()];
+    for (i, (share_bps, projects)) in categories.iter().enumerate() {
+        round.categories[i] = Category {
+            share_bps: *share_bps,
+            ..Category::default()
+        };
+        for &p in projects {
+            category_of[p] = i as u8;
+        }
+    }
+    round.category_count = categories.len() as u8;
+
+    let mut projects: Vec<Project> = category_of
+        .iter()
+        .map(|&category| Project {
+            category,
+            ..Project::default()
+        })
+        .collect();
+    for (project, voters) in projects.iter_mut().zip(votes) {
+        for &amount in voters {
+            let mut voter = Voter::default();
+            Processor::apply_vote(&mut round, project, &mut voter, amount, 0).unwrap();
+        }
+    }
+
+    round.status = RoundStatus::Claim;
+    round.votes = votes.iter().flatten().sum();
+    round.fund_remaining = fund.checked_add(round.votes).unwrap();
+    projects
+        .iter_mut()
+        .map(|project| Processor::payout(&round, project))
+        .collect()
+}
+
+/// Projects with the tokens each of their voters voted
+fn votes() -> impl Strategy<Value = Vec<Vec<u64>>> {
+    vec(vec(1..1_000_000_000_000u64, 0..12), 1..8)
+}
+
+/// Relative error the U256 fixed point sqrt and integer division are allowed
+const MAX_REL_ERROR: f64 = 1e-12;
+
+fn assert_close(fund: u64, payouts: &[u64], expected: &[f64]) -> Result<(), TestCaseError> {
+    let total = expected.iter().sum::<f64>().max(1.0);
+    for (&actual, &expected) in payouts.iter().zip(expected) {
+        // each match is floored, so a project can get up to one token less
+        let error = (actual as f64 - expected).abs();
+        prop_assert!(
+            error <= 1.0 + total * MAX_REL_ERROR,
+            "payout {} expected {} (fund {})",
+            actual,
+            expected,
+            fund
+        );
+    }
+    // rounding must never pay out more than the fund and the votes
+    let paid: u128 = payouts.iter().map(|&p| p as u128).sum();
+    prop_assert!(paid <= total.round() as u128 + payouts.len() as u128);
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn payouts_match_the_model(fund in 0..1_000_000_000_000_000u64, votes in votes()) {
+        let payouts = on_chain(fund, &votes, &[]);
+        assert_close(fund, &payouts, &model(fund, &votes, &[]))?;
+    }
+
+    #[test]
+    fn category_payouts_match_the_model(
+        fund in 0..1_000_000_000_000_000u64,
+        votes in votes(),
+        split in 0..=10_000u16,
+    ) {
+        // first half of the projects in one category, the rest in the other
+        let half = votes.len() - votes.len() / 2;
+        let categories = [
+            (split, (0..half).collect::<Vec<_>>()),
+            (10_000 - split, (half..votes.len()).collect::<Vec<_>>()),
+        ];
+        let payouts = on_chain(fund, &votes, &categories);
+        assert_close(fund, &payouts, &model(fund, &votes, &categories))?;
+    }
+
+    #[test]
+    fn a_single_voter_project_takes_the_whole_fund(
+        fund in 0..1_000_000_000_000_000u64,
+        amount in 1..1_000_000_000_000u64,
+    ) {
+        let payouts = on_chain(fund, &[vec![amount]], &[]);
+        prop_assert_eq!(payouts[0], fund + amount);
+    }
+}
@@ -0,0 +1,41 @@
+//! The quorum counts wallets, a wallet voting for several projects through one Voter
+//! account per project is a single voter of the round.
+
+use proptest::{collection::vec, prelude::*};
+use qf::{
+    processor::Processor,
+    state::{Project, Round, RoundStatus, Voter, VoterRegistry},
+};
+
+proptest! {
+    #[test]
+    fn one_wallet_voting_for_several_projects_misses_the_quorum(
+        votes in vec(1..1_000_000_000_000u64, 2..12),
+        revoked in any::<prop::sample::Index>(),
+    ) {
+        let mut round = Round {
+            status: RoundStatus::Voting,
+            fund: 1_000_000,
+            ..Round::default()
+        };
+        round.config.min_unique_voters = 2;
+        let mut registry = VoterRegistry::default();
+        let mut projects = vec![Project::default(); votes.len()];
+        let mut voters = vec![Voter::default(); votes.len()];
+        for ((project, voter), &amount) in projects.iter_mut().zip(voters.iter_mut()).zip(&votes) {
+            Processor::apply_vote(&mut round, project, voter, amount, 0).unwrap();
+            Processor::add_registry_vote(&mut round, &mut registry, amount, amount, 0);
+        }
+        prop_assert_eq!(round.voters, 1);
+
+        // taking back one of its votes keeps the wallet counted while others remain
+        let i = revoked.index(votes.len());
+        Processor::revert_vote(&mut round, &mut projects[i], &mut voters[i], 0).unwrap();
+        Processor::remove_registry_vote(&mut round, &mut registry, votes[i], votes[i]);
+        prop_assert_eq!(round.voters, 1);
+
+        round.status = RoundStatus::Tallying;
+        Processor::finalize(&mut round, 0).unwrap();
+        prop_assert_eq!(round.status, RoundStatus::Cancelled);
+    }
+}
@@ -0,0 +1,45 @@
+//! A revoked vote takes out the weight it was credited with, which is not its tokens when
+//! votes are normalized, so the voter, the project, the round and the registry are back
+//! to where they were before the vote.
+
+use proptest::prelude::*;
+use qf::{
+    processor::Processor,
+    state::{Project, Round, RoundStatus, Voter, VoterRegistry},
+};
+
+proptest! {
+    #[test]
+    fn revoking_a_weighted_vote_restores_the_tallies(
+        (first, first_weight) in (1..1_000_000_000u64, 1..1_000_000_000u64),
+        (last, last_weight) in (1..1_000_000_000u64, 1..1_000_000_000u64),
+    ) {
+        let mut round = Round {
+            status: RoundStatus::Voting,
+            ..Round::default()
+        };
+        let mut project = Project::default();
+        let mut voter = Voter::default();
+        let mut registry = VoterRegistry::default();
+
+        Processor::apply_weighted_vote(&mut round, &mut project, &mut voter, first, first_weight, 0)
+            .unwrap();
+        Processor::add_registry_vote(&mut round, &mut registry, first, first_weight, 0);
+        let before = (round, project, voter, registry);
+
+        Processor::apply_weighted_vote(&mut round, &mut project, &mut voter, last, last_weight, 0)
+            .unwrap();
+        Processor::add_registry_vote(&mut round, &mut registry, last, last_weight, 0);
+        Processor::remove_registry_vote(&mut round, &mut registry, last, voter.last_vote_weight);
+        Processor::revert_vote(&mut round, &mut project, &mut voter, 0).unwrap();
+
+        prop_assert_eq!(voter.votes, before.2.votes);
+        prop_assert_eq!(voter.weight, before.2.weight);
+        prop_assert_eq!(voter.votes_sqrt, before.2.votes_sqrt);
+        prop_assert_eq!(project.votes, before.1.votes);
+        prop_assert_eq!(project.area, before.1.area);
+        prop_assert_eq!(round.votes, before.0.votes);
+        prop_assert_eq!(round.area, before.0.area);
+        prop_assert_eq!(registry, before.3);
+    }
+}
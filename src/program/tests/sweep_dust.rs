@@ -0,0 +1,52 @@
+//! SweepDust only pays to a token account of the config's fee recipient, the same check
+//! WithdrawFee makes before it pays the protocol fee.
+
+use proptest::prelude::*;
+use qf::{error::QFError, processor::Processor, state::Config};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_pack::Pack, pubkey::Pubkey,
+};
+use spl_token::state::{Account, AccountState};
+
+fn pubkey() -> impl Strategy<Value = Pubkey> {
+    any::<[u8; 32]>().prop_map(Pubkey::new_from_array)
+}
+
+/// Checks a token account of `owner` against the fee recipient of `config`
+fn check(config: &Config, owner: Pubkey) -> ProgramResult {
+    let mut data = vec![0; Account::LEN];
+    Account::pack(
+        Account {
+            owner,
+            state: AccountState::Initialized,
+            ..Account::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    let (key, mut lamports) = (Pubkey::new_unique(), 0);
+    let to_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &spl_token::ID,
+        false,
+        0,
+    );
+    Processor::check_fee_recipient(config, &to_info)
+}
+
+proptest! {
+    #[test]
+    fn only_the_fee_recipient_receives_the_dust(fee_recipient in pubkey(), other in pubkey()) {
+        prop_assume!(other != fee_recipient);
+        let config = Config {
+            fee_recipient,
+            ..Config::default()
+        };
+        prop_assert_eq!(check(&config, fee_recipient), Ok(()));
+        prop_assert_eq!(check(&config, other), Err(QFError::FeeRecipientMismatch.into()));
+    }
+}
@@ -0,0 +1,73 @@
+//! The vote log is an append-only Merkle tree, its root is the one a client gets by
+//! replaying the `vote_logged` events and every single entry can be proven against it.
+
+use proptest::{collection::vec, prelude::*};
+use qf::{
+    events::VoteKind,
+    merkle,
+    processor::Processor,
+    state::{Round, VOTE_LOG_DEPTH},
+};
+use solana_program::pubkey::Pubkey;
+
+fn entry() -> impl Strategy<Value = [u8; 32]> {
+    (
+        any::<[u8; 32]>(),
+        any::<[u8; 32]>(),
+        any::<u64>(),
+        prop_oneof![
+            Just(VoteKind::Vote),
+            Just(VoteKind::Revoke),
+            Just(VoteKind::Downvote)
+        ],
+        any::<u64>(),
+    )
+        .prop_map(|(wallet, project, amount, kind, slot)| {
+            Processor::vote_log_leaf(
+                &Pubkey::new_from_array(wallet),
+                &Pubkey::new_from_array(project),
+                amount,
+                kind,
+                slot,
+            )
+        })
+}
+
+proptest! {
+    #[test]
+    fn every_entry_is_proven_against_the_root(
+        leaves in vec(entry(), 1..48),
+        tampered in any::<prop::sample::Index>(),
+    ) {
+        let mut round = Round::default();
+        for (i, leaf) in leaves.iter().enumerate() {
+            round.vote_log =
+                merkle::append(&mut round.vote_log_frontier, round.vote_log_len, leaf).unwrap();
+            round.vote_log_len += 1;
+            prop_assert_eq!(round.vote_log, merkle::root(&leaves[..=i], VOTE_LOG_DEPTH));
+        }
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle::proof(&leaves, index as u64, VOTE_LOG_DEPTH);
+            prop_assert_eq!(proof.len(), VOTE_LOG_DEPTH);
+            prop_assert!(merkle::verify(&round.vote_log, leaf, index as u64, &proof));
+        }
+
+        // an entry does not verify at another index or with another content
+        let index = tampered.index(leaves.len());
+        let proof = merkle::proof(&leaves, index as u64, VOTE_LOG_DEPTH);
+        prop_assert!(!merkle::verify(&round.vote_log, &leaves[index], index as u64 + 1, &proof));
+        let mut leaf = leaves[index];
+        leaf[0] ^= 1;
+        prop_assert!(!merkle::verify(&round.vote_log, &leaf, index as u64, &proof));
+    }
+
+    #[test]
+    fn a_full_log_takes_no_more_entries(leaf in entry()) {
+        let mut frontier = [merkle::EMPTY; 2];
+        for index in 0..4 {
+            prop_assert!(merkle::append(&mut frontier, index, &leaf).is_some());
+        }
+        prop_assert!(merkle::append(&mut frontier, 4, &leaf).is_none());
+    }
+}
@@ -0,0 +1,135 @@
+//! Writes to the voter tree proven against any root it still keeps land on the current
+//! tree, unless the same leaf changed in between, and a refund only empties the leaf it
+//! proves.
+
+use proptest::{collection::vec, prelude::*};
+use qf::{
+    error::QFError,
+    merkle::{self, Node},
+    processor::Processor,
+    state::{ChangeLog, VoterTree, VOTER_TREE_BUFFER, VOTER_TREE_DEPTH},
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+
+/// A write of `leaf` at `index` by a client whose proof is `lag` changes old
+fn write() -> impl Strategy<Value = (u64, Node, usize)> {
+    (0..32u64, any::<[u8; 32]>(), 0..2 * VOTER_TREE_BUFFER)
+}
+
+proptest! {
+    #[test]
+    fn stale_proofs_are_fast_forwarded(writes in vec(write(), 1..24)) {
+        let mut change_logs = vec![0; ChangeLog::LEN * VOTER_TREE_BUFFER];
+        let mut tree = VoterTree::init(Pubkey::new_unique(), &mut change_logs);
+        // the leaves and root after each change, the empty tree first, and the leaf each
+        // change wrote
+        let empty = vec![merkle::EMPTY; 32];
+        let mut history = vec![(merkle::root(&empty, VOTER_TREE_DEPTH), empty)];
+        let mut written = vec![];
+
+        for (index, leaf, lag) in writes {
+            let lag = lag.min(written.len());
+            let (root, seen) = &history[history.len() - 1 - lag];
+            let result = Processor::set_voter_leaf(
+                &mut tree,
+                &mut change_logs,
+                root,
+                &seen[index as usize],
+                &leaf,
+                index,
+                merkle::proof(seen, index, VOTER_TREE_DEPTH),
+            );
+
+            if lag >= tree.buffer_size as usize {
+                prop_assert_eq!(result, Err(QFError::StaleVoterTreeRoot.into()));
+            } else if written[written.len() - lag..].contains(&index) {
+                prop_assert_eq!(result, Err(QFError::InvalidVoterProof.into()));
+            } else {
+                prop_assert_eq!(result, Ok(()));
+                let mut next = history.last().unwrap().1.clone();
+                next[index as usize] = leaf;
+                history.push((merkle::root(&next, VOTER_TREE_DEPTH), next));
+                written.push(index);
+            }
+            prop_assert_eq!(tree.root(&change_logs)?, history.last().unwrap().0);
+        }
+        prop_assert_eq!(tree.sequence as usize, written.len());
+    }
+
+    #[test]
+    fn a_refund_empties_only_the_proven_leaf(
+        tree_slot in 1..4u64,
+        votes in vec(1..1_000_000_000u64, 1..6),
+        claimed in 0..1_000_000_000u64,
+    ) {
+        let round = Pubkey::new_unique();
+        let project = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let mut change_logs = vec![0; ChangeLog::LEN * VOTER_TREE_BUFFER];
+        let mut tree = VoterTree::init(round, &mut change_logs);
+        let mut leaves = vec![merkle::EMPTY; 256];
+
+        // the wallet votes for the project a few times
+        let index = Processor::voter_leaf_index(tree_slot, 3);
+        let mut total = 0;
+        for (slot, amount) in votes.iter().enumerate() {
+            let previous = Processor::voter_leaf(&project, &wallet, total, slot as u64);
+            total += amount;
+            let leaf = Processor::voter_leaf(&project, &wallet, total, slot as u64 + 1);
+            Processor::set_voter_leaf(
+                &mut tree,
+                &mut change_logs,
+                &merkle::root(&leaves, VOTER_TREE_DEPTH),
+                &previous,
+                &leaf,
+                index,
+                merkle::proof(&leaves, index, VOTER_TREE_DEPTH),
+            )
+            .unwrap();
+            leaves[index as usize] = leaf;
+        }
+
+        let root = merkle::root(&leaves, VOTER_TREE_DEPTH);
+        let proof = merkle::proof(&leaves, index, VOTER_TREE_DEPTH);
+        let last_slot = votes.len() as u64;
+        if claimed != total {
+            prop_assert_eq!(
+                Processor::set_voter_leaf(
+                    &mut tree,
+                    &mut change_logs,
+                    &root,
+                    &Processor::voter_leaf(&project, &wallet, claimed, last_slot),
+                    &merkle::EMPTY,
+                    index,
+                    proof.clone(),
+                ),
+                Err(QFError::InvalidVoterProof.into())
+            );
+        }
+        Processor::set_voter_leaf(
+            &mut tree,
+            &mut change_logs,
+            &root,
+            &Processor::voter_leaf(&project, &wallet, total, last_slot),
+            &merkle::EMPTY,
+            index,
+            proof.clone(),
+        )
+        .unwrap();
+
+        // the emptied leaf can not be refunded again
+        prop_assert_eq!(
+            Processor::set_voter_leaf(
+                &mut tree,
+                &mut change_logs,
+                &root,
+                &Processor::voter_leaf(&project, &wallet, total, last_slot),
+                &merkle::EMPTY,
+                index,
+                proof,
+            ),
+            Err(QFError::InvalidVoterProof.into())
+        );
+        prop_assert_eq!(tree.root(&change_logs)?, merkle::empty_root(VOTER_TREE_DEPTH));
+    }
+}